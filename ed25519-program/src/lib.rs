@@ -7,6 +7,8 @@ use {
     bytemuck_derive::{Pod, Zeroable},
     solana_instruction::Instruction,
 };
+#[cfg(feature = "verify")]
+use {ed25519_dalek::Verifier, solana_precompile_error::PrecompileError};
 
 pub const PUBKEY_SERIALIZED_SIZE: usize = 32;
 pub const SIGNATURE_SERIALIZED_SIZE: usize = 64;
@@ -106,3 +108,97 @@ pub fn new_ed25519_instruction_with_signature(
         data: instruction_data,
     }
 }
+
+/// Verify the signatures of an ed25519 instruction with `data`, as the
+/// runtime does before executing the transaction containing it.
+///
+/// `instruction_datas` are the data of every instruction of the transaction,
+/// which the signature offsets may refer to. `verify_strict` rejects small
+/// order public keys and non-canonical signatures.
+#[cfg(feature = "verify")]
+pub fn verify(
+    data: &[u8],
+    instruction_datas: &[&[u8]],
+    verify_strict: bool,
+) -> Result<(), PrecompileError> {
+    if data.len() < SIGNATURE_OFFSETS_START {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    let num_signatures = usize::from(data[0]);
+    if num_signatures == 0 && data.len() > SIGNATURE_OFFSETS_START {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    let expected_data_size = num_signatures
+        .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+        .saturating_add(SIGNATURE_OFFSETS_START);
+    if data.len() < expected_data_size {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+
+    for offsets in data[SIGNATURE_OFFSETS_START..]
+        .chunks_exact(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+        .take(num_signatures)
+    {
+        let offsets: Ed25519SignatureOffsets = bytemuck::pod_read_unaligned(offsets);
+
+        let signature = get_data_slice(
+            data,
+            instruction_datas,
+            offsets.signature_instruction_index,
+            offsets.signature_offset,
+            SIGNATURE_SERIALIZED_SIZE,
+        )?;
+        let signature = ed25519_dalek::Signature::try_from(signature)
+            .map_err(|_| PrecompileError::InvalidSignature)?;
+
+        let pubkey = get_data_slice(
+            data,
+            instruction_datas,
+            offsets.public_key_instruction_index,
+            offsets.public_key_offset,
+            PUBKEY_SERIALIZED_SIZE,
+        )?;
+        let pubkey = ed25519_dalek::PublicKey::from_bytes(pubkey)
+            .map_err(|_| PrecompileError::InvalidPublicKey)?;
+
+        let message = get_data_slice(
+            data,
+            instruction_datas,
+            offsets.message_instruction_index,
+            offsets.message_data_offset,
+            usize::from(offsets.message_data_size),
+        )?;
+
+        if verify_strict {
+            pubkey.verify_strict(message, &signature)
+        } else {
+            pubkey.verify(message, &signature)
+        }
+        .map_err(|_| PrecompileError::InvalidSignature)?;
+    }
+    Ok(())
+}
+
+/// Slice of `size` bytes at `offset` in the data of the instruction at
+/// `instruction_index`, where `u16::MAX` refers to the instruction being
+/// verified.
+#[cfg(feature = "verify")]
+fn get_data_slice<'a>(
+    data: &'a [u8],
+    instruction_datas: &'a [&[u8]],
+    instruction_index: u16,
+    offset: u16,
+    size: usize,
+) -> Result<&'a [u8], PrecompileError> {
+    let instruction = if instruction_index == u16::MAX {
+        data
+    } else {
+        instruction_datas
+            .get(usize::from(instruction_index))
+            .ok_or(PrecompileError::InvalidDataOffsets)?
+    };
+    let start = usize::from(offset);
+    instruction
+        .get(start..start.saturating_add(size))
+        .ok_or(PrecompileError::InvalidDataOffsets)
+}
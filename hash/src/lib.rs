@@ -144,6 +144,53 @@ impl Hash {
     }
 }
 
+#[cfg(feature = "hex")]
+impl Hash {
+    /// Decode a hash from HASH_BYTES bytes of hex digits of either case,
+    /// optionally prefixed by `0x`.
+    pub fn from_hex(s: &str) -> Result<Self, ParseHashError> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let mut bytes = [0; HASH_BYTES];
+        hex::decode_to_slice(s, &mut bytes).map_err(|err| match err {
+            hex::FromHexError::InvalidHexCharacter { .. } => ParseHashError::Invalid,
+            hex::FromHexError::OddLength | hex::FromHexError::InvalidStringLength => {
+                ParseHashError::WrongSize
+            }
+        })?;
+        Ok(Self(bytes))
+    }
+
+    /// Encode the bytes as lowercase hex digits, without a prefix.
+    pub fn to_hex(&self) -> std::string::String {
+        hex::encode(self.0)
+    }
+}
+
+#[cfg(feature = "base64")]
+impl Hash {
+    /// Decode a hash from standard, padded base64.
+    pub fn from_base64(s: &str) -> Result<Self, ParseHashError> {
+        use base64::{prelude::BASE64_STANDARD, DecodeError, Engine};
+        // 4 base64 characters encode 3 bytes
+        const MAX_BASE64_LEN: usize = HASH_BYTES.div_ceil(3) * 4;
+        if s.len() > MAX_BASE64_LEN {
+            return Err(ParseHashError::WrongSize);
+        }
+        let bytes = BASE64_STANDARD.decode(s).map_err(|err| match err {
+            DecodeError::InvalidLength(_) => ParseHashError::WrongSize,
+            _ => ParseHashError::Invalid,
+        })?;
+        let bytes = <[u8; HASH_BYTES]>::try_from(bytes).map_err(|_| ParseHashError::WrongSize)?;
+        Ok(Self(bytes))
+    }
+
+    /// Encode the bytes as standard, padded base64.
+    pub fn to_base64(&self) -> std::string::String {
+        use base64::{prelude::BASE64_STANDARD, Engine};
+        BASE64_STANDARD.encode(self.0)
+    }
+}
+
 #[cfg(target_arch = "wasm32")]
 #[allow(non_snake_case)]
 #[wasm_bindgen]
@@ -210,6 +257,41 @@ mod tests {
         assert!(Hash::new_unique() != Hash::new_unique());
     }
 
+    #[cfg(feature = "hex")]
+    #[test]
+    fn test_hash_hex() {
+        let hash = Hash::new_from_array(core::array::from_fn(|i| i as u8 * 7));
+        let hex = hash.to_hex();
+        assert_eq!(hex.len(), 64);
+        assert_eq!(Hash::from_hex(&hex), Ok(hash));
+        assert_eq!(Hash::from_hex(&hex.to_uppercase()), Ok(hash));
+        assert_eq!(Hash::from_hex(&std::format!("0x{hex}")), Ok(hash));
+        assert_eq!(Hash::from_hex(&hex[2..]), Err(ParseHashError::WrongSize));
+        assert_eq!(Hash::from_hex(&hex[1..]), Err(ParseHashError::WrongSize));
+        assert_eq!(
+            Hash::from_hex(&hex.replace('0', "g")),
+            Err(ParseHashError::Invalid)
+        );
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_hash_base64() {
+        let hash = Hash::new_from_array(core::array::from_fn(|i| i as u8 * 7));
+        let base64 = hash.to_base64();
+        assert_eq!(base64.len(), 44);
+        assert_eq!(Hash::from_base64(&base64), Ok(hash));
+        assert_eq!(Hash::from_base64("AAAA"), Err(ParseHashError::WrongSize));
+        assert_eq!(
+            Hash::from_base64(&std::format!("{base64}AAAA")),
+            Err(ParseHashError::WrongSize)
+        );
+        assert_eq!(
+            Hash::from_base64(&base64.replace(base64.chars().next().unwrap(), "*")),
+            Err(ParseHashError::Invalid)
+        );
+    }
+
     #[test]
     fn test_hash_fromstr() {
         let hash = Hash::new_from_array([1; 32]);
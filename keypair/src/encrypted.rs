@@ -0,0 +1,495 @@
+//! Password-protected keypair files.
+//!
+//! An [`EncryptedKeypair`] is stored as a JSON object of the form
+//!
+//! ```json
+//! {
+//!   "version": 1,
+//!   "pubkey": "<base58 pubkey>",
+//!   "kdf": { "name": "scrypt", "log_n": 17, "r": 8, "p": 1, "salt": "<hex>" },
+//!   "cipher": { "name": "xchacha20poly1305", "nonce": "<hex>" },
+//!   "ciphertext": "<hex>"
+//! }
+//! ```
+//!
+//! where `kdf` may instead be
+//! `{ "name": "argon2id", "m_cost": 19456, "t_cost": 2, "p_cost": 1, "salt": "<hex>" }`.
+//!
+//! The KDF stretches the password and the 16-byte salt into a 32-byte key.
+//! The 32-byte ed25519 secret key is encrypted with XChaCha20-Poly1305 under
+//! that key and the 24-byte nonce, with the 32 bytes of the pubkey as
+//! associated data. The ciphertext includes the 16-byte authentication tag.
+
+use {
+    crate::Keypair,
+    argon2::Argon2,
+    chacha20poly1305::{
+        aead::{Aead, KeyInit, Payload},
+        XChaCha20Poly1305, XNonce,
+    },
+    rand0_7::{rngs::OsRng, RngCore},
+    serde_derive::{Deserialize, Serialize},
+    solana_pubkey::Pubkey,
+    solana_signer::{EncodableKey, EncodableKeypair, Signer},
+    std::{
+        error, fmt,
+        io::{Read, Write},
+    },
+    zeroize::{Zeroize, Zeroizing},
+};
+
+/// The version of the keystore format written by [`EncryptedKeypair::encrypt`].
+pub const ENCRYPTED_KEYPAIR_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const KEY_LEN: usize = 32;
+
+/// Largest amount of memory, in bytes, a KDF may be configured to use.
+pub const MAX_KDF_MEMORY: u64 = 1 << 30;
+/// Largest scrypt `log_n`, so that N is at most 2^20.
+pub const MAX_SCRYPT_LOG_N: u8 = 20;
+/// Largest product of the scrypt `r` and `p` parameters.
+pub const MAX_SCRYPT_R_P: u64 = 32;
+/// Largest number of Argon2id passes over memory.
+pub const MAX_ARGON2_T_COST: u32 = 16;
+/// Largest number of Argon2id lanes.
+pub const MAX_ARGON2_P_COST: u32 = 16;
+
+/// Reasons an [`EncryptedKeypair`] cannot be created or decrypted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EncryptedKeypairError {
+    /// The keystore was written in a format version this crate cannot read.
+    UnsupportedVersion(u32),
+    /// The KDF parameters are out of range for the KDF, or exceed the
+    /// resource bounds documented on [`KdfParams`].
+    InvalidKdfParams,
+    /// The nonce does not have the length the cipher requires.
+    InvalidNonce,
+    /// The password is wrong, or the keystore was modified.
+    DecryptionFailed,
+}
+
+impl error::Error for EncryptedKeypairError {}
+
+impl fmt::Display for EncryptedKeypairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported encrypted keypair version: {version}")
+            }
+            Self::InvalidKdfParams => f.write_str("invalid key derivation parameters"),
+            Self::InvalidNonce => f.write_str("invalid nonce length"),
+            Self::DecryptionFailed => {
+                f.write_str("decryption failed: wrong password or corrupted keypair file")
+            }
+        }
+    }
+}
+
+/// The key derivation function stretching a password into an encryption key,
+/// and its cost parameters.
+///
+/// The parameters of a keystore file are not trusted, so parameters that
+/// would use more than [`MAX_KDF_MEMORY`] bytes or exceed
+/// [`MAX_SCRYPT_LOG_N`], [`MAX_SCRYPT_R_P`], [`MAX_ARGON2_T_COST`] or
+/// [`MAX_ARGON2_P_COST`] are rejected with
+/// [`EncryptedKeypairError::InvalidKdfParams`] before any key is derived.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "lowercase")]
+pub enum KdfParams {
+    /// scrypt with N = 2^`log_n`.
+    Scrypt { log_n: u8, r: u32, p: u32 },
+    /// Argon2id with `m_cost` KiB of memory and `t_cost` passes over it.
+    Argon2id {
+        m_cost: u32,
+        t_cost: u32,
+        p_cost: u32,
+    },
+}
+
+impl KdfParams {
+    /// scrypt with N = 2^17, r = 8, p = 1, using 128 MiB of memory.
+    pub const SCRYPT: Self = Self::Scrypt {
+        log_n: 17,
+        r: 8,
+        p: 1,
+    };
+    /// Argon2id with 19 MiB of memory and 2 passes.
+    pub const ARGON2ID: Self = Self::Argon2id {
+        m_cost: 19 * 1024,
+        t_cost: 2,
+        p_cost: 1,
+    };
+
+    /// Check that deriving a key stays within the documented resource
+    /// bounds.
+    fn check_bounds(&self) -> Result<(), EncryptedKeypairError> {
+        let within_bounds = match *self {
+            Self::Scrypt { log_n, r, p } => {
+                // scrypt uses 128 * r * N bytes
+                log_n <= MAX_SCRYPT_LOG_N
+                    && 128u64
+                        .saturating_mul(u64::from(r))
+                        .saturating_mul(1 << log_n)
+                        <= MAX_KDF_MEMORY
+                    && u64::from(r).saturating_mul(u64::from(p)) <= MAX_SCRYPT_R_P
+            }
+            Self::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                u64::from(m_cost).saturating_mul(1024) <= MAX_KDF_MEMORY
+                    && t_cost <= MAX_ARGON2_T_COST
+                    && p_cost <= MAX_ARGON2_P_COST
+            }
+        };
+        if within_bounds {
+            Ok(())
+        } else {
+            Err(EncryptedKeypairError::InvalidKdfParams)
+        }
+    }
+
+    fn derive_key(
+        &self,
+        password: &[u8],
+        salt: &[u8],
+    ) -> Result<Zeroizing<[u8; KEY_LEN]>, EncryptedKeypairError> {
+        self.check_bounds()?;
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        match *self {
+            Self::Scrypt { log_n, r, p } => {
+                let params = scrypt::Params::new(log_n, r, p, KEY_LEN)
+                    .map_err(|_| EncryptedKeypairError::InvalidKdfParams)?;
+                scrypt::scrypt(password, salt, &params, &mut key[..])
+                    .map_err(|_| EncryptedKeypairError::InvalidKdfParams)?;
+            }
+            Self::Argon2id {
+                m_cost,
+                t_cost,
+                p_cost,
+            } => {
+                let params = argon2::Params::new(m_cost, t_cost, p_cost, Some(KEY_LEN))
+                    .map_err(|_| EncryptedKeypairError::InvalidKdfParams)?;
+                Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+                    .hash_password_into(password, salt, &mut key[..])
+                    .map_err(|_| EncryptedKeypairError::InvalidKdfParams)?;
+            }
+        }
+        Ok(key)
+    }
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self::SCRYPT
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Kdf {
+    #[serde(flatten)]
+    params: KdfParams,
+    #[serde(with = "hex")]
+    salt: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+enum CipherName {
+    #[serde(rename = "xchacha20poly1305")]
+    XChaCha20Poly1305,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Cipher {
+    name: CipherName,
+    #[serde(with = "hex")]
+    nonce: Vec<u8>,
+}
+
+/// A [`Keypair`] encrypted with a password, readable and writable as a JSON
+/// keystore file.
+///
+/// The pubkey is stored in the clear, so it is available without the
+/// password.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EncryptedKeypair {
+    version: u32,
+    #[serde(with = "base58_pubkey")]
+    pubkey: Pubkey,
+    kdf: Kdf,
+    cipher: Cipher,
+    #[serde(with = "hex")]
+    ciphertext: Vec<u8>,
+}
+
+impl EncryptedKeypair {
+    /// Encrypt `keypair` with a key derived from `password` with a random
+    /// salt.
+    pub fn encrypt(
+        keypair: &Keypair,
+        password: &[u8],
+        kdf_params: KdfParams,
+    ) -> Result<Self, EncryptedKeypairError> {
+        let mut salt = vec![0u8; SALT_LEN];
+        let mut nonce = vec![0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut salt);
+        OsRng.fill_bytes(&mut nonce);
+
+        let key = kdf_params.derive_key(password, &salt)?;
+        let pubkey = keypair.pubkey();
+        let ciphertext = XChaCha20Poly1305::new(&(*key).into())
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: keypair.secret_bytes(),
+                    aad: pubkey.as_ref(),
+                },
+            )
+            // only fails for messages longer than the cipher's limit
+            .expect("secret key is short");
+
+        Ok(Self {
+            version: ENCRYPTED_KEYPAIR_VERSION,
+            pubkey,
+            kdf: Kdf {
+                params: kdf_params,
+                salt,
+            },
+            cipher: Cipher {
+                name: CipherName::XChaCha20Poly1305,
+                nonce,
+            },
+            ciphertext,
+        })
+    }
+
+    /// Decrypt the keypair with `password`.
+    pub fn decrypt(&self, password: &[u8]) -> Result<Keypair, EncryptedKeypairError> {
+        if self.version != ENCRYPTED_KEYPAIR_VERSION {
+            return Err(EncryptedKeypairError::UnsupportedVersion(self.version));
+        }
+        if self.cipher.nonce.len() != NONCE_LEN {
+            return Err(EncryptedKeypairError::InvalidNonce);
+        }
+
+        let key = self.kdf.params.derive_key(password, &self.kdf.salt)?;
+        let secret = XChaCha20Poly1305::new(&(*key).into())
+            .decrypt(
+                XNonce::from_slice(&self.cipher.nonce),
+                Payload {
+                    msg: &self.ciphertext,
+                    aad: self.pubkey.as_ref(),
+                },
+            )
+            .map(Zeroizing::new)
+            .map_err(|_| EncryptedKeypairError::DecryptionFailed)?;
+        let mut secret_key = [0u8; Keypair::SECRET_KEY_LENGTH];
+        if secret.len() != secret_key.len() {
+            return Err(EncryptedKeypairError::DecryptionFailed);
+        }
+        secret_key.copy_from_slice(&secret);
+        let keypair = Keypair::new_from_array(secret_key);
+        secret_key.zeroize();
+        // the tag authenticates the pubkey, but not that it matches the secret
+        if keypair.pubkey() != self.pubkey {
+            return Err(EncryptedKeypairError::DecryptionFailed);
+        }
+        Ok(keypair)
+    }
+
+    /// The pubkey of the encrypted keypair.
+    pub fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    /// The KDF the password is stretched with.
+    pub fn kdf_params(&self) -> KdfParams {
+        self.kdf.params
+    }
+}
+
+impl EncodableKey for EncryptedKeypair {
+    fn read<R: Read>(reader: &mut R) -> Result<Self, Box<dyn error::Error>> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    fn write<W: Write>(&self, writer: &mut W) -> Result<String, Box<dyn error::Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        writer.write_all(json.as_bytes())?;
+        Ok(json)
+    }
+}
+
+impl EncodableKeypair for EncryptedKeypair {
+    type Pubkey = Pubkey;
+
+    fn encodable_pubkey(&self) -> Self::Pubkey {
+        self.pubkey
+    }
+}
+
+mod base58_pubkey {
+    use {
+        serde::{de::Error, Deserialize, Deserializer, Serializer},
+        solana_pubkey::Pubkey,
+        std::str::FromStr,
+    };
+
+    pub fn serialize<S: Serializer>(pubkey: &Pubkey, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(pubkey)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Pubkey, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Pubkey::from_str(&s).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // cheap parameters to keep the tests fast
+    const TEST_SCRYPT: KdfParams = KdfParams::Scrypt {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+    const TEST_ARGON2ID: KdfParams = KdfParams::Argon2id {
+        m_cost: 64,
+        t_cost: 1,
+        p_cost: 1,
+    };
+
+    #[test]
+    fn test_encrypted_keypair_roundtrip() {
+        let keypair = Keypair::new();
+        for kdf_params in [TEST_SCRYPT, TEST_ARGON2ID] {
+            let encrypted = EncryptedKeypair::encrypt(&keypair, b"hunter2", kdf_params).unwrap();
+            assert_eq!(encrypted.pubkey(), keypair.pubkey());
+            assert_eq!(encrypted.kdf_params(), kdf_params);
+
+            let mut file = Vec::new();
+            encrypted.write(&mut file).unwrap();
+            let read = EncryptedKeypair::read(&mut file.as_slice()).unwrap();
+            assert_eq!(read, encrypted);
+            assert_eq!(
+                read.decrypt(b"hunter2").unwrap().to_bytes(),
+                keypair.to_bytes()
+            );
+            assert_eq!(
+                read.decrypt(b"hunter3").unwrap_err(),
+                EncryptedKeypairError::DecryptionFailed
+            );
+        }
+    }
+
+    #[test]
+    fn test_encrypted_keypair_schema() {
+        let keypair = Keypair::new();
+        let encrypted = EncryptedKeypair::encrypt(&keypair, b"", TEST_SCRYPT).unwrap();
+        let json: serde_json::Value = serde_json::to_value(&encrypted).unwrap();
+        assert_eq!(json["version"], 1);
+        assert_eq!(json["pubkey"], keypair.pubkey().to_string());
+        assert_eq!(json["kdf"]["name"], "scrypt");
+        assert_eq!(json["kdf"]["log_n"], 4);
+        assert_eq!(json["kdf"]["salt"].as_str().unwrap().len(), SALT_LEN * 2);
+        assert_eq!(json["cipher"]["name"], "xchacha20poly1305");
+        assert_eq!(
+            json["cipher"]["nonce"].as_str().unwrap().len(),
+            NONCE_LEN * 2
+        );
+        // secret key and tag
+        assert_eq!(json["ciphertext"].as_str().unwrap().len(), (32 + 16) * 2);
+
+        let encrypted = EncryptedKeypair::encrypt(&keypair, b"", TEST_ARGON2ID).unwrap();
+        let json: serde_json::Value = serde_json::to_value(&encrypted).unwrap();
+        assert_eq!(json["kdf"]["name"], "argon2id");
+        assert_eq!(json["kdf"]["m_cost"], 64);
+    }
+
+    #[test]
+    fn test_encrypted_keypair_tampered() {
+        let keypair = Keypair::new();
+        let encrypted = EncryptedKeypair::encrypt(&keypair, b"pw", TEST_SCRYPT).unwrap();
+
+        let mut tampered = encrypted.clone();
+        tampered.ciphertext[0] ^= 1;
+        assert_eq!(
+            tampered.decrypt(b"pw").unwrap_err(),
+            EncryptedKeypairError::DecryptionFailed
+        );
+
+        let mut tampered = encrypted.clone();
+        tampered.pubkey = Pubkey::new_unique();
+        assert_eq!(
+            tampered.decrypt(b"pw").unwrap_err(),
+            EncryptedKeypairError::DecryptionFailed
+        );
+
+        let mut tampered = encrypted.clone();
+        tampered.version = 2;
+        assert_eq!(
+            tampered.decrypt(b"pw").unwrap_err(),
+            EncryptedKeypairError::UnsupportedVersion(2)
+        );
+
+        let mut tampered = encrypted;
+        // below Argon2's minimum of 8 KiB per lane
+        tampered.kdf.params = KdfParams::Argon2id {
+            m_cost: 1,
+            t_cost: 1,
+            p_cost: 1,
+        };
+        assert_eq!(
+            tampered.decrypt(b"pw").unwrap_err(),
+            EncryptedKeypairError::InvalidKdfParams
+        );
+    }
+
+    #[test]
+    fn test_encrypted_keypair_oversized_kdf_params() {
+        let keypair = Keypair::new();
+        let encrypted = EncryptedKeypair::encrypt(&keypair, b"pw", TEST_SCRYPT).unwrap();
+        let mut json: serde_json::Value = serde_json::to_value(&encrypted).unwrap();
+        let salt = json["kdf"]["salt"].clone();
+
+        for kdf in [
+            // 2^40 * 128 * 8 bytes of memory
+            serde_json::json!({ "name": "scrypt", "log_n": 40, "r": 8, "p": 1 }),
+            // 2 GiB of memory
+            serde_json::json!({ "name": "scrypt", "log_n": 20, "r": 16, "p": 1 }),
+            serde_json::json!({ "name": "scrypt", "log_n": 4, "r": 8, "p": u32::MAX }),
+            serde_json::json!({ "name": "argon2id", "m_cost": u32::MAX, "t_cost": 1, "p_cost": 1 }),
+            serde_json::json!({ "name": "argon2id", "m_cost": 64, "t_cost": u32::MAX, "p_cost": 1 }),
+            serde_json::json!({ "name": "argon2id", "m_cost": 64, "t_cost": 1, "p_cost": 1024 }),
+        ] {
+            json["kdf"] = kdf;
+            json["kdf"]["salt"] = salt.clone();
+            let file = serde_json::to_vec(&json).unwrap();
+            let read = EncryptedKeypair::read(&mut file.as_slice()).unwrap();
+            assert_eq!(
+                read.decrypt(b"pw").unwrap_err(),
+                EncryptedKeypairError::InvalidKdfParams
+            );
+        }
+
+        assert_eq!(
+            EncryptedKeypair::encrypt(
+                &keypair,
+                b"pw",
+                KdfParams::Scrypt {
+                    log_n: MAX_SCRYPT_LOG_N + 1,
+                    r: 1,
+                    p: 1,
+                },
+            )
+            .unwrap_err(),
+            EncryptedKeypairError::InvalidKdfParams
+        );
+        assert_eq!(KdfParams::SCRYPT.check_bounds(), Ok(()));
+        assert_eq!(KdfParams::ARGON2ID.check_bounds(), Ok(()));
+    }
+}
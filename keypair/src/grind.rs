@@ -0,0 +1,36 @@
+//! Searching for keypairs whose address matches a pattern.
+
+pub use solana_pubkey::grind::{GrindPattern, GrindPatternError};
+use {crate::Keypair, solana_pubkey::grind::grind, solana_signer::Signer};
+
+/// Generate random keypairs on `num_threads` threads until the address of one
+/// matches `pattern`, and return it.
+///
+/// `progress` is called every few thousand keypairs with the number
+/// generated so far. At least one thread is used.
+pub fn grind_keypair(
+    pattern: &GrindPattern,
+    num_threads: usize,
+    progress: impl Fn(u64) + Sync,
+) -> Keypair {
+    grind(num_threads, progress, |_| {
+        let keypair = Keypair::new();
+        pattern.matches(&keypair.pubkey()).then_some(keypair)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grind_keypair() {
+        let pattern = GrindPattern::new("", "z", true).unwrap();
+        let keypair = grind_keypair(&pattern, 2, |_| {});
+        assert!(keypair
+            .pubkey()
+            .to_string()
+            .to_ascii_lowercase()
+            .ends_with('z'));
+    }
+}
@@ -3,30 +3,52 @@
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::*;
 use {
+    crate::secret_key::SecretKey,
     ed25519_dalek::Signer as DalekSigner,
+    hkdf::Hkdf,
     rand0_7::rngs::OsRng,
+    rand_core::CryptoRngCore,
+    sha2::Sha512,
     solana_pubkey::Pubkey,
     solana_seed_phrase::generate_seed_from_seed_phrase_and_passphrase,
     solana_signature::{error::Error as SignatureError, Signature},
     solana_signer::{EncodableKey, EncodableKeypair, Signer, SignerError},
     std::{
-        error,
+        error, fmt,
         io::{Read, Write},
         path::Path,
     },
+    zeroize::{Zeroize, Zeroizing},
 };
 
+#[cfg(feature = "encrypted")]
+pub mod encrypted;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod grind;
+#[cfg(all(feature = "mlock", unix))]
+pub mod locked;
+#[cfg(feature = "bip39")]
+pub mod mnemonic;
+pub mod secret_key;
 #[cfg(feature = "seed-derivable")]
 pub mod seed_derivable;
 pub mod signable;
+#[cfg(feature = "frost")]
+pub mod threshold;
 
 /// A vanilla Ed25519 key pair
+///
+/// The secret key is zeroed when the `Keypair` is dropped, and is never
+/// printed by `Debug`, which only shows the pubkey.
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
-#[derive(Debug)]
 pub struct Keypair(ed25519_dalek::Keypair);
 
 pub const KEYPAIR_LENGTH: usize = 64;
 
+/// HKDF salt separating [`Keypair::derive_child`] from other uses of the
+/// secret key.
+const DERIVE_CHILD_SALT: &[u8] = b"solana-keypair-derive-child";
+
 impl Keypair {
     /// Can be used for generating a Keypair without a dependency on `rand` types
     pub const SECRET_KEY_LENGTH: usize = 32;
@@ -38,6 +60,15 @@ impl Keypair {
         Self(ed25519_dalek::Keypair::generate(&mut rng))
     }
 
+    /// Constructs a new `Keypair` with a secret key drawn from `rng`
+    pub fn generate_with_rng(rng: &mut impl CryptoRngCore) -> Self {
+        let mut secret_key = [0u8; Self::SECRET_KEY_LENGTH];
+        rng.fill_bytes(&mut secret_key);
+        let keypair = Self::new_from_array(secret_key);
+        secret_key.zeroize();
+        keypair
+    }
+
     /// Constructs a new `Keypair` using secret key bytes
     pub fn new_from_array(secret_key: [u8; 32]) -> Self {
         // unwrap is safe because the only error condition is an incorrect length
@@ -46,6 +77,20 @@ impl Keypair {
         Self(ed25519_dalek::Keypair { secret, public })
     }
 
+    /// Constructs a `Keypair` from a secret key, deriving its pubkey
+    pub fn from_secret_key(secret_key: &SecretKey) -> Self {
+        // unwrap is safe because the only error condition is an incorrect length
+        let secret = ed25519_dalek::SecretKey::from_bytes(secret_key.as_bytes()).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        Self(ed25519_dalek::Keypair { secret, public })
+    }
+
+    /// Copies the secret key out of this `Keypair`, for APIs that hold only
+    /// the secret
+    pub fn to_secret_key(&self) -> SecretKey {
+        SecretKey::from_bytes(*self.secret_bytes())
+    }
+
     /// Returns this `Keypair` as a byte array
     pub fn to_bytes(&self) -> [u8; KEYPAIR_LENGTH] {
         self.0.to_bytes()
@@ -55,14 +100,20 @@ impl Keypair {
     pub fn from_base58_string(s: &str) -> Self {
         let mut buf = [0u8; ed25519_dalek::KEYPAIR_LENGTH];
         five8::decode_64(s, &mut buf).unwrap();
-        Self::try_from(&buf[..]).unwrap()
+        let keypair = Self::try_from(&buf[..]).unwrap();
+        buf.zeroize();
+        keypair
     }
 
     /// Returns this `Keypair` as a base58-encoded string
     pub fn to_base58_string(&self) -> String {
+        let mut bytes = self.0.to_bytes();
         let mut out = [0u8; five8::BASE58_ENCODED_64_MAX_LEN];
-        let len = five8::encode_64(&self.0.to_bytes(), &mut out);
-        unsafe { String::from_utf8_unchecked(out[..len as usize].to_vec()) }
+        let len = five8::encode_64(&bytes, &mut out);
+        let encoded = unsafe { String::from_utf8_unchecked(out[..len as usize].to_vec()) };
+        bytes.zeroize();
+        out.zeroize();
+        encoded
     }
 
     /// Gets this `Keypair`'s secret key bytes
@@ -70,6 +121,21 @@ impl Keypair {
         self.0.secret.as_bytes()
     }
 
+    /// Deterministically derives a keypair for the purpose named by `label`.
+    ///
+    /// The child's secret key is HKDF-SHA512 of this keypair's secret key,
+    /// with `label` as the info. Children with different labels, and the
+    /// parent, are unrelated to anyone without the parent's secret key.
+    pub fn derive_child(&self, label: &[u8]) -> Self {
+        let hkdf = Hkdf::<Sha512>::new(Some(DERIVE_CHILD_SALT), self.secret_bytes());
+        let mut secret_key = [0u8; Self::SECRET_KEY_LENGTH];
+        hkdf.expand(label, &mut secret_key)
+            .expect("32 bytes is a valid HKDF-SHA512 output length");
+        let child = Self::new_from_array(secret_key);
+        secret_key.zeroize();
+        child
+    }
+
     /// Allows Keypair cloning
     ///
     /// Note that the `Clone` trait is intentionally unimplemented because making a
@@ -86,6 +152,12 @@ impl Keypair {
     }
 }
 
+impl fmt::Debug for Keypair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Keypair").field(&self.pubkey()).finish()
+    }
+}
+
 impl TryFrom<&[u8]> for Keypair {
     type Error = SignatureError;
 
@@ -193,9 +265,16 @@ impl EncodableKeypair for Keypair {
     }
 }
 
+/// A deterministic RNG for generating reproducible sequences of keypairs in
+/// tests and simulations with [`Keypair::generate_with_rng`]
+#[cfg(feature = "dev-context-only-utils")]
+pub fn seeded_rng(seed: u64) -> rand_chacha::ChaCha20Rng {
+    rand_core::SeedableRng::seed_from_u64(seed)
+}
+
 /// Reads a JSON-encoded `Keypair` from a `Reader` implementor
 pub fn read_keypair<R: Read>(reader: &mut R) -> Result<Keypair, Box<dyn error::Error>> {
-    let mut buffer = String::new();
+    let mut buffer = Zeroizing::new(String::new());
     reader.read_to_string(&mut buffer)?;
     let trimmed = buffer.trim();
     if !trimmed.starts_with('[') || !trimmed.ends_with(']') {
@@ -227,7 +306,9 @@ pub fn read_keypair<R: Read>(reader: &mut R) -> Result<Keypair, Box<dyn error::E
         let parsed: u8 = element.parse()?;
         out[idx] = parsed;
     }
-    Keypair::try_from(&out[..]).map_err(|e| std::io::Error::other(e.to_string()).into())
+    let keypair = Keypair::try_from(&out[..]);
+    out.zeroize();
+    keypair.map_err(|e| std::io::Error::other(e.to_string()).into())
 }
 
 /// Reads a `Keypair` from a file
@@ -240,7 +321,7 @@ pub fn write_keypair<W: Write>(
     keypair: &Keypair,
     writer: &mut W,
 ) -> Result<String, Box<dyn error::Error>> {
-    let keypair_bytes = keypair.0.to_bytes();
+    let mut keypair_bytes = keypair.0.to_bytes();
     let mut result = Vec::with_capacity(64 * 4 + 2); // Estimate capacity: 64 numbers * (up to 3 digits + 1 comma) + 2 brackets
 
     result.push(b'['); // Opening bracket
@@ -254,6 +335,7 @@ pub fn write_keypair<W: Write>(
         let num_str = num.to_string();
         result.extend_from_slice(num_str.as_bytes());
     }
+    keypair_bytes.zeroize();
 
     result.push(b']'); // Closing bracket
     writer.write_all(&result)?;
@@ -285,10 +367,11 @@ pub fn keypair_from_seed_phrase_and_passphrase(
     seed_phrase: &str,
     passphrase: &str,
 ) -> Result<Keypair, Box<dyn std::error::Error>> {
-    keypair_from_seed(&generate_seed_from_seed_phrase_and_passphrase(
+    let seed = Zeroizing::new(generate_seed_from_seed_phrase_and_passphrase(
         seed_phrase,
         passphrase,
-    ))
+    ));
+    keypair_from_seed(&seed)
 }
 
 #[cfg(test)]
@@ -448,6 +531,45 @@ mod tests {
         foo(Keypair::new());
     }
 
+    #[test]
+    fn test_generate_with_rng() {
+        let mut rng = seeded_rng(42);
+        let keypairs: Vec<Keypair> = (0..3)
+            .map(|_| Keypair::generate_with_rng(&mut rng))
+            .collect();
+        let mut rng = seeded_rng(42);
+        for keypair in &keypairs {
+            assert_eq!(&Keypair::generate_with_rng(&mut rng), keypair);
+        }
+        assert_ne!(keypairs[0], keypairs[1]);
+        assert_ne!(Keypair::generate_with_rng(&mut seeded_rng(43)), keypairs[0]);
+    }
+
+    #[test]
+    fn test_derive_child() {
+        let parent = Keypair::new_from_array(std::array::from_fn(|i| i as u8));
+        assert_eq!(
+            parent.pubkey().to_string(),
+            "FAe4sisG95oZ42w7buUn5qEE4TAnfTTFPiguZUHmhiF"
+        );
+        let child = parent.derive_child(b"market-maker/0");
+        assert_eq!(
+            child.pubkey().to_string(),
+            "G6aHq7Bu5WrpiJXVqsYx9HTji7SQS5PBkCwMQhTvTFLY"
+        );
+        assert_eq!(parent.derive_child(b"market-maker/0"), child);
+        assert_ne!(parent.derive_child(b"market-maker/1"), child);
+        assert_ne!(parent.derive_child(b""), parent);
+    }
+
+    #[test]
+    fn test_keypair_debug_hides_secret() {
+        let keypair = Keypair::new();
+        let debug = format!("{keypair:?}");
+        assert_eq!(debug, format!("Keypair({:?})", keypair.pubkey()));
+        assert!(!debug.contains(&format!("{:?}", keypair.secret_bytes())));
+    }
+
     #[test]
     fn test_keypair_from_seed_phrase_and_passphrase() {
         let mnemonic = Mnemonic::new(MnemonicType::Words12, Language::English);
@@ -0,0 +1,114 @@
+//! Keypairs kept in memory that is never swapped to disk.
+
+use {
+    crate::Keypair,
+    solana_signer::Signer,
+    std::{
+        alloc::{self, Layout},
+        fmt, io, mem,
+        ops::Deref,
+        ptr::{self, NonNull},
+    },
+};
+
+/// A [`Keypair`] in heap memory locked with `mlock(2)`, so that its secret
+/// key is never written to swap.
+///
+/// Each keypair gets page-aligned memory of its own, since locks apply to
+/// whole pages and unlocking one keypair must not unlock another. The secret
+/// key is zeroed before the memory is unlocked and freed.
+pub struct LockedKeypair {
+    keypair: NonNull<Keypair>,
+    layout: Layout,
+}
+
+// SAFETY: `LockedKeypair` owns its allocation like a `Box<Keypair>`.
+unsafe impl Send for LockedKeypair {}
+unsafe impl Sync for LockedKeypair {}
+
+impl LockedKeypair {
+    /// Move `keypair` into locked memory.
+    ///
+    /// The location `keypair` is moved from is not zeroed, so this is best
+    /// called on a freshly created keypair. Fails if the process may not lock
+    /// more memory, see `RLIMIT_MEMLOCK`.
+    pub fn new(keypair: Keypair) -> io::Result<Self> {
+        let page_size = match unsafe { libc::sysconf(libc::_SC_PAGESIZE) } {
+            size if size > 0 => size as usize,
+            _ => return Err(io::Error::last_os_error()),
+        };
+        let size = mem::size_of::<Keypair>()
+            .checked_next_multiple_of(page_size)
+            .ok_or(io::ErrorKind::OutOfMemory)?;
+        let layout = Layout::from_size_align(size, page_size)
+            .map_err(|_| io::Error::from(io::ErrorKind::OutOfMemory))?;
+        let Some(ptr) = NonNull::new(unsafe { alloc::alloc_zeroed(layout) }) else {
+            alloc::handle_alloc_error(layout);
+        };
+        if unsafe { libc::mlock(ptr.as_ptr() as *const libc::c_void, size) } != 0 {
+            let err = io::Error::last_os_error();
+            unsafe { alloc::dealloc(ptr.as_ptr(), layout) };
+            return Err(err);
+        }
+        let keypair_ptr = ptr.cast::<Keypair>();
+        unsafe { ptr::write(keypair_ptr.as_ptr(), keypair) };
+        Ok(Self {
+            keypair: keypair_ptr,
+            layout,
+        })
+    }
+}
+
+impl Drop for LockedKeypair {
+    fn drop(&mut self) {
+        let ptr = self.keypair.as_ptr();
+        unsafe {
+            // zeroes the secret key
+            ptr::drop_in_place(ptr);
+            libc::munlock(ptr as *const libc::c_void, self.layout.size());
+            alloc::dealloc(ptr as *mut u8, self.layout);
+        }
+    }
+}
+
+impl Deref for LockedKeypair {
+    type Target = Keypair;
+
+    fn deref(&self) -> &Keypair {
+        unsafe { self.keypair.as_ref() }
+    }
+}
+
+impl fmt::Debug for LockedKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("LockedKeypair")
+            .field(&self.pubkey())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locked_keypair() {
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey();
+        let locked = LockedKeypair::new(keypair).unwrap();
+        assert_eq!(locked.pubkey(), pubkey);
+        let signature = locked.sign_message(b"hello");
+        assert!(signature.verify(pubkey.as_ref(), b"hello"));
+        assert_eq!(format!("{locked:?}"), format!("LockedKeypair({pubkey:?})"));
+    }
+
+    #[test]
+    fn test_locked_keypairs_do_not_share_pages() {
+        let first = LockedKeypair::new(Keypair::new()).unwrap();
+        let second = LockedKeypair::new(Keypair::new()).unwrap();
+        let page_size = first.layout.align();
+        let page = |locked: &LockedKeypair| locked.keypair.as_ptr() as usize / page_size;
+        assert_eq!(first.keypair.as_ptr() as usize % page_size, 0);
+        assert_ne!(page(&first), page(&second));
+    }
+}
@@ -0,0 +1,151 @@
+//! [BIP-39] mnemonic phrases and the [BIP-44] derivation conventions of
+//! common wallets.
+//!
+//! A mnemonic phrase and an optional passphrase are stretched into a 64-byte
+//! seed, from which keypairs are derived along a BIP-44 path with hardened
+//! [SLIP-10] derivation. Wallets disagree on which path holds the keypair of
+//! account `n`, so recovering a wallet requires knowing which one created it:
+//!
+//! * [`DerivationConvention::Ledger`]: `m/44'/501'/n'`, used by Ledger Live.
+//! * [`DerivationConvention::Phantom`]: `m/44'/501'/n'/0'`, used by Phantom,
+//!   Solflare and Backpack.
+//!
+//! Without a derivation path, [`keypair_from_mnemonic`] derives the keypair
+//! at `m/44'/501'`. Note that `solana-keygen` by default skips derivation
+//! altogether and uses the first 32 bytes of the seed, which is what
+//! [`keypair_from_seed_phrase_and_passphrase`] does.
+//!
+//! [BIP-39]: https://github.com/bitcoin/bips/blob/master/bip-0039.mediawiki
+//! [BIP-44]: https://github.com/bitcoin/bips/blob/master/bip-0044.mediawiki
+//! [SLIP-10]: https://github.com/satoshilabs/slips/blob/master/slip-0010.md
+//! [`keypair_from_seed_phrase_and_passphrase`]: crate::keypair_from_seed_phrase_and_passphrase
+
+use {
+    crate::{seed_derivable::keypair_from_seed_and_derivation_path, Keypair},
+    bip39::{Language, Mnemonic, MnemonicType, Seed},
+    solana_derivation_path::DerivationPath,
+    std::error,
+};
+
+/// Which BIP-44 path a wallet derives the keypair of each account from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DerivationConvention {
+    /// `m/44'/501'/account'`
+    Ledger,
+    /// `m/44'/501'/account'/0'`
+    Phantom,
+}
+
+impl DerivationConvention {
+    /// The derivation path of the keypair of `account`.
+    pub fn derivation_path(&self, account: u32) -> DerivationPath {
+        match self {
+            Self::Ledger => DerivationPath::new_bip44(Some(account), None),
+            Self::Phantom => DerivationPath::new_bip44(Some(account), Some(0)),
+        }
+    }
+}
+
+/// Generate a random English mnemonic phrase of 12, 15, 18, 21 or 24 words.
+pub fn generate_mnemonic_phrase(word_count: usize) -> Result<String, Box<dyn error::Error>> {
+    let mnemonic_type = MnemonicType::for_word_count(word_count)?;
+    Ok(Mnemonic::new(mnemonic_type, Language::English).into_phrase())
+}
+
+/// Derive the keypair at `derivation_path` from an English mnemonic phrase
+/// and passphrase, defaulting to `m/44'/501'`.
+///
+/// Unlike [`keypair_from_seed_phrase_and_passphrase`], the words and the
+/// checksum of the phrase are validated.
+///
+/// [`keypair_from_seed_phrase_and_passphrase`]: crate::keypair_from_seed_phrase_and_passphrase
+pub fn keypair_from_mnemonic(
+    phrase: &str,
+    passphrase: &str,
+    derivation_path: Option<DerivationPath>,
+) -> Result<Keypair, Box<dyn error::Error>> {
+    let mnemonic = Mnemonic::from_phrase(phrase, Language::English)?;
+    let seed = Seed::new(&mnemonic, passphrase);
+    keypair_from_seed_and_derivation_path(seed.as_bytes(), derivation_path)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::keypair_from_seed_phrase_and_passphrase,
+        solana_pubkey::{pubkey, Pubkey},
+        solana_signer::Signer,
+    };
+
+    const PHRASE: &str = "abandon abandon abandon abandon abandon abandon abandon abandon abandon \
+                          abandon abandon about";
+
+    fn derive(passphrase: &str, derivation_path: Option<DerivationPath>) -> Pubkey {
+        keypair_from_mnemonic(PHRASE, passphrase, derivation_path)
+            .unwrap()
+            .pubkey()
+    }
+
+    #[test]
+    fn test_keypair_from_mnemonic_vectors() {
+        use DerivationConvention::{Ledger, Phantom};
+
+        assert_eq!(
+            derive("", None),
+            pubkey!("D2PPQSYFe83nDzk96FqGumVU8JA7J8vj2Rhjc2oXzEi5")
+        );
+        assert_eq!(
+            derive("", Some(Ledger.derivation_path(0))),
+            pubkey!("GjJyeC1r2RgkuoCWMyPYkCWSGSGLcz266EaAkLA27AhL")
+        );
+        assert_eq!(
+            derive("", Some(Ledger.derivation_path(1))),
+            pubkey!("ANf3TEKFL6jPWjzkndo4CbnNdUNkBk4KHPggJs2nu8Xi")
+        );
+        assert_eq!(
+            derive("", Some(Phantom.derivation_path(0))),
+            pubkey!("HAgk14JpMQLgt6rVgv7cBQFJWFto5Dqxi472uT3DKpqk")
+        );
+        assert_eq!(
+            derive("", Some(Phantom.derivation_path(1))),
+            pubkey!("Hh8QwFUA6MtVu1qAoq12ucvFHNwCcVTV7hpWjeY1Hztb")
+        );
+        assert_eq!(
+            derive("solana", Some(Ledger.derivation_path(0))),
+            pubkey!("6tYP3r7ejdGByn6ygsRUxnsbKWAAuHerNEuFSohV2C4B")
+        );
+        assert_eq!(
+            derive("solana", Some(Phantom.derivation_path(0))),
+            pubkey!("FZzsB6QzpPvYD6qN6vPwwxmXpKg642nWzcf6gR1Hegs1")
+        );
+
+        // solana-keygen's default, without derivation
+        assert_eq!(
+            keypair_from_seed_phrase_and_passphrase(PHRASE, "")
+                .unwrap()
+                .pubkey(),
+            pubkey!("EHqmfkN89RJ7Y33CXM6uCzhVeuywHoJXZZLszBHHZy7o")
+        );
+    }
+
+    #[test]
+    fn test_keypair_from_mnemonic_invalid_phrase() {
+        // bad checksum
+        let phrase = ["abandon"; 12].join(" ");
+        assert!(keypair_from_mnemonic(&phrase, "", None).is_err());
+        // unknown word
+        let phrase = PHRASE.replace("about", "solana");
+        assert!(keypair_from_mnemonic(&phrase, "", None).is_err());
+    }
+
+    #[test]
+    fn test_generate_mnemonic_phrase() {
+        for word_count in [12, 15, 18, 21, 24] {
+            let phrase = generate_mnemonic_phrase(word_count).unwrap();
+            assert_eq!(phrase.split(' ').count(), word_count);
+            assert!(keypair_from_mnemonic(&phrase, "passphrase", None).is_ok());
+        }
+        assert!(generate_mnemonic_phrase(13).is_err());
+    }
+}
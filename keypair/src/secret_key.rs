@@ -0,0 +1,106 @@
+//! A secret key held without its keypair.
+
+use {
+    crate::Keypair,
+    rand0_7::{rngs::OsRng, RngCore},
+    rand_core::CryptoRngCore,
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+    std::fmt,
+    zeroize::Zeroize,
+};
+
+/// An Ed25519 secret key, whose public half is a [`Pubkey`].
+///
+/// The bytes are zeroed when the `SecretKey` is dropped. It is not `Clone`
+/// and `Debug` does not print it; use [`SecretKey::insecure_clone`] to copy
+/// it explicitly.
+pub struct SecretKey([u8; Keypair::SECRET_KEY_LENGTH]);
+
+impl SecretKey {
+    /// Constructs a new, random `SecretKey` using `OsRng`
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let mut secret_key = Self([0u8; Keypair::SECRET_KEY_LENGTH]);
+        OsRng.fill_bytes(&mut secret_key.0);
+        secret_key
+    }
+
+    /// Constructs a new `SecretKey` drawn from `rng`
+    pub fn generate_with_rng(rng: &mut impl CryptoRngCore) -> Self {
+        let mut secret_key = Self([0u8; Keypair::SECRET_KEY_LENGTH]);
+        rng.fill_bytes(&mut secret_key.0);
+        secret_key
+    }
+
+    /// Takes ownership of secret key bytes
+    pub fn from_bytes(bytes: [u8; Keypair::SECRET_KEY_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    /// Gets the secret key bytes
+    pub fn as_bytes(&self) -> &[u8; Keypair::SECRET_KEY_LENGTH] {
+        &self.0
+    }
+
+    /// Derives the public key
+    pub fn pubkey(&self) -> Pubkey {
+        Keypair::from_secret_key(self).pubkey()
+    }
+
+    /// Makes a second copy of the secret key
+    ///
+    /// See [`Keypair::insecure_clone`] for why `Clone` is not implemented.
+    pub fn insecure_clone(&self) -> Self {
+        Self(self.0)
+    }
+}
+
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+impl From<&SecretKey> for Keypair {
+    fn from(secret_key: &SecretKey) -> Self {
+        Self::from_secret_key(secret_key)
+    }
+}
+
+impl From<SecretKey> for Keypair {
+    fn from(secret_key: SecretKey) -> Self {
+        Self::from_secret_key(&secret_key)
+    }
+}
+
+impl From<Keypair> for SecretKey {
+    fn from(keypair: Keypair) -> Self {
+        keypair.to_secret_key()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secret_key() {
+        let secret_key = SecretKey::new();
+        let keypair = Keypair::from_secret_key(&secret_key);
+        assert_eq!(secret_key.pubkey(), keypair.pubkey());
+        assert_eq!(keypair.secret_bytes(), secret_key.as_bytes());
+        assert_eq!(keypair.to_secret_key().as_bytes(), secret_key.as_bytes());
+
+        let clone = secret_key.insecure_clone();
+        assert_eq!(Keypair::from(clone).pubkey(), keypair.pubkey());
+        assert_eq!(SecretKey::from(keypair).as_bytes(), secret_key.as_bytes());
+        assert_eq!(format!("{secret_key:?}"), "SecretKey(..)");
+    }
+}
@@ -30,6 +30,9 @@ impl SeedDerivable for Keypair {
 
 /// Generates a Keypair using Bip32 Hierarchical Derivation if derivation-path is provided;
 /// otherwise generates the base Bip44 Solana keypair from the seed
+///
+/// See `mnemonic::DerivationConvention` (behind the `bip39` feature) for the
+/// paths wallets derive their accounts from.
 pub fn keypair_from_seed_and_derivation_path(
     seed: &[u8],
     derivation_path: Option<DerivationPath>,
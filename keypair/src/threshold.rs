@@ -0,0 +1,237 @@
+//! t-of-n threshold signing with [FROST].
+//!
+//! A threshold key is split into `n` shares, any `t` of which produce an
+//! ordinary ed25519 signature, verifiable against an ordinary [`Pubkey`],
+//! without the key ever being reassembled. The shares are created either by
+//! a trusted dealer with [`generate_with_dealer`], or by the participants
+//! themselves in a distributed key generation ceremony with the [`dkg`]
+//! module, whose third part yields each participant's [`KeyPackage`] and the
+//! shared [`PublicKeyPackage`].
+//!
+//! Signing takes two rounds: each participant commits to fresh nonces, and
+//! then signs the message together with everyone's commitments. The
+//! [`ThresholdSigner`] coordinates the rounds over a set of
+//! [`ThresholdParticipant`]s, which may hold their share locally, like
+//! [`LocalParticipant`], or forward the rounds to another machine.
+//!
+//! [FROST]: https://datatracker.ietf.org/doc/rfc9591/
+
+pub use frost_ed25519::{
+    keys::{dkg, KeyPackage, PublicKeyPackage},
+    round1::SigningCommitments,
+    round2::SignatureShare,
+    Identifier, SigningPackage,
+};
+use {
+    frost_ed25519::{
+        keys::IdentifierList,
+        round1::{self, SigningNonces},
+        round2,
+    },
+    rand_core::OsRng,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_signer::{Signer, SignerError},
+    std::{collections::BTreeMap, sync::Mutex},
+};
+
+fn protocol_error(err: frost_ed25519::Error) -> SignerError {
+    SignerError::Protocol(err.to_string())
+}
+
+/// Split a new random threshold key into `max_signers` shares, any
+/// `min_signers` of which can sign.
+///
+/// Whoever runs this learns the whole key; use the [`dkg`] module to avoid
+/// trusting a dealer.
+pub fn generate_with_dealer(
+    max_signers: u16,
+    min_signers: u16,
+) -> Result<(Vec<LocalParticipant>, PublicKeyPackage), SignerError> {
+    let (shares, public_key_package) = frost_ed25519::keys::generate_with_dealer(
+        max_signers,
+        min_signers,
+        IdentifierList::Default,
+        OsRng,
+    )
+    .map_err(protocol_error)?;
+    let participants = shares
+        .into_values()
+        .map(|share| KeyPackage::try_from(share).map(LocalParticipant::new))
+        .collect::<Result<_, _>>()
+        .map_err(protocol_error)?;
+    Ok((participants, public_key_package))
+}
+
+/// A holder of one share of a threshold key.
+pub trait ThresholdParticipant {
+    fn identifier(&self) -> Identifier;
+
+    /// Round one: commit to fresh nonces for the next signature.
+    fn commit(&self) -> Result<SigningCommitments, SignerError>;
+
+    /// Round two: sign the message of `signing_package` with the nonces
+    /// committed to in round one.
+    fn sign(&self, signing_package: &SigningPackage) -> Result<SignatureShare, SignerError>;
+}
+
+/// A participant holding its share in process memory.
+pub struct LocalParticipant {
+    key_package: KeyPackage,
+    nonces: Mutex<Option<SigningNonces>>,
+}
+
+impl LocalParticipant {
+    pub fn new(key_package: KeyPackage) -> Self {
+        Self {
+            key_package,
+            nonces: Mutex::default(),
+        }
+    }
+}
+
+impl ThresholdParticipant for LocalParticipant {
+    fn identifier(&self) -> Identifier {
+        *self.key_package.identifier()
+    }
+
+    fn commit(&self) -> Result<SigningCommitments, SignerError> {
+        let (nonces, commitments) = round1::commit(self.key_package.signing_share(), &mut OsRng);
+        *self.nonces.lock().unwrap() = Some(nonces);
+        Ok(commitments)
+    }
+
+    fn sign(&self, signing_package: &SigningPackage) -> Result<SignatureShare, SignerError> {
+        // nonces must never be used twice
+        let nonces = self.nonces.lock().unwrap().take().ok_or_else(|| {
+            SignerError::Protocol("signing requested before commitment".to_string())
+        })?;
+        round2::sign(signing_package, &nonces, &self.key_package).map_err(protocol_error)
+    }
+}
+
+/// A [`Signer`] for a threshold key, coordinating the signing rounds of its
+/// participants.
+pub struct ThresholdSigner {
+    pubkey: Pubkey,
+    public_key_package: PublicKeyPackage,
+    participants: Vec<Box<dyn ThresholdParticipant + Send + Sync>>,
+}
+
+impl ThresholdSigner {
+    /// Sign with `participants`, of which there must be at least the
+    /// threshold of the key.
+    pub fn new(
+        public_key_package: PublicKeyPackage,
+        participants: Vec<Box<dyn ThresholdParticipant + Send + Sync>>,
+    ) -> Result<Self, SignerError> {
+        let verifying_key = public_key_package
+            .verifying_key()
+            .serialize()
+            .map_err(protocol_error)?;
+        let pubkey = Pubkey::try_from(verifying_key.as_slice())
+            .map_err(|_| SignerError::InvalidInput("invalid verifying key".to_string()))?;
+        Ok(Self {
+            pubkey,
+            public_key_package,
+            participants,
+        })
+    }
+}
+
+impl Signer for ThresholdSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn sign_message(&self, message: &[u8]) -> Signature {
+        self.try_sign_message(message).unwrap_or_default()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let commitments = self
+            .participants
+            .iter()
+            .map(|participant| Ok((participant.identifier(), participant.commit()?)))
+            .collect::<Result<BTreeMap<_, _>, SignerError>>()?;
+        let signing_package = SigningPackage::new(commitments, message);
+        let signature_shares = self
+            .participants
+            .iter()
+            .map(|participant| {
+                Ok((
+                    participant.identifier(),
+                    participant.sign(&signing_package)?,
+                ))
+            })
+            .collect::<Result<BTreeMap<_, _>, SignerError>>()?;
+        let signature = frost_ed25519::aggregate(
+            &signing_package,
+            &signature_shares,
+            &self.public_key_package,
+        )
+        .map_err(|err| match err {
+            frost_ed25519::Error::IncorrectNumberOfShares => SignerError::NotEnoughSigners,
+            err => protocol_error(err),
+        })?;
+        let signature = signature.serialize().map_err(protocol_error)?;
+        Signature::try_from(signature.as_slice())
+            .map_err(|_| SignerError::Protocol("invalid aggregate signature".to_string()))
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn boxed(
+        participants: Vec<LocalParticipant>,
+    ) -> Vec<Box<dyn ThresholdParticipant + Send + Sync>> {
+        participants
+            .into_iter()
+            .map(|participant| Box::new(participant) as Box<_>)
+            .collect()
+    }
+
+    #[test]
+    fn test_threshold_signer() {
+        let (mut participants, public_key_package) = generate_with_dealer(5, 3).unwrap();
+        participants.truncate(3);
+        let signer = ThresholdSigner::new(public_key_package, boxed(participants)).unwrap();
+
+        let message = b"threshold";
+        let signature = signer.try_sign_message(message).unwrap();
+        assert!(signature.verify(signer.pubkey().as_ref(), message));
+        // fresh nonces every time
+        let signature = signer.try_sign_message(message).unwrap();
+        assert!(signature.verify(signer.pubkey().as_ref(), message));
+    }
+
+    #[test]
+    fn test_threshold_signer_below_threshold() {
+        let (mut participants, public_key_package) = generate_with_dealer(5, 3).unwrap();
+        participants.truncate(2);
+        let signer = ThresholdSigner::new(public_key_package, boxed(participants)).unwrap();
+        assert!(signer.try_sign_message(b"threshold").is_err());
+    }
+
+    #[test]
+    fn test_local_participant_requires_commitment() {
+        let (participants, _) = generate_with_dealer(2, 2).unwrap();
+        let commitments = participants
+            .iter()
+            .map(|participant| (participant.identifier(), participant.commit().unwrap()))
+            .collect();
+        let signing_package = SigningPackage::new(commitments, b"message");
+        assert!(participants[0].sign(&signing_package).is_ok());
+        assert!(participants[0].sign(&signing_package).is_err());
+    }
+}
@@ -2,7 +2,13 @@ use {
     crate::{compiled_instruction::CompiledInstruction, v0::LoadedAddresses, CompileError},
     solana_instruction::Instruction,
     solana_pubkey::Pubkey,
-    std::{collections::BTreeMap, iter::zip, ops::Index},
+    std::{
+        borrow::Cow,
+        collections::{BTreeMap, HashMap},
+        iter::zip,
+        ops::Index,
+        sync::OnceLock,
+    },
 };
 
 /// Collection of static and dynamically loaded keys used to load accounts
@@ -11,8 +17,26 @@ use {
 pub struct AccountKeys<'a> {
     static_keys: &'a [Pubkey],
     dynamic_keys: Option<&'a LoadedAddresses>,
+    /// Position of each key, built on the first call to `position_of`
+    positions: Cow<'a, AccountKeyPositions>,
 }
 
+/// Position of each account key of a message, built on the first call to
+/// [`AccountKeys::position_of`].
+///
+/// The index is derived from the keys, so it is ignored when comparing
+/// messages.
+#[derive(Debug, Clone, Default)]
+pub struct AccountKeyPositions(OnceLock<HashMap<Pubkey, u8>>);
+
+impl PartialEq for AccountKeyPositions {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for AccountKeyPositions {}
+
 impl Index<usize> for AccountKeys<'_> {
     type Output = Pubkey;
     #[inline]
@@ -26,6 +50,18 @@ impl<'a> AccountKeys<'a> {
         Self {
             static_keys,
             dynamic_keys,
+            positions: Cow::default(),
+        }
+    }
+
+    /// Use `positions` as the position index of these keys, so that it is
+    /// built once and shared by every `AccountKeys` of the same message.
+    ///
+    /// `positions` must only ever be used with the same keys.
+    pub fn with_positions(self, positions: &'a AccountKeyPositions) -> Self {
+        Self {
+            positions: Cow::Borrowed(positions),
+            ..self
         }
     }
 
@@ -84,6 +120,26 @@ impl<'a> AccountKeys<'a> {
         self.key_segment_iter().flatten()
     }
 
+    /// Returns the index of the first occurrence of `key`, or `None` if the key
+    /// is missing or is only present at an index that doesn't fit in a `u8`.
+    ///
+    /// The first call builds a hash index of all keys, after which lookups take
+    /// constant time. The keys of a sanitized message share one index, see
+    /// [`AccountKeys::with_positions`].
+    pub fn position_of(&self, key: &Pubkey) -> Option<u8> {
+        self.positions
+            .0
+            .get_or_init(|| {
+                let mut positions = HashMap::with_capacity(self.len().min(256));
+                for (index, key) in (0..=u8::MAX).zip(self.iter()) {
+                    positions.entry(*key).or_insert(index);
+                }
+                positions
+            })
+            .get(key)
+            .copied()
+    }
+
     /// Compile instructions using the order of account keys to determine
     /// compiled instruction account indexes.
     ///
@@ -106,6 +162,9 @@ impl<'a> AccountKeys<'a> {
     ///
     /// Compilation will fail if any `instructions` use account keys which are located
     /// at an index which cannot be cast to a `u8` without overflow.
+    ///
+    /// Compilation will fail if any `instructions` invoke a program whose id is
+    /// only present among the dynamically loaded keys.
     pub fn try_compile_instructions(
         &self,
         instructions: &[Instruction],
@@ -132,8 +191,13 @@ impl<'a> AccountKeys<'a> {
                     .map(|account_meta| get_account_index(&account_meta.pubkey))
                     .collect::<Result<Vec<u8>, CompileError>>()?;
 
+                let program_id_index = get_account_index(&ix.program_id)?;
+                if usize::from(program_id_index) >= self.static_keys.len() {
+                    return Err(CompileError::ProgramIdNotStatic(ix.program_id));
+                }
+
                 Ok(CompiledInstruction {
-                    program_id_index: get_account_index(&ix.program_id)?,
+                    program_id_index,
                     data: ix.data.clone(),
                     accounts,
                 })
@@ -265,6 +329,50 @@ mod tests {
         assert_eq!(account_keys.get(5), Some(&keys[5]));
     }
 
+    #[test]
+    fn test_position_of() {
+        let keys = test_account_keys();
+
+        let static_keys = vec![keys[0], keys[1], keys[2]];
+        let dynamic_keys = LoadedAddresses {
+            writable: vec![keys[3], keys[1]],
+            readonly: vec![keys[4]],
+        };
+        let account_keys = AccountKeys::new(&static_keys, Some(&dynamic_keys));
+
+        assert_eq!(account_keys.position_of(&keys[0]), Some(0));
+        assert_eq!(account_keys.position_of(&keys[1]), Some(1));
+        assert_eq!(account_keys.position_of(&keys[3]), Some(3));
+        assert_eq!(account_keys.position_of(&keys[4]), Some(5));
+        assert_eq!(account_keys.position_of(&keys[5]), None);
+    }
+
+    #[test]
+    fn test_position_of_shared_positions() {
+        let keys = test_account_keys();
+        let static_keys = vec![keys[0], keys[1], keys[2]];
+        let positions = AccountKeyPositions::default();
+
+        let account_keys = AccountKeys::new(&static_keys, None).with_positions(&positions);
+        assert_eq!(account_keys.position_of(&keys[2]), Some(2));
+        assert_eq!(positions.0.get().map(HashMap::len), Some(3));
+
+        // a new `AccountKeys` reuses the index built by the first one
+        let account_keys = AccountKeys::new(&static_keys, None).with_positions(&positions);
+        assert_eq!(account_keys.positions.0.get().map(HashMap::len), Some(3));
+        assert_eq!(account_keys.position_of(&keys[1]), Some(1));
+        assert_eq!(account_keys.position_of(&keys[3]), None);
+    }
+
+    #[test]
+    fn test_position_of_with_too_many_keys() {
+        let static_keys: Vec<Pubkey> = (0..257).map(|_| Pubkey::new_unique()).collect();
+        let account_keys = AccountKeys::new(&static_keys, None);
+
+        assert_eq!(account_keys.position_of(&static_keys[255]), Some(255));
+        assert_eq!(account_keys.position_of(&static_keys[256]), None);
+    }
+
     #[test]
     fn test_try_compile_instructions() {
         let keys = test_account_keys();
@@ -325,6 +433,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_compile_instructions_with_loaded_program_id() {
+        let keys = test_account_keys();
+
+        let static_keys = vec![keys[0], keys[1]];
+        let dynamic_keys = LoadedAddresses {
+            writable: vec![keys[2]],
+            readonly: vec![keys[3]],
+        };
+        let account_keys = AccountKeys::new(&static_keys, Some(&dynamic_keys));
+
+        let instruction = Instruction {
+            program_id: keys[3],
+            accounts: vec![AccountMeta::new(keys[0], true)],
+            data: vec![],
+        };
+        assert_eq!(
+            account_keys.try_compile_instructions(&[instruction]),
+            Err(CompileError::ProgramIdNotStatic(keys[3]))
+        );
+    }
+
     #[test]
     fn test_try_compile_instructions_with_too_many_account_keys() {
         const MAX_LENGTH_WITHOUT_OVERFLOW: usize = u8::MAX as usize + 1;
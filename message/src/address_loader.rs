@@ -1,6 +1,11 @@
 use {
     crate::v0::{LoadedAddresses, MessageAddressTableLookup},
+    solana_pubkey::Pubkey,
     solana_transaction_error::AddressLoaderError,
+    std::{
+        collections::HashMap,
+        sync::{Arc, RwLock},
+    },
 };
 
 pub trait AddressLoader: Clone {
@@ -27,3 +32,299 @@ impl AddressLoader for SimpleAddressLoader {
         }
     }
 }
+
+/// Contents of an address lookup table as seen by a [`CachingAddressLoader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LookupTableContents {
+    /// Addresses stored in the table
+    pub addresses: Vec<Pubkey>,
+    /// Slot at which the table was deactivated, or `u64::MAX` if it is active
+    pub deactivation_slot: u64,
+}
+
+/// Source of address lookup table contents, typically backed by an accounts
+/// database or an RPC client.
+pub trait LookupTableSource {
+    fn load_table(&self, table_key: &Pubkey) -> Result<LookupTableContents, AddressLoaderError>;
+}
+
+/// An [`AddressLoader`] that caches the contents of lookup tables loaded from
+/// a [`LookupTableSource`].
+///
+/// Cached tables are identified by their address and deactivation slot:
+/// [`Self::update_deactivation_slot`] evicts a table whose deactivation slot
+/// changed, and [`Self::evict_deactivated`] evicts every table deactivated at
+/// or before a slot. Tables can only grow while active, so a lookup index past
+/// the end of a cached table reloads it from the source.
+///
+/// Clones share the same cache.
+pub struct CachingAddressLoader<S> {
+    source: Arc<S>,
+    cache: Arc<RwLock<HashMap<Pubkey, Arc<LookupTableContents>>>>,
+}
+
+impl<S> Clone for CachingAddressLoader<S> {
+    fn clone(&self) -> Self {
+        Self {
+            source: Arc::clone(&self.source),
+            cache: Arc::clone(&self.cache),
+        }
+    }
+}
+
+impl<S: LookupTableSource> CachingAddressLoader<S> {
+    pub fn new(source: S) -> Self {
+        Self {
+            source: Arc::new(source),
+            cache: Arc::default(),
+        }
+    }
+
+    /// Number of tables in the cache.
+    pub fn len(&self) -> usize {
+        self.cache.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Evict the table at `table_key`.
+    pub fn invalidate(&self, table_key: &Pubkey) {
+        self.cache.write().unwrap().remove(table_key);
+    }
+
+    /// Evict every table.
+    pub fn clear(&self) {
+        self.cache.write().unwrap().clear();
+    }
+
+    /// Record the current deactivation slot of the table at `table_key`,
+    /// evicting it if its cached contents were loaded under another one.
+    pub fn update_deactivation_slot(&self, table_key: &Pubkey, deactivation_slot: u64) {
+        let mut cache = self.cache.write().unwrap();
+        if cache
+            .get(table_key)
+            .is_some_and(|table| table.deactivation_slot != deactivation_slot)
+        {
+            cache.remove(table_key);
+        }
+    }
+
+    /// Evict every table deactivated at or before `slot`.
+    pub fn evict_deactivated(&self, slot: u64) {
+        self.cache
+            .write()
+            .unwrap()
+            .retain(|_, table| table.deactivation_slot > slot);
+    }
+
+    fn get_table(
+        &self,
+        table_key: &Pubkey,
+        min_len: usize,
+    ) -> Result<Arc<LookupTableContents>, AddressLoaderError> {
+        if let Some(table) = self.cache.read().unwrap().get(table_key) {
+            if table.addresses.len() >= min_len {
+                return Ok(Arc::clone(table));
+            }
+        }
+        let table = Arc::new(self.source.load_table(table_key)?);
+        self.cache
+            .write()
+            .unwrap()
+            .insert(*table_key, Arc::clone(&table));
+        Ok(table)
+    }
+}
+
+impl<S: LookupTableSource> AddressLoader for CachingAddressLoader<S> {
+    fn load_addresses(
+        self,
+        lookups: &[MessageAddressTableLookup],
+    ) -> Result<LoadedAddresses, AddressLoaderError> {
+        let mut loaded_addresses = LoadedAddresses::default();
+        for lookup in lookups {
+            let min_len = lookup
+                .writable_indexes
+                .iter()
+                .chain(&lookup.readonly_indexes)
+                .map(|index| usize::from(*index).saturating_add(1))
+                .max()
+                .unwrap_or(0);
+            let table = self.get_table(&lookup.account_key, min_len)?;
+            let address = |index: &u8| {
+                table.addresses.get(usize::from(*index)).copied().ok_or(
+                    AddressLoaderError::LookupIndexOutOfRange {
+                        lookup_table_key: lookup.account_key,
+                        index: *index,
+                        table_len: table.addresses.len(),
+                    },
+                )
+            };
+            for index in &lookup.writable_indexes {
+                loaded_addresses.writable.push(address(index)?);
+            }
+            for index in &lookup.readonly_indexes {
+                loaded_addresses.readonly.push(address(index)?);
+            }
+        }
+        Ok(loaded_addresses)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std::sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[derive(Default)]
+    struct TestSource {
+        tables: RwLock<HashMap<Pubkey, LookupTableContents>>,
+        loads: AtomicUsize,
+    }
+
+    impl LookupTableSource for TestSource {
+        fn load_table(
+            &self,
+            table_key: &Pubkey,
+        ) -> Result<LookupTableContents, AddressLoaderError> {
+            self.loads.fetch_add(1, Ordering::Relaxed);
+            self.tables
+                .read()
+                .unwrap()
+                .get(table_key)
+                .cloned()
+                .ok_or(AddressLoaderError::LookupTableAccountNotFound)
+        }
+    }
+
+    impl LookupTableSource for Arc<TestSource> {
+        fn load_table(
+            &self,
+            table_key: &Pubkey,
+        ) -> Result<LookupTableContents, AddressLoaderError> {
+            self.as_ref().load_table(table_key)
+        }
+    }
+
+    fn lookup(account_key: Pubkey, writable: &[u8], readonly: &[u8]) -> MessageAddressTableLookup {
+        MessageAddressTableLookup {
+            account_key,
+            writable_indexes: writable.to_vec(),
+            readonly_indexes: readonly.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_caching_address_loader() {
+        let table_key = Pubkey::new_unique();
+        let addresses: Vec<_> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let source = Arc::new(TestSource::default());
+        source.tables.write().unwrap().insert(
+            table_key,
+            LookupTableContents {
+                addresses: addresses.clone(),
+                deactivation_slot: u64::MAX,
+            },
+        );
+        let loader = CachingAddressLoader::new(Arc::clone(&source));
+
+        let lookups = [lookup(table_key, &[2], &[0, 1])];
+        let expected = LoadedAddresses {
+            writable: vec![addresses[2]],
+            readonly: vec![addresses[0], addresses[1]],
+        };
+        assert_eq!(
+            loader.clone().load_addresses(&lookups),
+            Ok(expected.clone())
+        );
+        assert_eq!(loader.clone().load_addresses(&lookups), Ok(expected));
+        assert_eq!(source.loads.load(Ordering::Relaxed), 1);
+        assert_eq!(loader.len(), 1);
+
+        // An index past the end of the cached table reloads it
+        let new_address = Pubkey::new_unique();
+        source
+            .tables
+            .write()
+            .unwrap()
+            .get_mut(&table_key)
+            .unwrap()
+            .addresses
+            .push(new_address);
+        assert_eq!(
+            loader
+                .clone()
+                .load_addresses(&[lookup(table_key, &[], &[3])]),
+            Ok(LoadedAddresses {
+                writable: vec![],
+                readonly: vec![new_address],
+            })
+        );
+        assert_eq!(source.loads.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            loader
+                .clone()
+                .load_addresses(&[lookup(table_key, &[], &[4])]),
+            Err(AddressLoaderError::LookupIndexOutOfRange {
+                lookup_table_key: table_key,
+                index: 4,
+                table_len: 4,
+            })
+        );
+
+        // Unknown tables are not cached
+        assert_eq!(
+            loader
+                .clone()
+                .load_addresses(&[lookup(Pubkey::new_unique(), &[0], &[])]),
+            Err(AddressLoaderError::LookupTableAccountNotFound)
+        );
+        assert_eq!(loader.len(), 1);
+    }
+
+    #[test]
+    fn test_caching_address_loader_invalidation() {
+        let table_key = Pubkey::new_unique();
+        let source = Arc::new(TestSource::default());
+        source.tables.write().unwrap().insert(
+            table_key,
+            LookupTableContents {
+                addresses: vec![Pubkey::new_unique()],
+                deactivation_slot: u64::MAX,
+            },
+        );
+        let loader = CachingAddressLoader::new(Arc::clone(&source));
+        let lookups = [lookup(table_key, &[0], &[])];
+        loader.clone().load_addresses(&lookups).unwrap();
+
+        loader.update_deactivation_slot(&table_key, u64::MAX);
+        assert_eq!(loader.len(), 1);
+        loader.update_deactivation_slot(&table_key, 10);
+        assert!(loader.is_empty());
+
+        source
+            .tables
+            .write()
+            .unwrap()
+            .get_mut(&table_key)
+            .unwrap()
+            .deactivation_slot = 10;
+        loader.clone().load_addresses(&lookups).unwrap();
+        loader.evict_deactivated(9);
+        assert_eq!(loader.len(), 1);
+        loader.evict_deactivated(10);
+        assert!(loader.is_empty());
+
+        loader.clone().load_addresses(&lookups).unwrap();
+        loader.invalidate(&table_key);
+        assert!(loader.is_empty());
+        loader.clone().load_addresses(&lookups).unwrap();
+        loader.clear();
+        assert!(loader.is_empty());
+        assert_eq!(source.loads.load(Ordering::Relaxed), 4);
+    }
+}
@@ -1,3 +1,5 @@
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "frozen-abi")]
@@ -12,6 +14,11 @@ use {solana_pubkey::Pubkey, solana_sanitize::Sanitize};
 ///
 /// [`Message`]: crate::Message
 #[cfg_attr(feature = "frozen-abi", derive(AbiExample))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(BorshSerialize, BorshDeserialize),
+    borsh(crate = "borsh")
+)]
 #[cfg_attr(
     feature = "serde",
     derive(Deserialize, Serialize),
@@ -1,5 +1,7 @@
 #[cfg(not(target_os = "solana"))]
 use crate::{
+    compiled_instruction::CompiledInstruction,
+    legacy,
     v0::{LoadedAddresses, MessageAddressTableLookup},
     AddressLookupTableAccount,
 };
@@ -8,19 +10,67 @@ use {
     solana_sdk_ids::system_program, std::collections::BTreeMap,
 };
 
-/// A helper struct to collect pubkeys compiled for a set of instructions
+/// The account keys referenced by a set of instructions, deduplicated and
+/// classified by the roles the instructions give them.
+///
+/// This is the intermediate step of compiling a message. It predicts the
+/// index and the roles each key will have in the compiled message: the fee
+/// payer comes first, followed by writable signers, readonly signers,
+/// writable non-signers and readonly non-signers, each sorted by pubkey. A
+/// key takes every role any instruction requests of it.
+///
+/// ```
+/// # use solana_instruction::{AccountMeta, Instruction};
+/// # use solana_message::{CompiledKeys, Message};
+/// # use solana_pubkey::Pubkey;
+/// let payer = Pubkey::new_unique();
+/// let account = Pubkey::new_unique();
+/// let program_id = Pubkey::new_unique();
+/// let instructions = [Instruction::new_with_bytes(
+///     program_id,
+///     &[],
+///     vec![AccountMeta::new(account, false)],
+/// )];
+///
+/// let compiled_keys = CompiledKeys::compile(&instructions, Some(payer));
+/// assert_eq!(compiled_keys.position(&account), Some(1));
+/// assert!(compiled_keys.get_key_meta(&account).unwrap().is_writable);
+///
+/// let message = Message::new(&instructions, Some(&payer));
+/// assert_eq!(message.account_keys[1], account);
+/// ```
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
-pub(crate) struct CompiledKeys {
+pub struct CompiledKeys {
     payer: Option<Pubkey>,
     key_meta_map: BTreeMap<Pubkey, CompiledKeyMeta>,
 }
 
+/// An error compiling instructions into a message.
+///
+/// The enum is non-exhaustive, so that variants reporting more detail can be
+/// added without a breaking change.
 #[cfg_attr(target_os = "solana", allow(dead_code))]
 #[derive(PartialEq, Debug, Eq, Clone)]
+#[non_exhaustive]
 pub enum CompileError {
     AccountIndexOverflow,
+    /// No longer returned by compilation, which reports
+    /// [`CompileError::AddressTableLookupIndexOutOfRange`] instead.
     AddressTableLookupIndexOverflow,
     UnknownInstructionKey(Pubkey),
+    /// An address to load is stored in a lookup table at an index past the
+    /// last one a lookup can refer to.
+    AddressTableLookupIndexOutOfRange {
+        lookup_table_key: Pubkey,
+        address: Pubkey,
+        index: usize,
+    },
+    /// An instruction invokes a program whose id is loaded from a lookup
+    /// table, while program ids must be static account keys.
+    ProgramIdNotStatic(Pubkey),
+    /// An account key is stored in the message and also loaded from an
+    /// address lookup table.
+    DuplicateAccountKey(Pubkey),
 }
 
 impl std::error::Error for CompileError {}
@@ -34,25 +84,45 @@ impl fmt::Display for CompileError {
             CompileError::AddressTableLookupIndexOverflow => {
                 f.write_str("address lookup table index overflowed during compilation")
             }
+            CompileError::AddressTableLookupIndexOutOfRange {
+                lookup_table_key,
+                address,
+                index,
+            } => f.write_fmt(format_args!(
+                "address lookup table index overflowed during compilation: account key \
+                 `{address}` is at index {index} of lookup table `{lookup_table_key}`",
+            )),
             CompileError::UnknownInstructionKey(key) => f.write_fmt(format_args!(
                 "encountered unknown account key `{key}` during instruction compilation",
             )),
+            CompileError::ProgramIdNotStatic(key) => f.write_fmt(format_args!(
+                "program id `{key}` is invoked by an instruction but loaded from an address \
+                 lookup table",
+            )),
+            CompileError::DuplicateAccountKey(key) => f.write_fmt(format_args!(
+                "account key `{key}` is stored in the message and also loaded from an address \
+                 lookup table",
+            )),
         }
     }
 }
 
+/// The roles of a key in a compiled message.
 #[derive(Default, Debug, Clone, PartialEq, Eq)]
-struct CompiledKeyMeta {
-    is_signer: bool,
-    is_writable: bool,
-    is_invoked: bool,
-    is_nonce: bool,
+pub struct CompiledKeyMeta {
+    pub is_signer: bool,
+    pub is_writable: bool,
+    /// Whether an instruction invokes the key as its program.
+    pub is_invoked: bool,
+    /// Whether the key is the nonce account advanced by the first
+    /// instruction.
+    pub is_nonce: bool,
 }
 
 impl CompiledKeys {
     /// Compiles the pubkeys referenced by a list of instructions and organizes by
     /// signer/non-signer and writable/readonly.
-    pub(crate) fn compile(instructions: &[Instruction], payer: Option<Pubkey>) -> Self {
+    pub fn compile(instructions: &[Instruction], payer: Option<Pubkey>) -> Self {
         let mut key_meta_map = BTreeMap::<Pubkey, CompiledKeyMeta>::new();
         for ix in instructions {
             let meta = key_meta_map.entry(ix.program_id).or_default();
@@ -78,76 +148,117 @@ impl CompiledKeys {
         }
     }
 
-    pub(crate) fn try_into_message_components(
-        self,
-    ) -> Result<(MessageHeader, Vec<Pubkey>), CompileError> {
-        let try_into_u8 = |num: usize| -> Result<u8, CompileError> {
-            u8::try_from(num).map_err(|_| CompileError::AccountIndexOverflow)
-        };
+    /// Collects the keys of a legacy message with the roles the message gives
+    /// them.
+    #[cfg(not(target_os = "solana"))]
+    pub(crate) fn from_legacy_message(message: &legacy::Message) -> Self {
+        let mut key_meta_map = BTreeMap::<Pubkey, CompiledKeyMeta>::new();
+        for (index, key) in message.account_keys.iter().enumerate() {
+            let meta = key_meta_map.entry(*key).or_default();
+            meta.is_signer |= message.is_signer(index);
+            meta.is_writable |= message.is_writable_index(index);
+            meta.is_invoked |= message.is_key_called_as_program(index);
+        }
+        if let Some(nonce_pubkey) =
+            get_compiled_nonce_pubkey(&message.account_keys, &message.instructions)
+        {
+            let meta = key_meta_map.entry(*nonce_pubkey).or_default();
+            meta.is_nonce = true;
+        }
+        Self {
+            payer: message.account_keys.first().copied(),
+            key_meta_map,
+        }
+    }
 
-        let Self {
-            payer,
-            mut key_meta_map,
-        } = self;
+    /// Returns the roles of `pubkey` for updating, adding the key without
+    /// any role if it is missing.
+    #[cfg(not(target_os = "solana"))]
+    pub(crate) fn key_meta_mut(&mut self, pubkey: Pubkey) -> &mut CompiledKeyMeta {
+        self.key_meta_map.entry(pubkey).or_default()
+    }
 
-        if let Some(payer) = &payer {
-            key_meta_map.remove_entry(payer);
-        }
+    /// Returns the fee payer, which always comes first.
+    pub fn payer(&self) -> Option<&Pubkey> {
+        self.payer.as_ref()
+    }
 
-        let writable_signer_keys: Vec<Pubkey> = payer
-            .into_iter()
-            .chain(
-                key_meta_map
-                    .iter()
-                    .filter_map(|(key, meta)| (meta.is_signer && meta.is_writable).then_some(*key)),
-            )
-            .collect();
-        let readonly_signer_keys: Vec<Pubkey> = key_meta_map
-            .iter()
-            .filter_map(|(key, meta)| (meta.is_signer && !meta.is_writable).then_some(*key))
-            .collect();
-        let writable_non_signer_keys: Vec<Pubkey> = key_meta_map
-            .iter()
-            .filter_map(|(key, meta)| (!meta.is_signer && meta.is_writable).then_some(*key))
-            .collect();
-        let readonly_non_signer_keys: Vec<Pubkey> = key_meta_map
+    /// Returns the roles of `pubkey`, or `None` if no instruction refers to
+    /// it and it is not the payer.
+    pub fn get_key_meta(&self, pubkey: &Pubkey) -> Option<&CompiledKeyMeta> {
+        self.key_meta_map.get(pubkey)
+    }
+
+    /// Returns the index `pubkey` will have in the static account keys of the
+    /// compiled message.
+    pub fn position(&self, pubkey: &Pubkey) -> Option<usize> {
+        self.ordered_keys().position(|key| key == pubkey)
+    }
+
+    /// Iterate over the keys in the order of the static account keys of the
+    /// compiled message.
+    pub fn ordered_keys(&self) -> impl Iterator<Item = &Pubkey> {
+        self.payer
             .iter()
-            .filter_map(|(key, meta)| (!meta.is_signer && !meta.is_writable).then_some(*key))
-            .collect();
+            .chain(self.keys_with_role(true, true))
+            .chain(self.keys_with_role(true, false))
+            .chain(self.keys_with_role(false, true))
+            .chain(self.keys_with_role(false, false))
+    }
 
-        let signers_len = writable_signer_keys
-            .len()
-            .saturating_add(readonly_signer_keys.len());
+    /// Iterate over the keys other than the payer with the given roles, in
+    /// order.
+    fn keys_with_role(&self, is_signer: bool, is_writable: bool) -> impl Iterator<Item = &Pubkey> {
+        self.key_meta_map.iter().filter_map(move |(key, meta)| {
+            (Some(key) != self.payer.as_ref()
+                && meta.is_signer == is_signer
+                && meta.is_writable == is_writable)
+                .then_some(key)
+        })
+    }
+
+    /// Returns the header and the static account keys of the compiled
+    /// message.
+    pub fn try_into_message_components(self) -> Result<(MessageHeader, Vec<Pubkey>), CompileError> {
+        let try_into_u8 = |num: usize| -> Result<u8, CompileError> {
+            u8::try_from(num).map_err(|_| CompileError::AccountIndexOverflow)
+        };
+
+        let num_readonly_signers = self.keys_with_role(true, false).count();
+        let signers_len = usize::from(self.payer.is_some())
+            .saturating_add(self.keys_with_role(true, true).count())
+            .saturating_add(num_readonly_signers);
 
         let header = MessageHeader {
             num_required_signatures: try_into_u8(signers_len)?,
-            num_readonly_signed_accounts: try_into_u8(readonly_signer_keys.len())?,
-            num_readonly_unsigned_accounts: try_into_u8(readonly_non_signer_keys.len())?,
+            num_readonly_signed_accounts: try_into_u8(num_readonly_signers)?,
+            num_readonly_unsigned_accounts: try_into_u8(self.keys_with_role(false, false).count())?,
         };
 
-        let static_account_keys = std::iter::empty()
-            .chain(writable_signer_keys)
-            .chain(readonly_signer_keys)
-            .chain(writable_non_signer_keys)
-            .chain(readonly_non_signer_keys)
-            .collect();
+        let static_account_keys = self.ordered_keys().copied().collect();
 
         Ok((header, static_account_keys))
     }
 
+    /// Move the keys that can be loaded from `lookup_table_account` out of
+    /// the static keys, returning the lookup that loads them and the keys
+    /// loaded. Signers, invoked programs and the nonce account are never
+    /// moved. Returns `None` if the table holds none of the keys.
     #[cfg(not(target_os = "solana"))]
-    pub(crate) fn try_extract_table_lookup(
+    pub fn try_extract_table_lookup(
         &mut self,
         lookup_table_account: &AddressLookupTableAccount,
     ) -> Result<Option<(MessageAddressTableLookup, LoadedAddresses)>, CompileError> {
-        let (writable_indexes, drained_writable_keys) = self
-            .try_drain_keys_found_in_lookup_table(&lookup_table_account.addresses, |meta| {
-                !meta.is_signer && !meta.is_invoked && !meta.is_nonce && meta.is_writable
-            })?;
-        let (readonly_indexes, drained_readonly_keys) = self
-            .try_drain_keys_found_in_lookup_table(&lookup_table_account.addresses, |meta| {
-                !meta.is_signer && !meta.is_invoked && !meta.is_nonce && !meta.is_writable
-            })?;
+        let (writable_indexes, drained_writable_keys) = self.try_drain_keys_found_in_lookup_table(
+            &lookup_table_account.key,
+            &lookup_table_account.addresses,
+            |meta| !meta.is_signer && !meta.is_invoked && !meta.is_nonce && meta.is_writable,
+        )?;
+        let (readonly_indexes, drained_readonly_keys) = self.try_drain_keys_found_in_lookup_table(
+            &lookup_table_account.key,
+            &lookup_table_account.addresses,
+            |meta| !meta.is_signer && !meta.is_invoked && !meta.is_nonce && !meta.is_writable,
+        )?;
 
         // Don't extract lookup if no keys were found
         if writable_indexes.is_empty() && readonly_indexes.is_empty() {
@@ -167,9 +278,28 @@ impl CompiledKeys {
         )))
     }
 
+    /// Returns the number of keys that [`Self::try_extract_table_lookup`]
+    /// would load from the lookup table.
+    #[cfg(not(target_os = "solana"))]
+    pub(crate) fn count_keys_found_in_lookup_table(
+        &self,
+        lookup_table_account: &AddressLookupTableAccount,
+    ) -> usize {
+        self.key_meta_map
+            .iter()
+            .filter(|(key, meta)| {
+                !meta.is_signer
+                    && !meta.is_invoked
+                    && !meta.is_nonce
+                    && lookup_table_account.addresses.contains(key)
+            })
+            .count()
+    }
+
     #[cfg(not(target_os = "solana"))]
     fn try_drain_keys_found_in_lookup_table(
         &mut self,
+        lookup_table_key: &Pubkey,
         lookup_table_addresses: &[Pubkey],
         key_meta_filter: impl Fn(&CompiledKeyMeta) -> bool,
     ) -> Result<(Vec<u8>, Vec<Pubkey>), CompileError> {
@@ -183,8 +313,13 @@ impl CompiledKeys {
         {
             for (key_index, key) in lookup_table_addresses.iter().enumerate() {
                 if key == search_key {
-                    let lookup_table_index = u8::try_from(key_index)
-                        .map_err(|_| CompileError::AddressTableLookupIndexOverflow)?;
+                    let lookup_table_index = u8::try_from(key_index).map_err(|_| {
+                        CompileError::AddressTableLookupIndexOutOfRange {
+                            lookup_table_key: *lookup_table_key,
+                            address: *search_key,
+                            index: key_index,
+                        }
+                    })?;
 
                     lookup_table_indexes.push(lookup_table_index);
                     drained_keys.push(*search_key);
@@ -202,23 +337,57 @@ impl CompiledKeys {
 }
 
 // inlined to avoid solana_nonce dep
-const NONCED_TX_MARKER_IX_INDEX: usize = 0;
+pub(crate) const NONCED_TX_MARKER_IX_INDEX: usize = 0;
+#[cfg(test)]
+static_assertions::const_assert_eq!(
+    NONCED_TX_MARKER_IX_INDEX,
+    solana_nonce::NONCED_TX_MARKER_IX_INDEX as usize
+);
 // inlined to avoid solana_system_interface and bincode deps
 const ADVANCE_NONCE_PREFIX: [u8; 4] = [4, 0, 0, 0];
 
+/// Returns true if `data` is that of a system program advance nonce
+/// instruction, which serializes to just its 4 byte variant index.
+/// Trailing bytes are ignored, as the system program does.
+pub(crate) fn is_advance_nonce_instruction_data(data: &[u8]) -> bool {
+    data.get(0..4) == Some(&ADVANCE_NONCE_PREFIX[..])
+}
+
 fn get_nonce_pubkey(instructions: &[Instruction]) -> Option<&Pubkey> {
     let ix = instructions.get(NONCED_TX_MARKER_IX_INDEX)?;
     if !system_program::check_id(&ix.program_id) {
         return None;
     }
 
-    if ix.data.get(0..4) != Some(&ADVANCE_NONCE_PREFIX[..]) {
+    if !is_advance_nonce_instruction_data(&ix.data) {
         return None;
     }
 
     ix.accounts.first().map(|meta| &meta.pubkey)
 }
 
+/// Returns the nonce account of compiled instructions that start with an
+/// advance nonce instruction, if it is one of the static `account_keys`.
+#[cfg(not(target_os = "solana"))]
+pub(crate) fn get_compiled_nonce_pubkey<'a>(
+    account_keys: &'a [Pubkey],
+    instructions: &[CompiledInstruction],
+) -> Option<&'a Pubkey> {
+    let ix = instructions.get(NONCED_TX_MARKER_IX_INDEX)?;
+    let program_id = account_keys.get(usize::from(ix.program_id_index))?;
+    if !system_program::check_id(program_id) {
+        return None;
+    }
+
+    if !is_advance_nonce_instruction_data(&ix.data) {
+        return None;
+    }
+
+    ix.accounts
+        .first()
+        .and_then(|index| account_keys.get(usize::from(*index)))
+}
+
 #[cfg(test)]
 mod tests {
     use {
@@ -462,6 +631,38 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_from_legacy_message() {
+        let payer = Pubkey::new_unique();
+        let nonce = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+        let message = legacy::Message::new(
+            &[
+                advance_nonce_account(&nonce, &payer),
+                Instruction::new_with_bytes(
+                    readonly,
+                    &[],
+                    vec![AccountMeta::new_readonly(readonly, false)],
+                ),
+            ],
+            Some(&payer),
+        );
+
+        assert_eq!(
+            CompiledKeys::from_legacy_message(&message),
+            CompiledKeys {
+                payer: Some(payer),
+                key_meta_map: BTreeMap::from([
+                    (payer, (KeyFlags::SIGNER | KeyFlags::WRITABLE).into()),
+                    (nonce, (KeyFlags::NONCE | KeyFlags::WRITABLE).into()),
+                    (readonly, KeyFlags::INVOKED.into()),
+                    (recent_blockhashes::id(), KeyFlags::empty().into()),
+                    (system_program::id(), KeyFlags::INVOKED.into()),
+                ]),
+            }
+        );
+    }
+
     #[test]
     fn test_try_into_message_components() {
         let keys = vec![
@@ -496,6 +697,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_position() {
+        let keys = [
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+        let payer = keys[4];
+
+        let compiled_keys = CompiledKeys {
+            payer: Some(payer),
+            key_meta_map: BTreeMap::from([
+                (keys[0], KeyFlags::empty().into()),
+                (keys[1], KeyFlags::WRITABLE.into()),
+                (keys[2], KeyFlags::SIGNER.into()),
+                (keys[3], (KeyFlags::SIGNER | KeyFlags::WRITABLE).into()),
+                (payer, (KeyFlags::SIGNER | KeyFlags::WRITABLE).into()),
+            ]),
+        };
+
+        let ordered_keys: Vec<_> = compiled_keys.ordered_keys().copied().collect();
+        assert_eq!(
+            ordered_keys,
+            vec![payer, keys[3], keys[2], keys[1], keys[0]]
+        );
+        for (index, key) in ordered_keys.iter().enumerate() {
+            assert_eq!(compiled_keys.position(key), Some(index));
+        }
+        assert_eq!(compiled_keys.position(&Pubkey::new_unique()), None);
+        assert_eq!(
+            compiled_keys.get_key_meta(&keys[2]),
+            Some(&KeyFlags::SIGNER.into())
+        );
+        assert_eq!(
+            compiled_keys
+                .clone()
+                .try_into_message_components()
+                .unwrap()
+                .1,
+            ordered_keys
+        );
+    }
+
+    #[test]
+    fn test_ordered_keys_independent_of_instruction_order() {
+        let payer = Pubkey::new_unique();
+        let instructions: Vec<Instruction> = (0..4)
+            .map(|i| {
+                Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[],
+                    vec![
+                        AccountMeta::new(Pubkey::new_unique(), i % 2 == 0),
+                        AccountMeta::new_readonly(Pubkey::new_unique(), i % 2 == 1),
+                    ],
+                )
+            })
+            .collect();
+        let mut reversed = instructions.clone();
+        reversed.reverse();
+
+        let compiled_keys = CompiledKeys::compile(&instructions, Some(payer));
+        assert_eq!(
+            compiled_keys.clone().try_into_message_components(),
+            CompiledKeys::compile(&reversed, Some(payer)).try_into_message_components()
+        );
+        let (_, static_keys) = compiled_keys.try_into_message_components().unwrap();
+        assert!(static_keys[1..3].is_sorted());
+        assert!(static_keys[3..5].is_sorted());
+        assert!(static_keys[5..7].is_sorted());
+        assert!(static_keys[7..].is_sorted());
+    }
+
     #[test]
     fn test_try_into_message_components_with_too_many_keys() {
         const TOO_MANY_KEYS: usize = 257;
@@ -570,6 +846,37 @@ mod tests {
         assert!(!compiled_keys.key_meta_map.contains_key(&keys[3]));
     }
 
+    #[test]
+    fn test_count_keys_found_in_lookup_table() {
+        let keys = vec![
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        ];
+
+        let compiled_keys = CompiledKeys {
+            payer: None,
+            key_meta_map: BTreeMap::from([
+                (keys[0], (KeyFlags::SIGNER | KeyFlags::WRITABLE).into()),
+                (keys[1], KeyFlags::WRITABLE.into()),
+                (keys[2], KeyFlags::empty().into()),
+                (keys[3], KeyFlags::INVOKED.into()),
+                (keys[4], (KeyFlags::NONCE | KeyFlags::WRITABLE).into()),
+            ]),
+        };
+
+        let lookup_table_account = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: [keys.clone(), vec![Pubkey::new_unique()]].concat(),
+        };
+        assert_eq!(
+            compiled_keys.count_keys_found_in_lookup_table(&lookup_table_account),
+            2
+        );
+    }
+
     #[test]
     fn test_try_extract_table_lookup_returns_none() {
         let mut compiled_keys = CompiledKeys {
@@ -616,7 +923,11 @@ mod tests {
         let expected_compiled_keys = compiled_keys.clone();
         assert_eq!(
             compiled_keys.try_extract_table_lookup(&lookup_table_account),
-            Err(CompileError::AddressTableLookupIndexOverflow),
+            Err(CompileError::AddressTableLookupIndexOutOfRange {
+                lookup_table_key: lookup_table_account.key,
+                address: writable_key,
+                index: MAX_LENGTH_WITHOUT_OVERFLOW,
+            }),
         );
         assert_eq!(compiled_keys, expected_compiled_keys);
     }
@@ -652,10 +963,11 @@ mod tests {
             Pubkey::new_unique(),
         ];
 
-        let drain_result = compiled_keys
-            .try_drain_keys_found_in_lookup_table(&lookup_table_addresses, |meta| {
-                !meta.is_writable
-            });
+        let drain_result = compiled_keys.try_drain_keys_found_in_lookup_table(
+            &Pubkey::new_unique(),
+            &lookup_table_addresses,
+            |meta| !meta.is_writable,
+        );
         assert_eq!(drain_result.as_ref().err(), None);
         let (lookup_table_indexes, drained_keys) = drain_result.unwrap();
 
@@ -677,8 +989,11 @@ mod tests {
             Pubkey::new_unique(),
         ];
 
-        let drain_result =
-            compiled_keys.try_drain_keys_found_in_lookup_table(&lookup_table_addresses, |_| true);
+        let drain_result = compiled_keys.try_drain_keys_found_in_lookup_table(
+            &Pubkey::new_unique(),
+            &lookup_table_addresses,
+            |_| true,
+        );
         assert_eq!(drain_result.as_ref().err(), None);
         let (lookup_table_indexes, drained_keys) = drain_result.unwrap();
 
@@ -705,8 +1020,11 @@ mod tests {
 
         let lookup_table_addresses = vec![];
 
-        let drain_result =
-            compiled_keys.try_drain_keys_found_in_lookup_table(&lookup_table_addresses, |_| true);
+        let drain_result = compiled_keys.try_drain_keys_found_in_lookup_table(
+            &Pubkey::new_unique(),
+            &lookup_table_addresses,
+            |_| true,
+        );
         assert_eq!(drain_result.as_ref().err(), None);
         let (lookup_table_indexes, drained_keys) = drain_result.unwrap();
 
@@ -727,11 +1045,19 @@ mod tests {
         let mut lookup_table_addresses = vec![Pubkey::default(); MAX_LENGTH_WITHOUT_OVERFLOW];
         lookup_table_addresses.push(key);
 
-        let drain_result =
-            compiled_keys.try_drain_keys_found_in_lookup_table(&lookup_table_addresses, |_| true);
+        let lookup_table_key = Pubkey::new_unique();
+        let drain_result = compiled_keys.try_drain_keys_found_in_lookup_table(
+            &lookup_table_key,
+            &lookup_table_addresses,
+            |_| true,
+        );
         assert_eq!(
             drain_result.err(),
-            Some(CompileError::AddressTableLookupIndexOverflow)
+            Some(CompileError::AddressTableLookupIndexOutOfRange {
+                lookup_table_key,
+                address: key,
+                index: MAX_LENGTH_WITHOUT_OVERFLOW,
+            })
         );
     }
 }
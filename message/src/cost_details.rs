@@ -0,0 +1,130 @@
+//! The properties of a message that cost models and rate limiters charge for.
+
+use crate::{SanitizedMessage, TransactionSignatureDetails};
+
+/// Cost-relevant counts of a message.
+///
+/// The precompile signature counts are the number of signatures each
+/// precompile instruction declares in its first data byte, summed over all
+/// instructions invoking that precompile.
+pub trait MessageCostDetails {
+    /// Return the number of signatures required by the message header.
+    fn num_signatures_required(&self) -> u64;
+
+    /// Return the number of write locks requested by the message, before
+    /// any demotion.
+    fn num_write_locks(&self) -> u64;
+
+    /// Return the number of account keys, including loaded addresses.
+    fn num_account_keys(&self) -> usize;
+
+    fn num_instructions(&self) -> usize;
+
+    /// Return the sum of the data lengths of all instructions.
+    fn total_instruction_data_len(&self) -> usize;
+
+    /// Return the transaction and precompile signature counts.
+    fn signature_details(&self) -> TransactionSignatureDetails;
+
+    fn num_secp256k1_precompile_signatures(&self) -> u64 {
+        self.signature_details()
+            .num_secp256k1_instruction_signatures()
+    }
+
+    fn num_ed25519_precompile_signatures(&self) -> u64 {
+        self.signature_details()
+            .num_ed25519_instruction_signatures()
+    }
+
+    fn num_secp256r1_precompile_signatures(&self) -> u64 {
+        self.signature_details()
+            .num_secp256r1_instruction_signatures()
+    }
+
+    /// Return the number of required signatures plus the precompile
+    /// signatures.
+    fn num_total_signatures(&self) -> u64 {
+        self.signature_details().total_signatures()
+    }
+}
+
+impl MessageCostDetails for SanitizedMessage {
+    fn num_signatures_required(&self) -> u64 {
+        u64::from(self.header().num_required_signatures)
+    }
+
+    fn num_write_locks(&self) -> u64 {
+        SanitizedMessage::num_write_locks(self)
+    }
+
+    fn num_account_keys(&self) -> usize {
+        self.account_keys().len()
+    }
+
+    fn num_instructions(&self) -> usize {
+        self.instructions().len()
+    }
+
+    fn total_instruction_data_len(&self) -> usize {
+        self.instructions()
+            .iter()
+            .map(|instruction| instruction.data.len())
+            .fold(0usize, usize::saturating_add)
+    }
+
+    fn signature_details(&self) -> TransactionSignatureDetails {
+        self.get_signature_details()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{compiled_instruction::CompiledInstruction, v0, MessageHeader},
+        solana_pubkey::Pubkey,
+        solana_sdk_ids::{ed25519_program, secp256k1_program},
+        std::collections::HashSet,
+    };
+
+    #[test]
+    fn test_sanitized_message_cost_details() {
+        let message = SanitizedMessage::V0(v0::LoadedMessage::new(
+            v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 2,
+                    num_readonly_signed_accounts: 1,
+                    num_readonly_unsigned_accounts: 2,
+                },
+                account_keys: vec![
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    ed25519_program::id(),
+                    secp256k1_program::id(),
+                ],
+                instructions: vec![
+                    CompiledInstruction::new_from_raw_parts(2, vec![3, 0, 0], vec![]),
+                    CompiledInstruction::new_from_raw_parts(3, vec![1], vec![]),
+                    CompiledInstruction::new_from_raw_parts(2, vec![2], vec![4]),
+                ],
+                ..v0::Message::default()
+            },
+            v0::LoadedAddresses {
+                writable: vec![Pubkey::new_unique()],
+                readonly: vec![Pubkey::new_unique()],
+            },
+            &HashSet::default(),
+        ));
+
+        assert_eq!(message.num_signatures_required(), 2);
+        // the payer and the loaded writable address
+        assert_eq!(MessageCostDetails::num_write_locks(&message), 2);
+        assert_eq!(message.num_account_keys(), 6);
+        assert_eq!(message.num_instructions(), 3);
+        assert_eq!(message.total_instruction_data_len(), 5);
+        assert_eq!(message.num_ed25519_precompile_signatures(), 5);
+        assert_eq!(message.num_secp256k1_precompile_signatures(), 1);
+        assert_eq!(message.num_secp256r1_precompile_signatures(), 0);
+        assert_eq!(MessageCostDetails::num_total_signatures(&message), 8);
+    }
+}
@@ -0,0 +1,257 @@
+//! Structurally plausible messages for fuzzing.
+//!
+//! The [`Arbitrary`] implementations of the message types generate headers
+//! that are consistent with the account keys, account indexes that are in
+//! bounds and program ids that are not the fee payer, so that generated
+//! messages pass [`VersionedMessage::sanitize`] and reach the code under
+//! test. Account keys, blockhashes and instruction data are unconstrained.
+//!
+//! The proptest strategies in this module are built on the same
+//! implementations.
+
+use {
+    crate::{
+        compiled_instruction::CompiledInstruction,
+        legacy,
+        v0::{self, MessageAddressTableLookup},
+        MessageHeader, VersionedMessage,
+    },
+    arbitrary::{Arbitrary, Result, Unstructured},
+    proptest::{collection::vec, prelude::*},
+    solana_hash::Hash,
+    solana_pubkey::Pubkey,
+    std::fmt::Debug,
+};
+
+/// Maximum number of static account keys in a generated message.
+const MAX_STATIC_ACCOUNT_KEYS: usize = 64;
+/// Maximum number of instructions in a generated message.
+const MAX_INSTRUCTIONS: usize = 16;
+/// Maximum number of accounts of a generated instruction.
+const MAX_INSTRUCTION_ACCOUNTS: usize = 32;
+/// Maximum length of the data of a generated instruction.
+const MAX_INSTRUCTION_DATA_LEN: usize = 256;
+/// Maximum number of address table lookups in a generated message.
+const MAX_ADDRESS_TABLE_LOOKUPS: usize = 4;
+/// Maximum number of writable or readonly indexes of a generated lookup.
+const MAX_LOOKUP_INDEXES: usize = 16;
+/// Maximum number of random bytes fed to [`Arbitrary`] by the strategies.
+const MAX_STRATEGY_BYTES: usize = 8 * 1024;
+
+/// Strategy generating values with their [`Arbitrary`] implementation.
+///
+/// The implementations in this crate never fail, whatever the input, so the
+/// strategy shrinks by shrinking the random bytes.
+pub fn arbitrary_strategy<T>() -> impl Strategy<Value = T>
+where
+    T: for<'a> Arbitrary<'a> + Debug,
+{
+    vec(any::<u8>(), 0..MAX_STRATEGY_BYTES).prop_map(|bytes| {
+        T::arbitrary(&mut Unstructured::new(&bytes)).expect("arbitrary values never fail")
+    })
+}
+
+pub fn compiled_instruction() -> impl Strategy<Value = CompiledInstruction> {
+    arbitrary_strategy()
+}
+
+pub fn legacy_message() -> impl Strategy<Value = legacy::Message> {
+    arbitrary_strategy()
+}
+
+pub fn v0_message() -> impl Strategy<Value = v0::Message> {
+    arbitrary_strategy()
+}
+
+pub fn versioned_message() -> impl Strategy<Value = VersionedMessage> {
+    arbitrary_strategy()
+}
+
+impl<'a> Arbitrary<'a> for CompiledInstruction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        arbitrary_instruction(u, 1 << u8::BITS, 1 << u8::BITS)
+    }
+}
+
+impl<'a> Arbitrary<'a> for legacy::Message {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let account_keys = arbitrary_account_keys(u)?;
+        let header = arbitrary_header(u, account_keys.len())?;
+        let recent_blockhash = Hash::new_from_array(u.arbitrary()?);
+        let instructions = arbitrary_instructions(u, account_keys.len(), account_keys.len())?;
+        Ok(Self {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for v0::Message {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let account_keys = arbitrary_account_keys(u)?;
+        let header = arbitrary_header(u, account_keys.len())?;
+        let recent_blockhash = Hash::new_from_array(u.arbitrary()?);
+        let num_address_table_lookups = u.int_in_range(0..=MAX_ADDRESS_TABLE_LOOKUPS)?;
+        let address_table_lookups = (0..num_address_table_lookups)
+            .map(|_| arbitrary_address_table_lookup(u))
+            .collect::<Result<Vec<_>>>()?;
+        let num_loaded_addresses = address_table_lookups
+            .iter()
+            .map(|lookup| {
+                lookup
+                    .writable_indexes
+                    .len()
+                    .saturating_add(lookup.readonly_indexes.len())
+            })
+            .fold(0usize, usize::saturating_add);
+        let instructions = arbitrary_instructions(
+            u,
+            account_keys.len(),
+            account_keys.len().saturating_add(num_loaded_addresses),
+        )?;
+        Ok(Self {
+            header,
+            account_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups,
+        })
+    }
+}
+
+impl<'a> Arbitrary<'a> for VersionedMessage {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        if u.arbitrary()? {
+            Ok(Self::V0(u.arbitrary()?))
+        } else {
+            Ok(Self::Legacy(u.arbitrary()?))
+        }
+    }
+}
+
+fn arbitrary_account_keys(u: &mut Unstructured) -> Result<Vec<Pubkey>> {
+    let num_account_keys = u.int_in_range(1..=MAX_STATIC_ACCOUNT_KEYS)?;
+    (0..num_account_keys)
+        .map(|_| Ok(Pubkey::new_from_array(u.arbitrary()?)))
+        .collect()
+}
+
+/// Generate a header with a writable fee payer that describes at most
+/// `num_account_keys` accounts.
+fn arbitrary_header(u: &mut Unstructured, num_account_keys: usize) -> Result<MessageHeader> {
+    let num_required_signatures = u.int_in_range(1..=num_account_keys)?;
+    let num_readonly_signed_accounts =
+        u.int_in_range(0..=num_required_signatures.saturating_sub(1))?;
+    let num_readonly_unsigned_accounts =
+        u.int_in_range(0..=num_account_keys.saturating_sub(num_required_signatures))?;
+    Ok(MessageHeader {
+        num_required_signatures: num_required_signatures as u8,
+        num_readonly_signed_accounts: num_readonly_signed_accounts as u8,
+        num_readonly_unsigned_accounts: num_readonly_unsigned_accounts as u8,
+    })
+}
+
+fn arbitrary_address_table_lookup(u: &mut Unstructured) -> Result<MessageAddressTableLookup> {
+    let account_key = Pubkey::new_from_array(u.arbitrary()?);
+    let num_writable_indexes = u.int_in_range(0..=MAX_LOOKUP_INDEXES)?;
+    let writable_indexes = (0..num_writable_indexes)
+        .map(|_| u.arbitrary())
+        .collect::<Result<_>>()?;
+    // Each lookup must load at least one address.
+    let min_readonly_indexes = usize::from(num_writable_indexes == 0);
+    let num_readonly_indexes = u.int_in_range(min_readonly_indexes..=MAX_LOOKUP_INDEXES)?;
+    let readonly_indexes = (0..num_readonly_indexes)
+        .map(|_| u.arbitrary())
+        .collect::<Result<_>>()?;
+    Ok(MessageAddressTableLookup {
+        account_key,
+        writable_indexes,
+        readonly_indexes,
+    })
+}
+
+/// Generate the instructions of a message. A program id cannot be the fee
+/// payer, so a message with a single static account key has none.
+fn arbitrary_instructions(
+    u: &mut Unstructured,
+    num_static_account_keys: usize,
+    num_account_keys: usize,
+) -> Result<Vec<CompiledInstruction>> {
+    if num_static_account_keys < 2 {
+        return Ok(vec![]);
+    }
+    let num_instructions = u.int_in_range(0..=MAX_INSTRUCTIONS)?;
+    (0..num_instructions)
+        .map(|_| arbitrary_instruction(u, num_static_account_keys, num_account_keys))
+        .collect()
+}
+
+/// Generate an instruction whose program id is one of the static account keys
+/// other than the fee payer, and whose accounts index into `num_account_keys`
+/// keys. Both counts must be at least 2 and at most 256.
+fn arbitrary_instruction(
+    u: &mut Unstructured,
+    num_static_account_keys: usize,
+    num_account_keys: usize,
+) -> Result<CompiledInstruction> {
+    let program_id_index = u.int_in_range(1..=num_static_account_keys.saturating_sub(1))? as u8;
+    let num_accounts = u.int_in_range(0..=MAX_INSTRUCTION_ACCOUNTS)?;
+    let accounts = (0..num_accounts)
+        .map(|_| Ok(u.int_in_range(0..=num_account_keys.saturating_sub(1))? as u8))
+        .collect::<Result<_>>()?;
+    let data_len = u.int_in_range(0..=MAX_INSTRUCTION_DATA_LEN)?;
+    let data = (0..data_len)
+        .map(|_| u.arbitrary())
+        .collect::<Result<_>>()?;
+    Ok(CompiledInstruction {
+        program_id_index,
+        accounts,
+        data,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arbitrary_exhausted_input() {
+        let message = VersionedMessage::arbitrary(&mut Unstructured::new(&[])).unwrap();
+        assert_eq!(message.static_account_keys().len(), 1);
+        assert!(message.instructions().is_empty());
+        assert_eq!(message.sanitize(), Ok(()));
+    }
+
+    proptest! {
+        #[test]
+        fn test_arbitrary_messages_sanitize(message in versioned_message()) {
+            prop_assert_eq!(message.sanitize(), Ok(()));
+        }
+
+        #[test]
+        fn test_arbitrary_v0_message_indexes(message in v0_message()) {
+            let num_loaded_addresses = message
+                .address_table_lookups
+                .iter()
+                .map(|lookup| {
+                    lookup
+                        .writable_indexes
+                        .len()
+                        .saturating_add(lookup.readonly_indexes.len())
+                })
+                .fold(0usize, usize::saturating_add);
+            let num_account_keys = message.account_keys.len().saturating_add(num_loaded_addresses);
+            prop_assert!(num_account_keys <= 1 << u8::BITS);
+            for instruction in &message.instructions {
+                prop_assert!(instruction.program_id_index > 0);
+                prop_assert!(usize::from(instruction.program_id_index) < message.account_keys.len());
+                prop_assert!(instruction
+                    .accounts
+                    .iter()
+                    .all(|index| usize::from(*index) < num_account_keys));
+            }
+        }
+    }
+}
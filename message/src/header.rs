@@ -0,0 +1,152 @@
+use {crate::MessageHeader, std::fmt};
+
+/// The constraint on a [`MessageHeader`] that
+/// [`MessageHeader::validate`] found to be violated.
+#[derive(PartialEq, Debug, Eq, Clone)]
+pub enum MessageHeaderError {
+    /// No signature is required, so the message has no fee payer.
+    NoRequiredSignatures,
+    /// All signers are read-only, so the fee payer is not writable.
+    NoWritableSigner {
+        num_required_signatures: u8,
+        num_readonly_signed_accounts: u8,
+    },
+    /// There are fewer account keys than signed and read-only unsigned
+    /// accounts.
+    TooFewAccountKeys {
+        num_required_signatures: u8,
+        num_readonly_unsigned_accounts: u8,
+        num_account_keys: usize,
+    },
+    /// The number of signatures differs from the number of required
+    /// signatures.
+    SignatureCountMismatch {
+        num_required_signatures: u8,
+        num_signatures: usize,
+    },
+}
+
+impl std::error::Error for MessageHeaderError {}
+
+impl fmt::Display for MessageHeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MessageHeaderError::NoRequiredSignatures => {
+                f.write_str("message requires no signatures and has no fee payer")
+            }
+            MessageHeaderError::NoWritableSigner {
+                num_required_signatures,
+                num_readonly_signed_accounts,
+            } => f.write_fmt(format_args!(
+                "all {num_required_signatures} signers are read-only \
+                 ({num_readonly_signed_accounts} read-only signed accounts), so the fee payer \
+                 is not writable",
+            )),
+            MessageHeaderError::TooFewAccountKeys {
+                num_required_signatures,
+                num_readonly_unsigned_accounts,
+                num_account_keys,
+            } => f.write_fmt(format_args!(
+                "{num_required_signatures} signed and {num_readonly_unsigned_accounts} \
+                 read-only unsigned accounts exceed the {num_account_keys} account keys",
+            )),
+            MessageHeaderError::SignatureCountMismatch {
+                num_required_signatures,
+                num_signatures,
+            } => f.write_fmt(format_args!(
+                "message requires {num_required_signatures} signatures but has \
+                 {num_signatures}",
+            )),
+        }
+    }
+}
+
+impl MessageHeader {
+    /// Check that the header describes a message with `num_account_keys`
+    /// static account keys that carries `num_signatures` signatures.
+    ///
+    /// The constraints are checked in the order of the variants of
+    /// [`MessageHeaderError`], and the first one violated is returned.
+    /// Sanitization checks the same constraints on the account keys, except
+    /// for the number of signatures, which is checked with the transaction.
+    pub fn validate(
+        &self,
+        num_account_keys: usize,
+        num_signatures: usize,
+    ) -> Result<(), MessageHeaderError> {
+        if self.num_required_signatures == 0 {
+            return Err(MessageHeaderError::NoRequiredSignatures);
+        }
+        if self.num_readonly_signed_accounts >= self.num_required_signatures {
+            return Err(MessageHeaderError::NoWritableSigner {
+                num_required_signatures: self.num_required_signatures,
+                num_readonly_signed_accounts: self.num_readonly_signed_accounts,
+            });
+        }
+        if usize::from(self.num_required_signatures)
+            .saturating_add(usize::from(self.num_readonly_unsigned_accounts))
+            > num_account_keys
+        {
+            return Err(MessageHeaderError::TooFewAccountKeys {
+                num_required_signatures: self.num_required_signatures,
+                num_readonly_unsigned_accounts: self.num_readonly_unsigned_accounts,
+                num_account_keys,
+            });
+        }
+        if usize::from(self.num_required_signatures) != num_signatures {
+            return Err(MessageHeaderError::SignatureCountMismatch {
+                num_required_signatures: self.num_required_signatures,
+                num_signatures,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(
+        num_required_signatures: u8,
+        num_readonly_signed_accounts: u8,
+        num_readonly_unsigned_accounts: u8,
+    ) -> MessageHeader {
+        MessageHeader {
+            num_required_signatures,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(header(2, 1, 1).validate(3, 2), Ok(()));
+        assert_eq!(
+            header(0, 0, 0).validate(1, 0),
+            Err(MessageHeaderError::NoRequiredSignatures)
+        );
+        assert_eq!(
+            header(2, 2, 0).validate(2, 2),
+            Err(MessageHeaderError::NoWritableSigner {
+                num_required_signatures: 2,
+                num_readonly_signed_accounts: 2,
+            })
+        );
+        assert_eq!(
+            header(2, 1, 2).validate(3, 2),
+            Err(MessageHeaderError::TooFewAccountKeys {
+                num_required_signatures: 2,
+                num_readonly_unsigned_accounts: 2,
+                num_account_keys: 3,
+            })
+        );
+        assert_eq!(
+            header(2, 1, 1).validate(3, 1),
+            Err(MessageHeaderError::SignatureCountMismatch {
+                num_required_signatures: 2,
+                num_signatures: 1,
+            })
+        );
+    }
+}
@@ -41,8 +41,13 @@
 
 pub mod compiled_instruction;
 mod compiled_keys;
+#[cfg(all(feature = "fuzz", not(target_os = "solana")))]
+pub mod fuzz;
+mod header;
 pub mod inner_instruction;
 pub mod legacy;
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "frozen-abi")]
@@ -53,15 +58,20 @@ use solana_frozen_abi_macro::AbiExample;
 mod non_bpf_modules {
     mod account_keys;
     mod address_loader;
+    mod cost_details;
     mod sanitized;
     mod versions;
 
-    pub use {account_keys::*, address_loader::*, sanitized::*, versions::*};
+    pub use {account_keys::*, address_loader::*, cost_details::*, sanitized::*, versions::*};
 }
 
 #[cfg(not(target_os = "solana"))]
 pub use non_bpf_modules::*;
-pub use {compiled_keys::CompileError, legacy::Message};
+pub use {
+    compiled_keys::{CompileError, CompiledKeyMeta, CompiledKeys},
+    header::MessageHeaderError,
+    legacy::Message,
+};
 
 /// The length of a message header in bytes.
 pub const MESSAGE_HEADER_LENGTH: usize = 3;
@@ -100,6 +110,11 @@ pub const MESSAGE_HEADER_LENGTH: usize = 3;
 ///
 /// [PoH]: https://docs.solanalabs.com/consensus/synchronization
 #[cfg_attr(feature = "frozen-abi", derive(AbiExample))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(BorshSerialize, BorshDeserialize),
+    borsh(crate = "borsh")
+)]
 #[cfg_attr(
     feature = "serde",
     derive(Deserialize, Serialize),
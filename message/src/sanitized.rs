@@ -1,28 +1,24 @@
 use {
     crate::{
         compiled_instruction::CompiledInstruction,
+        compiled_keys::{is_advance_nonce_instruction_data, NONCED_TX_MARKER_IX_INDEX},
         legacy,
         v0::{self, LoadedAddresses},
-        AccountKeys, AddressLoader, MessageHeader, SanitizedVersionedMessage, VersionedMessage,
+        AccountKeyPositions, AccountKeys, AddressLoader, MessageHeader, SanitizedVersionedMessage,
+        VersionedMessage, VersionedMessageRef,
     },
     solana_hash::Hash,
-    solana_instruction::{BorrowedAccountMeta, BorrowedInstruction},
+    solana_instruction::{error::InstructionError, BorrowedAccountMeta, BorrowedInstruction},
     solana_pubkey::Pubkey,
     solana_sanitize::Sanitize,
-    solana_sdk_ids::{ed25519_program, secp256k1_program, secp256r1_program},
-    solana_transaction_error::SanitizeMessageError,
-    std::{borrow::Cow, collections::HashSet, convert::TryFrom},
+    solana_sdk_ids::{
+        bpf_loader_upgradeable, compute_budget, ed25519_program, secp256k1_program,
+        secp256r1_program,
+    },
+    solana_transaction_error::{SanitizeMessageError, TransactionError},
+    std::{borrow::Cow, collections::HashSet, convert::TryFrom, sync::OnceLock},
 };
 
-// inlined to avoid solana_nonce dep
-#[cfg(feature = "bincode")]
-const NONCED_TX_MARKER_IX_INDEX: u8 = 0;
-#[cfg(test)]
-static_assertions::const_assert_eq!(
-    NONCED_TX_MARKER_IX_INDEX,
-    solana_nonce::NONCED_TX_MARKER_IX_INDEX
-);
-
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct LegacyMessage<'a> {
     /// Legacy message
@@ -30,23 +26,25 @@ pub struct LegacyMessage<'a> {
     /// List of boolean with same length as account_keys(), each boolean value indicates if
     /// corresponding account key is writable or not.
     pub is_writable_account_cache: Vec<bool>,
+    /// Compute budget details of the message, computed on first use.
+    pub compute_budget_details: ComputeBudgetDetailsCache,
+    /// Position of each account key, built on first use.
+    pub account_key_positions: AccountKeyPositions,
 }
 
 impl LegacyMessage<'_> {
     pub fn new(message: legacy::Message, reserved_account_keys: &HashSet<Pubkey>) -> Self {
-        let is_writable_account_cache = message
-            .account_keys
-            .iter()
-            .enumerate()
-            .map(|(i, _key)| {
-                message.is_writable_index(i)
-                    && !reserved_account_keys.contains(&message.account_keys[i])
-                    && !message.demote_program_id(i)
-            })
-            .collect::<Vec<_>>();
+        let is_writable_account_cache = new_is_writable_account_cache(
+            AccountKeys::new(&message.account_keys, None),
+            &message.instructions,
+            |i| message.is_writable_index(i),
+            reserved_account_keys,
+        );
         Self {
             message: Cow::Owned(message),
             is_writable_account_cache,
+            compute_budget_details: ComputeBudgetDetailsCache::default(),
+            account_key_positions: AccountKeyPositions::default(),
         }
     }
 
@@ -66,6 +64,7 @@ impl LegacyMessage<'_> {
     /// Returns the full list of account keys.
     pub fn account_keys(&self) -> AccountKeys<'_> {
         AccountKeys::new(&self.message.account_keys, None)
+            .with_positions(&self.account_key_positions)
     }
 
     pub fn is_writable(&self, index: usize) -> bool {
@@ -73,6 +72,37 @@ impl LegacyMessage<'_> {
     }
 }
 
+/// Returns whether each of the `account_keys` of a message is writable.
+///
+/// Program demotion is determined once for all keys, so building the cache
+/// takes linear rather than quadratic time in the number of keys.
+pub(crate) fn new_is_writable_account_cache(
+    account_keys: AccountKeys<'_>,
+    instructions: &[CompiledInstruction],
+    is_writable_index: impl Fn(usize) -> bool,
+    reserved_account_keys: &HashSet<Pubkey>,
+) -> Vec<bool> {
+    let is_upgradeable_loader_present = account_keys
+        .iter()
+        .any(|key| key == &bpf_loader_upgradeable::id());
+    let mut is_demoted = vec![false; account_keys.len()];
+    if !is_upgradeable_loader_present {
+        for instruction in instructions {
+            if let Some(demoted) = is_demoted.get_mut(usize::from(instruction.program_id_index)) {
+                *demoted = true;
+            }
+        }
+    }
+    account_keys
+        .iter()
+        .zip(is_demoted)
+        .enumerate()
+        .map(|(i, (key, is_demoted))| {
+            is_writable_index(i) && !reserved_account_keys.contains(key) && !is_demoted
+        })
+        .collect()
+}
+
 /// Sanitized message of a transaction.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum SanitizedMessage {
@@ -145,6 +175,14 @@ impl SanitizedMessage {
         }
     }
 
+    /// Borrows the underlying legacy or v0 message without cloning it
+    pub fn as_versioned_message(&self) -> VersionedMessageRef<'_> {
+        match self {
+            Self::Legacy(legacy_message) => VersionedMessageRef::Legacy(&legacy_message.message),
+            Self::V0(loaded_msg) => VersionedMessageRef::V0(&loaded_msg.message),
+        }
+    }
+
     /// Returns the fee payer for the transaction
     pub fn fee_payer(&self) -> &Pubkey {
         self.account_keys()
@@ -265,31 +303,34 @@ impl SanitizedMessage {
 
     /// Decompile message instructions without cloning account keys
     pub fn decompile_instructions(&self) -> Vec<BorrowedInstruction<'_>> {
-        let account_keys = self.account_keys();
-        self.program_instructions_iter()
-            .map(|(program_id, instruction)| {
-                let accounts = instruction
-                    .accounts
-                    .iter()
-                    .map(|account_index| {
-                        let account_index = *account_index as usize;
-                        BorrowedAccountMeta {
-                            is_signer: self.is_signer(account_index),
-                            is_writable: self.is_writable(account_index),
-                            pubkey: account_keys.get(account_index).unwrap(),
-                        }
-                    })
-                    .collect();
-
-                BorrowedInstruction {
-                    accounts,
-                    data: &instruction.data,
-                    program_id,
-                }
+        self.resolved_instructions_iter()
+            .map(|instruction| BorrowedInstruction {
+                accounts: instruction.accounts().collect(),
+                data: instruction.data(),
+                program_id: instruction.program_id(),
             })
             .collect()
     }
 
+    /// Iterate over the instructions of this message with their program id
+    /// and accounts resolved to addresses, including the addresses loaded
+    /// from lookup tables.
+    pub fn resolved_instructions_iter(
+        &self,
+    ) -> impl ExactSizeIterator<Item = ResolvedInstruction<'_>> + Clone {
+        let account_keys = self.account_keys();
+        self.instructions()
+            .iter()
+            .map(move |instruction| ResolvedInstruction {
+                message: self,
+                program_id: account_keys
+                    .get(usize::from(instruction.program_id_index))
+                    .expect("program id index is sanitized"),
+                account_keys: account_keys.clone(),
+                instruction,
+            })
+    }
+
     /// Inspect all message keys for the bpf upgradeable loader
     pub fn is_upgradeable_loader_present(&self) -> bool {
         match self {
@@ -313,25 +354,45 @@ impl SanitizedMessage {
             })
     }
 
-    #[cfg(feature = "bincode")]
+    /// Get the role of each account of the instruction at the given index,
+    /// in instruction order, or `None` if there is no such instruction.
+    pub fn instruction_account_roles(
+        &self,
+        ix_index: usize,
+    ) -> Option<Vec<InstructionAccountRole<'_>>> {
+        let instruction = self.instructions().get(ix_index)?;
+        let account_keys = self.account_keys();
+        let num_static_account_keys = self.static_account_keys().len();
+        Some(
+            instruction
+                .accounts
+                .iter()
+                .map(|account_index| {
+                    let account_index = usize::from(*account_index);
+                    InstructionAccountRole {
+                        pubkey: account_keys
+                            .get(account_index)
+                            .expect("account index is sanitized"),
+                        is_signer: self.is_signer(account_index),
+                        is_writable: self.is_writable(account_index),
+                        is_loaded: account_index >= num_static_account_keys,
+                    }
+                })
+                .collect(),
+        )
+    }
+
     /// If the message uses a durable nonce, return the pubkey of the nonce account
     pub fn get_durable_nonce(&self) -> Option<&Pubkey> {
         self.instructions()
-            .get(NONCED_TX_MARKER_IX_INDEX as usize)
+            .get(NONCED_TX_MARKER_IX_INDEX)
             .filter(
                 |ix| match self.account_keys().get(ix.program_id_index as usize) {
                     Some(program_id) => solana_sdk_ids::system_program::check_id(program_id),
                     _ => false,
                 },
             )
-            .filter(|ix| {
-                matches!(
-                    solana_bincode::limited_deserialize(
-                        &ix.data, 4 /* serialized size of AdvanceNonceAccount */
-                    ),
-                    Ok(solana_system_interface::instruction::SystemInstruction::AdvanceNonceAccount)
-                )
-            })
+            .filter(|ix| is_advance_nonce_instruction_data(&ix.data))
             .and_then(|ix| {
                 ix.accounts.first().and_then(|idx| {
                     let idx = *idx as usize;
@@ -361,38 +422,251 @@ impl SanitizedMessage {
 
     /// return detailed signature counts
     pub fn get_signature_details(&self) -> TransactionSignatureDetails {
-        let mut transaction_signature_details = TransactionSignatureDetails {
-            num_transaction_signatures: u64::from(self.header().num_required_signatures),
-            ..TransactionSignatureDetails::default()
+        TransactionSignatureDetails::from_instructions(
+            self.header().num_required_signatures,
+            self.program_instructions_iter(),
+        )
+    }
+
+    /// Return the compute budget details of this message, scanning its
+    /// compute budget instructions on first use only. See
+    /// [`ComputeBudgetDetails::try_from_instructions`].
+    pub fn get_compute_budget_details(&self) -> Result<ComputeBudgetDetails, TransactionError> {
+        let cache = match self {
+            SanitizedMessage::Legacy(message) => &message.compute_budget_details,
+            SanitizedMessage::V0(message) => &message.compute_budget_details,
         };
+        cache
+            .0
+            .get_or_init(|| {
+                ComputeBudgetDetails::try_from_instructions(self.program_instructions_iter())
+            })
+            .clone()
+    }
 
-        // counting the number of pre-processor operations separately
-        for (program_id, instruction) in self.program_instructions_iter() {
-            if secp256k1_program::check_id(program_id) {
-                if let Some(num_verifies) = instruction.data.first() {
-                    transaction_signature_details.num_secp256k1_instruction_signatures =
-                        transaction_signature_details
-                            .num_secp256k1_instruction_signatures
-                            .saturating_add(u64::from(*num_verifies));
+    /// Price of a compute unit in micro-lamports, 0 if the message does not
+    /// set one. See [`SanitizedMessage::get_compute_budget_details`].
+    pub fn compute_unit_price(&self) -> Result<u64, TransactionError> {
+        Ok(self.get_compute_budget_details()?.compute_unit_price)
+    }
+
+    /// Maximum number of compute units the message may consume. See
+    /// [`SanitizedMessage::get_compute_budget_details`].
+    pub fn compute_unit_limit(&self) -> Result<u32, TransactionError> {
+        Ok(self.get_compute_budget_details()?.compute_unit_limit)
+    }
+
+    /// Maximum number of bytes of account data the message may load. See
+    /// [`SanitizedMessage::get_compute_budget_details`].
+    pub fn loaded_accounts_data_size_limit(&self) -> Result<u32, TransactionError> {
+        Ok(self
+            .get_compute_budget_details()?
+            .loaded_accounts_data_size_limit)
+    }
+}
+
+/// An instruction of a [`SanitizedMessage`] with its program id and accounts
+/// resolved to addresses.
+#[derive(Debug, Clone)]
+pub struct ResolvedInstruction<'a> {
+    message: &'a SanitizedMessage,
+    account_keys: AccountKeys<'a>,
+    program_id: &'a Pubkey,
+    instruction: &'a CompiledInstruction,
+}
+
+impl<'a> ResolvedInstruction<'a> {
+    pub fn program_id(&self) -> &'a Pubkey {
+        self.program_id
+    }
+
+    pub fn data(&self) -> &'a [u8] {
+        &self.instruction.data
+    }
+
+    /// The accounts of the instruction, in order, with their address and
+    /// whether they are signers and writable in the message.
+    pub fn accounts(&self) -> impl ExactSizeIterator<Item = BorrowedAccountMeta<'a>> + Clone {
+        let message = self.message;
+        let account_keys = self.account_keys.clone();
+        self.instruction.accounts.iter().map(move |account_index| {
+            let account_index = usize::from(*account_index);
+            BorrowedAccountMeta {
+                pubkey: account_keys
+                    .get(account_index)
+                    .expect("account index is sanitized"),
+                is_signer: message.is_signer(account_index),
+                is_writable: message.is_writable(account_index),
+            }
+        })
+    }
+
+    /// The instruction as compiled in the message.
+    pub fn compiled_instruction(&self) -> &'a CompiledInstruction {
+        self.instruction
+    }
+}
+
+/// The role of an instruction account in a [`SanitizedMessage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstructionAccountRole<'a> {
+    pub pubkey: &'a Pubkey,
+    /// Whether the account signed the message
+    pub is_signer: bool,
+    /// Whether the account is writable, after demotion of reserved and
+    /// invoked accounts
+    pub is_writable: bool,
+    /// Whether the account was loaded from an address lookup table
+    pub is_loaded: bool,
+}
+
+/// Compute units given to each instruction when a message does not set a
+/// compute unit limit.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+/// Maximum compute unit limit a message may set.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Maximum number of bytes of account data a message may load.
+pub const MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES: u32 = 64 * 1024 * 1024;
+const MIN_HEAP_FRAME_BYTES: u32 = 32 * 1024;
+const MAX_HEAP_FRAME_BYTES: u32 = 256 * 1024;
+
+/// Compute budget details of a message, computed on first use.
+///
+/// The cache is derived from the message, so it is ignored when comparing
+/// messages.
+#[derive(Debug, Clone, Default)]
+pub struct ComputeBudgetDetailsCache(OnceLock<Result<ComputeBudgetDetails, TransactionError>>);
+
+impl PartialEq for ComputeBudgetDetailsCache {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for ComputeBudgetDetailsCache {}
+
+/// Compute budget of a message, as set by its compute budget instructions.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ComputeBudgetDetails {
+    compute_unit_price: u64,
+    compute_unit_limit: u32,
+    loaded_accounts_data_size_limit: u32,
+}
+
+impl ComputeBudgetDetails {
+    pub const fn new(
+        compute_unit_price: u64,
+        compute_unit_limit: u32,
+        loaded_accounts_data_size_limit: u32,
+    ) -> Self {
+        Self {
+            compute_unit_price,
+            compute_unit_limit,
+            loaded_accounts_data_size_limit,
+        }
+    }
+
+    /// Scan the compute budget instructions among `instructions`, applying the
+    /// runtime's defaults and caps for values it does not set.
+    ///
+    /// Each kind of compute budget instruction may appear at most once: a
+    /// second one fails with [`TransactionError::DuplicateInstruction`],
+    /// like it does in the runtime. Malformed instructions and invalid heap
+    /// frame requests fail with [`InstructionError::InvalidInstructionData`].
+    ///
+    /// Instructions of other programs are each given the default compute unit
+    /// limit, whether or not they are builtins.
+    pub fn try_from_instructions<'a>(
+        instructions: impl Iterator<Item = (&'a Pubkey, &'a CompiledInstruction)>,
+    ) -> Result<Self, TransactionError> {
+        let mut compute_unit_limit = None;
+        let mut compute_unit_price = None;
+        let mut loaded_accounts_data_size_limit = None;
+        let mut requested_heap_size = None;
+        let mut num_non_compute_budget_instructions: u32 = 0;
+
+        for (index, (program_id, instruction)) in instructions.enumerate() {
+            if !compute_budget::check_id(program_id) {
+                num_non_compute_budget_instructions =
+                    num_non_compute_budget_instructions.saturating_add(1);
+                continue;
+            }
+            let index = index as u8;
+            let invalid_instruction_data = || {
+                TransactionError::InstructionError(index, InstructionError::InvalidInstructionData)
+            };
+            let set_once = |value: &mut Option<u64>, new_value: u64| {
+                if value.replace(new_value).is_some() {
+                    Err(TransactionError::DuplicateInstruction(index))
+                } else {
+                    Ok(())
                 }
-            } else if ed25519_program::check_id(program_id) {
-                if let Some(num_verifies) = instruction.data.first() {
-                    transaction_signature_details.num_ed25519_instruction_signatures =
-                        transaction_signature_details
-                            .num_ed25519_instruction_signatures
-                            .saturating_add(u64::from(*num_verifies));
+            };
+            // Compute budget instructions are a borsh enum of a single integer.
+            // Like the runtime, which decodes them with
+            // `try_from_slice_unchecked`, any bytes after it are ignored.
+            let (&discriminant, payload) = instruction
+                .data
+                .split_first()
+                .ok_or_else(invalid_instruction_data)?;
+            let read_u32 = || {
+                payload
+                    .first_chunk::<4>()
+                    .map(|bytes| u32::from_le_bytes(*bytes))
+                    .ok_or_else(invalid_instruction_data)
+            };
+            match discriminant {
+                1 => {
+                    let bytes = read_u32()?;
+                    if !(MIN_HEAP_FRAME_BYTES..=MAX_HEAP_FRAME_BYTES).contains(&bytes)
+                        || bytes % 1024 != 0
+                    {
+                        return Err(invalid_instruction_data());
+                    }
+                    set_once(&mut requested_heap_size, u64::from(bytes))?;
                 }
-            } else if secp256r1_program::check_id(program_id) {
-                if let Some(num_verifies) = instruction.data.first() {
-                    transaction_signature_details.num_secp256r1_instruction_signatures =
-                        transaction_signature_details
-                            .num_secp256r1_instruction_signatures
-                            .saturating_add(u64::from(*num_verifies));
+                2 => set_once(&mut compute_unit_limit, u64::from(read_u32()?))?,
+                3 => {
+                    let micro_lamports = payload
+                        .first_chunk::<8>()
+                        .map(|bytes| u64::from_le_bytes(*bytes))
+                        .ok_or_else(invalid_instruction_data)?;
+                    set_once(&mut compute_unit_price, micro_lamports)?;
                 }
+                4 => set_once(&mut loaded_accounts_data_size_limit, u64::from(read_u32()?))?,
+                _ => return Err(invalid_instruction_data()),
             }
         }
 
-        transaction_signature_details
+        if loaded_accounts_data_size_limit == Some(0) {
+            return Err(TransactionError::InvalidLoadedAccountsDataSizeLimit);
+        }
+        let default_compute_unit_limit = num_non_compute_budget_instructions
+            .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT);
+        Ok(Self {
+            compute_unit_price: compute_unit_price.unwrap_or_default(),
+            compute_unit_limit: compute_unit_limit
+                .map_or(default_compute_unit_limit, |limit| limit as u32)
+                .min(MAX_COMPUTE_UNIT_LIMIT),
+            loaded_accounts_data_size_limit: loaded_accounts_data_size_limit
+                .map_or(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES, |limit| limit as u32)
+                .min(MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES),
+        })
+    }
+
+    /// return the price of a compute unit in micro-lamports
+    pub fn compute_unit_price(&self) -> u64 {
+        self.compute_unit_price
+    }
+
+    /// return the maximum number of compute units
+    pub fn compute_unit_limit(&self) -> u32 {
+        self.compute_unit_limit
+    }
+
+    /// return the maximum number of bytes of account data to load
+    pub fn loaded_accounts_data_size_limit(&self) -> u32 {
+        self.loaded_accounts_data_size_limit
     }
 }
 
@@ -421,6 +695,46 @@ impl TransactionSignatureDetails {
         }
     }
 
+    /// Count the transaction signatures and the signatures verified by the
+    /// precompile instructions among `instructions`.
+    pub fn from_instructions<'a>(
+        num_required_signatures: u8,
+        instructions: impl Iterator<Item = (&'a Pubkey, &'a CompiledInstruction)>,
+    ) -> Self {
+        let mut transaction_signature_details = TransactionSignatureDetails {
+            num_transaction_signatures: u64::from(num_required_signatures),
+            ..TransactionSignatureDetails::default()
+        };
+
+        // counting the number of pre-processor operations separately
+        for (program_id, instruction) in instructions {
+            if secp256k1_program::check_id(program_id) {
+                if let Some(num_verifies) = instruction.data.first() {
+                    transaction_signature_details.num_secp256k1_instruction_signatures =
+                        transaction_signature_details
+                            .num_secp256k1_instruction_signatures
+                            .saturating_add(u64::from(*num_verifies));
+                }
+            } else if ed25519_program::check_id(program_id) {
+                if let Some(num_verifies) = instruction.data.first() {
+                    transaction_signature_details.num_ed25519_instruction_signatures =
+                        transaction_signature_details
+                            .num_ed25519_instruction_signatures
+                            .saturating_add(u64::from(*num_verifies));
+                }
+            } else if secp256r1_program::check_id(program_id) {
+                if let Some(num_verifies) = instruction.data.first() {
+                    transaction_signature_details.num_secp256r1_instruction_signatures =
+                        transaction_signature_details
+                            .num_secp256r1_instruction_signatures
+                            .saturating_add(u64::from(*num_verifies));
+                }
+            }
+        }
+
+        transaction_signature_details
+    }
+
     /// return total number of signature, treating pre-processor operations as signature
     pub fn total_signatures(&self) -> u64 {
         self.num_transaction_signatures
@@ -633,6 +947,174 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_is_writable_account_cache_demotes_programs() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let reserved_key = Pubkey::new_unique();
+        let message = |extra_key: Pubkey| legacy::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 0,
+            },
+            account_keys: vec![payer, program_id, reserved_key, extra_key],
+            instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![], vec![])],
+            ..legacy::Message::default()
+        };
+        let reserved_account_keys = HashSet::from([reserved_key]);
+
+        let legacy_message =
+            LegacyMessage::new(message(Pubkey::new_unique()), &reserved_account_keys);
+        assert_eq!(
+            legacy_message.is_writable_account_cache,
+            vec![true, false, false, true]
+        );
+
+        // invoked programs are not demoted if the upgradeable loader is present
+        let legacy_message = LegacyMessage::new(
+            message(bpf_loader_upgradeable::id()),
+            &reserved_account_keys,
+        );
+        assert_eq!(
+            legacy_message.is_writable_account_cache,
+            vec![true, true, false, true]
+        );
+    }
+
+    #[test]
+    fn test_get_durable_nonce() {
+        let payer = Pubkey::new_unique();
+        let nonce_account = Pubkey::new_unique();
+        let message = |data: Vec<u8>| {
+            SanitizedMessage::Legacy(LegacyMessage::new(
+                legacy::Message {
+                    header: MessageHeader {
+                        num_required_signatures: 1,
+                        num_readonly_signed_accounts: 0,
+                        num_readonly_unsigned_accounts: 1,
+                    },
+                    account_keys: vec![payer, nonce_account, solana_sdk_ids::system_program::id()],
+                    instructions: vec![CompiledInstruction::new_from_raw_parts(
+                        2,
+                        data,
+                        vec![1, 0],
+                    )],
+                    ..legacy::Message::default()
+                },
+                &HashSet::default(),
+            ))
+        };
+
+        assert_eq!(
+            message(vec![4, 0, 0, 0]).get_durable_nonce(),
+            Some(&nonce_account)
+        );
+        assert_eq!(
+            message(vec![4, 0, 0, 0, 1]).get_durable_nonce(),
+            Some(&nonce_account)
+        );
+        assert_eq!(message(vec![4, 0, 0]).get_durable_nonce(), None);
+        assert_eq!(message(vec![2, 0, 0, 0]).get_durable_nonce(), None);
+    }
+
+    #[test]
+    fn test_resolved_instructions_iter() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let loaded_writable = Pubkey::new_unique();
+        let loaded_readonly = Pubkey::new_unique();
+
+        let message = SanitizedMessage::V0(v0::LoadedMessage::new(
+            v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![payer, program_id],
+                instructions: vec![
+                    CompiledInstruction::new_from_raw_parts(1, vec![7], vec![0, 2, 3]),
+                    CompiledInstruction::new_from_raw_parts(1, vec![], vec![]),
+                ],
+                ..v0::Message::default()
+            },
+            LoadedAddresses {
+                writable: vec![loaded_writable],
+                readonly: vec![loaded_readonly],
+            },
+            &HashSet::default(),
+        ));
+
+        let instructions: Vec<_> = message.resolved_instructions_iter().collect();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[0].program_id(), &program_id);
+        assert_eq!(instructions[0].data(), &[7]);
+        assert_eq!(
+            instructions[0]
+                .accounts()
+                .map(|account| (*account.pubkey, account.is_signer, account.is_writable))
+                .collect::<Vec<_>>(),
+            vec![
+                (payer, true, true),
+                (loaded_writable, false, true),
+                (loaded_readonly, false, false),
+            ]
+        );
+        assert_eq!(instructions[1].accounts().len(), 0);
+        assert_eq!(
+            instructions[1].compiled_instruction(),
+            &message.instructions()[1]
+        );
+    }
+
+    #[test]
+    fn test_instruction_account_roles() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let loaded_writable = Pubkey::new_unique();
+        let loaded_readonly = Pubkey::new_unique();
+
+        let message = SanitizedMessage::V0(v0::LoadedMessage::new(
+            v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![payer, program_id],
+                instructions: vec![CompiledInstruction::new_from_raw_parts(
+                    1,
+                    vec![],
+                    vec![3, 0, 2, 1],
+                )],
+                ..v0::Message::default()
+            },
+            LoadedAddresses {
+                writable: vec![loaded_writable],
+                readonly: vec![loaded_readonly],
+            },
+            &HashSet::default(),
+        ));
+
+        let role = |pubkey, is_signer, is_writable, is_loaded| InstructionAccountRole {
+            pubkey,
+            is_signer,
+            is_writable,
+            is_loaded,
+        };
+        assert_eq!(
+            message.instruction_account_roles(0),
+            Some(vec![
+                role(&loaded_readonly, false, false, true),
+                role(&payer, true, true, false),
+                role(&loaded_writable, false, true, true),
+                role(&program_id, false, false, false),
+            ])
+        );
+        assert_eq!(message.instruction_account_roles(1), None);
+    }
+
     #[test]
     fn test_get_signature_details() {
         let key0 = Pubkey::new_unique();
@@ -676,6 +1158,102 @@ mod tests {
         assert_eq!(5, signature_details.num_ed25519_instruction_signatures);
     }
 
+    #[test]
+    fn test_get_compute_budget_details() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let message = |compute_budget_datas: &[&[u8]]| {
+            let mut instructions: Vec<_> = compute_budget_datas
+                .iter()
+                .map(|data| CompiledInstruction::new_from_raw_parts(2, data.to_vec(), vec![]))
+                .collect();
+            instructions.push(CompiledInstruction::new_from_raw_parts(1, vec![], vec![0]));
+            instructions.push(CompiledInstruction::new_from_raw_parts(1, vec![], vec![0]));
+            SanitizedMessage::try_from_legacy_message(
+                legacy::Message::new_with_compiled_instructions(
+                    1,
+                    0,
+                    2,
+                    vec![payer, program_id, compute_budget::id()],
+                    Hash::default(),
+                    instructions,
+                ),
+                &HashSet::new(),
+            )
+            .unwrap()
+        };
+        let set_compute_unit_limit = [2, 0x40, 0x0d, 0x03, 0x00];
+        let set_compute_unit_price = [3, 5, 0, 0, 0, 0, 0, 0, 0];
+        let set_loaded_accounts_data_size_limit = [4, 0, 0, 1, 0];
+        let request_heap_frame = [1, 0, 0, 1, 0];
+
+        assert_eq!(
+            message(&[]).get_compute_budget_details(),
+            Ok(ComputeBudgetDetails::new(
+                0,
+                2 * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+                MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES,
+            ))
+        );
+        let message_with_budget = message(&[
+            &request_heap_frame,
+            &set_compute_unit_limit,
+            &set_compute_unit_price,
+            &set_loaded_accounts_data_size_limit,
+        ]);
+        assert_eq!(
+            message_with_budget.get_compute_budget_details(),
+            Ok(ComputeBudgetDetails::new(5, 200_000, 65_536))
+        );
+        assert_eq!(message_with_budget.compute_unit_price(), Ok(5));
+        assert_eq!(message_with_budget.compute_unit_limit(), Ok(200_000));
+        assert_eq!(
+            message_with_budget.loaded_accounts_data_size_limit(),
+            Ok(65_536)
+        );
+        let SanitizedMessage::Legacy(legacy_message) = &message_with_budget else {
+            unreachable!()
+        };
+        assert_eq!(
+            legacy_message.compute_budget_details.0.get(),
+            Some(&Ok(ComputeBudgetDetails::new(5, 200_000, 65_536)))
+        );
+
+        assert_eq!(
+            message(&[&[2, 0xff, 0xff, 0xff, 0xff]]).compute_unit_limit(),
+            Ok(MAX_COMPUTE_UNIT_LIMIT)
+        );
+        // trailing bytes are ignored
+        assert_eq!(
+            message(&[&[2, 0x40, 0x0d, 0x03, 0x00, 0xff, 0xff]]).compute_unit_limit(),
+            Ok(200_000)
+        );
+        assert_eq!(
+            message(&[&set_compute_unit_price, &set_compute_unit_price])
+                .get_compute_budget_details(),
+            Err(TransactionError::DuplicateInstruction(1))
+        );
+        for invalid in [
+            &[2, 1, 0][..],
+            &[3, 1, 0, 0, 0],
+            &[0],
+            &[],
+            &[1, 0, 1, 0, 0],
+        ] {
+            assert_eq!(
+                message(&[&set_compute_unit_price, invalid]).get_compute_budget_details(),
+                Err(TransactionError::InstructionError(
+                    1,
+                    InstructionError::InvalidInstructionData
+                ))
+            );
+        }
+        assert_eq!(
+            message(&[&[4, 0, 0, 0, 0]]).get_compute_budget_details(),
+            Err(TransactionError::InvalidLoadedAccountsDataSizeLimit)
+        );
+    }
+
     #[test]
     fn test_static_account_keys() {
         let keys = vec![
@@ -0,0 +1,420 @@
+//! Building a message one instruction at a time.
+
+use {
+    crate::{
+        legacy, v0, AccountKeys, AddressLookupTableAccount, CompileError, CompiledKeys,
+        VersionedMessage,
+    },
+    solana_hash::Hash,
+    solana_instruction::Instruction,
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+    solana_sdk_ids::{
+        address_lookup_table, bpf_loader_upgradeable, ed25519_program, secp256k1_program,
+        secp256r1_program, system_program, sysvar,
+    },
+};
+
+/// Instructions that always invoke the system program, which then has to be
+/// one of their accounts, as the program id and the bincode `u32` tag of the
+/// instruction.
+const SYSTEM_PROGRAM_INVOKERS: [(Pubkey, u32); 2] = [
+    // CreateLookupTable
+    (address_lookup_table::ID, 0),
+    // DeployWithMaxDataLen
+    (bpf_loader_upgradeable::ID, 2),
+];
+
+/// Programs whose instruction data embeds arbitrary bytes, such as the
+/// messages and keys checked by precompiles, and never refers to sysvars.
+const OPAQUE_DATA_PROGRAMS: [Pubkey; 3] = [
+    ed25519_program::ID,
+    secp256k1_program::ID,
+    secp256r1_program::ID,
+];
+
+/// Sysvars that instructions may refer to by id in their data.
+const WELL_KNOWN_SYSVARS: [Pubkey; 12] = [
+    sysvar::clock::ID,
+    sysvar::epoch_rewards::ID,
+    sysvar::epoch_schedule::ID,
+    sysvar::fees::ID,
+    sysvar::instructions::ID,
+    sysvar::last_restart_slot::ID,
+    sysvar::recent_blockhashes::ID,
+    sysvar::rent::ID,
+    sysvar::rewards::ID,
+    sysvar::slot_hashes::ID,
+    sysvar::slot_history::ID,
+    sysvar::stake_history::ID,
+];
+
+/// An account that an instruction is likely to need but does not list,
+/// reported by [`MessageBuilder::missing_accounts`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissingAccount {
+    /// Index of the instruction that does not list the account.
+    pub instruction_index: usize,
+    pub pubkey: Pubkey,
+}
+
+/// Accumulates instructions and compiles them into a legacy or v0 message.
+///
+/// Unlike the message constructors, which compile all instructions at once,
+/// the builder can report the layout of the message before it is compiled:
+/// [`MessageBuilder::compiled_keys`] returns the keys with their roles and
+/// order, and [`MessageBuilder::required_signers`] the keys that have to sign.
+///
+/// Accounts take the roles the instructions give them. Keys marked with
+/// [`MessageBuilder::mark_signer`] or [`MessageBuilder::mark_writable`] also
+/// take that role, and are added to the message even if no instruction refers
+/// to them. Marking never takes a role away.
+///
+/// ```
+/// # use solana_hash::Hash;
+/// # use solana_instruction::{AccountMeta, Instruction};
+/// # use solana_message::MessageBuilder;
+/// # use solana_pubkey::Pubkey;
+/// let payer = Pubkey::new_unique();
+/// let authority = Pubkey::new_unique();
+/// let account = Pubkey::new_unique();
+/// let mut builder = MessageBuilder::new(payer);
+/// builder
+///     .push_instruction(Instruction::new_with_bytes(
+///         Pubkey::new_unique(),
+///         &[],
+///         vec![AccountMeta::new(account, false)],
+///     ))
+///     .mark_signer(authority)
+///     .set_recent_blockhash(Hash::new_unique());
+/// assert_eq!(builder.required_signers(), vec![payer, authority]);
+/// let message = builder.try_build()?;
+/// assert_eq!(message.header().num_required_signatures, 2);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageBuilder {
+    payer: Pubkey,
+    instructions: Vec<Instruction>,
+    signer_overrides: Vec<Pubkey>,
+    writable_overrides: Vec<Pubkey>,
+    recent_blockhash: Hash,
+    /// Candidate tables of a v0 message, `None` for a legacy message.
+    address_lookup_table_accounts: Option<Vec<AddressLookupTableAccount>>,
+}
+
+impl MessageBuilder {
+    /// Start building a legacy message without instructions that is paid
+    /// for by `payer`.
+    pub fn new(payer: Pubkey) -> Self {
+        Self {
+            payer,
+            instructions: vec![],
+            signer_overrides: vec![],
+            writable_overrides: vec![],
+            recent_blockhash: Hash::default(),
+            address_lookup_table_accounts: None,
+        }
+    }
+
+    /// Append `instruction` to the instructions of the message.
+    pub fn push_instruction(&mut self, instruction: Instruction) -> &mut Self {
+        self.instructions.push(instruction);
+        self
+    }
+
+    /// Append `instructions` to the instructions of the message.
+    pub fn extend_instructions(
+        &mut self,
+        instructions: impl IntoIterator<Item = Instruction>,
+    ) -> &mut Self {
+        self.instructions.extend(instructions);
+        self
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// Make `pubkey` sign the message.
+    pub fn mark_signer(&mut self, pubkey: Pubkey) -> &mut Self {
+        self.signer_overrides.push(pubkey);
+        self
+    }
+
+    /// Make `pubkey` writable in the message.
+    pub fn mark_writable(&mut self, pubkey: Pubkey) -> &mut Self {
+        self.writable_overrides.push(pubkey);
+        self
+    }
+
+    pub fn set_recent_blockhash(&mut self, recent_blockhash: Hash) -> &mut Self {
+        self.recent_blockhash = recent_blockhash;
+        self
+    }
+
+    /// Build a legacy message, which is the default.
+    pub fn set_legacy(&mut self) -> &mut Self {
+        self.address_lookup_table_accounts = None;
+        self
+    }
+
+    /// Build a v0 message that loads accounts from the tables
+    /// [`v0::Message::try_compile_with_tables`] picks among
+    /// `address_lookup_table_accounts`.
+    pub fn set_v0(
+        &mut self,
+        address_lookup_table_accounts: Vec<AddressLookupTableAccount>,
+    ) -> &mut Self {
+        self.address_lookup_table_accounts = Some(address_lookup_table_accounts);
+        self
+    }
+
+    /// Returns the keys of the message with their roles, before any of them
+    /// are moved to lookup tables.
+    pub fn compiled_keys(&self) -> CompiledKeys {
+        let mut compiled_keys = CompiledKeys::compile(&self.instructions, Some(self.payer));
+        for pubkey in &self.signer_overrides {
+            compiled_keys.key_meta_mut(*pubkey).is_signer = true;
+        }
+        for pubkey in &self.writable_overrides {
+            compiled_keys.key_meta_mut(*pubkey).is_writable = true;
+        }
+        compiled_keys
+    }
+
+    /// Returns the keys that have to sign the message, in the order of their
+    /// signatures. The payer always comes first.
+    pub fn required_signers(&self) -> Vec<Pubkey> {
+        let compiled_keys = self.compiled_keys();
+        compiled_keys
+            .ordered_keys()
+            .filter(|key| {
+                compiled_keys
+                    .get_key_meta(key)
+                    .is_some_and(|meta| meta.is_signer)
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Compile the instructions into a message.
+    pub fn try_build(&self) -> Result<VersionedMessage, CompileError> {
+        let compiled_keys = self.compiled_keys();
+        match &self.address_lookup_table_accounts {
+            None => {
+                let (header, account_keys) = compiled_keys.try_into_message_components()?;
+                let instructions = AccountKeys::new(&account_keys, None)
+                    .try_compile_instructions(&self.instructions)?;
+                Ok(VersionedMessage::Legacy(legacy::Message {
+                    header,
+                    account_keys,
+                    recent_blockhash: self.recent_blockhash,
+                    instructions,
+                }))
+            }
+            Some(address_lookup_table_accounts) => v0::Message::try_compile_with_keys_and_tables(
+                compiled_keys,
+                &self.instructions,
+                address_lookup_table_accounts,
+                self.recent_blockhash,
+            )
+            .map(VersionedMessage::V0),
+        }
+    }
+
+    /// Returns the program and sysvar accounts the instructions are likely to
+    /// need without listing them, so that they can be reported before the
+    /// message is sent.
+    ///
+    /// Accounts are positional, so they are only reported: the caller has to
+    /// add each one at the place the program expects it.
+    ///
+    /// The address lookup table program's `CreateLookupTable` and the
+    /// upgradeable loader's `DeployWithMaxDataLen` instructions need the
+    /// system program. Instructions whose data contains the id of a
+    /// well-known sysvar are reported as needing that sysvar, except for
+    /// instructions of the precompiles. As the data is not decoded, such an id
+    /// may also be part of an unrelated payload.
+    pub fn missing_accounts(&self) -> Vec<MissingAccount> {
+        let mut missing_accounts = vec![];
+        for (instruction_index, instruction) in self.instructions.iter().enumerate() {
+            let system_program = instruction
+                .data
+                .first_chunk()
+                .map(|tag| u32::from_le_bytes(*tag))
+                .filter(|tag| SYSTEM_PROGRAM_INVOKERS.contains(&(instruction.program_id, *tag)))
+                .map(|_| system_program::ID);
+            let sysvars = WELL_KNOWN_SYSVARS.into_iter().filter(|sysvar| {
+                !OPAQUE_DATA_PROGRAMS.contains(&instruction.program_id)
+                    && instruction
+                        .data
+                        .windows(PUBKEY_BYTES)
+                        .any(|window| window == sysvar.as_ref())
+            });
+            for pubkey in system_program.into_iter().chain(sysvars) {
+                if instruction
+                    .accounts
+                    .iter()
+                    .all(|account| account.pubkey != pubkey)
+                {
+                    missing_accounts.push(MissingAccount {
+                        instruction_index,
+                        pubkey,
+                    });
+                }
+            }
+        }
+        missing_accounts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::MessageHeader, solana_instruction::AccountMeta};
+
+    fn instruction(accounts: Vec<AccountMeta>) -> Instruction {
+        Instruction::new_with_bytes(Pubkey::new_unique(), &[1, 2], accounts)
+    }
+
+    #[test]
+    fn test_try_build_legacy() {
+        let payer = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let instructions = vec![
+            instruction(vec![AccountMeta::new(account, false)]),
+            instruction(vec![AccountMeta::new_readonly(payer, false)]),
+        ];
+        let blockhash = Hash::new_unique();
+
+        let mut builder = MessageBuilder::new(payer);
+        builder
+            .extend_instructions(instructions.clone())
+            .set_recent_blockhash(blockhash);
+        assert_eq!(builder.required_signers(), vec![payer]);
+        assert_eq!(
+            builder.try_build(),
+            Ok(VersionedMessage::Legacy(
+                legacy::Message::new_with_blockhash(&instructions, Some(&payer), &blockhash)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_try_build_with_overrides() {
+        let payer = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let ix = instruction(vec![AccountMeta::new_readonly(account, false)]);
+
+        let mut builder = MessageBuilder::new(payer);
+        builder
+            .push_instruction(ix.clone())
+            .mark_writable(account)
+            .mark_signer(signer);
+        assert_eq!(builder.required_signers(), vec![payer, signer]);
+
+        let message = builder.try_build().unwrap();
+        assert_eq!(
+            message.header(),
+            &MessageHeader {
+                num_required_signatures: 2,
+                num_readonly_signed_accounts: 1,
+                num_readonly_unsigned_accounts: 1,
+            }
+        );
+        assert_eq!(
+            message.static_account_keys(),
+            &[payer, signer, account, ix.program_id]
+        );
+    }
+
+    #[test]
+    fn test_try_build_v0() {
+        let payer = Pubkey::new_unique();
+        let accounts: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: accounts.clone(),
+        };
+        let ix = instruction(
+            accounts
+                .iter()
+                .map(|key| AccountMeta::new(*key, false))
+                .collect(),
+        );
+
+        let mut builder = MessageBuilder::new(payer);
+        builder
+            .push_instruction(ix.clone())
+            .set_v0(vec![table.clone()]);
+        assert_eq!(
+            builder.try_build(),
+            v0::Message::try_compile_with_tables(&payer, &[ix], &[table], Hash::default())
+                .map(VersionedMessage::V0)
+        );
+        assert!(!builder
+            .try_build()
+            .unwrap()
+            .address_table_lookups()
+            .unwrap()
+            .is_empty());
+
+        builder.set_legacy();
+        assert!(matches!(
+            builder.try_build(),
+            Ok(VersionedMessage::Legacy(_))
+        ));
+    }
+
+    #[test]
+    fn test_missing_accounts() {
+        let payer = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let mut sysvar_data = vec![7];
+        sysvar_data.extend_from_slice(sysvar::clock::ID.as_ref());
+        let create_lookup_table_data = 0u32.to_le_bytes();
+        let freeze_lookup_table_data = 1u32.to_le_bytes();
+        let deploy_data = 2u32.to_le_bytes();
+        let instructions = vec![
+            Instruction::new_with_bytes(
+                address_lookup_table::ID,
+                &create_lookup_table_data,
+                vec![AccountMeta::new(account, false)],
+            ),
+            Instruction::new_with_bytes(
+                address_lookup_table::ID,
+                &freeze_lookup_table_data,
+                vec![AccountMeta::new(account, false)],
+            ),
+            Instruction::new_with_bytes(
+                bpf_loader_upgradeable::ID,
+                &deploy_data,
+                vec![AccountMeta::new_readonly(system_program::ID, false)],
+            ),
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &sysvar_data,
+                vec![AccountMeta::new(account, false)],
+            ),
+            Instruction::new_with_bytes(ed25519_program::ID, &sysvar_data, vec![]),
+            instruction(vec![AccountMeta::new(account, false)]),
+        ];
+
+        let mut builder = MessageBuilder::new(payer);
+        builder.extend_instructions(instructions.clone());
+        assert_eq!(
+            builder.missing_accounts(),
+            vec![
+                MissingAccount {
+                    instruction_index: 0,
+                    pubkey: system_program::ID,
+                },
+                MissingAccount {
+                    instruction_index: 3,
+                    pubkey: sysvar::clock::ID,
+                },
+            ]
+        );
+        assert_eq!(builder.instructions(), instructions);
+    }
+}
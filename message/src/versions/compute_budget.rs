@@ -0,0 +1,357 @@
+//! Setting the compute budget of compiled messages.
+//!
+//! Relayers that top up the priority fee of a transaction only need to change
+//! one compute budget instruction. [`VersionedMessage::set_compute_unit_limit`]
+//! and [`VersionedMessage::set_compute_unit_price`] do so on the compiled
+//! message, leaving the other instructions and accounts as they are.
+
+use {
+    crate::{
+        compiled_instruction::CompiledInstruction, compiled_keys::get_compiled_nonce_pubkey,
+        v0::LoadedAddresses, CompileError, VersionedMessage,
+    },
+    solana_sdk_ids::compute_budget,
+};
+
+// inlined to avoid solana-compute-budget-interface dep
+const SET_COMPUTE_UNIT_LIMIT_DISCRIMINANT: u8 = 2;
+const SET_COMPUTE_UNIT_PRICE_DISCRIMINANT: u8 = 3;
+
+impl VersionedMessage {
+    /// Set the compute unit limit of the message to `units`.
+    ///
+    /// See [`VersionedMessage::set_compute_unit_price`] for how the message is
+    /// changed.
+    pub fn set_compute_unit_limit(&mut self, units: u32) -> Result<(), CompileError> {
+        self.set_compute_budget_instruction(compute_unit_limit_data(units), None)
+    }
+
+    /// Set the compute unit limit of the message to `units`, given the
+    /// addresses the message loads from lookup tables.
+    ///
+    /// See [`VersionedMessage::set_compute_unit_price_with_loaded_addresses`].
+    pub fn set_compute_unit_limit_with_loaded_addresses(
+        &mut self,
+        units: u32,
+        loaded_addresses: &LoadedAddresses,
+    ) -> Result<(), CompileError> {
+        self.set_compute_budget_instruction(compute_unit_limit_data(units), Some(loaded_addresses))
+    }
+
+    /// Set the compute unit price of the message to `micro_lamports`.
+    ///
+    /// An existing `SetComputeUnitPrice` instruction is updated in place.
+    /// Otherwise one is inserted as the first instruction, or directly after
+    /// the advance nonce instruction of durable nonce messages, and the
+    /// compute budget program is added to the static account keys if needed.
+    /// All other instructions and accounts are left unchanged.
+    ///
+    /// The compute budget program must not be loaded from a lookup table, which
+    /// is only checked by
+    /// [`VersionedMessage::set_compute_unit_price_with_loaded_addresses`].
+    ///
+    /// Fails with [`CompileError::AccountIndexOverflow`] if the compute budget
+    /// program has to be added to a message that already has as many accounts
+    /// as instructions can index. The message is left unchanged on error.
+    pub fn set_compute_unit_price(&mut self, micro_lamports: u64) -> Result<(), CompileError> {
+        self.set_compute_budget_instruction(compute_unit_price_data(micro_lamports), None)
+    }
+
+    /// Set the compute unit price of the message to `micro_lamports`, given
+    /// the addresses the message loads from lookup tables.
+    ///
+    /// Works like [`VersionedMessage::set_compute_unit_price`], but also fails
+    /// with [`CompileError::DuplicateAccountKey`] if the compute budget program
+    /// has to be added to the static account keys while it is one of
+    /// `loaded_addresses`.
+    pub fn set_compute_unit_price_with_loaded_addresses(
+        &mut self,
+        micro_lamports: u64,
+        loaded_addresses: &LoadedAddresses,
+    ) -> Result<(), CompileError> {
+        self.set_compute_budget_instruction(
+            compute_unit_price_data(micro_lamports),
+            Some(loaded_addresses),
+        )
+    }
+
+    fn set_compute_budget_instruction(
+        &mut self,
+        data: Vec<u8>,
+        loaded_addresses: Option<&LoadedAddresses>,
+    ) -> Result<(), CompileError> {
+        let (header, account_keys, instructions, num_loaded_addresses) = match self {
+            Self::Legacy(message) => (
+                &mut message.header,
+                &mut message.account_keys,
+                &mut message.instructions,
+                0,
+            ),
+            Self::V0(message) => (
+                &mut message.header,
+                &mut message.account_keys,
+                &mut message.instructions,
+                message
+                    .address_table_lookups
+                    .iter()
+                    .map(|lookup| {
+                        lookup
+                            .writable_indexes
+                            .len()
+                            .saturating_add(lookup.readonly_indexes.len())
+                    })
+                    .sum(),
+            ),
+        };
+
+        let program_index = account_keys.iter().position(compute_budget::check_id);
+        if let Some(instruction) = instructions.iter_mut().find(|instruction| {
+            Some(usize::from(instruction.program_id_index)) == program_index
+                && instruction.data.first() == data.first()
+        }) {
+            instruction.data = data;
+            return Ok(());
+        }
+
+        let program_id_index = match program_index {
+            Some(program_index) => program_index as u8,
+            None => {
+                // Append the program as a readonly unsigned static key. Loaded
+                // addresses are indexed after the static keys so references to
+                // them shift by one.
+                if account_keys.len().saturating_add(num_loaded_addresses) > usize::from(u8::MAX) {
+                    return Err(CompileError::AccountIndexOverflow);
+                }
+                if loaded_addresses.is_some_and(|loaded_addresses| {
+                    loaded_addresses
+                        .writable
+                        .iter()
+                        .chain(&loaded_addresses.readonly)
+                        .any(compute_budget::check_id)
+                }) {
+                    return Err(CompileError::DuplicateAccountKey(compute_budget::id()));
+                }
+                let program_index = account_keys.len() as u8;
+                header.num_readonly_unsigned_accounts = header
+                    .num_readonly_unsigned_accounts
+                    .checked_add(1)
+                    .ok_or(CompileError::AccountIndexOverflow)?;
+                for instruction in instructions.iter_mut() {
+                    for index in std::iter::once(&mut instruction.program_id_index)
+                        .chain(instruction.accounts.iter_mut())
+                    {
+                        if *index >= program_index {
+                            *index = index.saturating_add(1);
+                        }
+                    }
+                }
+                account_keys.push(compute_budget::id());
+                program_index
+            }
+        };
+
+        let insert_index =
+            usize::from(get_compiled_nonce_pubkey(account_keys, instructions).is_some());
+        instructions.insert(
+            insert_index.min(instructions.len()),
+            CompiledInstruction::new_from_raw_parts(program_id_index, data, vec![]),
+        );
+        Ok(())
+    }
+}
+
+fn compute_unit_limit_data(units: u32) -> Vec<u8> {
+    [SET_COMPUTE_UNIT_LIMIT_DISCRIMINANT]
+        .into_iter()
+        .chain(units.to_le_bytes())
+        .collect()
+}
+
+fn compute_unit_price_data(micro_lamports: u64) -> Vec<u8> {
+    [SET_COMPUTE_UNIT_PRICE_DISCRIMINANT]
+        .into_iter()
+        .chain(micro_lamports.to_le_bytes())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{
+            legacy,
+            v0::{self, MessageAddressTableLookup},
+            MessageHeader,
+        },
+        solana_hash::Hash,
+        solana_instruction::AccountMeta,
+        solana_pubkey::Pubkey,
+        solana_system_interface::instruction as system_instruction,
+    };
+
+    #[test]
+    fn test_set_compute_unit_price() {
+        let payer = Pubkey::new_unique();
+        let transfer = system_instruction::transfer(&payer, &Pubkey::new_unique(), 42);
+        let mut message = VersionedMessage::Legacy(legacy::Message::new(&[transfer], Some(&payer)));
+        let original = message.clone();
+
+        // Inserted when missing
+        message.set_compute_unit_price(100).unwrap();
+        assert!(message.sanitize().is_ok());
+        assert_eq!(
+            message.static_account_keys().last(),
+            Some(&compute_budget::id())
+        );
+        assert_eq!(message.header().num_readonly_unsigned_accounts, 2);
+        let price_instruction = |micro_lamports: u64| {
+            let mut data = vec![SET_COMPUTE_UNIT_PRICE_DISCRIMINANT];
+            data.extend(micro_lamports.to_le_bytes());
+            CompiledInstruction::new_from_raw_parts(3, data, vec![])
+        };
+        assert_eq!(
+            message.instructions(),
+            &[price_instruction(100), original.instructions()[0].clone()]
+        );
+
+        // Replaced when present
+        let keys = message.static_account_keys().to_vec();
+        message.set_compute_unit_price(200).unwrap();
+        assert_eq!(message.static_account_keys(), keys);
+        assert_eq!(
+            message.instructions(),
+            &[price_instruction(200), original.instructions()[0].clone()]
+        );
+
+        // The limit is another instruction
+        message.set_compute_unit_limit(300).unwrap();
+        assert_eq!(message.static_account_keys(), keys);
+        let mut limit_data = vec![SET_COMPUTE_UNIT_LIMIT_DISCRIMINANT];
+        limit_data.extend(300u32.to_le_bytes());
+        assert_eq!(
+            message.instructions()[0],
+            CompiledInstruction::new_from_raw_parts(3, limit_data, vec![])
+        );
+        assert_eq!(message.instructions().len(), 3);
+    }
+
+    #[test]
+    fn test_set_compute_unit_price_keeps_nonce_first() {
+        let payer = Pubkey::new_unique();
+        let nonce = Pubkey::new_unique();
+        let mut message = VersionedMessage::Legacy(legacy::Message::new_with_nonce(
+            vec![system_instruction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                42,
+            )],
+            Some(&payer),
+            &nonce,
+            &payer,
+        ));
+        let nonce_instruction = message.instructions()[0].clone();
+
+        message.set_compute_unit_price(1).unwrap();
+        assert_eq!(message.instructions()[0], nonce_instruction);
+        assert!(compute_budget::check_id(
+            &message.static_account_keys()[usize::from(message.instructions()[1].program_id_index)]
+        ));
+    }
+
+    #[test]
+    fn test_set_compute_unit_price_shifts_loaded_indexes() {
+        let mut message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                1,
+                vec![],
+                vec![0, 2],
+            )],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+        });
+        message.set_compute_unit_price(5).unwrap();
+        assert!(message.sanitize().is_ok());
+        assert_eq!(message.header().num_readonly_unsigned_accounts, 2);
+        assert_eq!(
+            message.instructions(),
+            &[
+                CompiledInstruction::new_from_raw_parts(
+                    2,
+                    [SET_COMPUTE_UNIT_PRICE_DISCRIMINANT]
+                        .into_iter()
+                        .chain(5u64.to_le_bytes())
+                        .collect(),
+                    vec![]
+                ),
+                CompiledInstruction::new_from_raw_parts(1, vec![], vec![0, 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_compute_unit_price_with_loaded_compute_budget_program() {
+        let mut message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![], vec![2])],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![],
+                readonly_indexes: vec![0],
+            }],
+        });
+        let original = message.clone();
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![],
+            readonly: vec![compute_budget::id()],
+        };
+        assert_eq!(
+            message.set_compute_unit_price_with_loaded_addresses(1, &loaded_addresses),
+            Err(CompileError::DuplicateAccountKey(compute_budget::id()))
+        );
+        assert_eq!(message, original);
+
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![],
+            readonly: vec![Pubkey::new_unique()],
+        };
+        message
+            .set_compute_unit_limit_with_loaded_addresses(1, &loaded_addresses)
+            .unwrap();
+        assert_eq!(message.instructions().len(), 2);
+    }
+
+    #[test]
+    fn test_set_compute_unit_price_with_too_many_accounts() {
+        let payer = Pubkey::new_unique();
+        let accounts = (0..254)
+            .map(|_| AccountMeta::new_readonly(Pubkey::new_unique(), false))
+            .collect();
+        let instruction =
+            solana_instruction::Instruction::new_with_bytes(Pubkey::new_unique(), &[], accounts);
+        let mut message =
+            VersionedMessage::Legacy(legacy::Message::new(&[instruction], Some(&payer)));
+        assert_eq!(message.static_account_keys().len(), 256);
+        let original = message.clone();
+        assert_eq!(
+            message.set_compute_unit_price(1),
+            Err(CompileError::AccountIndexOverflow)
+        );
+        assert_eq!(message, original);
+    }
+}
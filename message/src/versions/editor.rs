@@ -0,0 +1,616 @@
+//! Editing the instructions of a compiled message.
+
+use {
+    crate::{
+        compiled_instruction::CompiledInstruction, legacy, v0, CompileError, MessageHeader,
+        VersionedMessage,
+    },
+    solana_hash::Hash,
+    solana_instruction::Instruction,
+    solana_pubkey::Pubkey,
+    solana_sanitize::SanitizeError,
+    std::collections::{HashMap, HashSet},
+};
+
+/// An account referenced by an instruction being edited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum AccountRef {
+    /// An account stored in the message.
+    Static(Pubkey),
+    /// An account loaded from an address lookup table, identified by the
+    /// position of the lookup in the message, whether it is loaded as
+    /// writable and its index in the table.
+    Loaded {
+        lookup_index: usize,
+        is_writable: bool,
+        table_index: u8,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StaticKey {
+    pubkey: Pubkey,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EditedInstruction {
+    program_id: AccountRef,
+    accounts: Vec<AccountRef>,
+    data: Vec<u8>,
+}
+
+/// Inserts, removes and reorders the instructions of a compiled message.
+///
+/// The account keys, header and address table lookups of the edited message
+/// are recomputed by [`MessageEditor::try_into_message`]:
+///
+/// - Accounts keep the roles they had in the original message. An inserted
+///   instruction may make an account a signer or writable, but never takes
+///   these roles away.
+/// - Signers and the fee payer are always kept. Other accounts are dropped
+///   once no instruction refers to them, including accounts loaded from
+///   lookup tables, and lookups left without accounts are dropped.
+/// - Accounts keep their relative order, so signers keep the order of their
+///   signatures unless an inserted instruction adds one.
+///
+/// Accounts of inserted instructions are always stored in the message. The
+/// message must not also load them from a table, which is only checked if the
+/// loaded addresses are given to [`MessageEditor::new_with_loaded_addresses`].
+///
+/// ```
+/// # use solana_hash::Hash;
+/// # use solana_instruction::Instruction;
+/// # use solana_message::{Message, MessageEditor, VersionedMessage};
+/// # use solana_pubkey::Pubkey;
+/// # let payer = Pubkey::new_unique();
+/// # let instruction = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+/// # let message = VersionedMessage::Legacy(Message::new(&[instruction], Some(&payer)));
+/// let memo = Instruction::new_with_bytes(Pubkey::new_unique(), b"checked", vec![]);
+/// let mut editor = MessageEditor::new(message)?;
+/// editor.push_instruction(&memo);
+/// let message = editor.try_into_message()?;
+/// assert_eq!(message.instructions().len(), 2);
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageEditor {
+    is_legacy: bool,
+    static_keys: Vec<StaticKey>,
+    recent_blockhash: Hash,
+    instructions: Vec<EditedInstruction>,
+    address_table_lookups: Vec<v0::MessageAddressTableLookup>,
+    loaded_keys: Vec<(AccountRef, Pubkey)>,
+}
+
+impl MessageEditor {
+    /// Start editing `message`, which must pass sanitization.
+    pub fn new(message: VersionedMessage) -> Result<Self, SanitizeError> {
+        Self::new_with_optional_loaded_addresses(message, None)
+    }
+
+    /// Start editing `message`, which must pass sanitization, given the
+    /// addresses it loads from lookup tables.
+    ///
+    /// [`MessageEditor::try_into_message`] then fails if an inserted
+    /// instruction refers to an account the edited message also loads from a
+    /// table. Fails if `loaded_addresses` doesn't match the lookups of the
+    /// message.
+    pub fn new_with_loaded_addresses(
+        message: VersionedMessage,
+        loaded_addresses: &v0::LoadedAddresses,
+    ) -> Result<Self, SanitizeError> {
+        Self::new_with_optional_loaded_addresses(message, Some(loaded_addresses))
+    }
+
+    fn new_with_optional_loaded_addresses(
+        message: VersionedMessage,
+        loaded_addresses: Option<&v0::LoadedAddresses>,
+    ) -> Result<Self, SanitizeError> {
+        message.sanitize()?;
+        let (is_legacy, header, account_keys, recent_blockhash, instructions, lookups) =
+            match message {
+                VersionedMessage::Legacy(message) => (
+                    true,
+                    message.header,
+                    message.account_keys,
+                    message.recent_blockhash,
+                    message.instructions,
+                    vec![],
+                ),
+                VersionedMessage::V0(message) => (
+                    false,
+                    message.header,
+                    message.account_keys,
+                    message.recent_blockhash,
+                    message.instructions,
+                    message.address_table_lookups,
+                ),
+            };
+
+        let num_signers = usize::from(header.num_required_signatures);
+        let num_writable_signers =
+            num_signers.saturating_sub(usize::from(header.num_readonly_signed_accounts));
+        let num_writable_keys = account_keys
+            .len()
+            .saturating_sub(usize::from(header.num_readonly_unsigned_accounts));
+        let static_keys: Vec<StaticKey> = account_keys
+            .iter()
+            .enumerate()
+            .map(|(index, pubkey)| StaticKey {
+                pubkey: *pubkey,
+                is_signer: index < num_signers,
+                is_writable: index < num_writable_signers
+                    || (index >= num_signers && index < num_writable_keys),
+            })
+            .collect();
+
+        let loaded_refs: Vec<AccountRef> = [true, false]
+            .into_iter()
+            .flat_map(|is_writable| {
+                lookups
+                    .iter()
+                    .enumerate()
+                    .flat_map(move |(lookup_index, lookup)| {
+                        let table_indexes = if is_writable {
+                            &lookup.writable_indexes
+                        } else {
+                            &lookup.readonly_indexes
+                        };
+                        table_indexes
+                            .iter()
+                            .map(move |table_index| AccountRef::Loaded {
+                                lookup_index,
+                                is_writable,
+                                table_index: *table_index,
+                            })
+                    })
+            })
+            .collect();
+        let loaded_keys = match loaded_addresses {
+            Some(loaded_addresses) => {
+                if loaded_addresses.len() != loaded_refs.len() {
+                    return Err(SanitizeError::InvalidValue);
+                }
+                loaded_refs
+                    .iter()
+                    .copied()
+                    .zip(
+                        loaded_addresses
+                            .writable
+                            .iter()
+                            .chain(&loaded_addresses.readonly)
+                            .copied(),
+                    )
+                    .collect()
+            }
+            None => vec![],
+        };
+        // Sanitization checked that every index is in bounds
+        let account_ref = |index: &u8| {
+            let index = usize::from(*index);
+            static_keys
+                .get(index)
+                .map(|key| AccountRef::Static(key.pubkey))
+                .unwrap_or_else(|| loaded_refs[index.saturating_sub(static_keys.len())])
+        };
+        let instructions = instructions
+            .iter()
+            .map(|instruction| EditedInstruction {
+                program_id: account_ref(&instruction.program_id_index),
+                accounts: instruction.accounts.iter().map(account_ref).collect(),
+                data: instruction.data.clone(),
+            })
+            .collect();
+
+        Ok(Self {
+            is_legacy,
+            static_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups: lookups,
+            loaded_keys,
+        })
+    }
+
+    pub fn num_instructions(&self) -> usize {
+        self.instructions.len()
+    }
+
+    /// Insert `instruction` at position `index`, shifting the instructions
+    /// after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is greater than the number of instructions.
+    pub fn insert_instruction(&mut self, index: usize, instruction: &Instruction) {
+        let instruction = self.add_instruction_accounts(instruction);
+        self.instructions.insert(index, instruction);
+    }
+
+    /// Append `instruction` after the last instruction.
+    pub fn push_instruction(&mut self, instruction: &Instruction) {
+        let instruction = self.add_instruction_accounts(instruction);
+        self.instructions.push(instruction);
+    }
+
+    /// Remove the instruction at position `index`, shifting the instructions
+    /// after it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is out of bounds.
+    pub fn remove_instruction(&mut self, index: usize) {
+        self.instructions.remove(index);
+    }
+
+    /// Move the instruction at position `from` to position `to`, shifting the
+    /// instructions in between.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `from` or `to` is out of bounds.
+    pub fn move_instruction(&mut self, from: usize, to: usize) {
+        let instruction = self.instructions.remove(from);
+        self.instructions.insert(to, instruction);
+    }
+
+    fn add_instruction_accounts(&mut self, instruction: &Instruction) -> EditedInstruction {
+        self.add_static_key(&instruction.program_id, false, false);
+        for account_meta in &instruction.accounts {
+            self.add_static_key(
+                &account_meta.pubkey,
+                account_meta.is_signer,
+                account_meta.is_writable,
+            );
+        }
+        EditedInstruction {
+            program_id: AccountRef::Static(instruction.program_id),
+            accounts: instruction
+                .accounts
+                .iter()
+                .map(|account_meta| AccountRef::Static(account_meta.pubkey))
+                .collect(),
+            data: instruction.data.clone(),
+        }
+    }
+
+    fn add_static_key(&mut self, pubkey: &Pubkey, is_signer: bool, is_writable: bool) {
+        match self
+            .static_keys
+            .iter_mut()
+            .find(|key| key.pubkey == *pubkey)
+        {
+            Some(key) => {
+                key.is_signer |= is_signer;
+                key.is_writable |= is_writable;
+            }
+            None => self.static_keys.push(StaticKey {
+                pubkey: *pubkey,
+                is_signer,
+                is_writable,
+            }),
+        }
+    }
+
+    /// Compile the edited message. Legacy messages stay legacy and v0
+    /// messages stay v0.
+    ///
+    /// Fails with [`CompileError::AccountIndexOverflow`] if the message would
+    /// refer to more accounts than an instruction can index, and with
+    /// [`CompileError::DuplicateAccountKey`] if an account stored in the
+    /// message is also loaded from a table.
+    pub fn try_into_message(self) -> Result<VersionedMessage, CompileError> {
+        let Self {
+            is_legacy,
+            static_keys,
+            recent_blockhash,
+            instructions,
+            address_table_lookups,
+            loaded_keys,
+        } = self;
+
+        let referenced: HashSet<&AccountRef> = instructions
+            .iter()
+            .flat_map(|instruction| {
+                std::iter::once(&instruction.program_id).chain(&instruction.accounts)
+            })
+            .collect();
+
+        // The fee payer stays first
+        let mut static_keys = static_keys.into_iter();
+        let payer = static_keys.next().map(|mut payer| {
+            payer.is_signer = true;
+            payer.is_writable = true;
+            payer
+        });
+        let kept_keys: Vec<StaticKey> = static_keys
+            .filter(|key| key.is_signer || referenced.contains(&AccountRef::Static(key.pubkey)))
+            .collect();
+        let keys_with_role = |is_signer: bool, is_writable: bool| {
+            kept_keys
+                .iter()
+                .filter(move |key| key.is_signer == is_signer && key.is_writable == is_writable)
+        };
+        let try_into_u8 =
+            |num: usize| u8::try_from(num).map_err(|_| CompileError::AccountIndexOverflow);
+        let header = MessageHeader {
+            num_required_signatures: try_into_u8(
+                usize::from(payer.is_some())
+                    .saturating_add(kept_keys.iter().filter(|key| key.is_signer).count()),
+            )?,
+            num_readonly_signed_accounts: try_into_u8(keys_with_role(true, false).count())?,
+            num_readonly_unsigned_accounts: try_into_u8(keys_with_role(false, false).count())?,
+        };
+        let account_keys: Vec<Pubkey> = payer
+            .iter()
+            .chain(keys_with_role(true, true))
+            .chain(keys_with_role(true, false))
+            .chain(keys_with_role(false, true))
+            .chain(keys_with_role(false, false))
+            .map(|key| key.pubkey)
+            .collect();
+        if let Some((_, pubkey)) = loaded_keys.iter().find(|(account_ref, pubkey)| {
+            referenced.contains(account_ref) && account_keys.contains(pubkey)
+        }) {
+            return Err(CompileError::DuplicateAccountKey(*pubkey));
+        }
+
+        let mut address_table_lookups = address_table_lookups;
+        for (lookup_index, lookup) in address_table_lookups.iter_mut().enumerate() {
+            for (is_writable, table_indexes) in [
+                (true, &mut lookup.writable_indexes),
+                (false, &mut lookup.readonly_indexes),
+            ] {
+                table_indexes.retain(|table_index| {
+                    referenced.contains(&AccountRef::Loaded {
+                        lookup_index,
+                        is_writable,
+                        table_index: *table_index,
+                    })
+                });
+            }
+        }
+        let loaded_refs = [true, false].into_iter().flat_map(|is_writable| {
+            address_table_lookups
+                .iter()
+                .enumerate()
+                .flat_map(move |(lookup_index, lookup)| {
+                    let table_indexes = if is_writable {
+                        &lookup.writable_indexes
+                    } else {
+                        &lookup.readonly_indexes
+                    };
+                    table_indexes
+                        .iter()
+                        .map(move |table_index| AccountRef::Loaded {
+                            lookup_index,
+                            is_writable,
+                            table_index: *table_index,
+                        })
+                })
+        });
+
+        let mut account_indexes = HashMap::<AccountRef, u8>::new();
+        for (index, account_ref) in account_keys
+            .iter()
+            .map(|pubkey| AccountRef::Static(*pubkey))
+            .chain(loaded_refs)
+            .enumerate()
+        {
+            account_indexes.insert(account_ref, try_into_u8(index)?);
+        }
+        let instructions = instructions
+            .iter()
+            .map(|instruction| CompiledInstruction {
+                program_id_index: account_indexes[&instruction.program_id],
+                accounts: instruction
+                    .accounts
+                    .iter()
+                    .map(|account_ref| account_indexes[account_ref])
+                    .collect(),
+                data: instruction.data.clone(),
+            })
+            .collect();
+        address_table_lookups.retain(|lookup| {
+            !lookup.writable_indexes.is_empty() || !lookup.readonly_indexes.is_empty()
+        });
+
+        Ok(if is_legacy {
+            VersionedMessage::Legacy(legacy::Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+            })
+        } else {
+            VersionedMessage::V0(v0::Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+                address_table_lookups,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_instruction::AccountMeta,
+        v0::{LoadedAddresses, MessageAddressTableLookup},
+    };
+
+    #[test]
+    fn test_edit_legacy_message() {
+        let payer = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let first = Instruction::new_with_bytes(
+            program_id,
+            &[1],
+            vec![
+                AccountMeta::new(account, false),
+                AccountMeta::new_readonly(signer, true),
+            ],
+        );
+        let second = Instruction::new_with_bytes(program_id, &[2], vec![]);
+        let recent_blockhash = Hash::new_unique();
+        let message =
+            legacy::Message::new_with_blockhash(&[first, second], Some(&payer), &recent_blockhash);
+
+        let memo_program_id = Pubkey::new_unique();
+        let memo = Instruction::new_with_bytes(
+            memo_program_id,
+            b"memo",
+            vec![AccountMeta::new_readonly(account, false)],
+        );
+        let mut editor = MessageEditor::new(VersionedMessage::Legacy(message)).unwrap();
+        assert_eq!(editor.num_instructions(), 2);
+        editor.insert_instruction(1, &memo);
+        editor.move_instruction(2, 0);
+        editor.remove_instruction(1);
+
+        // `account` stays writable and `signer` stays a signer although no
+        // instruction requires it anymore
+        assert_eq!(
+            editor.try_into_message(),
+            Ok(VersionedMessage::Legacy(legacy::Message {
+                header: MessageHeader {
+                    num_required_signatures: 2,
+                    num_readonly_signed_accounts: 1,
+                    num_readonly_unsigned_accounts: 2,
+                },
+                account_keys: vec![payer, signer, account, program_id, memo_program_id],
+                recent_blockhash,
+                instructions: vec![
+                    CompiledInstruction::new_from_raw_parts(3, vec![2], vec![]),
+                    CompiledInstruction::new_from_raw_parts(4, b"memo".to_vec(), vec![2]),
+                ],
+            }))
+        );
+    }
+
+    #[test]
+    fn test_edit_v0_message() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            readonly: vec![Pubkey::new_unique()],
+        };
+        let lookup_table_key = Pubkey::new_unique();
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer, program_id],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![
+                CompiledInstruction::new_from_raw_parts(1, vec![1], vec![2, 4]),
+                CompiledInstruction::new_from_raw_parts(1, vec![2], vec![3]),
+            ],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: lookup_table_key,
+                writable_indexes: vec![7, 8],
+                readonly_indexes: vec![9],
+            }],
+        };
+
+        let new_account = Pubkey::new_unique();
+        let mut editor = MessageEditor::new_with_loaded_addresses(
+            VersionedMessage::V0(message.clone()),
+            &loaded_addresses,
+        )
+        .unwrap();
+        editor.remove_instruction(0);
+        editor.insert_instruction(
+            0,
+            &Instruction::new_with_bytes(
+                program_id,
+                &[3],
+                vec![AccountMeta::new(new_account, false)],
+            ),
+        );
+        assert_eq!(
+            editor.try_into_message(),
+            Ok(VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![payer, new_account, program_id],
+                recent_blockhash: message.recent_blockhash,
+                instructions: vec![
+                    CompiledInstruction::new_from_raw_parts(2, vec![3], vec![1]),
+                    CompiledInstruction::new_from_raw_parts(2, vec![2], vec![3]),
+                ],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: lookup_table_key,
+                    writable_indexes: vec![8],
+                    readonly_indexes: vec![],
+                }],
+            }))
+        );
+
+        // Removing every instruction that loads from the table drops it
+        let mut editor = MessageEditor::new(VersionedMessage::V0(message.clone())).unwrap();
+        editor.remove_instruction(1);
+        editor.remove_instruction(0);
+        let VersionedMessage::V0(edited) = editor.try_into_message().unwrap() else {
+            panic!("expected a v0 message");
+        };
+        assert_eq!(edited.account_keys, vec![payer]);
+        assert_eq!(edited.address_table_lookups, vec![]);
+        // Inserted accounts must not be loaded from the table, unless no
+        // instruction refers to the loaded account anymore
+        let loaded_account = loaded_addresses.writable[0];
+        let instruction = Instruction::new_with_bytes(
+            program_id,
+            &[4],
+            vec![AccountMeta::new(loaded_account, false)],
+        );
+        let mut editor = MessageEditor::new_with_loaded_addresses(
+            VersionedMessage::V0(message.clone()),
+            &loaded_addresses,
+        )
+        .unwrap();
+        editor.push_instruction(&instruction);
+        assert_eq!(
+            editor.clone().try_into_message(),
+            Err(CompileError::DuplicateAccountKey(loaded_account))
+        );
+        editor.remove_instruction(0);
+        assert!(editor.try_into_message().is_ok());
+
+        assert_eq!(
+            MessageEditor::new_with_loaded_addresses(
+                VersionedMessage::V0(message),
+                &LoadedAddresses::default(),
+            ),
+            Err(SanitizeError::InvalidValue)
+        );
+    }
+
+    #[test]
+    fn test_new_rejects_unsanitized_message() {
+        let message = legacy::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 0,
+            },
+            account_keys: vec![Pubkey::new_unique()],
+            recent_blockhash: Hash::default(),
+            instructions: vec![CompiledInstruction::new_from_raw_parts(5, vec![], vec![])],
+        };
+        assert!(MessageEditor::new(VersionedMessage::Legacy(message)).is_err());
+    }
+}
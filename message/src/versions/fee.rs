@@ -0,0 +1,157 @@
+//! Fee estimation for versioned messages.
+
+use {
+    crate::{ComputeBudgetDetails, TransactionSignatureDetails, VersionedMessage},
+    solana_transaction_error::TransactionError,
+};
+
+/// Number of micro-lamports in a lamport.
+const MICRO_LAMPORTS_PER_LAMPORT: u128 = 1_000_000;
+
+/// The components of the fee of a transaction.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeEstimate {
+    /// Number of signatures charged for, including those verified by
+    /// precompile instructions.
+    pub num_signatures: u64,
+    /// Fee charged for the signatures, in lamports.
+    pub signature_fee: u64,
+    /// Compute unit limit of the transaction.
+    pub compute_unit_limit: u32,
+    /// Price of a compute unit, in micro-lamports.
+    pub compute_unit_price: u64,
+    /// Fee paid for prioritization, in lamports.
+    pub prioritization_fee: u64,
+}
+
+impl FeeEstimate {
+    /// Total fee, in lamports.
+    pub fn total(&self) -> u64 {
+        self.signature_fee.saturating_add(self.prioritization_fee)
+    }
+}
+
+impl VersionedMessage {
+    /// Estimate the fee of a transaction carrying this message when each
+    /// signature costs `lamports_per_signature`.
+    ///
+    /// Signatures verified by precompile instructions are charged like
+    /// transaction signatures. The prioritization fee is the compute unit
+    /// limit times the compute unit price, rounded up to the next lamport.
+    ///
+    /// Fails if the message does not pass sanitization or if its compute
+    /// budget instructions are invalid.
+    pub fn estimate_fee(
+        &self,
+        lamports_per_signature: u64,
+    ) -> Result<FeeEstimate, TransactionError> {
+        self.sanitize()?;
+        let static_account_keys = self.static_account_keys();
+        let program_instructions = || {
+            self.instructions().iter().map(|instruction| {
+                (
+                    &static_account_keys[usize::from(instruction.program_id_index)],
+                    instruction,
+                )
+            })
+        };
+
+        let signature_details = TransactionSignatureDetails::from_instructions(
+            self.header().num_required_signatures,
+            program_instructions(),
+        );
+        let compute_budget_details =
+            ComputeBudgetDetails::try_from_instructions(program_instructions())?;
+
+        let num_signatures = signature_details.total_signatures();
+        let compute_unit_limit = compute_budget_details.compute_unit_limit();
+        let compute_unit_price = compute_budget_details.compute_unit_price();
+        let prioritization_fee = u128::from(compute_unit_limit)
+            .saturating_mul(u128::from(compute_unit_price))
+            .div_ceil(MICRO_LAMPORTS_PER_LAMPORT);
+        Ok(FeeEstimate {
+            num_signatures,
+            signature_fee: num_signatures.saturating_mul(lamports_per_signature),
+            compute_unit_limit,
+            compute_unit_price,
+            prioritization_fee: u64::try_from(prioritization_fee).unwrap_or(u64::MAX),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{compiled_instruction::CompiledInstruction, legacy, MessageHeader},
+        solana_hash::Hash,
+        solana_pubkey::Pubkey,
+        solana_sdk_ids::{compute_budget, ed25519_program},
+    };
+
+    fn message(instructions: Vec<CompiledInstruction>) -> VersionedMessage {
+        VersionedMessage::Legacy(legacy::Message {
+            header: MessageHeader {
+                num_required_signatures: 2,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 2,
+            },
+            account_keys: vec![
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                compute_budget::id(),
+                ed25519_program::id(),
+            ],
+            recent_blockhash: Hash::default(),
+            instructions,
+        })
+    }
+
+    #[test]
+    fn test_estimate_fee() {
+        let message = message(vec![
+            CompiledInstruction::new_from_raw_parts(2, vec![2, 0xa0, 0x86, 0x01, 0x00], vec![]),
+            CompiledInstruction::new_from_raw_parts(2, vec![3, 0x15, 0, 0, 0, 0, 0, 0, 0], vec![]),
+            CompiledInstruction::new_from_raw_parts(3, vec![3, 0], vec![]),
+        ]);
+        let fee = message.estimate_fee(5_000).unwrap();
+        assert_eq!(
+            fee,
+            FeeEstimate {
+                num_signatures: 5,
+                signature_fee: 25_000,
+                compute_unit_limit: 100_000,
+                compute_unit_price: 21,
+                // 2.1 lamports, rounded up
+                prioritization_fee: 3,
+            }
+        );
+        assert_eq!(fee.total(), 25_003);
+    }
+
+    #[test]
+    fn test_estimate_fee_without_compute_budget() {
+        let fee = message(vec![]).estimate_fee(5_000).unwrap();
+        assert_eq!(fee.signature_fee, 10_000);
+        assert_eq!(fee.prioritization_fee, 0);
+        assert_eq!(fee.total(), 10_000);
+    }
+
+    #[test]
+    fn test_estimate_fee_invalid() {
+        let duplicate_price =
+            vec![
+                CompiledInstruction::new_from_raw_parts(2, vec![3, 1, 0, 0, 0, 0, 0, 0, 0], vec![]);
+                2
+            ];
+        assert_eq!(
+            message(duplicate_price).estimate_fee(5_000),
+            Err(TransactionError::DuplicateInstruction(1))
+        );
+        let out_of_bounds = vec![CompiledInstruction::new_from_raw_parts(4, vec![], vec![])];
+        assert_eq!(
+            message(out_of_bounds).estimate_fee(5_000),
+            Err(TransactionError::SanitizeFailure)
+        );
+    }
+}
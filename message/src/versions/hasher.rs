@@ -0,0 +1,64 @@
+//! Hashing messages with a pluggable hash function.
+
+#[cfg(feature = "blake3")]
+use crate::VersionedMessage;
+use solana_hash::Hash;
+
+/// Computes the hash that identifies a message from its serialized bytes.
+///
+/// [`Blake3MessageHasher`] computes the hash the runtime expects. Other
+/// implementations can compute the same hash with another blake3
+/// implementation, such as a hardware-accelerated one, in builds without the
+/// `blake3` feature. Closures taking the message bytes are hashers too.
+pub trait MessageHasher {
+    fn hash_raw_message(&self, message_bytes: &[u8]) -> Hash;
+}
+
+impl<F: Fn(&[u8]) -> Hash> MessageHasher for F {
+    fn hash_raw_message(&self, message_bytes: &[u8]) -> Hash {
+        self(message_bytes)
+    }
+}
+
+/// The default [`MessageHasher`], which hashes with
+/// [`VersionedMessage::hash_raw_message`].
+#[cfg(feature = "blake3")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Blake3MessageHasher;
+
+#[cfg(feature = "blake3")]
+impl MessageHasher for Blake3MessageHasher {
+    fn hash_raw_message(&self, message_bytes: &[u8]) -> Hash {
+        VersionedMessage::hash_raw_message(message_bytes)
+    }
+}
+
+#[cfg(all(test, feature = "bincode", feature = "blake3"))]
+mod tests {
+    use {
+        super::*,
+        crate::{legacy, v0},
+        solana_pubkey::Pubkey,
+    };
+
+    #[test]
+    fn test_hash_with() {
+        let message = VersionedMessage::V0(v0::Message {
+            account_keys: vec![Pubkey::new_unique()],
+            ..v0::Message::default()
+        });
+        assert_eq!(message.hash_with(&Blake3MessageHasher), message.hash());
+
+        let message_len = |bytes: &[u8]| Hash::new_from_array([bytes.len() as u8; 32]);
+        assert_eq!(
+            message.hash_with(&message_len),
+            Hash::new_from_array([message.serialize().len() as u8; 32])
+        );
+
+        let legacy_message = VersionedMessage::Legacy(legacy::Message::default());
+        assert_ne!(
+            legacy_message.hash_with(&Blake3MessageHasher),
+            message.hash_with(&Blake3MessageHasher)
+        );
+    }
+}
@@ -0,0 +1,131 @@
+//! Usage statistics of address lookup tables.
+//!
+//! Shared lookup tables accumulate addresses that no transaction loads
+//! anymore. [`lookup_table_usage`] reports which indexes of each table a batch
+//! of v0 messages loads and how many bytes the lookups save, so that operators
+//! can find the entries worth keeping.
+
+use {
+    super::short_vec_bytes_len,
+    crate::v0,
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+    std::collections::{BTreeMap, HashMap},
+};
+
+/// How a batch of messages uses one address lookup table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LookupTableUsage {
+    /// Number of address table lookups of the table.
+    pub num_lookups: usize,
+    /// Number of times each index is loaded, as writable or readonly.
+    pub index_counts: BTreeMap<u8, usize>,
+    /// Bytes the lookups save over passing the loaded addresses as static
+    /// account keys, negative if they cost more than they save. The change
+    /// in the length prefixes of the message account keys and lookups is
+    /// not counted.
+    pub bytes_saved: i64,
+}
+
+impl LookupTableUsage {
+    /// Iterate over the indexes of a table of `table_len` addresses that no
+    /// lookup loads.
+    pub fn unused_indexes(&self, table_len: usize) -> impl Iterator<Item = u8> + '_ {
+        (0..=u8::MAX)
+            .take(table_len)
+            .filter(|index| !self.index_counts.contains_key(index))
+    }
+}
+
+/// Collect the usage of each address lookup table that `messages` load
+/// addresses from, keyed by table address.
+pub fn lookup_table_usage<'a>(
+    messages: impl IntoIterator<Item = &'a v0::Message>,
+) -> HashMap<Pubkey, LookupTableUsage> {
+    let mut usage = HashMap::<Pubkey, LookupTableUsage>::new();
+    for lookup in messages
+        .into_iter()
+        .flat_map(|message| &message.address_table_lookups)
+    {
+        let table_usage = usage.entry(lookup.account_key).or_default();
+        table_usage.num_lookups = table_usage.num_lookups.saturating_add(1);
+        for index in lookup
+            .writable_indexes
+            .iter()
+            .chain(&lookup.readonly_indexes)
+        {
+            let count = table_usage.index_counts.entry(*index).or_default();
+            *count = count.saturating_add(1);
+        }
+
+        let num_loaded = lookup
+            .writable_indexes
+            .len()
+            .saturating_add(lookup.readonly_indexes.len());
+        let static_keys_size = num_loaded.saturating_mul(PUBKEY_BYTES);
+        let lookup_size = PUBKEY_BYTES
+            .saturating_add(short_vec_bytes_len(lookup.writable_indexes.len()))
+            .saturating_add(short_vec_bytes_len(lookup.readonly_indexes.len()));
+        table_usage.bytes_saved = table_usage
+            .bytes_saved
+            .saturating_add(static_keys_size as i64)
+            .saturating_sub(lookup_size as i64);
+    }
+    usage
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::v0::MessageAddressTableLookup};
+
+    fn message(lookups: &[(Pubkey, Vec<u8>, Vec<u8>)]) -> v0::Message {
+        v0::Message {
+            address_table_lookups: lookups
+                .iter()
+                .map(|(account_key, writable_indexes, readonly_indexes)| {
+                    MessageAddressTableLookup {
+                        account_key: *account_key,
+                        writable_indexes: writable_indexes.clone(),
+                        readonly_indexes: readonly_indexes.clone(),
+                    }
+                })
+                .collect(),
+            ..v0::Message::default()
+        }
+    }
+
+    #[test]
+    fn test_lookup_table_usage() {
+        let table_a = Pubkey::new_unique();
+        let table_b = Pubkey::new_unique();
+        let messages = [
+            message(&[(table_a, vec![0, 2], vec![3]), (table_b, vec![], vec![1])]),
+            message(&[(table_a, vec![2], vec![])]),
+            message(&[]),
+        ];
+
+        let usage = lookup_table_usage(&messages);
+        assert_eq!(usage.len(), 2);
+        assert_eq!(
+            usage[&table_a],
+            LookupTableUsage {
+                num_lookups: 2,
+                index_counts: BTreeMap::from([(0, 1), (2, 2), (3, 1)]),
+                // 3 loaded addresses for 32 + 3 + 2 bytes, then 1 for 32 + 2 + 1
+                bytes_saved: (3 * 32 - 37) + (32 - 35),
+            }
+        );
+        assert_eq!(
+            usage[&table_b],
+            LookupTableUsage {
+                num_lookups: 1,
+                index_counts: BTreeMap::from([(1, 1)]),
+                bytes_saved: 32 - 35,
+            }
+        );
+        assert_eq!(
+            usage[&table_a].unused_indexes(5).collect::<Vec<_>>(),
+            vec![1, 4]
+        );
+        assert_eq!(usage[&table_b].unused_indexes(300).count(), 255);
+    }
+}
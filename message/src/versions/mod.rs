@@ -1,14 +1,16 @@
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(feature = "frozen-abi")]
 use solana_frozen_abi_macro::{frozen_abi, AbiEnumVisitor, AbiExample};
 use {
     crate::{
         compiled_instruction::CompiledInstruction, legacy::Message as LegacyMessage,
-        v0::MessageAddressTableLookup, MessageHeader,
+        v0::MessageAddressTableLookup, MessageHeader, MESSAGE_HEADER_LENGTH,
     },
-    solana_hash::Hash,
-    solana_pubkey::Pubkey,
+    solana_hash::{Hash, HASH_BYTES},
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
     solana_sanitize::{Sanitize, SanitizeError},
-    std::collections::HashSet,
+    std::{collections::HashSet, fmt},
 };
 #[cfg(feature = "serde")]
 use {
@@ -17,17 +19,94 @@ use {
         ser::{SerializeTuple, Serializer},
     },
     serde_derive::{Deserialize, Serialize},
-    std::fmt,
 };
 
+mod builder;
+mod compute_budget;
+mod editor;
+mod fee;
+mod hasher;
+mod lookup_usage;
+#[cfg(feature = "unknown-versions")]
+mod relay;
 mod sanitized;
+mod signers;
+mod size_plan;
 pub mod v0;
 
-pub use sanitized::*;
+#[cfg(feature = "unknown-versions")]
+pub use relay::*;
+pub use {
+    builder::*, editor::*, fee::*, hasher::*, lookup_usage::*, sanitized::*, signers::*,
+    size_plan::*,
+};
 
 /// Bit mask that indicates whether a serialized message is versioned.
 pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
 
+// inlined to avoid solana-signature dep
+const SIGNATURE_BYTES: usize = 64;
+
+/// The buffer passed to [`VersionedMessage::serialize_into`] is too small to
+/// hold the message.
+#[derive(PartialEq, Debug, Eq, Clone)]
+pub struct BufferTooSmallError {
+    /// Serialized size of the message
+    pub required: usize,
+    /// Length of the buffer
+    pub available: usize,
+}
+
+impl std::error::Error for BufferTooSmallError {}
+
+impl fmt::Display for BufferTooSmallError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_fmt(format_args!(
+            "message of {} bytes does not fit in a buffer of {} bytes",
+            self.required, self.available
+        ))
+    }
+}
+
+/// Number of bytes used by the compact-u16 encoding of `len`.
+fn short_vec_encoded_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+/// Number of bytes used by a compact-u16 length prefixed byte vector.
+fn short_vec_bytes_len(len: usize) -> usize {
+    short_vec_encoded_len(len).saturating_add(len)
+}
+
+/// Number of bytes used by a compact-u16 length prefixed list of
+/// instructions.
+fn instructions_serialized_size(instructions: &[CompiledInstruction]) -> usize {
+    instructions.iter().fold(
+        short_vec_encoded_len(instructions.len()),
+        |size, instruction| {
+            size.saturating_add(1) // program_id_index
+                .saturating_add(short_vec_bytes_len(instruction.accounts.len()))
+                .saturating_add(short_vec_bytes_len(instruction.data.len()))
+        },
+    )
+}
+
+/// Number of bytes used by a compact-u16 length prefixed list of address
+/// table lookups.
+fn lookups_serialized_size(lookups: &[MessageAddressTableLookup]) -> usize {
+    lookups
+        .iter()
+        .fold(short_vec_encoded_len(lookups.len()), |size, lookup| {
+            size.saturating_add(PUBKEY_BYTES)
+                .saturating_add(short_vec_bytes_len(lookup.writable_indexes.len()))
+                .saturating_add(short_vec_bytes_len(lookup.readonly_indexes.len()))
+        })
+}
+
 /// Either a legacy message or a v0 message.
 ///
 /// # Serialization
@@ -36,11 +115,19 @@ pub const MESSAGE_VERSION_PREFIX: u8 = 0x80;
 /// which message version is serialized starting from version `0`. If the first
 /// is bit is not set, all bytes are used to encode the legacy `Message`
 /// format.
+///
+/// The borsh encoding does not follow this scheme: it is the standard borsh
+/// enum encoding, a one byte variant index followed by the message.
 #[cfg_attr(
     feature = "frozen-abi",
     frozen_abi(digest = "2RTtea34NPrb8p9mWHCWjFh76cwP3MbjSmeoj5CXEBwN"),
     derive(AbiEnumVisitor, AbiExample)
 )]
+#[cfg_attr(
+    feature = "borsh",
+    derive(BorshSerialize, BorshDeserialize),
+    borsh(crate = "borsh")
+)]
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum VersionedMessage {
     Legacy(LegacyMessage),
@@ -55,6 +142,15 @@ impl VersionedMessage {
         }
     }
 
+    /// Returns the version of a versioned message, or `None` for a legacy
+    /// message.
+    pub fn version(&self) -> Option<u8> {
+        match self {
+            Self::Legacy(_) => None,
+            Self::V0(_) => Some(0),
+        }
+    }
+
     pub fn header(&self) -> &MessageHeader {
         match self {
             Self::Legacy(message) => &message.header,
@@ -145,16 +241,105 @@ impl VersionedMessage {
         }
     }
 
+    /// Convert the message to an equivalent legacy message, which is possible
+    /// for legacy messages and for v0 messages that load no addresses from
+    /// lookup tables. Otherwise the message is returned unchanged.
+    ///
+    /// The legacy message has the same account keys, header and instructions,
+    /// but it serializes differently, so signatures of the original message
+    /// do not verify against it.
+    pub fn try_into_legacy(self) -> Result<LegacyMessage, Self> {
+        match self {
+            Self::Legacy(message) => Ok(message),
+            Self::V0(message) if message.address_table_lookups.is_empty() => {
+                let v0::Message {
+                    header,
+                    account_keys,
+                    recent_blockhash,
+                    instructions,
+                    address_table_lookups: _,
+                } = message;
+                Ok(LegacyMessage {
+                    header,
+                    account_keys,
+                    recent_blockhash,
+                    instructions,
+                })
+            }
+            message => Err(message),
+        }
+    }
+
+    /// Clone the message with the program id `from` replaced by `to`.
+    ///
+    /// The static account key `from` is replaced in place, so the header and
+    /// all account and program id indexes of the instructions are unchanged.
+    /// Returns `None` if `to` is already a static account key of the message.
+    /// A message without the key `from` is cloned unchanged.
+    pub fn with_replaced_program_id(&self, from: &Pubkey, to: &Pubkey) -> Option<Self> {
+        let mut message = self.clone();
+        let account_keys = match &mut message {
+            Self::Legacy(message) => &mut message.account_keys,
+            Self::V0(message) => &mut message.account_keys,
+        };
+        if from != to && account_keys.contains(to) {
+            return None;
+        }
+        for key in account_keys.iter_mut().filter(|key| *key == from) {
+            *key = *to;
+        }
+        Some(message)
+    }
+
+    /// Compute the exact number of bytes this message occupies on the wire
+    /// without serializing it.
+    pub fn serialized_size(&self) -> usize {
+        match self {
+            Self::Legacy(message) => VersionedMessageRef::Legacy(message).serialized_size(),
+            Self::V0(message) => VersionedMessageRef::V0(message).serialized_size(),
+        }
+    }
+
+    /// Compute the exact number of bytes a transaction carrying this message
+    /// and `num_signatures` signatures occupies on the wire, without
+    /// serializing it.
+    pub fn serialized_size_with_signatures(&self, num_signatures: usize) -> usize {
+        short_vec_encoded_len(num_signatures)
+            .saturating_add(num_signatures.saturating_mul(SIGNATURE_BYTES))
+            .saturating_add(self.serialized_size())
+    }
+
     #[cfg(feature = "bincode")]
     pub fn serialize(&self) -> Vec<u8> {
         bincode::serialize(self).unwrap()
     }
 
+    #[cfg(feature = "bincode")]
+    /// Serialize this message into the start of `buffer` without allocating,
+    /// returning the number of bytes written. Nothing is written if the
+    /// message does not fit.
+    pub fn serialize_into(&self, buffer: &mut [u8]) -> Result<usize, BufferTooSmallError> {
+        let required = self.serialized_size();
+        let available = buffer.len();
+        let message_buffer = buffer.get_mut(..required).ok_or(BufferTooSmallError {
+            required,
+            available,
+        })?;
+        bincode::serialize_into(message_buffer, self).unwrap();
+        Ok(required)
+    }
+
     #[cfg(all(feature = "bincode", feature = "blake3"))]
     /// Compute the blake3 hash of this transaction's message
     pub fn hash(&self) -> Hash {
+        self.hash_with(&Blake3MessageHasher)
+    }
+
+    #[cfg(feature = "bincode")]
+    /// Compute the hash of this transaction's message with `hasher`
+    pub fn hash_with(&self, hasher: &impl MessageHasher) -> Hash {
         let message_bytes = self.serialize();
-        Self::hash_raw_message(&message_bytes)
+        hasher.hash_raw_message(&message_bytes)
     }
 
     #[cfg(feature = "blake3")]
@@ -175,8 +360,58 @@ impl Default for VersionedMessage {
     }
 }
 
+/// A borrowed view of a legacy or v0 message that serializes exactly like
+/// the equivalent [`VersionedMessage`], without cloning the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VersionedMessageRef<'a> {
+    Legacy(&'a LegacyMessage),
+    V0(&'a v0::Message),
+}
+
+impl VersionedMessageRef<'_> {
+    /// Compute the exact number of bytes this message occupies on the wire
+    /// without serializing it.
+    pub fn serialized_size(&self) -> usize {
+        let header_and_keys_size = |account_keys: &[Pubkey]| {
+            MESSAGE_HEADER_LENGTH
+                .saturating_add(short_vec_encoded_len(account_keys.len()))
+                .saturating_add(account_keys.len().saturating_mul(PUBKEY_BYTES))
+                .saturating_add(HASH_BYTES)
+        };
+
+        match self {
+            Self::Legacy(message) => header_and_keys_size(&message.account_keys)
+                .saturating_add(instructions_serialized_size(&message.instructions)),
+            Self::V0(message) => {
+                1usize // version prefix
+                    .saturating_add(header_and_keys_size(&message.account_keys))
+                    .saturating_add(instructions_serialized_size(&message.instructions))
+                    .saturating_add(lookups_serialized_size(&message.address_table_lookups))
+            }
+        }
+    }
+
+    /// Compute the exact number of bytes a transaction carrying this message
+    /// and `num_signatures` signatures occupies on the wire, without
+    /// serializing it.
+    pub fn serialized_size_with_signatures(&self, num_signatures: usize) -> usize {
+        short_vec_encoded_len(num_signatures)
+            .saturating_add(num_signatures.saturating_mul(SIGNATURE_BYTES))
+            .saturating_add(self.serialized_size())
+    }
+}
+
+impl From<VersionedMessageRef<'_>> for VersionedMessage {
+    fn from(message: VersionedMessageRef<'_>) -> Self {
+        match message {
+            VersionedMessageRef::Legacy(message) => Self::Legacy(message.clone()),
+            VersionedMessageRef::V0(message) => Self::V0(message.clone()),
+        }
+    }
+}
+
 #[cfg(feature = "serde")]
-impl serde::Serialize for VersionedMessage {
+impl serde::Serialize for VersionedMessageRef<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
@@ -197,6 +432,23 @@ impl serde::Serialize for VersionedMessage {
     }
 }
 
+#[cfg(feature = "serde")]
+impl serde::Serialize for VersionedMessage {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Self::Legacy(message) => {
+                serde::Serialize::serialize(&VersionedMessageRef::Legacy(message), serializer)
+            }
+            Self::V0(message) => {
+                serde::Serialize::serialize(&VersionedMessageRef::V0(message), serializer)
+            }
+        }
+    }
+}
+
 #[cfg(feature = "serde")]
 enum MessagePrefix {
     Legacy(u8),
@@ -382,6 +634,194 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_into_legacy() {
+        let payer = Pubkey::new_unique();
+        let instructions = [Instruction::new_with_bincode(
+            Pubkey::new_unique(),
+            &0,
+            vec![
+                AccountMeta::new(Pubkey::new_unique(), false),
+                AccountMeta::new_readonly(Pubkey::new_unique(), true),
+            ],
+        )];
+        let recent_blockhash = Hash::new_unique();
+
+        let legacy_message =
+            LegacyMessage::new_with_blockhash(&instructions, Some(&payer), &recent_blockhash);
+        assert_eq!(
+            VersionedMessage::Legacy(legacy_message.clone()).try_into_legacy(),
+            Ok(legacy_message.clone())
+        );
+
+        let v0_message =
+            v0::Message::try_compile(&payer, &instructions, &[], recent_blockhash).unwrap();
+        assert_eq!(
+            VersionedMessage::V0(v0_message.clone()).try_into_legacy(),
+            Ok(legacy_message)
+        );
+
+        let mut v0_message = v0_message;
+        v0_message
+            .address_table_lookups
+            .push(MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            });
+        let message = VersionedMessage::V0(v0_message);
+        assert_eq!(message.clone().try_into_legacy(), Err(message));
+    }
+
+    #[test]
+    fn test_with_replaced_program_id() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let account = Pubkey::new_unique();
+        let message = VersionedMessage::Legacy(LegacyMessage::new(
+            &[
+                Instruction::new_with_bytes(
+                    program_id,
+                    &[1],
+                    vec![AccountMeta::new(account, false)],
+                ),
+                Instruction::new_with_bytes(Pubkey::new_unique(), &[2], vec![]),
+            ],
+            Some(&payer),
+        ));
+
+        let forked_program_id = Pubkey::new_unique();
+        let forked_message = message
+            .with_replaced_program_id(&program_id, &forked_program_id)
+            .unwrap();
+        let mut expected_account_keys = message.static_account_keys().to_vec();
+        let program_index = usize::from(message.instructions()[0].program_id_index);
+        expected_account_keys[program_index] = forked_program_id;
+        assert_eq!(forked_message.static_account_keys(), expected_account_keys);
+        assert_eq!(forked_message.header(), message.header());
+        assert_eq!(forked_message.instructions(), message.instructions());
+
+        assert_eq!(
+            message.with_replaced_program_id(&Pubkey::new_unique(), &forked_program_id),
+            Some(message.clone())
+        );
+        assert_eq!(
+            message.with_replaced_program_id(&program_id, &account),
+            None
+        );
+    }
+
+    #[test]
+    fn test_serialize_into() {
+        let message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            recent_blockhash: Hash::new_unique(),
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![3],
+                readonly_indexes: vec![1, 2],
+            }],
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                1,
+                vec![7; 10],
+                vec![0, 2],
+            )],
+        });
+        let serialized = message.serialize();
+
+        let mut buffer = [0xff; 1232];
+        assert_eq!(message.serialize_into(&mut buffer), Ok(serialized.len()));
+        assert_eq!(&buffer[..serialized.len()], serialized.as_slice());
+        assert!(buffer[serialized.len()..].iter().all(|byte| *byte == 0xff));
+
+        let mut buffer = vec![0; serialized.len()];
+        assert_eq!(message.serialize_into(&mut buffer), Ok(serialized.len()));
+        assert_eq!(buffer, serialized);
+
+        let mut buffer = vec![0; serialized.len() - 1];
+        assert_eq!(
+            message.serialize_into(&mut buffer),
+            Err(BufferTooSmallError {
+                required: serialized.len(),
+                available: serialized.len() - 1,
+            })
+        );
+        assert!(buffer.iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_serialized_size() {
+        let legacy_message = VersionedMessage::Legacy(LegacyMessage::new(
+            &[
+                Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[7; 300],
+                    vec![AccountMeta::new(Pubkey::new_unique(), true)],
+                ),
+                Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]),
+            ],
+            Some(&Pubkey::new_unique()),
+        ));
+        let v0_message = VersionedMessage::V0(v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 0,
+            },
+            recent_blockhash: Hash::new_unique(),
+            account_keys: vec![Pubkey::new_unique(); 130],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: (0..200).collect(),
+                readonly_indexes: vec![0],
+            }],
+            instructions: vec![CompiledInstruction {
+                program_id_index: 1,
+                accounts: vec![0; 128],
+                data: vec![1; 0x4000],
+            }],
+        });
+
+        for message in [legacy_message, v0_message, VersionedMessage::default()] {
+            let message_size = bincode::serialized_size(&message).unwrap() as usize;
+            assert_eq!(message.serialized_size(), message_size);
+            for num_signatures in [0, 1, 0x80] {
+                assert_eq!(
+                    message.serialized_size_with_signatures(num_signatures),
+                    short_vec_encoded_len(num_signatures)
+                        + num_signatures * SIGNATURE_BYTES
+                        + message_size
+                );
+            }
+
+            let message_ref = match &message {
+                VersionedMessage::Legacy(message) => VersionedMessageRef::Legacy(message),
+                VersionedMessage::V0(message) => VersionedMessageRef::V0(message),
+            };
+            assert_eq!(message_ref.serialized_size(), message_size);
+            assert_eq!(
+                bincode::serialize(&message_ref).unwrap(),
+                bincode::serialize(&message).unwrap()
+            );
+            assert_eq!(VersionedMessage::from(message_ref), message);
+        }
+    }
+
+    #[test]
+    fn test_short_vec_encoded_len() {
+        for len in [0u16, 0x7f, 0x80, 0x3fff, 0x4000, u16::MAX] {
+            assert_eq!(
+                short_vec_encoded_len(usize::from(len)),
+                bincode::serialized_size(&solana_short_vec::ShortU16(len)).unwrap() as usize
+            );
+        }
+    }
+
     #[test]
     fn test_versioned_message_serialization() {
         let message = VersionedMessage::V0(v0::Message {
@@ -0,0 +1,162 @@
+//! Messages kept for relaying, including versions this crate can't parse.
+//!
+//! Forwarders have to pass on messages of versions introduced after they
+//! were built. [`RelayedMessage::deserialize`] keeps such a message as a
+//! [`RawVersionedMessage`] holding its bytes, which serializes back to the
+//! exact input. [`VersionedMessage`] itself only ever holds messages this
+//! crate understands.
+
+use crate::{VersionedMessage, MESSAGE_VERSION_PREFIX};
+
+/// A message of a version this crate can't parse, kept as the bytes following
+/// the version prefix.
+///
+/// Nothing is known about the contents of the message, so it can't be
+/// sanitized or inspected, only serialized again.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RawVersionedMessage {
+    /// Message version, without the [`MESSAGE_VERSION_PREFIX`] bit.
+    pub version: u8,
+    /// Bytes following the version prefix.
+    pub bytes: Vec<u8>,
+}
+
+impl RawVersionedMessage {
+    /// Number of bytes the message occupies on the wire.
+    pub fn serialized_size(&self) -> usize {
+        1usize.saturating_add(self.bytes.len())
+    }
+
+    /// Serialize the message, version prefix included.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.serialized_size());
+        bytes.push(MESSAGE_VERSION_PREFIX | self.version);
+        bytes.extend_from_slice(&self.bytes);
+        bytes
+    }
+}
+
+/// A message deserialized by a forwarder: either a message this crate
+/// understands or the raw bytes of a message of an unknown version.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum RelayedMessage {
+    Known(VersionedMessage),
+    Unknown(RawVersionedMessage),
+}
+
+impl RelayedMessage {
+    /// Deserialize a message that spans all of `bytes`, as the message of a
+    /// serialized transaction does.
+    ///
+    /// Legacy and v0 messages are deserialized as with `bincode::deserialize`,
+    /// and rejected if they are followed by trailing bytes. A message with a
+    /// version prefix of any other version is kept as a [`RawVersionedMessage`],
+    /// except for version 127, which is rejected as an off-chain message.
+    #[cfg(feature = "bincode")]
+    pub fn deserialize(bytes: &[u8]) -> bincode::Result<Self> {
+        use bincode::Options;
+
+        if let Some((&prefix, message_bytes)) = bytes.split_first() {
+            let version = prefix & !MESSAGE_VERSION_PREFIX;
+            if prefix & MESSAGE_VERSION_PREFIX != 0 && !matches!(version, 0 | 127) {
+                return Ok(Self::Unknown(RawVersionedMessage {
+                    version,
+                    bytes: message_bytes.to_vec(),
+                }));
+            }
+        }
+        bincode::options()
+            .with_fixint_encoding()
+            .reject_trailing_bytes()
+            .deserialize(bytes)
+            .map(Self::Known)
+    }
+
+    /// Returns the version of a versioned message, or `None` for a legacy
+    /// message.
+    pub fn version(&self) -> Option<u8> {
+        match self {
+            Self::Known(message) => message.version(),
+            Self::Unknown(message) => Some(message.version),
+        }
+    }
+
+    /// Number of bytes the message occupies on the wire.
+    pub fn serialized_size(&self) -> usize {
+        match self {
+            Self::Known(message) => message.serialized_size(),
+            Self::Unknown(message) => message.serialized_size(),
+        }
+    }
+
+    /// Serialize the message as it was received.
+    #[cfg(feature = "bincode")]
+    pub fn serialize(&self) -> Vec<u8> {
+        match self {
+            Self::Known(message) => message.serialize(),
+            Self::Unknown(message) => message.serialize(),
+        }
+    }
+}
+
+impl From<VersionedMessage> for RelayedMessage {
+    fn from(message: VersionedMessage) -> Self {
+        Self::Known(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::v0};
+
+    #[test]
+    fn test_deserialize_unknown_version() {
+        let bytes = vec![MESSAGE_VERSION_PREFIX | 1, 4, 5, 6];
+        let message = RelayedMessage::deserialize(&bytes).unwrap();
+        assert_eq!(
+            message,
+            RelayedMessage::Unknown(RawVersionedMessage {
+                version: 1,
+                bytes: vec![4, 5, 6],
+            })
+        );
+        assert_eq!(message.version(), Some(1));
+        assert_eq!(message.serialize(), bytes);
+        assert_eq!(message.serialized_size(), bytes.len());
+
+        // VersionedMessage still rejects unknown versions
+        assert!(bincode::deserialize::<VersionedMessage>(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_known_versions() {
+        for message in [
+            VersionedMessage::default(),
+            VersionedMessage::V0(v0::Message::default()),
+        ] {
+            let bytes = message.serialize();
+            let relayed = RelayedMessage::deserialize(&bytes).unwrap();
+            assert_eq!(relayed, RelayedMessage::Known(message));
+            assert_eq!(relayed.serialize(), bytes);
+            assert_eq!(relayed.serialized_size(), bytes.len());
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rejects_off_chain_and_empty_messages() {
+        assert!(RelayedMessage::deserialize(&[0xff, 4, 5, 6]).is_err());
+        assert!(RelayedMessage::deserialize(&[]).is_err());
+    }
+
+    #[test]
+    fn test_deserialize_rejects_trailing_bytes() {
+        for message in [
+            VersionedMessage::default(),
+            VersionedMessage::V0(v0::Message::default()),
+        ] {
+            let mut bytes = message.serialize();
+            bytes.extend_from_slice(&[1, 2, 3]);
+            assert!(RelayedMessage::deserialize(&bytes).is_err());
+        }
+    }
+}
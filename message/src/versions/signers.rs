@@ -0,0 +1,82 @@
+//! The signers a versioned message requires.
+
+use {crate::VersionedMessage, solana_pubkey::Pubkey};
+
+/// The role of a signer in a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignerRole {
+    /// The first signer, which pays the fees and is always writable.
+    FeePayer,
+    Writable,
+    Readonly,
+}
+
+/// A key that must sign a message.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RequiredSigner<'a> {
+    pub pubkey: &'a Pubkey,
+    pub role: SignerRole,
+}
+
+impl VersionedMessage {
+    /// Iterate over the keys that must sign this message, in the order of
+    /// their signatures, with their roles as described by the header.
+    ///
+    /// Writability is not adjusted for reserved accounts or invoked programs.
+    /// If the header requires more signatures than there are account keys,
+    /// only the existing keys are returned.
+    pub fn required_signers(&self) -> impl ExactSizeIterator<Item = RequiredSigner<'_>> {
+        let header = self.header();
+        let num_required_signatures = usize::from(header.num_required_signatures);
+        let num_writable_signers = num_required_signatures
+            .saturating_sub(usize::from(header.num_readonly_signed_accounts));
+        self.static_account_keys()
+            .iter()
+            .take(num_required_signatures)
+            .enumerate()
+            .map(move |(index, pubkey)| RequiredSigner {
+                pubkey,
+                role: if index == 0 {
+                    SignerRole::FeePayer
+                } else if index < num_writable_signers {
+                    SignerRole::Writable
+                } else {
+                    SignerRole::Readonly
+                },
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{legacy, MessageHeader},
+    };
+
+    #[test]
+    fn test_required_signers() {
+        let keys: Vec<Pubkey> = (0..5).map(|_| Pubkey::new_unique()).collect();
+        let message = VersionedMessage::Legacy(legacy::Message {
+            header: MessageHeader {
+                num_required_signatures: 4,
+                num_readonly_signed_accounts: 2,
+                num_readonly_unsigned_accounts: 0,
+            },
+            account_keys: keys.clone(),
+            ..legacy::Message::default()
+        });
+        let signer = |pubkey, role| RequiredSigner { pubkey, role };
+        assert_eq!(
+            message.required_signers().collect::<Vec<_>>(),
+            vec![
+                signer(&keys[0], SignerRole::FeePayer),
+                signer(&keys[1], SignerRole::Writable),
+                signer(&keys[2], SignerRole::Readonly),
+                signer(&keys[3], SignerRole::Readonly),
+            ]
+        );
+
+        assert_eq!(VersionedMessage::default().required_signers().len(), 0);
+    }
+}
@@ -0,0 +1,183 @@
+//! Planning whether instructions fit in a single transaction.
+//!
+//! A transaction must fit in one packet. Builders that pack instructions need
+//! to know how far over the limit a candidate message is, and which part of
+//! it grew, to decide between adding addresses to a lookup table and
+//! splitting the instructions across transactions.
+
+use {
+    super::{
+        instructions_serialized_size, lookups_serialized_size, short_vec_encoded_len,
+        SIGNATURE_BYTES,
+    },
+    crate::{v0, AddressLookupTableAccount, CompileError, VersionedMessage, MESSAGE_HEADER_LENGTH},
+    solana_hash::{Hash, HASH_BYTES},
+    solana_instruction::Instruction,
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+};
+
+// inlined to avoid solana-packet dep
+const PACKET_DATA_SIZE: usize = 1280 - 40 - 8;
+
+/// Number of bytes each part of a transaction occupies on the wire.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MessageSizePlan {
+    /// Signatures of the required signers and their length prefix.
+    pub signatures: usize,
+    /// Version prefix, message header and recent blockhash.
+    pub header: usize,
+    /// Static account keys and their length prefix.
+    pub static_keys: usize,
+    /// Address table lookups and their length prefix.
+    pub lookups: usize,
+    /// Instructions, including their account indexes and data, and their
+    /// length prefix.
+    pub instructions: usize,
+}
+
+impl MessageSizePlan {
+    /// Plan the size of a transaction compiling `instructions` for `payer`.
+    ///
+    /// Without `address_lookup_table_accounts` the instructions are compiled
+    /// into a legacy message. Otherwise they are compiled into a v0 message
+    /// that loads keys from the tables as
+    /// [`v0::Message::try_compile_with_tables`] does.
+    pub fn try_new(
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        address_lookup_table_accounts: Option<&[AddressLookupTableAccount]>,
+    ) -> Result<Self, CompileError> {
+        let message = v0::Message::try_compile_with_tables(
+            payer,
+            instructions,
+            address_lookup_table_accounts.unwrap_or_default(),
+            Hash::default(),
+        )?;
+        let message = match address_lookup_table_accounts {
+            // a message compiled without tables has no lookups
+            None => VersionedMessage::V0(message)
+                .try_into_legacy()
+                .map(VersionedMessage::Legacy)
+                .unwrap_or_else(|message| message),
+            Some(_) => VersionedMessage::V0(message),
+        };
+        Ok(Self::from_message(&message))
+    }
+
+    /// Plan the size of a transaction carrying `message` and one signature
+    /// per required signer.
+    pub fn from_message(message: &VersionedMessage) -> Self {
+        let num_signatures = usize::from(message.header().num_required_signatures);
+        let signatures = short_vec_encoded_len(num_signatures)
+            .saturating_add(num_signatures.saturating_mul(SIGNATURE_BYTES));
+        let static_keys_size = |account_keys: &[Pubkey]| {
+            short_vec_encoded_len(account_keys.len())
+                .saturating_add(account_keys.len().saturating_mul(PUBKEY_BYTES))
+        };
+
+        match message {
+            VersionedMessage::Legacy(message) => Self {
+                signatures,
+                header: MESSAGE_HEADER_LENGTH.saturating_add(HASH_BYTES),
+                static_keys: static_keys_size(&message.account_keys),
+                lookups: 0,
+                instructions: instructions_serialized_size(&message.instructions),
+            },
+            VersionedMessage::V0(message) => Self {
+                signatures,
+                header: 1usize // version prefix
+                    .saturating_add(MESSAGE_HEADER_LENGTH)
+                    .saturating_add(HASH_BYTES),
+                static_keys: static_keys_size(&message.account_keys),
+                lookups: lookups_serialized_size(&message.address_table_lookups),
+                instructions: instructions_serialized_size(&message.instructions),
+            },
+        }
+    }
+
+    /// Total size of the transaction.
+    pub fn total(&self) -> usize {
+        self.signatures
+            .saturating_add(self.header)
+            .saturating_add(self.static_keys)
+            .saturating_add(self.lookups)
+            .saturating_add(self.instructions)
+    }
+
+    /// Returns true if the transaction fits in a packet.
+    pub fn fits(&self) -> bool {
+        self.total() <= PACKET_DATA_SIZE
+    }
+
+    /// Number of bytes the transaction exceeds the packet size by, 0 if it
+    /// fits.
+    pub fn bytes_over(&self) -> usize {
+        self.total().saturating_sub(PACKET_DATA_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_instruction::AccountMeta};
+
+    fn instruction(accounts: &[Pubkey], data_len: usize) -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &vec![0; data_len],
+            accounts
+                .iter()
+                .map(|key| AccountMeta::new(*key, false))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_try_new() {
+        let payer = Pubkey::new_unique();
+        let accounts: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let instructions = [instruction(&accounts, 10)];
+
+        let plan = MessageSizePlan::try_new(&payer, &instructions, None).unwrap();
+        let message =
+            VersionedMessage::Legacy(crate::legacy::Message::new(&instructions, Some(&payer)));
+        assert_eq!(
+            plan,
+            MessageSizePlan {
+                signatures: 1 + SIGNATURE_BYTES,
+                header: MESSAGE_HEADER_LENGTH + HASH_BYTES,
+                static_keys: 1 + 6 * PUBKEY_BYTES,
+                lookups: 0,
+                instructions: 1 + 1 + 5 + 11,
+            }
+        );
+        assert_eq!(plan.total(), message.serialized_size_with_signatures(1));
+        assert!(plan.fits());
+        assert_eq!(plan.bytes_over(), 0);
+
+        let table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: accounts,
+        };
+        let plan = MessageSizePlan::try_new(&payer, &instructions, Some(&[table][..])).unwrap();
+        assert_eq!(
+            plan,
+            MessageSizePlan {
+                signatures: 1 + SIGNATURE_BYTES,
+                header: 1 + MESSAGE_HEADER_LENGTH + HASH_BYTES,
+                static_keys: 1 + 2 * PUBKEY_BYTES,
+                lookups: 1 + PUBKEY_BYTES + 5 + 1,
+                instructions: 1 + 1 + 5 + 11,
+            }
+        );
+    }
+
+    #[test]
+    fn test_bytes_over() {
+        let payer = Pubkey::new_unique();
+        let instructions = [instruction(&[], PACKET_DATA_SIZE)];
+        let plan = MessageSizePlan::try_new(&payer, &instructions, None).unwrap();
+        assert!(!plan.fits());
+        assert_eq!(plan.bytes_over(), plan.total() - PACKET_DATA_SIZE);
+        assert_eq!(plan.instructions, 1 + 1 + 1 + 2 + PACKET_DATA_SIZE);
+    }
+}
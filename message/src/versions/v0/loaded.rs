@@ -1,10 +1,17 @@
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
 use {
-    crate::{v0, AccountKeys},
+    crate::{
+        new_is_writable_account_cache,
+        v0::{self, MessageAddressTableLookup},
+        AccountKeyPositions, AccountKeys, ComputeBudgetDetailsCache,
+    },
     solana_pubkey::Pubkey,
     solana_sdk_ids::bpf_loader_upgradeable,
-    std::{borrow::Cow, collections::HashSet},
+    std::{
+        borrow::Cow,
+        collections::{HashMap, HashSet},
+    },
 };
 
 /// Combination of a version #0 message and its loaded addresses
@@ -17,6 +24,10 @@ pub struct LoadedMessage<'a> {
     /// List of boolean with same length as account_keys(), each boolean value indicates if
     /// corresponding account key is writable or not.
     pub is_writable_account_cache: Vec<bool>,
+    /// Compute budget details of the message, computed on first use.
+    pub compute_budget_details: ComputeBudgetDetailsCache,
+    /// Position of each account key, built on first use.
+    pub account_key_positions: AccountKeyPositions,
 }
 
 /// Collection of addresses loaded from on-chain lookup tables, split
@@ -43,6 +54,17 @@ impl FromIterator<LoadedAddresses> for LoadedAddresses {
     }
 }
 
+/// Where an address loaded with an address table lookup came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LoadedAddressSource {
+    /// Address lookup table account key
+    pub table_account_key: Pubkey,
+    /// Index of the address in the lookup table
+    pub index: u8,
+    /// Whether the address was loaded as writable
+    pub is_writable: bool,
+}
+
 impl LoadedAddresses {
     /// Checks if there are no writable or readonly addresses
     pub fn is_empty(&self) -> bool {
@@ -53,6 +75,64 @@ impl LoadedAddresses {
     pub fn len(&self) -> usize {
         self.writable.len().saturating_add(self.readonly.len())
     }
+
+    /// Map each loaded address to the lookup table, table index and
+    /// writability it was loaded with, given the `lookups` the addresses were
+    /// loaded from.
+    ///
+    /// Returns `None` if the number of writable or readonly addresses doesn't
+    /// match the number of writable or readonly lookup indexes. If an address
+    /// was loaded more than once, its first source is returned.
+    pub fn sources(
+        &self,
+        lookups: &[MessageAddressTableLookup],
+    ) -> Option<HashMap<Pubkey, LoadedAddressSource>> {
+        let num_writable_indexes: usize = lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len())
+            .sum();
+        let num_readonly_indexes: usize = lookups
+            .iter()
+            .map(|lookup| lookup.readonly_indexes.len())
+            .sum();
+        if self.writable.len() != num_writable_indexes
+            || self.readonly.len() != num_readonly_indexes
+        {
+            return None;
+        }
+
+        let writable_sources = lookups.iter().flat_map(|lookup| {
+            lookup
+                .writable_indexes
+                .iter()
+                .map(|index| LoadedAddressSource {
+                    table_account_key: lookup.account_key,
+                    index: *index,
+                    is_writable: true,
+                })
+        });
+        let readonly_sources = lookups.iter().flat_map(|lookup| {
+            lookup
+                .readonly_indexes
+                .iter()
+                .map(|index| LoadedAddressSource {
+                    table_account_key: lookup.account_key,
+                    index: *index,
+                    is_writable: false,
+                })
+        });
+
+        let mut sources = HashMap::with_capacity(self.len());
+        for (address, source) in self
+            .writable
+            .iter()
+            .zip(writable_sources)
+            .chain(self.readonly.iter().zip(readonly_sources))
+        {
+            sources.entry(*address).or_insert(source);
+        }
+        Some(sources)
+    }
 }
 
 impl<'a> LoadedMessage<'a> {
@@ -65,6 +145,8 @@ impl<'a> LoadedMessage<'a> {
             message: Cow::Owned(message),
             loaded_addresses: Cow::Owned(loaded_addresses),
             is_writable_account_cache: Vec::default(),
+            compute_budget_details: ComputeBudgetDetailsCache::default(),
+            account_key_positions: AccountKeyPositions::default(),
         };
         loaded_message.set_is_writable_account_cache(reserved_account_keys);
         loaded_message
@@ -79,18 +161,20 @@ impl<'a> LoadedMessage<'a> {
             message: Cow::Borrowed(message),
             loaded_addresses: Cow::Borrowed(loaded_addresses),
             is_writable_account_cache: Vec::default(),
+            compute_budget_details: ComputeBudgetDetailsCache::default(),
+            account_key_positions: AccountKeyPositions::default(),
         };
         loaded_message.set_is_writable_account_cache(reserved_account_keys);
         loaded_message
     }
 
     fn set_is_writable_account_cache(&mut self, reserved_account_keys: &HashSet<Pubkey>) {
-        let is_writable_account_cache = self
-            .account_keys()
-            .iter()
-            .enumerate()
-            .map(|(i, _key)| self.is_writable_internal(i, reserved_account_keys))
-            .collect::<Vec<_>>();
+        let is_writable_account_cache = new_is_writable_account_cache(
+            self.account_keys(),
+            &self.message.instructions,
+            |i| self.is_writable_index(i),
+            reserved_account_keys,
+        );
         let _ = std::mem::replace(
             &mut self.is_writable_account_cache,
             is_writable_account_cache,
@@ -100,6 +184,7 @@ impl<'a> LoadedMessage<'a> {
     /// Returns the full list of static and dynamic account keys that are loaded for this message.
     pub fn account_keys(&self) -> AccountKeys<'_> {
         AccountKeys::new(&self.message.account_keys, Some(&self.loaded_addresses))
+            .with_positions(&self.account_key_positions)
     }
 
     /// Returns the list of static account keys that are loaded for this message.
@@ -135,20 +220,6 @@ impl<'a> LoadedMessage<'a> {
         }
     }
 
-    /// Returns true if the account at the specified index was loaded as writable
-    fn is_writable_internal(
-        &self,
-        key_index: usize,
-        reserved_account_keys: &HashSet<Pubkey>,
-    ) -> bool {
-        if self.is_writable_index(key_index) {
-            if let Some(key) = self.account_keys().get(key_index) {
-                return !(reserved_account_keys.contains(key) || self.demote_program_id(key_index));
-            }
-        }
-        false
-    }
-
     pub fn is_writable(&self, key_index: usize) -> bool {
         *self
             .is_writable_account_cache
@@ -221,6 +292,48 @@ mod tests {
         (message, [key0, key1, key2, key3, key4, key5])
     }
 
+    #[test]
+    fn test_sources() {
+        let table0 = Pubkey::new_unique();
+        let table1 = Pubkey::new_unique();
+        let lookups = [
+            MessageAddressTableLookup {
+                account_key: table0,
+                writable_indexes: vec![3],
+                readonly_indexes: vec![0, 1],
+            },
+            MessageAddressTableLookup {
+                account_key: table1,
+                writable_indexes: vec![7],
+                readonly_indexes: vec![],
+            },
+        ];
+        let keys: Vec<Pubkey> = (0..4).map(|_| Pubkey::new_unique()).collect();
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![keys[0], keys[1]],
+            readonly: vec![keys[2], keys[3]],
+        };
+
+        let source = |table_account_key, index, is_writable| LoadedAddressSource {
+            table_account_key,
+            index,
+            is_writable,
+        };
+        assert_eq!(
+            loaded_addresses.sources(&lookups),
+            Some(HashMap::from([
+                (keys[0], source(table0, 3, true)),
+                (keys[1], source(table1, 7, true)),
+                (keys[2], source(table0, 0, false)),
+                (keys[3], source(table0, 1, false)),
+            ]))
+        );
+
+        let mut mismatched_addresses = loaded_addresses;
+        mismatched_addresses.readonly.pop();
+        assert_eq!(mismatched_addresses.sources(&lookups), None);
+    }
+
     #[test]
     fn test_has_duplicates() {
         let message = check_test_loaded_message().0;
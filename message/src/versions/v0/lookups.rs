@@ -0,0 +1,402 @@
+use {
+    crate::v0::{Message, MessageAddressTableLookup},
+    solana_pubkey::Pubkey,
+    solana_transaction_error::AddressLoaderError,
+    std::{
+        collections::{BTreeSet, HashMap},
+        fmt,
+    },
+};
+
+/// Error returned when address table lookups cannot be merged.
+#[derive(PartialEq, Debug, Eq, Clone)]
+pub enum AddressTableLookupError {
+    /// The same index of a table is loaded more than once.
+    DuplicateIndex { account_key: Pubkey, index: u8 },
+}
+
+impl std::error::Error for AddressTableLookupError {}
+
+impl fmt::Display for AddressTableLookupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            AddressTableLookupError::DuplicateIndex { account_key, index } => {
+                write!(
+                    f,
+                    "index {index} of address lookup table {account_key} is loaded more than once"
+                )
+            }
+        }
+    }
+}
+
+/// A lookup refers to an index past the end of its address lookup table.
+#[derive(PartialEq, Debug, Eq, Clone)]
+pub struct LookupIndexError {
+    pub lookup_table_key: Pubkey,
+    pub index: u8,
+    /// Number of addresses in the table
+    pub table_len: usize,
+}
+
+impl std::error::Error for LookupIndexError {}
+
+impl fmt::Display for LookupIndexError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let LookupIndexError {
+            lookup_table_key,
+            index,
+            table_len,
+        } = self;
+        write!(
+            f,
+            "index {index} is out of range for address lookup table {lookup_table_key} with \
+             {table_len} addresses"
+        )
+    }
+}
+
+impl From<LookupIndexError> for AddressLoaderError {
+    fn from(err: LookupIndexError) -> Self {
+        let LookupIndexError {
+            lookup_table_key,
+            index,
+            table_len,
+        } = err;
+        AddressLoaderError::LookupIndexOutOfRange {
+            lookup_table_key,
+            index,
+            table_len,
+        }
+    }
+}
+
+/// Merge address table lookups that reference the same table.
+///
+/// Each table is kept once, at the position of its first lookup, with its
+/// writable and readonly indexes sorted in ascending order. Fails if a table
+/// index is loaded more than once, whether as writable or readonly.
+///
+/// Merging changes the order of the loaded addresses, so instructions that
+/// index into them have to be updated, which
+/// [`Message::merge_address_table_lookups`] does.
+pub fn merge_address_table_lookups(
+    lookups: &[MessageAddressTableLookup],
+) -> Result<Vec<MessageAddressTableLookup>, AddressTableLookupError> {
+    let mut table_positions = HashMap::<Pubkey, usize>::new();
+    let mut merged_indexes = Vec::<(Pubkey, BTreeSet<u8>, BTreeSet<u8>)>::new();
+    for lookup in lookups {
+        let position = *table_positions
+            .entry(lookup.account_key)
+            .or_insert_with(|| {
+                merged_indexes.push((lookup.account_key, BTreeSet::new(), BTreeSet::new()));
+                merged_indexes.len().saturating_sub(1)
+            });
+        let (account_key, writable_indexes, readonly_indexes) = &mut merged_indexes[position];
+        for (&index, is_writable) in lookup
+            .writable_indexes
+            .iter()
+            .map(|index| (index, true))
+            .chain(lookup.readonly_indexes.iter().map(|index| (index, false)))
+        {
+            if writable_indexes.contains(&index) || readonly_indexes.contains(&index) {
+                return Err(AddressTableLookupError::DuplicateIndex {
+                    account_key: *account_key,
+                    index,
+                });
+            }
+            if is_writable {
+                writable_indexes.insert(index);
+            } else {
+                readonly_indexes.insert(index);
+            }
+        }
+    }
+
+    Ok(merged_indexes
+        .into_iter()
+        .map(
+            |(account_key, writable_indexes, readonly_indexes)| MessageAddressTableLookup {
+                account_key,
+                writable_indexes: writable_indexes.into_iter().collect(),
+                readonly_indexes: readonly_indexes.into_iter().collect(),
+            },
+        )
+        .collect())
+}
+
+/// Table key and index of each address loaded by `lookups`, in the order the
+/// addresses are loaded: all writable addresses followed by all readonly ones.
+fn loaded_table_indexes(
+    lookups: &[MessageAddressTableLookup],
+) -> impl Iterator<Item = (Pubkey, u8)> + '_ {
+    let writable = lookups.iter().flat_map(|lookup| {
+        lookup
+            .writable_indexes
+            .iter()
+            .map(|index| (lookup.account_key, *index))
+    });
+    let readonly = lookups.iter().flat_map(|lookup| {
+        lookup
+            .readonly_indexes
+            .iter()
+            .map(|index| (lookup.account_key, *index))
+    });
+    writable.chain(readonly)
+}
+
+impl Message {
+    /// Check that every index of the address table lookups of this message
+    /// is within its table, where `table_len` returns the number of
+    /// addresses of a table, or `None` if the table is not known.
+    ///
+    /// Lookups of unknown tables are not checked; loading the addresses
+    /// reports those tables as missing. The first out of range index is
+    /// returned, identifying its table, so that a failed load can be traced
+    /// to the lookup that caused it.
+    pub fn check_lookup_indexes(
+        &self,
+        mut table_len: impl FnMut(&Pubkey) -> Option<usize>,
+    ) -> Result<(), LookupIndexError> {
+        for lookup in &self.address_table_lookups {
+            let Some(len) = table_len(&lookup.account_key) else {
+                continue;
+            };
+            if let Some(index) = lookup
+                .writable_indexes
+                .iter()
+                .chain(&lookup.readonly_indexes)
+                .find(|index| usize::from(**index) >= len)
+            {
+                return Err(LookupIndexError {
+                    lookup_table_key: lookup.account_key,
+                    index: *index,
+                    table_len: len,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Merge the address table lookups of this message with
+    /// [`merge_address_table_lookups`] and update the instruction account
+    /// indexes that refer to loaded addresses to match the new order.
+    ///
+    /// The message is left unchanged if merging fails.
+    pub fn merge_address_table_lookups(&mut self) -> Result<(), AddressTableLookupError> {
+        let address_table_lookups = merge_address_table_lookups(&self.address_table_lookups)?;
+        let new_positions: HashMap<(Pubkey, u8), usize> =
+            loaded_table_indexes(&address_table_lookups)
+                .enumerate()
+                .map(|(position, table_index)| (table_index, position))
+                .collect();
+        let new_indexes: Vec<usize> = loaded_table_indexes(&self.address_table_lookups)
+            .map(|table_index| new_positions[&table_index])
+            .collect();
+
+        let num_static_keys = self.account_keys.len();
+        for instruction in &mut self.instructions {
+            for index in &mut instruction.accounts {
+                let Some(loaded_index) = usize::from(*index).checked_sub(num_static_keys) else {
+                    continue;
+                };
+                if let Some(new_index) = new_indexes.get(loaded_index) {
+                    // the number of loaded addresses is unchanged so the new
+                    // index fits wherever the old one did
+                    *index = num_static_keys.saturating_add(*new_index) as u8;
+                }
+            }
+        }
+        self.address_table_lookups = address_table_lookups;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{compiled_instruction::CompiledInstruction, MessageHeader},
+    };
+
+    #[test]
+    fn test_merge_address_table_lookups() {
+        let table_a = Pubkey::new_unique();
+        let table_b = Pubkey::new_unique();
+        let lookups = vec![
+            MessageAddressTableLookup {
+                account_key: table_a,
+                writable_indexes: vec![5, 1],
+                readonly_indexes: vec![7],
+            },
+            MessageAddressTableLookup {
+                account_key: table_b,
+                writable_indexes: vec![],
+                readonly_indexes: vec![0],
+            },
+            MessageAddressTableLookup {
+                account_key: table_a,
+                writable_indexes: vec![3],
+                readonly_indexes: vec![2],
+            },
+        ];
+        assert_eq!(
+            merge_address_table_lookups(&lookups),
+            Ok(vec![
+                MessageAddressTableLookup {
+                    account_key: table_a,
+                    writable_indexes: vec![1, 3, 5],
+                    readonly_indexes: vec![2, 7],
+                },
+                MessageAddressTableLookup {
+                    account_key: table_b,
+                    writable_indexes: vec![],
+                    readonly_indexes: vec![0],
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_merge_address_table_lookups_rejects_duplicates() {
+        let account_key = Pubkey::new_unique();
+        for (writable_indexes, readonly_indexes) in [
+            (vec![4, 4], vec![]),
+            (vec![], vec![4, 4]),
+            (vec![4], vec![4]),
+        ] {
+            let lookups = [MessageAddressTableLookup {
+                account_key,
+                writable_indexes,
+                readonly_indexes,
+            }];
+            assert_eq!(
+                merge_address_table_lookups(&lookups),
+                Err(AddressTableLookupError::DuplicateIndex {
+                    account_key,
+                    index: 4
+                })
+            );
+        }
+
+        let lookups = [
+            MessageAddressTableLookup {
+                account_key,
+                writable_indexes: vec![4],
+                readonly_indexes: vec![],
+            },
+            MessageAddressTableLookup {
+                account_key,
+                writable_indexes: vec![],
+                readonly_indexes: vec![4],
+            },
+        ];
+        assert_eq!(
+            merge_address_table_lookups(&lookups),
+            Err(AddressTableLookupError::DuplicateIndex {
+                account_key,
+                index: 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_lookup_indexes() {
+        let table_a = Pubkey::new_unique();
+        let table_b = Pubkey::new_unique();
+        let message = Message {
+            address_table_lookups: vec![
+                MessageAddressTableLookup {
+                    account_key: table_a,
+                    writable_indexes: vec![0, 3],
+                    readonly_indexes: vec![],
+                },
+                MessageAddressTableLookup {
+                    account_key: table_b,
+                    writable_indexes: vec![1],
+                    readonly_indexes: vec![2, 5],
+                },
+            ],
+            ..Message::default()
+        };
+        let table_lens = HashMap::from([(table_a, 4), (table_b, 5)]);
+        assert_eq!(
+            message.check_lookup_indexes(|key| table_lens.get(key).copied()),
+            Err(LookupIndexError {
+                lookup_table_key: table_b,
+                index: 5,
+                table_len: 5,
+            })
+        );
+        assert_eq!(
+            message.check_lookup_indexes(|key| (key == &table_a).then_some(4)),
+            Ok(())
+        );
+        assert_eq!(
+            AddressLoaderError::from(message.check_lookup_indexes(|_| Some(3)).unwrap_err()),
+            AddressLoaderError::LookupIndexOutOfRange {
+                lookup_table_key: table_a,
+                index: 3,
+                table_len: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn test_message_merge_address_table_lookups() {
+        let table_a = Pubkey::new_unique();
+        let table_b = Pubkey::new_unique();
+        // loaded addresses before merging: a[9], b[0], a[1], b[3], a[2]
+        let mut message = Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                1,
+                vec![],
+                vec![0, 2, 3, 4, 5, 6],
+            )],
+            address_table_lookups: vec![
+                MessageAddressTableLookup {
+                    account_key: table_a,
+                    writable_indexes: vec![9],
+                    readonly_indexes: vec![],
+                },
+                MessageAddressTableLookup {
+                    account_key: table_b,
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![3],
+                },
+                MessageAddressTableLookup {
+                    account_key: table_a,
+                    writable_indexes: vec![1],
+                    readonly_indexes: vec![2],
+                },
+            ],
+            ..Message::default()
+        };
+
+        message.merge_address_table_lookups().unwrap();
+        assert!(message.sanitize().is_ok());
+        // loaded addresses after merging: a[1], a[9], b[0], a[2], b[3]
+        assert_eq!(
+            message.address_table_lookups,
+            vec![
+                MessageAddressTableLookup {
+                    account_key: table_a,
+                    writable_indexes: vec![1, 9],
+                    readonly_indexes: vec![2],
+                },
+                MessageAddressTableLookup {
+                    account_key: table_b,
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![3],
+                },
+            ]
+        );
+        assert_eq!(message.instructions[0].accounts, vec![0, 3, 4, 2, 6, 5]);
+    }
+}
@@ -9,7 +9,8 @@
 //! [`v0`]: crate::v0
 //! [future message format]: https://docs.solanalabs.com/proposals/versioned-transactions
 
-pub use loaded::*;
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
 #[cfg(feature = "serde")]
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "frozen-abi")]
@@ -18,21 +19,32 @@ use {
     crate::{
         compiled_instruction::CompiledInstruction,
         compiled_keys::{CompileError, CompiledKeys},
-        AccountKeys, AddressLookupTableAccount, MessageHeader,
+        legacy, AccountKeys, AddressLookupTableAccount, MessageHeader,
     },
     solana_hash::Hash,
     solana_instruction::Instruction,
-    solana_pubkey::Pubkey,
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
     solana_sanitize::SanitizeError,
     solana_sdk_ids::bpf_loader_upgradeable,
     std::collections::HashSet,
 };
+pub use {loaded::*, lookups::*};
 
 mod loaded;
+mod lookups;
+
+/// Number of bytes an address table lookup adds to a message besides its
+/// indexes: the table key and the length prefixes of both index lists.
+const ADDRESS_TABLE_LOOKUP_OVERHEAD: usize = PUBKEY_BYTES + 2;
 
 /// Address table lookups describe an on-chain address lookup table to use
 /// for loading more readonly and writable accounts in a single tx.
 #[cfg_attr(feature = "frozen-abi", derive(AbiExample))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(BorshSerialize, BorshDeserialize),
+    borsh(crate = "borsh")
+)]
 #[cfg_attr(
     feature = "serde",
     derive(Deserialize, Serialize),
@@ -58,6 +70,11 @@ pub struct MessageAddressTableLookup {
 /// See the crate documentation for further description.
 ///
 #[cfg_attr(feature = "frozen-abi", derive(AbiExample))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(BorshSerialize, BorshDeserialize),
+    borsh(crate = "borsh")
+)]
 #[cfg_attr(
     feature = "serde",
     derive(Deserialize, Serialize),
@@ -283,6 +300,153 @@ impl Message {
             }
         }
 
+        Self::try_compile_with_table_lookups(
+            compiled_keys,
+            instructions,
+            address_table_lookups,
+            loaded_addresses_list,
+            recent_blockhash,
+        )
+    }
+
+    /// Create a signable transaction message like [`Message::try_compile`],
+    /// choosing which of the `candidate_lookup_table_accounts` to use.
+    ///
+    /// Loading an address from a table instead of storing it in the message
+    /// saves all but one of its bytes, while each table used costs its key
+    /// and two length prefixes, so a table is only worth using if it holds at
+    /// least two of the addresses that can be loaded. Tables are picked one
+    /// at a time, each time taking the one that holds the most addresses not
+    /// loaded by the tables already picked, until none is worth using. This
+    /// is not guaranteed to find the smallest message when tables overlap,
+    /// but avoids searching every subset of the candidates.
+    ///
+    /// Signers, invoked programs and the nonce account are always stored in
+    /// the message.
+    pub fn try_compile_with_tables(
+        payer: &Pubkey,
+        instructions: &[Instruction],
+        candidate_lookup_table_accounts: &[AddressLookupTableAccount],
+        recent_blockhash: Hash,
+    ) -> Result<Self, CompileError> {
+        Self::try_compile_with_keys_and_tables(
+            CompiledKeys::compile(instructions, Some(*payer)),
+            instructions,
+            candidate_lookup_table_accounts,
+            recent_blockhash,
+        )
+    }
+
+    /// Compile `instructions` like [`Message::try_compile_with_tables`] with
+    /// the roles collected in `compiled_keys`.
+    pub(crate) fn try_compile_with_keys_and_tables(
+        mut compiled_keys: CompiledKeys,
+        instructions: &[Instruction],
+        candidate_lookup_table_accounts: &[AddressLookupTableAccount],
+        recent_blockhash: Hash,
+    ) -> Result<Self, CompileError> {
+        let (address_table_lookups, loaded_addresses_list) =
+            try_select_table_lookups(&mut compiled_keys, candidate_lookup_table_accounts)?;
+
+        Self::try_compile_with_table_lookups(
+            compiled_keys,
+            instructions,
+            address_table_lookups,
+            loaded_addresses_list,
+            recent_blockhash,
+        )
+    }
+
+    /// Convert a legacy message to a v0 message that loads some of its
+    /// accounts from the `address_lookup_table_accounts`.
+    ///
+    /// Tables are chosen as in [`Message::try_compile_with_tables`], so the
+    /// accounts that can be loaded are the ones that neither sign, are invoked
+    /// as a program nor are the nonce account. The accounts left in the
+    /// message keep their order, which keeps the signers and their
+    /// signatures in the same order, and every instruction refers to the same
+    /// accounts with the same roles as before.
+    ///
+    /// Fails with [`CompileError::AccountIndexOverflow`] if an instruction
+    /// refers to an account index past the end of the legacy account keys.
+    pub fn try_from_legacy(
+        message: &legacy::Message,
+        address_lookup_table_accounts: &[AddressLookupTableAccount],
+    ) -> Result<Self, CompileError> {
+        let mut compiled_keys = CompiledKeys::from_legacy_message(message);
+        let (address_table_lookups, loaded_addresses_list) =
+            try_select_table_lookups(&mut compiled_keys, address_lookup_table_accounts)?;
+        let loaded_addresses: LoadedAddresses = loaded_addresses_list.into_iter().collect();
+
+        let loaded_keys: HashSet<&Pubkey> = loaded_addresses
+            .writable
+            .iter()
+            .chain(&loaded_addresses.readonly)
+            .collect();
+        let mut num_readonly_unsigned_accounts = 0u8;
+        let account_keys: Vec<Pubkey> = message
+            .account_keys
+            .iter()
+            .enumerate()
+            .filter(|(_, key)| !loaded_keys.contains(key))
+            .map(|(index, key)| {
+                if !message.is_signer(index) && !message.is_writable_index(index) {
+                    num_readonly_unsigned_accounts =
+                        num_readonly_unsigned_accounts.saturating_add(1);
+                }
+                *key
+            })
+            .collect();
+
+        let instructions = {
+            let account_keys = AccountKeys::new(&account_keys, Some(&loaded_addresses));
+            let get_account_index = |index: &u8| -> Result<u8, CompileError> {
+                let key = message
+                    .account_keys
+                    .get(usize::from(*index))
+                    .ok_or(CompileError::AccountIndexOverflow)?;
+                let index = account_keys
+                    .iter()
+                    .position(|account_key| account_key == key)
+                    .ok_or(CompileError::UnknownInstructionKey(*key))?;
+                u8::try_from(index).map_err(|_| CompileError::AccountIndexOverflow)
+            };
+            message
+                .instructions
+                .iter()
+                .map(|ix| {
+                    Ok(CompiledInstruction {
+                        program_id_index: get_account_index(&ix.program_id_index)?,
+                        accounts: ix
+                            .accounts
+                            .iter()
+                            .map(get_account_index)
+                            .collect::<Result<_, _>>()?,
+                        data: ix.data.clone(),
+                    })
+                })
+                .collect::<Result<_, CompileError>>()?
+        };
+
+        Ok(Self {
+            header: MessageHeader {
+                num_readonly_unsigned_accounts,
+                ..message.header
+            },
+            account_keys,
+            recent_blockhash: message.recent_blockhash,
+            instructions,
+            address_table_lookups,
+        })
+    }
+
+    fn try_compile_with_table_lookups(
+        compiled_keys: CompiledKeys,
+        instructions: &[Instruction],
+        address_table_lookups: Vec<MessageAddressTableLookup>,
+        loaded_addresses_list: Vec<LoadedAddresses>,
+        recent_blockhash: Hash,
+    ) -> Result<Self, CompileError> {
         let (header, static_keys) = compiled_keys.try_into_message_components()?;
         let dynamic_keys = loaded_addresses_list.into_iter().collect();
         let account_keys = AccountKeys::new(&static_keys, Some(&dynamic_keys));
@@ -385,6 +549,48 @@ impl Message {
     }
 }
 
+/// Extract table lookups for the keys of `compiled_keys` from the candidate
+/// tables, as described in [`Message::try_compile_with_tables`].
+fn try_select_table_lookups(
+    compiled_keys: &mut CompiledKeys,
+    candidate_lookup_table_accounts: &[AddressLookupTableAccount],
+) -> Result<(Vec<MessageAddressTableLookup>, Vec<LoadedAddresses>), CompileError> {
+    let mut candidates: Vec<_> = candidate_lookup_table_accounts.iter().collect();
+    let mut address_table_lookups = Vec::new();
+    let mut loaded_addresses_list = Vec::new();
+    loop {
+        // Ties go to the earliest candidate
+        let best_candidate = candidates
+            .iter()
+            .enumerate()
+            .map(|(index, lookup_table_account)| {
+                (
+                    index,
+                    compiled_keys.count_keys_found_in_lookup_table(lookup_table_account),
+                )
+            })
+            .filter(|(_, num_keys)| {
+                num_keys.saturating_mul(PUBKEY_BYTES - 1) > ADDRESS_TABLE_LOOKUP_OVERHEAD
+            })
+            .max_by(|(a_index, a_num_keys), (b_index, b_num_keys)| {
+                a_num_keys.cmp(b_num_keys).then(b_index.cmp(a_index))
+            });
+        let Some((index, _)) = best_candidate else {
+            break;
+        };
+
+        let lookup_table_account = candidates.remove(index);
+        if let Some((lookup, loaded_addresses)) =
+            compiled_keys.try_extract_table_lookup(lookup_table_account)?
+        {
+            address_table_lookups.push(lookup);
+            loaded_addresses_list.push(loaded_addresses);
+        }
+    }
+
+    Ok((address_table_lookups, loaded_addresses_list))
+}
+
 #[cfg(test)]
 mod tests {
     use {super::*, crate::VersionedMessage, solana_instruction::AccountMeta};
@@ -714,6 +920,182 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_try_compile_with_tables() {
+        let mut keys = vec![];
+        keys.resize_with(7, Pubkey::new_unique);
+
+        let payer = keys[0];
+        let program_id = keys[6];
+        let instructions = vec![Instruction {
+            program_id,
+            accounts: vec![
+                AccountMeta::new(keys[1], false),
+                AccountMeta::new(keys[2], false),
+                AccountMeta::new_readonly(keys[3], false),
+                AccountMeta::new_readonly(keys[4], false),
+                AccountMeta::new_readonly(keys[5], false),
+            ],
+            data: vec![],
+        }];
+        let address_lookup_table_accounts = vec![
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![keys[1]],
+            },
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![keys[1], keys[2], keys[3]],
+            },
+            AddressLookupTableAccount {
+                key: Pubkey::new_unique(),
+                addresses: vec![keys[2], keys[3], keys[4], keys[5], program_id],
+            },
+        ];
+
+        let recent_blockhash = Hash::new_unique();
+        let message = Message::try_compile_with_tables(
+            &payer,
+            &instructions,
+            &address_lookup_table_accounts,
+            recent_blockhash,
+        )
+        .unwrap();
+        // Once the last table is used, the others hold a single key each
+        assert_eq!(
+            message,
+            Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1
+                },
+                recent_blockhash,
+                account_keys: vec![payer, keys[1], program_id],
+                instructions: vec![CompiledInstruction {
+                    program_id_index: 2,
+                    accounts: vec![1, 3, 4, 5, 6],
+                    data: vec![],
+                }],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: address_lookup_table_accounts[2].key,
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1, 2, 3],
+                }],
+            }
+        );
+
+        let message_with_all_tables = Message::try_compile(
+            &payer,
+            &instructions,
+            &address_lookup_table_accounts,
+            recent_blockhash,
+        )
+        .unwrap();
+        assert_eq!(message_with_all_tables.address_table_lookups.len(), 3);
+        assert!(message.serialize().len() < message_with_all_tables.serialize().len());
+
+        assert_eq!(
+            Message::try_compile_with_tables(
+                &payer,
+                &instructions,
+                &address_lookup_table_accounts[..1],
+                recent_blockhash,
+            )
+            .unwrap()
+            .address_table_lookups,
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_try_from_legacy() {
+        let mut keys = vec![];
+        keys.resize_with(6, Pubkey::new_unique);
+
+        let payer = keys[0];
+        let signer = keys[1];
+        let program_id = keys[5];
+        let legacy_message = legacy::Message::new_with_blockhash(
+            &[Instruction {
+                program_id,
+                accounts: vec![
+                    AccountMeta::new_readonly(keys[4], false),
+                    AccountMeta::new(keys[3], false),
+                    AccountMeta::new_readonly(signer, true),
+                    AccountMeta::new(keys[2], false),
+                    AccountMeta::new_readonly(keys[4], false),
+                ],
+                data: vec![1, 2, 3],
+            }],
+            Some(&payer),
+            &Hash::new_unique(),
+        );
+        let address_lookup_table_accounts = vec![AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![signer, keys[4], keys[3], program_id],
+        }];
+
+        let message =
+            Message::try_from_legacy(&legacy_message, &address_lookup_table_accounts).unwrap();
+        assert_eq!(
+            message,
+            Message {
+                header: MessageHeader {
+                    num_required_signatures: 2,
+                    num_readonly_signed_accounts: 1,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![payer, signer, keys[2], program_id],
+                recent_blockhash: legacy_message.recent_blockhash,
+                instructions: vec![CompiledInstruction {
+                    program_id_index: 3,
+                    accounts: vec![5, 4, 1, 2, 5],
+                    data: vec![1, 2, 3],
+                }],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: address_lookup_table_accounts[0].key,
+                    writable_indexes: vec![2],
+                    readonly_indexes: vec![1],
+                }],
+            }
+        );
+        assert_eq!(message.sanitize(), Ok(()));
+
+        // The instructions refer to the same accounts with the same roles
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![keys[3]],
+            readonly: vec![keys[4]],
+        };
+        let account_keys = AccountKeys::new(&message.account_keys, Some(&loaded_addresses));
+        let legacy_account_keys = AccountKeys::new(&legacy_message.account_keys, None);
+        let loaded_message =
+            LoadedMessage::new_borrowed(&message, &loaded_addresses, &HashSet::default());
+        let legacy_ix = &legacy_message.instructions[0];
+        let ix = &message.instructions[0];
+        for (legacy_index, index) in legacy_ix.accounts.iter().zip(&ix.accounts) {
+            let (legacy_index, index) = (usize::from(*legacy_index), usize::from(*index));
+            assert_eq!(
+                legacy_account_keys.get(legacy_index),
+                account_keys.get(index)
+            );
+            assert_eq!(
+                legacy_message.is_signer(legacy_index),
+                loaded_message.is_signer(index)
+            );
+            assert_eq!(
+                legacy_message.is_writable_index(legacy_index),
+                loaded_message.is_writable(index)
+            );
+        }
+
+        // Without tables, the message keeps every account
+        let message = Message::try_from_legacy(&legacy_message, &[]).unwrap();
+        assert_eq!(message.header, legacy_message.header);
+        assert_eq!(message.account_keys, legacy_message.account_keys);
+        assert_eq!(message.instructions, legacy_message.instructions);
+    }
+
     #[test]
     fn test_is_maybe_writable() {
         let key0 = Pubkey::new_unique();
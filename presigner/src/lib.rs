@@ -3,8 +3,52 @@ use {
     solana_pubkey::Pubkey,
     solana_signature::Signature,
     solana_signer::{Signer, SignerError},
+    std::{error, fmt, str::FromStr},
 };
 
+/// Errors from building presigners out of offline signing output
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresignerParseError {
+    /// An entry was not of the form `pubkey=signature`
+    MissingSeparator(String),
+    /// An entry's pubkey was not valid base58
+    InvalidPubkey(String),
+    /// An entry's signature was not valid base58
+    InvalidSignature(String),
+    /// The signature for the given pubkey does not verify against the message
+    VerificationFailure(Pubkey),
+    /// The JSON input could not be parsed
+    InvalidJson(String),
+}
+
+impl error::Error for PresignerParseError {}
+
+impl fmt::Display for PresignerParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingSeparator(entry) => {
+                write!(f, "expected `pubkey=signature`, found `{entry}`")
+            }
+            Self::InvalidPubkey(pubkey) => write!(f, "invalid pubkey `{pubkey}`"),
+            Self::InvalidSignature(signature) => write!(f, "invalid signature `{signature}`"),
+            Self::VerificationFailure(pubkey) => {
+                write!(
+                    f,
+                    "signature for {pubkey} does not verify against the message"
+                )
+            }
+            Self::InvalidJson(err) => write!(f, "invalid presigner JSON: {err}"),
+        }
+    }
+}
+
+#[cfg(feature = "json")]
+#[derive(serde_derive::Deserialize)]
+struct PresignerEntry {
+    pubkey: String,
+    signature: String,
+}
+
 /// A `Signer` implementation that represents a `Signature` that has been
 /// constructed externally. Performs a signature verification against the
 /// expected message upon `sign()` requests to affirm its relationship to
@@ -22,6 +66,65 @@ impl Presigner {
             signature: *signature,
         }
     }
+
+    /// Parse a single `pubkey=signature` entry, as printed by sign-only
+    /// commands. The signature is not checked against any message.
+    pub fn from_pubkey_signature(entry: &str) -> Result<Self, PresignerParseError> {
+        let (pubkey, signature) = entry
+            .trim()
+            .split_once('=')
+            .ok_or_else(|| PresignerParseError::MissingSeparator(entry.to_string()))?;
+        Self::from_strs(pubkey, signature)
+    }
+
+    /// Parse a batch of `pubkey=signature` entries, verifying every signature
+    /// against `message` before returning. Blank entries are skipped; other
+    /// lines of a sign-only output, such as its `Signers (Pubkey=Signature):`
+    /// header, must be filtered out first.
+    pub fn from_pubkey_signature_pairs<'a, I>(
+        entries: I,
+        message: &[u8],
+    ) -> Result<Vec<Self>, PresignerParseError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        entries
+            .into_iter()
+            .filter(|entry| !entry.trim().is_empty())
+            .map(|entry| Self::from_pubkey_signature(entry)?.verified(message))
+            .collect()
+    }
+
+    /// Parse a JSON array of `{"pubkey": ..., "signature": ...}` objects,
+    /// verifying every signature against `message` before returning.
+    #[cfg(feature = "json")]
+    pub fn from_json(json: &str, message: &[u8]) -> Result<Vec<Self>, PresignerParseError> {
+        let entries: Vec<PresignerEntry> = serde_json::from_str(json)
+            .map_err(|err| PresignerParseError::InvalidJson(err.to_string()))?;
+        entries
+            .iter()
+            .map(|entry| Self::from_strs(&entry.pubkey, &entry.signature)?.verified(message))
+            .collect()
+    }
+
+    fn from_strs(pubkey: &str, signature: &str) -> Result<Self, PresignerParseError> {
+        let pubkey = pubkey.trim();
+        let signature = signature.trim();
+        Ok(Self {
+            pubkey: Pubkey::from_str(pubkey)
+                .map_err(|_| PresignerParseError::InvalidPubkey(pubkey.to_string()))?,
+            signature: Signature::from_str(signature)
+                .map_err(|_| PresignerParseError::InvalidSignature(signature.to_string()))?,
+        })
+    }
+
+    fn verified(self, message: &[u8]) -> Result<Self, PresignerParseError> {
+        if self.signature.verify(self.pubkey.as_ref(), message) {
+            Ok(self)
+        } else {
+            Err(PresignerParseError::VerificationFailure(self.pubkey))
+        }
+    }
 }
 
 impl Signer for Presigner {
@@ -78,4 +181,67 @@ mod tests {
         let presigner2 = Presigner::new(&pubkey, &sig);
         assert_eq!(presigner, presigner2);
     }
+
+    #[test]
+    fn test_presigners_from_sign_only_output() {
+        let keypair0 = keypair_from_seed(&[0u8; 32]).unwrap();
+        let keypair1 = keypair_from_seed(&[1u8; 32]).unwrap();
+        let data = [1u8];
+        let sig0 = keypair0.sign_message(&data);
+        let sig1 = keypair1.sign_message(&data);
+
+        let output = format!(
+            "  {}={}\n\n  {}={}\n",
+            keypair0.pubkey(),
+            sig0,
+            keypair1.pubkey(),
+            sig1
+        );
+        let presigners = Presigner::from_pubkey_signature_pairs(output.lines(), &data).unwrap();
+        assert_eq!(presigners.len(), 2);
+        assert_eq!(presigners[0], keypair0);
+        assert_eq!(presigners[1].sign_message(&data), sig1);
+
+        let json = format!(
+            r#"[{{"pubkey":"{}","signature":"{}"}},{{"pubkey":"{}","signature":"{}"}}]"#,
+            keypair0.pubkey(),
+            sig0,
+            keypair1.pubkey(),
+            sig1
+        );
+        let from_json = Presigner::from_json(&json, &data).unwrap();
+        assert_eq!(from_json[0], presigners[0]);
+        assert_eq!(from_json[1], presigners[1]);
+    }
+
+    #[test]
+    fn test_presigners_from_sign_only_output_errors() {
+        let keypair = keypair_from_seed(&[0u8; 32]).unwrap();
+        let pubkey = keypair.pubkey();
+        let data = [1u8];
+        let sig = keypair.sign_message(&data);
+
+        assert_eq!(
+            Presigner::from_pubkey_signature(&pubkey.to_string()),
+            Err(PresignerParseError::MissingSeparator(pubkey.to_string()))
+        );
+        assert_eq!(
+            Presigner::from_pubkey_signature(&format!("bogus={sig}")).unwrap_err(),
+            PresignerParseError::InvalidPubkey("bogus".to_string())
+        );
+        assert_eq!(
+            Presigner::from_pubkey_signature(&format!("{pubkey}=bogus")).unwrap_err(),
+            PresignerParseError::InvalidSignature("bogus".to_string())
+        );
+        // Verification happens up front rather than at signing time
+        let entry = format!("{pubkey}={sig}");
+        assert_eq!(
+            Presigner::from_pubkey_signature_pairs([entry.as_str()], &[2u8]),
+            Err(PresignerParseError::VerificationFailure(pubkey))
+        );
+        assert!(matches!(
+            Presigner::from_json("[{\"pubkey\":1}]", &data),
+            Err(PresignerParseError::InvalidJson(_))
+        ));
+    }
 }
@@ -0,0 +1,232 @@
+//! Searching for addresses whose base58 form matches a pattern.
+//!
+//! Vanity addresses are found by trying candidates until one matches a
+//! [`GrindPattern`]. [`grind`] spreads the attempts over several threads and
+//! reports progress; [`grind_program_address`] searches for a program derived
+//! address, and the keypair crate builds its keypair search on the same
+//! functions.
+
+#[cfg(feature = "curve25519")]
+use crate::MAX_SEEDS;
+use {
+    crate::{Pubkey, BASE58_ALPHABET, MAX_BASE58_LEN},
+    core::fmt,
+    std::{
+        string::String,
+        sync::{
+            atomic::{AtomicBool, AtomicU64, Ordering},
+            Mutex,
+        },
+        thread,
+    },
+};
+
+/// Number of attempts each thread makes between progress reports.
+const PROGRESS_INTERVAL: u64 = 1 << 14;
+
+/// A character of a [`GrindPattern`] that no base58 address contains.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrindPatternError(pub char);
+
+impl std::error::Error for GrindPatternError {}
+
+impl fmt::Display for GrindPatternError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_fmt(format_args!("'{}' is not a base58 character", self.0))
+    }
+}
+
+/// A prefix and a suffix the base58 form of an address must have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrindPattern {
+    prefix: String,
+    suffix: String,
+    ignore_case: bool,
+}
+
+impl GrindPattern {
+    /// Create a pattern matching addresses that start with `prefix` and end
+    /// with `suffix`, either of which may be empty. If `ignore_case` is true,
+    /// letters match regardless of their case.
+    pub fn new(prefix: &str, suffix: &str, ignore_case: bool) -> Result<Self, GrindPatternError> {
+        let is_base58 = |c: char| {
+            c.is_ascii()
+                && (BASE58_ALPHABET.contains(&(c as u8))
+                    || ignore_case
+                        && (BASE58_ALPHABET.contains(&(c.to_ascii_lowercase() as u8))
+                            || BASE58_ALPHABET.contains(&(c.to_ascii_uppercase() as u8))))
+        };
+        if let Some(c) = prefix
+            .chars()
+            .chain(suffix.chars())
+            .find(|c| !is_base58(*c))
+        {
+            return Err(GrindPatternError(c));
+        }
+        let normalize = |pattern: &str| {
+            if ignore_case {
+                pattern.to_ascii_lowercase()
+            } else {
+                String::from(pattern)
+            }
+        };
+        Ok(Self {
+            prefix: normalize(prefix),
+            suffix: normalize(suffix),
+            ignore_case,
+        })
+    }
+
+    /// Returns true if the base58 form of `pubkey` matches the pattern.
+    pub fn matches(&self, pubkey: &Pubkey) -> bool {
+        let mut encoded = [0u8; MAX_BASE58_LEN];
+        let len = five8::encode_32(pubkey.as_array(), &mut encoded) as usize;
+        let encoded = &mut encoded[..len];
+        if self.ignore_case {
+            encoded.make_ascii_lowercase();
+        }
+        encoded.starts_with(self.prefix.as_bytes()) && encoded.ends_with(self.suffix.as_bytes())
+    }
+}
+
+/// Call `attempt` on `num_threads` threads until it returns a result, and
+/// return that result.
+///
+/// Each call receives a distinct attempt number, counting up from 0 across
+/// all threads. `progress` is called every few thousand attempts with the
+/// number of attempts made so far. At least one thread is used.
+pub fn grind<T: Send>(
+    num_threads: usize,
+    progress: impl Fn(u64) + Sync,
+    attempt: impl Fn(u64) -> Option<T> + Sync,
+) -> T {
+    let num_threads = num_threads.max(1) as u64;
+    let found = AtomicBool::new(false);
+    let num_attempts = AtomicU64::new(0);
+    let result = Mutex::new(None);
+    thread::scope(|scope| {
+        for thread_index in 0..num_threads {
+            let (found, num_attempts, result) = (&found, &num_attempts, &result);
+            let (progress, attempt) = (&progress, &attempt);
+            scope.spawn(move || {
+                let mut attempt_number = thread_index;
+                while !found.load(Ordering::Relaxed) {
+                    for _ in 0..PROGRESS_INTERVAL {
+                        if let Some(value) = attempt(attempt_number) {
+                            found.store(true, Ordering::Relaxed);
+                            result.lock().unwrap().get_or_insert(value);
+                            return;
+                        }
+                        attempt_number = attempt_number.wrapping_add(num_threads);
+                    }
+                    progress(
+                        num_attempts
+                            .fetch_add(PROGRESS_INTERVAL, Ordering::Relaxed)
+                            .saturating_add(PROGRESS_INTERVAL),
+                    );
+                }
+            });
+        }
+    });
+    result
+        .into_inner()
+        .unwrap()
+        .expect("a thread found a result")
+}
+
+/// Find a program derived address matching `pattern`, returning the
+/// address, its bump seed and the grind seed.
+///
+/// The grind seed is an extra seed, the little-endian bytes of a `u64`,
+/// appended to `seeds`; the address is that of
+/// `[seeds.., &grind_seed.to_le_bytes()]`. See [`grind`] for `num_threads` and
+/// `progress`.
+#[cfg(feature = "curve25519")]
+pub fn grind_program_address(
+    seeds: &[&[u8]],
+    program_id: &Pubkey,
+    pattern: &GrindPattern,
+    num_threads: usize,
+    progress: impl Fn(u64) + Sync,
+) -> (Pubkey, u8, u64) {
+    assert!(seeds.len() < MAX_SEEDS, "no room for the grind seed");
+    grind(num_threads, progress, |grind_seed| {
+        let grind_seed_bytes = grind_seed.to_le_bytes();
+        let mut seeds_with_grind_seed = seeds.to_vec();
+        seeds_with_grind_seed.push(&grind_seed_bytes);
+        let (address, bump_seed) =
+            Pubkey::try_find_program_address(&seeds_with_grind_seed, program_id)?;
+        pattern
+            .matches(&address)
+            .then_some((address, bump_seed, grind_seed))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::string::ToString};
+
+    #[test]
+    fn test_grind_pattern() {
+        assert_eq!(
+            GrindPattern::new("0x", "", false),
+            Err(GrindPatternError('0'))
+        );
+        assert_eq!(
+            GrindPattern::new("", "l", false),
+            Err(GrindPatternError('l'))
+        );
+        // 'l' is not base58, but 'L' is
+        assert!(GrindPattern::new("", "l", true).is_ok());
+
+        let pubkey = Pubkey::new_unique();
+        let encoded = pubkey.to_string();
+        let (prefix, suffix) = (&encoded[..3], &encoded[encoded.len() - 2..]);
+        assert!(GrindPattern::new(prefix, suffix, false)
+            .unwrap()
+            .matches(&pubkey));
+        assert!(GrindPattern::new("", "", false).unwrap().matches(&pubkey));
+        let swapped_case: String = prefix
+            .chars()
+            .map(|c| {
+                if c.is_ascii_uppercase() {
+                    c.to_ascii_lowercase()
+                } else {
+                    c.to_ascii_uppercase()
+                }
+            })
+            .collect();
+        assert!(GrindPattern::new(&swapped_case, suffix, true)
+            .unwrap()
+            .matches(&pubkey));
+    }
+
+    #[test]
+    fn test_grind() {
+        let reported = AtomicU64::new(0);
+        let result = grind(
+            4,
+            |num_attempts| {
+                reported.fetch_max(num_attempts, Ordering::Relaxed);
+            },
+            |attempt_number| (attempt_number == 100_000).then_some(attempt_number),
+        );
+        assert_eq!(result, 100_000);
+        assert!(reported.load(Ordering::Relaxed) > 0);
+        assert_eq!(grind(0, |_| {}, Some), 0);
+    }
+
+    #[test]
+    fn test_grind_program_address() {
+        let program_id = Pubkey::new_unique();
+        let pattern = GrindPattern::new("a", "", true).unwrap();
+        let seeds: &[&[u8]] = &[b"vanity"];
+        let (address, bump_seed, grind_seed) =
+            grind_program_address(seeds, &program_id, &pattern, 2, |_| {});
+        assert!(pattern.matches(&address));
+        assert_eq!(
+            Pubkey::find_program_address(&[b"vanity", &grind_seed.to_le_bytes()], &program_id),
+            (address, bump_seed)
+        );
+    }
+}
@@ -37,6 +37,16 @@ use {
     wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue},
 };
 
+#[cfg(all(
+    feature = "std",
+    not(target_os = "solana"),
+    not(target_arch = "wasm32")
+))]
+pub mod grind;
+#[cfg(all(feature = "std", feature = "curve25519", not(target_os = "solana")))]
+pub mod pda_cache;
+#[cfg(feature = "std")]
+pub mod pubkey_set;
 #[cfg(target_os = "solana")]
 pub mod syscalls;
 
@@ -48,6 +58,8 @@ pub const MAX_SEED_LEN: usize = 32;
 pub const MAX_SEEDS: usize = 16;
 /// Maximum string length of a base58 encoded pubkey
 const MAX_BASE58_LEN: usize = 44;
+/// Characters of base58 encoded pubkeys, in the order of their values
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
 
 #[cfg(any(target_os = "solana", feature = "sha2", feature = "curve25519"))]
 const PDA_MARKER: &[u8; 21] = b"ProgramDerivedAddress";
@@ -386,23 +398,50 @@ impl From<Infallible> for ParsePubkeyError {
     }
 }
 
+/// Why a string is not a base58 encoded pubkey, in more detail than
+/// [`ParsePubkeyError`], for reporting to users.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePubkeyDetailedError {
+    /// The string of `len` characters does not encode 32 bytes.
+    WrongSize { len: usize },
+    /// The character at `position`, counted in characters from 0, is not in
+    /// the base58 alphabet.
+    InvalidChar { character: char, position: usize },
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParsePubkeyDetailedError {}
+
+impl fmt::Display for ParsePubkeyDetailedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParsePubkeyDetailedError::WrongSize { len } => f.write_fmt(format_args!(
+                "String of {len} characters is the wrong size for a pubkey"
+            )),
+            ParsePubkeyDetailedError::InvalidChar {
+                character,
+                position,
+            } => f.write_fmt(format_args!(
+                "Invalid Base58 character {character:?} at position {position}"
+            )),
+        }
+    }
+}
+
+impl From<ParsePubkeyDetailedError> for ParsePubkeyError {
+    fn from(err: ParsePubkeyDetailedError) -> Self {
+        match err {
+            ParsePubkeyDetailedError::WrongSize { .. } => ParsePubkeyError::WrongSize,
+            ParsePubkeyDetailedError::InvalidChar { .. } => ParsePubkeyError::Invalid,
+        }
+    }
+}
+
 impl FromStr for Pubkey {
     type Err = ParsePubkeyError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        use five8::DecodeError;
-        if s.len() > MAX_BASE58_LEN {
-            return Err(ParsePubkeyError::WrongSize);
-        }
-        let mut bytes = [0; PUBKEY_BYTES];
-        five8::decode_32(s, &mut bytes).map_err(|e| match e {
-            DecodeError::InvalidChar(_) => ParsePubkeyError::Invalid,
-            DecodeError::TooLong
-            | DecodeError::TooShort
-            | DecodeError::LargestTermTooHigh
-            | DecodeError::OutputTooLong => ParsePubkeyError::WrongSize,
-        })?;
-        Ok(Pubkey(bytes))
+        Self::parse_base58(s).map_err(ParsePubkeyError::from)
     }
 }
 
@@ -537,6 +576,38 @@ impl Pubkey {
         Ok(Pubkey::from(hash.to_bytes()))
     }
 
+    /// Derive the address [`create_with_seed`] derives from `base` and
+    /// `owner` for each of `seeds`, in order.
+    ///
+    /// The base is hashed once and the hashing state is shared by all seeds.
+    /// Fails if any seed is too long or if `owner` is not allowed.
+    ///
+    /// [`create_with_seed`]: Pubkey::create_with_seed
+    #[cfg(all(feature = "std", feature = "sha2", not(target_os = "solana")))]
+    pub fn create_with_seed_batch(
+        base: &Pubkey,
+        seeds: &[&str],
+        owner: &Pubkey,
+    ) -> Result<Vec<Pubkey>, PubkeyError> {
+        if seeds.iter().any(|seed| seed.len() > MAX_SEED_LEN) {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+        if owner.as_ref().ends_with(PDA_MARKER) {
+            return Err(PubkeyError::IllegalOwner);
+        }
+
+        let mut base_hasher = solana_sha256_hasher::Hasher::default();
+        base_hasher.hash(base.as_ref());
+        Ok(seeds
+            .iter()
+            .map(|seed| {
+                let mut hasher = base_hasher.clone();
+                hasher.hashv(&[seed.as_ref(), owner.as_ref()]);
+                Pubkey::from(hasher.result().to_bytes())
+            })
+            .collect())
+    }
+
     /// Find a valid [program derived address][pda] and its corresponding bump seed.
     ///
     /// [pda]: https://solana.com/docs/core/cpi#program-derived-addresses
@@ -791,6 +862,68 @@ impl Pubkey {
             .unwrap_or_else(|| panic!("Unable to find a viable program address bump seed"))
     }
 
+    /// Find the [program derived addresses][pda] of many lists of seeds at
+    /// once, returning each address with its bump seed in the order of
+    /// `seeds_list`.
+    ///
+    /// [pda]: https://solana.com/docs/core/cpi#program-derived-addresses
+    ///
+    /// With the `rayon` feature the addresses are derived in parallel off
+    /// chain. Panics like [`find_program_address`] if any address cannot be
+    /// found.
+    ///
+    /// [`find_program_address`]: Pubkey::find_program_address
+    #[cfg(all(feature = "std", any(target_os = "solana", feature = "curve25519")))]
+    pub fn find_program_addresses<'a, S: AsRef<[&'a [u8]]> + Sync>(
+        seeds_list: &[S],
+        program_id: &Pubkey,
+    ) -> Vec<(Pubkey, u8)> {
+        #[cfg(all(feature = "rayon", not(target_os = "solana")))]
+        {
+            use rayon::prelude::*;
+            seeds_list
+                .par_iter()
+                .map(|seeds| Self::find_program_address(seeds.as_ref(), program_id))
+                .collect()
+        }
+        #[cfg(not(all(feature = "rayon", not(target_os = "solana"))))]
+        {
+            seeds_list
+                .iter()
+                .map(|seeds| Self::find_program_address(seeds.as_ref(), program_id))
+                .collect()
+        }
+    }
+
+    /// Find every bump seed that makes `seeds` derive a valid [program
+    /// derived address][pda], with the address, from bump seed 255 down to 0.
+    ///
+    /// [pda]: https://solana.com/docs/core/cpi#program-derived-addresses
+    ///
+    /// The first bump seed returned is the canonical one that
+    /// [`find_program_address`] finds, except that bump seed 0, which
+    /// [`find_program_address`] never tries, can be the only one. Returns an
+    /// empty list if the seeds are invalid.
+    ///
+    /// [`find_program_address`]: Pubkey::find_program_address
+    #[cfg(all(feature = "std", any(target_os = "solana", feature = "curve25519")))]
+    pub fn find_all_program_address_bumps(
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+    ) -> Vec<(Pubkey, u8)> {
+        (0..=u8::MAX)
+            .rev()
+            .filter_map(|bump_seed| {
+                let bump_seed = [bump_seed];
+                let mut seeds_with_bump = seeds.to_vec();
+                seeds_with_bump.push(&bump_seed);
+                Self::create_program_address(&seeds_with_bump, program_id)
+                    .ok()
+                    .map(|address| (address, bump_seed[0]))
+            })
+            .collect()
+    }
+
     /// Find a valid [program derived address][pda] and its corresponding bump seed.
     ///
     /// [pda]: https://solana.com/docs/core/cpi#program-derived-addresses
@@ -950,6 +1083,49 @@ impl Pubkey {
         self.0
     }
 
+    /// Encode the pubkey as base58 into `out`, returning the encoded string
+    /// without allocating.
+    pub fn write_base58<'a>(&self, out: &'a mut [u8; MAX_BASE58_LEN]) -> &'a str {
+        let len = five8::encode_32(&self.0, out) as usize;
+        // any sequence of base58 chars is valid utf8
+        unsafe { from_utf8_unchecked(&out[..len]) }
+    }
+
+    /// Decode a base58 string into a Pubkey like [`FromStr`] does, reporting
+    /// the offending character of an invalid string.
+    pub fn parse_base58(s: &str) -> Result<Self, ParsePubkeyDetailedError> {
+        use five8::DecodeError;
+        let wrong_size = || ParsePubkeyDetailedError::WrongSize {
+            len: s.chars().count(),
+        };
+        // a non-ASCII character is reported as such even though it also
+        // makes the string too long in bytes
+        let invalid_char_or_wrong_size = || {
+            s.chars()
+                .enumerate()
+                .find(|(_, c)| !c.is_ascii() || !BASE58_ALPHABET.contains(&(*c as u8)))
+                .map(
+                    |(position, character)| ParsePubkeyDetailedError::InvalidChar {
+                        character,
+                        position,
+                    },
+                )
+                .unwrap_or_else(wrong_size)
+        };
+        if s.len() > MAX_BASE58_LEN {
+            return Err(invalid_char_or_wrong_size());
+        }
+        let mut bytes = [0; PUBKEY_BYTES];
+        five8::decode_32(s, &mut bytes).map_err(|e| match e {
+            DecodeError::InvalidChar(_) => invalid_char_or_wrong_size(),
+            DecodeError::TooLong
+            | DecodeError::TooShort
+            | DecodeError::LargestTermTooHigh
+            | DecodeError::OutputTooLong => wrong_size(),
+        })?;
+        Ok(Pubkey(bytes))
+    }
+
     /// Return a reference to the `Pubkey`'s byte array.
     #[inline(always)]
     pub const fn as_array(&self) -> &[u8; 32] {
@@ -964,6 +1140,24 @@ impl Pubkey {
         bytes_are_curve_point(self)
     }
 
+    /// Check whether each of `pubkeys` is on the ed25519 curve, returning the
+    /// results in the order of `pubkeys`.
+    ///
+    /// With the `rayon` feature the checks are spread over the rayon thread
+    /// pool, which pays off for batches of more than a few hundred keys.
+    #[cfg(all(feature = "std", feature = "curve25519", not(target_os = "solana")))]
+    pub fn are_on_curve(pubkeys: &[Pubkey]) -> Vec<bool> {
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            pubkeys.par_iter().map(Pubkey::is_on_curve).collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            pubkeys.iter().map(Pubkey::is_on_curve).collect()
+        }
+    }
+
     /// Log a `Pubkey` from a program
     pub fn log(&self) {
         #[cfg(target_os = "solana")]
@@ -976,6 +1170,63 @@ impl Pubkey {
     }
 }
 
+#[cfg(feature = "hex")]
+impl Pubkey {
+    /// Decode a pubkey from PUBKEY_BYTES bytes of hex digits of either case,
+    /// optionally prefixed by `0x`.
+    pub fn from_hex(s: &str) -> Result<Self, ParsePubkeyError> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let mut bytes = [0; PUBKEY_BYTES];
+        hex::decode_to_slice(s, &mut bytes).map_err(|err| match err {
+            hex::FromHexError::InvalidHexCharacter { .. } => ParsePubkeyError::Invalid,
+            hex::FromHexError::OddLength | hex::FromHexError::InvalidStringLength => {
+                ParsePubkeyError::WrongSize
+            }
+        })?;
+        Ok(Self(bytes))
+    }
+
+    /// Encode the bytes as lowercase hex digits, without a prefix.
+    pub fn to_hex(&self) -> std::string::String {
+        hex::encode(self.0)
+    }
+}
+
+#[cfg(feature = "base64")]
+impl Pubkey {
+    /// Decode a pubkey from standard, padded base64.
+    pub fn from_base64(s: &str) -> Result<Self, ParsePubkeyError> {
+        use base64::{prelude::BASE64_STANDARD, DecodeError, Engine};
+        // 4 base64 characters encode 3 bytes
+        if s.len() > PUBKEY_BYTES.div_ceil(3) * 4 {
+            return Err(ParsePubkeyError::WrongSize);
+        }
+        let bytes = BASE64_STANDARD.decode(s).map_err(|err| match err {
+            DecodeError::InvalidLength(_) => ParsePubkeyError::WrongSize,
+            _ => ParsePubkeyError::Invalid,
+        })?;
+        let bytes =
+            <[u8; PUBKEY_BYTES]>::try_from(bytes).map_err(|_| ParsePubkeyError::WrongSize)?;
+        Ok(Self(bytes))
+    }
+
+    /// Encode the bytes as standard, padded base64.
+    pub fn to_base64(&self) -> std::string::String {
+        use base64::{prelude::BASE64_STANDARD, Engine};
+        BASE64_STANDARD.encode(self.0)
+    }
+}
+
+/// Compares pubkeys in constant time, for secret-adjacent contexts such as
+/// checking derived authentication keys, where the short-circuiting
+/// [`PartialEq`] would leak how many leading bytes match.
+#[cfg(feature = "subtle")]
+impl subtle::ConstantTimeEq for Pubkey {
+    fn ct_eq(&self, other: &Self) -> subtle::Choice {
+        subtle::ConstantTimeEq::ct_eq(&self.0[..], &other.0[..])
+    }
+}
+
 impl AsRef<[u8]> for Pubkey {
     fn as_ref(&self) -> &[u8] {
         &self.0[..]
@@ -990,10 +1241,7 @@ impl AsMut<[u8]> for Pubkey {
 
 fn write_as_base58(f: &mut fmt::Formatter, p: &Pubkey) -> fmt::Result {
     let mut out = [0u8; MAX_BASE58_LEN];
-    let len = five8::encode_32(&p.0, &mut out) as usize;
-    // any sequence of base58 chars is valid utf8
-    let as_str = unsafe { from_utf8_unchecked(&out[..len]) };
-    f.write_str(as_str)
+    f.write_str(p.write_base58(&mut out))
 }
 
 impl fmt::Debug for Pubkey {
@@ -1156,8 +1404,31 @@ impl Pubkey {
 /// let my_id = Pubkey::from_str("My11111111111111111111111111111111111111111").unwrap();
 /// assert_eq!(id(), my_id);
 /// ```
+///
+/// The ID can also be read from an environment variable at compile time, with
+/// an optional fallback used when the variable is not set. A malformed value
+/// is a compile error. See [`pubkey`] for the syntax.
+///
+/// ```
+/// # mod item_wrapper {
+/// #   use solana_pubkey::declare_id;
+/// declare_id!(
+///     env = "MY_PROGRAM_ID",
+///     default = "My11111111111111111111111111111111111111111"
+/// );
+/// # }
+/// ```
 #[macro_export]
 macro_rules! declare_id {
+    (env = $var:literal, default = $default:literal) => {
+        $crate::declare_id!(match ::core::option_env!($var) {
+            Some(address) => address,
+            None => $default,
+        });
+    };
+    (env = $var:literal) => {
+        $crate::declare_id!(::core::env!($var));
+    };
     ($address:expr) => {
         /// The const program ID.
         pub const ID: $crate::Pubkey = $crate::Pubkey::from_str_const($address);
@@ -1227,8 +1498,31 @@ macro_rules! declare_deprecated_id {
 /// let my_id = Pubkey::from_str("My11111111111111111111111111111111111111111").unwrap();
 /// assert_eq!(ID, my_id);
 /// ```
+///
+/// The pubkey can also be read from an environment variable at compile time,
+/// so that a build for each cluster can use its own ID. Without a `default`
+/// the variable must be set. A malformed value is a compile error.
+///
+/// ```
+/// use solana_pubkey::{pubkey, Pubkey};
+///
+/// const ID: Pubkey = pubkey!(
+///     env = "MY_PROGRAM_ID",
+///     default = "My11111111111111111111111111111111111111111"
+/// );
+/// # assert_eq!(ID, pubkey!("My11111111111111111111111111111111111111111"));
+/// ```
 #[macro_export]
 macro_rules! pubkey {
+    (env = $var:literal, default = $default:literal) => {
+        $crate::Pubkey::from_str_const(match ::core::option_env!($var) {
+            Some(input) => input,
+            None => $default,
+        })
+    };
+    (env = $var:literal) => {
+        $crate::Pubkey::from_str_const(::core::env!($var))
+    };
     ($input:literal) => {
         $crate::Pubkey::from_str_const($input)
     };
@@ -1249,6 +1543,50 @@ mod tests {
         assert!(Pubkey::new_unique() != Pubkey::new_unique());
     }
 
+    #[cfg(feature = "hex")]
+    #[test]
+    fn test_pubkey_hex() {
+        let pubkey = Pubkey::new_from_array(core::array::from_fn(|i| i as u8 * 7));
+        let hex = pubkey.to_hex();
+        assert_eq!(hex.len(), 64);
+        assert_eq!(Pubkey::from_hex(&hex), Ok(pubkey));
+        assert_eq!(Pubkey::from_hex(&hex.to_uppercase()), Ok(pubkey));
+        assert_eq!(Pubkey::from_hex(&std::format!("0x{hex}")), Ok(pubkey));
+        assert_eq!(
+            Pubkey::from_hex(&hex[2..]),
+            Err(ParsePubkeyError::WrongSize)
+        );
+        assert_eq!(
+            Pubkey::from_hex(&hex[1..]),
+            Err(ParsePubkeyError::WrongSize)
+        );
+        assert_eq!(
+            Pubkey::from_hex(&hex.replace('0', "g")),
+            Err(ParsePubkeyError::Invalid)
+        );
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn test_pubkey_base64() {
+        let pubkey = Pubkey::new_from_array(core::array::from_fn(|i| i as u8 * 7));
+        let base64 = pubkey.to_base64();
+        assert_eq!(base64.len(), 44);
+        assert_eq!(Pubkey::from_base64(&base64), Ok(pubkey));
+        assert_eq!(
+            Pubkey::from_base64("AAAA"),
+            Err(ParsePubkeyError::WrongSize)
+        );
+        assert_eq!(
+            Pubkey::from_base64(&std::format!("{base64}AAAA")),
+            Err(ParsePubkeyError::WrongSize)
+        );
+        assert_eq!(
+            Pubkey::from_base64(&base64.replace(base64.chars().next().unwrap(), "*")),
+            Err(ParsePubkeyError::Invalid)
+        );
+    }
+
     #[test]
     fn pubkey_fromstr() {
         let pubkey = Pubkey::new_unique();
@@ -1289,6 +1627,106 @@ mod tests {
         assert_eq!(too_long.parse::<Pubkey>(), Err(ParsePubkeyError::WrongSize));
     }
 
+    #[test]
+    fn test_write_base58() {
+        let mut out = [0u8; MAX_BASE58_LEN];
+        for pubkey in [
+            Pubkey::default(),
+            Pubkey::new_unique(),
+            Pubkey::from([255; 32]),
+        ] {
+            assert_eq!(
+                pubkey.write_base58(&mut out),
+                bs58::encode(pubkey.0).into_string()
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_base58() {
+        // full width, so that appending a character overflows 32 bytes
+        let pubkey = Pubkey::from([u8::MAX; 32]);
+        let pubkey_base58_str = bs58::encode(pubkey.0).into_string();
+        assert_eq!(Pubkey::parse_base58(&pubkey_base58_str), Ok(pubkey));
+
+        let mut invalid = pubkey_base58_str.clone();
+        invalid.replace_range(3..4, "0");
+        assert_eq!(
+            Pubkey::parse_base58(&invalid),
+            Err(ParsePubkeyDetailedError::InvalidChar {
+                character: '0',
+                position: 3,
+            })
+        );
+        assert_eq!(invalid.parse::<Pubkey>(), Err(ParsePubkeyError::Invalid));
+
+        let mut invalid = pubkey_base58_str.clone();
+        invalid.replace_range(1..2, "☉");
+        assert_eq!(
+            Pubkey::parse_base58(&invalid),
+            Err(ParsePubkeyDetailedError::InvalidChar {
+                character: '☉',
+                position: 1,
+            })
+        );
+
+        let too_short = &pubkey_base58_str[..10];
+        assert_eq!(
+            Pubkey::parse_base58(too_short),
+            Err(ParsePubkeyDetailedError::WrongSize { len: 10 })
+        );
+        let too_long = std::format!("{pubkey_base58_str}1");
+        assert_eq!(
+            Pubkey::parse_base58(&too_long),
+            Err(ParsePubkeyDetailedError::WrongSize {
+                len: pubkey_base58_str.len() + 1
+            })
+        );
+        assert_eq!(too_long.parse::<Pubkey>(), Err(ParsePubkeyError::WrongSize));
+    }
+
+    #[cfg(feature = "subtle")]
+    #[test]
+    fn test_ct_eq() {
+        use subtle::ConstantTimeEq;
+        let pubkey = Pubkey::new_unique();
+        let mut other = pubkey.to_bytes();
+        assert!(bool::from(pubkey.ct_eq(&Pubkey::from(other))));
+        other[31] ^= 1;
+        assert!(!bool::from(pubkey.ct_eq(&Pubkey::from(other))));
+    }
+
+    #[test]
+    fn test_create_with_seed_batch() {
+        let base = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let seeds = ["", "stake:0", "stake:1", "☉"];
+        assert_eq!(
+            Pubkey::create_with_seed_batch(&base, &seeds, &owner),
+            seeds
+                .iter()
+                .map(|seed| Pubkey::create_with_seed(&base, seed, &owner))
+                .collect::<Result<Vec<_>, _>>()
+        );
+        assert_eq!(
+            Pubkey::create_with_seed_batch(&base, &[], &owner),
+            Ok(std::vec![])
+        );
+
+        let long_seed = from_utf8(&[127; MAX_SEED_LEN + 1]).unwrap();
+        assert_eq!(
+            Pubkey::create_with_seed_batch(&base, &["ok", long_seed], &owner),
+            Err(PubkeyError::MaxSeedLengthExceeded)
+        );
+
+        let mut illegal_owner = [0; PUBKEY_BYTES];
+        illegal_owner[PUBKEY_BYTES - PDA_MARKER.len()..].copy_from_slice(PDA_MARKER);
+        assert_eq!(
+            Pubkey::create_with_seed_batch(&base, &seeds, &Pubkey::from(illegal_owner)),
+            Err(PubkeyError::IllegalOwner)
+        );
+    }
+
     #[test]
     fn test_create_with_seed() {
         assert!(
@@ -1466,6 +1904,75 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_find_all_program_address_bumps() {
+        let program_id = Pubkey::new_unique();
+        let seeds: &[&[u8]] = &[b"Lil'", b"Bits"];
+        let bumps = Pubkey::find_all_program_address_bumps(seeds, &program_id);
+
+        // about half of all hashes are off the curve
+        assert!(bumps.len() > 64 && bumps.len() < 192);
+        assert_eq!(bumps[0], Pubkey::find_program_address(seeds, &program_id));
+        assert!(bumps.windows(2).all(|pair| pair[0].1 > pair[1].1));
+        for bump_seed in 0..=u8::MAX {
+            let address =
+                Pubkey::create_program_address(&[seeds[0], seeds[1], &[bump_seed]], &program_id);
+            assert_eq!(
+                address.ok(),
+                bumps
+                    .iter()
+                    .find(|(_, bump)| *bump == bump_seed)
+                    .map(|(address, _)| *address)
+            );
+        }
+
+        let long_seed = [0; MAX_SEED_LEN + 1];
+        assert!(Pubkey::find_all_program_address_bumps(&[&long_seed], &program_id).is_empty());
+    }
+
+    #[test]
+    fn test_are_on_curve() {
+        let program_id = Pubkey::new_unique();
+        let pubkeys: Vec<Pubkey> = (0..50u8)
+            .flat_map(|i| {
+                [
+                    Pubkey::find_program_address(&[&[i]], &program_id).0,
+                    Pubkey::from(
+                        curve25519_dalek::constants::ED25519_BASEPOINT_POINT
+                            .mul_by_cofactor()
+                            .compress()
+                            .to_bytes(),
+                    ),
+                ]
+            })
+            .collect();
+        let on_curve = Pubkey::are_on_curve(&pubkeys);
+        assert_eq!(
+            on_curve,
+            pubkeys.iter().map(Pubkey::is_on_curve).collect::<Vec<_>>()
+        );
+        assert_eq!(on_curve.iter().filter(|on_curve| **on_curve).count(), 50);
+        assert!(Pubkey::are_on_curve(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_find_program_addresses() {
+        let program_id = Pubkey::new_unique();
+        let owners: Vec<Pubkey> = (0..100).map(|_| Pubkey::new_unique()).collect();
+        let seeds_list: Vec<[&[u8]; 2]> = owners
+            .iter()
+            .map(|owner| [b"vault".as_slice(), owner.as_ref()])
+            .collect();
+        assert_eq!(
+            Pubkey::find_program_addresses(&seeds_list, &program_id),
+            seeds_list
+                .iter()
+                .map(|seeds| Pubkey::find_program_address(seeds, &program_id))
+                .collect::<Vec<_>>()
+        );
+        assert!(Pubkey::find_program_addresses::<Vec<&[u8]>>(&[], &program_id).is_empty());
+    }
+
     fn pubkey_from_seed_by_marker(marker: &[u8]) -> Result<Pubkey, PubkeyError> {
         let key = Pubkey::new_unique();
         let owner = Pubkey::default();
@@ -1515,6 +2022,13 @@ mod tests {
     fn test_pubkey_macro() {
         const PK: Pubkey = Pubkey::from_str_const("9h1HyLCW5dZnBVap8C5egQ9Z6pHyjsh5MNy83iPqqRuq");
         assert_eq!(pubkey!("9h1HyLCW5dZnBVap8C5egQ9Z6pHyjsh5MNy83iPqqRuq"), PK);
+        assert_eq!(
+            pubkey!(
+                env = "SOLANA_PUBKEY_TEST_UNSET_ID",
+                default = "9h1HyLCW5dZnBVap8C5egQ9Z6pHyjsh5MNy83iPqqRuq"
+            ),
+            PK
+        );
         assert_eq!(
             Pubkey::from_str("9h1HyLCW5dZnBVap8C5egQ9Z6pHyjsh5MNy83iPqqRuq").unwrap(),
             PK
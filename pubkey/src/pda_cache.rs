@@ -0,0 +1,184 @@
+//! A cache in front of [`Pubkey::find_program_address`].
+//!
+//! Finding a program derived address hashes the seeds once per bump seed
+//! tried and checks each hash against the curve. Services that derive the
+//! same addresses over and over can look them up in a shared
+//! [`ProgramAddressCache`] instead.
+
+use {
+    crate::Pubkey,
+    std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            RwLock,
+        },
+    },
+};
+
+/// Hash of the seeds and program id of a derivation. Each seed is prefixed
+/// by its length so that different splits of the same bytes differ.
+type DerivationKey = [u8; 32];
+
+/// Hit and miss counts of a [`ProgramAddressCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ProgramAddressCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl ProgramAddressCacheStats {
+    /// Fraction of lookups answered from the cache, 0 if there were none.
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits.saturating_add(self.misses);
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / lookups as f64
+        }
+    }
+}
+
+/// A thread-safe cache of program derived addresses and their bump seeds,
+/// keyed by a hash of the seeds and the program id.
+///
+/// The cache holds at most `capacity` addresses. Inserting into a full cache
+/// evicts an arbitrary address. Derivations that find no address are not
+/// cached.
+#[derive(Debug)]
+pub struct ProgramAddressCache {
+    capacity: usize,
+    addresses: RwLock<HashMap<DerivationKey, (Pubkey, u8)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl ProgramAddressCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            addresses: RwLock::default(),
+            hits: AtomicU64::default(),
+            misses: AtomicU64::default(),
+        }
+    }
+
+    /// Like [`Pubkey::find_program_address`], but returns the cached address
+    /// if the seeds were derived before.
+    pub fn find_program_address(&self, seeds: &[&[u8]], program_id: &Pubkey) -> (Pubkey, u8) {
+        self.try_find_program_address(seeds, program_id)
+            .unwrap_or_else(|| panic!("Unable to find a viable program address bump seed"))
+    }
+
+    /// Like [`Pubkey::try_find_program_address`], but returns the cached
+    /// address if the seeds were derived before.
+    pub fn try_find_program_address(
+        &self,
+        seeds: &[&[u8]],
+        program_id: &Pubkey,
+    ) -> Option<(Pubkey, u8)> {
+        let key = derivation_key(seeds, program_id);
+        if let Some(address) = self.addresses.read().unwrap().get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Some(*address);
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+
+        let address = Pubkey::try_find_program_address(seeds, program_id)?;
+        if self.capacity > 0 {
+            let mut addresses = self.addresses.write().unwrap();
+            if addresses.len() >= self.capacity && !addresses.contains_key(&key) {
+                let evicted = *addresses.keys().next().expect("cache is full");
+                addresses.remove(&evicted);
+            }
+            addresses.insert(key, address);
+        }
+        Some(address)
+    }
+
+    /// Number of cached addresses.
+    pub fn len(&self) -> usize {
+        self.addresses.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Evict every address. The hit and miss counts are kept.
+    pub fn clear(&self) {
+        self.addresses.write().unwrap().clear();
+    }
+
+    pub fn stats(&self) -> ProgramAddressCacheStats {
+        ProgramAddressCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+fn derivation_key(seeds: &[&[u8]], program_id: &Pubkey) -> DerivationKey {
+    let mut hasher = solana_sha256_hasher::Hasher::default();
+    for seed in seeds {
+        hasher.hash(&(seed.len() as u64).to_le_bytes());
+        hasher.hash(seed);
+    }
+    hasher.hash(program_id.as_ref());
+    hasher.result().to_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_program_address() {
+        let cache = ProgramAddressCache::new(2);
+        let program_id = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let seeds: &[&[u8]] = &[b"vault", owner.as_ref()];
+
+        let expected = Pubkey::find_program_address(seeds, &program_id);
+        assert_eq!(cache.find_program_address(seeds, &program_id), expected);
+        assert_eq!(cache.find_program_address(seeds, &program_id), expected);
+        assert_eq!(
+            cache.stats(),
+            ProgramAddressCacheStats { hits: 1, misses: 1 }
+        );
+        assert_eq!(cache.stats().hit_rate(), 0.5);
+
+        // the same bytes split into other seeds derive another address
+        let split_seeds: &[&[u8]] = &[b"vau", b"lt", owner.as_ref()];
+        assert_eq!(
+            cache.find_program_address(split_seeds, &program_id),
+            Pubkey::find_program_address(split_seeds, &program_id)
+        );
+        assert_eq!(cache.len(), 2);
+
+        // a full cache evicts an address to make room
+        cache.find_program_address(seeds, &Pubkey::new_unique());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.stats().misses, 3);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert_eq!(ProgramAddressCacheStats::default().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let cache = ProgramAddressCache::new(0);
+        let program_id = Pubkey::new_unique();
+        let seeds: &[&[u8]] = &[b"seed"];
+        assert_eq!(
+            cache.find_program_address(seeds, &program_id),
+            Pubkey::find_program_address(seeds, &program_id)
+        );
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,257 @@
+//! A compact, probabilistic set of pubkeys.
+//!
+//! [`PubkeySet`] is an xor filter: it answers membership queries in constant
+//! time using a little under 10 bits per pubkey, compared to the 32 bytes per
+//! pubkey of a `HashSet<Pubkey>`. Every pubkey in the set is reported as a
+//! member, and about 1 in 256 other pubkeys is reported as a member too. The
+//! set cannot be modified after it is built.
+
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+use {
+    crate::Pubkey,
+    std::{vec, vec::Vec},
+};
+
+/// Length of the header of [`PubkeySet::to_bytes`]: the seed and the block
+/// length.
+const HEADER_LEN: usize = 12;
+
+/// A set of pubkeys that reports about 1 in 256 non-members as members.
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(try_from = "PubkeySetFields")
+)]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PubkeySet {
+    seed: u64,
+    block_length: u32,
+    /// Three blocks of `block_length` fingerprints, empty for an empty set.
+    fingerprints: Vec<u8>,
+}
+
+/// The fields of a deserialized [`PubkeySet`], checked like
+/// [`PubkeySet::from_bytes`] checks its input.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+struct PubkeySetFields {
+    seed: u64,
+    block_length: u32,
+    fingerprints: Vec<u8>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<PubkeySetFields> for PubkeySet {
+    type Error = &'static str;
+
+    fn try_from(fields: PubkeySetFields) -> Result<Self, Self::Error> {
+        Self::from_parts(fields.seed, fields.block_length, fields.fingerprints)
+            .ok_or("fingerprints do not match the block length")
+    }
+}
+
+impl PubkeySet {
+    /// Build the set of `pubkeys`. Duplicates are allowed.
+    pub fn new<'a>(pubkeys: impl IntoIterator<Item = &'a Pubkey>) -> Self {
+        let mut keys: Vec<u64> = pubkeys.into_iter().map(pubkey_key).collect();
+        keys.sort_unstable();
+        keys.dedup();
+        if keys.is_empty() {
+            return Self::default();
+        }
+
+        // 1.23 slots per key make the construction succeed with high
+        // probability; the constant covers small sets.
+        let capacity = keys
+            .len()
+            .saturating_mul(123)
+            .saturating_div(100)
+            .saturating_add(32);
+        let block_length = u32::try_from(capacity.div_ceil(3)).expect("too many pubkeys");
+        let capacity = (block_length as usize).saturating_mul(3);
+
+        let mut seed_state = 0x9e37_79b9_7f4a_7c15;
+        loop {
+            let seed = splitmix64(&mut seed_state);
+            if let Some(fingerprints) = try_build(&keys, seed, block_length, capacity) {
+                return Self {
+                    seed,
+                    block_length,
+                    fingerprints,
+                };
+            }
+        }
+    }
+
+    /// Returns true if `pubkey` is in the set, or, with a probability of
+    /// about 1/256, if it is not.
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        if self.fingerprints.is_empty() {
+            return false;
+        }
+        let hash = mix(pubkey_key(pubkey).wrapping_add(self.seed));
+        let [h0, h1, h2] = slots(hash, self.block_length);
+        fingerprint(hash) ^ self.fingerprints[h0] ^ self.fingerprints[h1] ^ self.fingerprints[h2]
+            == 0
+    }
+
+    /// Number of bytes the fingerprints occupy.
+    pub fn size_in_bytes(&self) -> usize {
+        self.fingerprints.len()
+    }
+
+    /// Serialize the set: the seed and the block length in little-endian
+    /// byte order, followed by the fingerprints.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_LEN.saturating_add(self.fingerprints.len()));
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.block_length.to_le_bytes());
+        bytes.extend_from_slice(&self.fingerprints);
+        bytes
+    }
+
+    /// Deserialize a set serialized by [`PubkeySet::to_bytes`], or return
+    /// `None` if the length of `bytes` does not match its header.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (seed, rest) = bytes.split_first_chunk::<8>()?;
+        let (block_length, fingerprints) = rest.split_first_chunk::<4>()?;
+        Self::from_parts(
+            u64::from_le_bytes(*seed),
+            u32::from_le_bytes(*block_length),
+            fingerprints.to_vec(),
+        )
+    }
+
+    fn from_parts(seed: u64, block_length: u32, fingerprints: Vec<u8>) -> Option<Self> {
+        if fingerprints.len() != (block_length as usize).checked_mul(3)? {
+            return None;
+        }
+        Some(Self {
+            seed,
+            block_length,
+            fingerprints,
+        })
+    }
+}
+
+/// Peel the keys into the fingerprints, or return `None` if `seed` does not
+/// allow it.
+fn try_build(keys: &[u64], seed: u64, block_length: u32, capacity: usize) -> Option<Vec<u8>> {
+    // xor of the hashes and number of keys mapped to each slot
+    let mut slot_hashes = vec![0u64; capacity];
+    let mut slot_counts = vec![0u32; capacity];
+    for key in keys {
+        let hash = mix(key.wrapping_add(seed));
+        for slot in slots(hash, block_length) {
+            slot_hashes[slot] ^= hash;
+            slot_counts[slot] = slot_counts[slot].saturating_add(1);
+        }
+    }
+
+    let mut queue: Vec<usize> = (0..capacity).filter(|i| slot_counts[*i] == 1).collect();
+    let mut peeled = Vec::with_capacity(keys.len());
+    while let Some(slot) = queue.pop() {
+        if slot_counts[slot] != 1 {
+            continue;
+        }
+        let hash = slot_hashes[slot];
+        peeled.push((slot, hash));
+        for other_slot in slots(hash, block_length) {
+            slot_hashes[other_slot] ^= hash;
+            slot_counts[other_slot] = slot_counts[other_slot].saturating_sub(1);
+            if slot_counts[other_slot] == 1 {
+                queue.push(other_slot);
+            }
+        }
+    }
+    if peeled.len() != keys.len() {
+        return None;
+    }
+
+    let mut fingerprints = vec![0u8; capacity];
+    for (slot, hash) in peeled.into_iter().rev() {
+        let [h0, h1, h2] = slots(hash, block_length);
+        fingerprints[slot] =
+            fingerprint(hash) ^ fingerprints[h0] ^ fingerprints[h1] ^ fingerprints[h2];
+    }
+    Some(fingerprints)
+}
+
+fn pubkey_key(pubkey: &Pubkey) -> u64 {
+    pubkey.0.chunks_exact(8).fold(0, |key, word| {
+        mix(key ^ u64::from_le_bytes(word.try_into().unwrap()))
+    })
+}
+
+/// The slot of `hash` in each of the three blocks.
+fn slots(hash: u64, block_length: u32) -> [usize; 3] {
+    // The product of two 32-bit values can't overflow
+    let reduce =
+        |x: u64| (u64::from(x as u32).wrapping_mul(u64::from(block_length)) >> 32) as usize;
+    let block_length = block_length as usize;
+    [
+        reduce(hash),
+        reduce(hash.rotate_left(21)).saturating_add(block_length),
+        reduce(hash.rotate_left(42)).saturating_add(block_length.saturating_mul(2)),
+    ]
+}
+
+fn fingerprint(hash: u64) -> u8 {
+    (hash ^ (hash >> 32)) as u8
+}
+
+/// The murmur3 64-bit finalizer.
+fn mix(mut h: u64) -> u64 {
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    h ^= h >> 33;
+    h = h.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    h ^ (h >> 33)
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pubkey_set() {
+        let members: Vec<Pubkey> = (0..10_000).map(|_| Pubkey::new_unique()).collect();
+        let set = PubkeySet::new(members.iter().chain(&members[..100]));
+        assert!(members.iter().all(|pubkey| set.contains(pubkey)));
+        assert!(set.size_in_bytes() < members.len() * 2);
+
+        let false_positives = (0..10_000)
+            .filter(|_| set.contains(&Pubkey::new_unique()))
+            .count();
+        // about 40 expected
+        assert!(false_positives < 100, "{false_positives}");
+
+        let empty = PubkeySet::new(&[]);
+        assert!(!empty.contains(&members[0]));
+        let single = PubkeySet::new(&members[..1]);
+        assert!(single.contains(&members[0]));
+    }
+
+    #[test]
+    fn test_to_bytes() {
+        let members: Vec<Pubkey> = (0..100).map(|_| Pubkey::new_unique()).collect();
+        let set = PubkeySet::new(&members);
+        let bytes = set.to_bytes();
+        assert_eq!(bytes.len(), HEADER_LEN + set.size_in_bytes());
+        assert_eq!(PubkeySet::from_bytes(&bytes), Some(set));
+        assert_eq!(PubkeySet::from_bytes(&bytes[..bytes.len() - 1]), None);
+        assert_eq!(PubkeySet::from_bytes(&bytes[..4]), None);
+
+        let empty = PubkeySet::default();
+        assert_eq!(PubkeySet::from_bytes(&empty.to_bytes()), Some(empty));
+    }
+}
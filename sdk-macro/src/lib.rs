@@ -5,16 +5,18 @@
 extern crate proc_macro;
 
 use {
+    curve25519_dalek::edwards::CompressedEdwardsY,
     proc_macro::TokenStream,
     proc_macro2::Span,
     quote::{quote, ToTokens},
+    sha2::{Digest, Sha256},
     syn::{
         bracketed,
         parse::{Parse, ParseStream, Result},
         parse_macro_input,
         punctuated::Punctuated,
         token::Bracket,
-        Expr, Ident, LitByte, LitStr, Token,
+        Expr, Ident, Lit, LitByte, LitStr, Token,
     },
 };
 
@@ -229,6 +231,120 @@ pub fn pubkeys(input: TokenStream) -> TokenStream {
     TokenStream::from(quote! {#pubkeys})
 }
 
+// inlined to avoid solana-pubkey dep
+const MAX_SEED_LEN: usize = 32;
+const MAX_SEEDS: usize = 16;
+const PDA_MARKER: &[u8; 21] = b"ProgramDerivedAddress";
+
+struct ProgramAddress {
+    address: [u8; 32],
+    bump_seed: u8,
+}
+
+impl Parse for ProgramAddress {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let program_id_literal: LitStr = input.parse()?;
+        let program_id = bs58::decode(program_id_literal.value())
+            .into_vec()
+            .ok()
+            .and_then(|program_id| <[u8; 32]>::try_from(program_id).ok())
+            .ok_or_else(|| {
+                syn::Error::new_spanned(&program_id_literal, "invalid base58 program id")
+            })?;
+        let _comma: Token![,] = input.parse()?;
+
+        let seed_literals;
+        bracketed!(seed_literals in input);
+        let seed_literals: Punctuated<Lit, Token![,]> =
+            Punctuated::parse_terminated(&seed_literals)?;
+        // one seed is taken by the bump seed
+        if seed_literals.len() >= MAX_SEEDS {
+            return Err(syn::Error::new_spanned(
+                &seed_literals,
+                format!("at most {} seeds are allowed", MAX_SEEDS - 1),
+            ));
+        }
+        let mut seeds = Vec::with_capacity(seed_literals.len());
+        for seed_literal in &seed_literals {
+            let seed = match seed_literal {
+                Lit::ByteStr(seed) => seed.value(),
+                Lit::Str(seed) => seed.value().into_bytes(),
+                _ => {
+                    return Err(syn::Error::new_spanned(
+                        seed_literal,
+                        "seeds must be string or byte string literals",
+                    ))
+                }
+            };
+            if seed.len() > MAX_SEED_LEN {
+                return Err(syn::Error::new_spanned(
+                    seed_literal,
+                    format!("seeds are at most {MAX_SEED_LEN} bytes long"),
+                ));
+            }
+            seeds.push(seed);
+        }
+
+        if !input.is_empty() {
+            let stream: proc_macro2::TokenStream = input.parse()?;
+            return Err(syn::Error::new_spanned(stream, "unexpected token"));
+        }
+
+        // the bump seeds `find_program_address` tries, in its order
+        (1..=u8::MAX)
+            .rev()
+            .find_map(|bump_seed| {
+                let mut hasher = Sha256::new();
+                for seed in &seeds {
+                    hasher.update(seed);
+                }
+                hasher.update([bump_seed]);
+                hasher.update(program_id);
+                hasher.update(PDA_MARKER);
+                let address: [u8; 32] = hasher.finalize().into();
+                CompressedEdwardsY(address)
+                    .decompress()
+                    .is_none()
+                    .then_some(Self { address, bump_seed })
+            })
+            .ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &seed_literals,
+                    "unable to find a viable program address bump seed",
+                )
+            })
+    }
+}
+
+impl ToTokens for ProgramAddress {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let bytes = self
+            .address
+            .iter()
+            .map(|b| LitByte::new(*b, Span::call_site()));
+        let bump_seed = self.bump_seed;
+        tokens.extend(quote! {
+            (
+                ::solana_pubkey::Pubkey::new_from_array([#(#bytes,)*]),
+                #bump_seed,
+            )
+        });
+    }
+}
+
+/// Derive a program address and its bump seed at compile time, as
+/// `Pubkey::find_program_address` does at run time.
+///
+/// Input: a literal base58 program id and a bracketed list of string or byte
+/// string literal seeds. Expands to a `(Pubkey, u8)` expression that can
+/// initialize a `const`. The expansion names `::solana_pubkey::Pubkey`, so the
+/// calling crate must depend on `solana-pubkey`, as on-chain programs do.
+#[proc_macro]
+pub fn program_address(input: TokenStream) -> TokenStream {
+    let program_address = parse_macro_input!(input as ProgramAddress);
+    TokenStream::from(quote! {#program_address})
+}
+
 // Sets padding in structures to zero explicitly.
 // Otherwise padding could be inconsistent across the network and lead to divergence / consensus failures.
 #[proc_macro_derive(CloneZeroed)]
@@ -7,6 +7,7 @@ import {
   SystemInstruction,
   Instructions,
   Transaction,
+  VersionedTransaction,
 } from "crate";
 solana_program_init();
 
@@ -53,4 +54,36 @@ describe("Transaction", function () {
       "AoZrVzP93eyp3vbl6CU9XQjQfm4Xp/7nSiBlsX/kJmfTQZsGTOrFnt6EUqHVte97fGZ71UAXDfLbR5B31OtRdgdab57BOU8mq0ztMutZAVBPtGJHVly8RPz4TYa+OFU7EIk3Wrv4WUMCb/NR+LxELLH+tQt5SrkvB7rCE2DniM8JAgABBPwcAnjq1ItvYAiozCJIx811pVIzIF3TJO/1i9pj08+xwUDHXd5TrOB0zTYmv7KVR0GELkd+UT/+FWVaNEPMgMcAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAQAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAxJrndgN4IFTxep3s6kO0ROug7bEsbx0xxuDkqEvwUusBAwIBAgwCAAAAewAAAAAAAAA="
     );
   });
+
+  it("VersionedTransaction", () => {
+    const payer = new Keypair();
+    const src = new Keypair();
+    const dst = new Pubkey("11111111111111111111111111111112");
+    const recent_blockhash = new Hash(
+      "EETubP5AKHgjPAhzPAFcb8BAY1hMH639CWCFTqi3hq1k"
+    );
+
+    let instructions = new Instructions();
+    instructions.push(
+      SystemInstruction.transfer(src.pubkey(), dst, BigInt(123))
+    );
+
+    let transaction = new VersionedTransaction(
+      instructions,
+      payer.pubkey(),
+      recent_blockhash
+    );
+    expect(transaction.legacyMessage()).to.be.undefined;
+    transaction.partialSign(payer);
+    expect(transaction.isSigned()).to.be.false;
+    expect(() => transaction.partialSign(new Keypair())).to.throw();
+    transaction.partialSign(src);
+    expect(transaction.isSigned()).to.be.true;
+    transaction.verify();
+
+    const bytes = transaction.toBytes();
+    expect(VersionedTransaction.fromBytes(bytes).toBytes()).to.deep.equal(
+      bytes
+    );
+  });
 });
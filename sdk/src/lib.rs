@@ -56,6 +56,7 @@ pub mod entrypoint_deprecated;
 pub mod example_mocks;
 pub mod hash;
 pub mod log;
+pub mod mobile;
 pub mod native_loader;
 pub mod pubkey;
 #[cfg(feature = "full")]
@@ -137,6 +138,27 @@ pub use solana_sdk_macro::declare_deprecated_id;
 /// assert_eq!(id(), my_id);
 /// ```
 pub use solana_sdk_macro::declare_id;
+/// Derive a program address and its bump seed at compile time.
+///
+/// Input: a literal base58 program id and a bracketed list of string or byte
+/// string literal seeds. The expansion refers to `solana_pubkey::Pubkey`, so
+/// the calling crate must depend on `solana-pubkey`.
+///
+/// # Example
+///
+/// ```
+/// use {solana_pubkey::Pubkey, solana_sdk::program_address};
+///
+/// const VAULT: (Pubkey, u8) =
+///     program_address!("My11111111111111111111111111111111111111111", [b"vault", "fees"]);
+///
+/// let program_id = Pubkey::from_str_const("My11111111111111111111111111111111111111111");
+/// assert_eq!(
+///     VAULT,
+///     Pubkey::find_program_address(&[b"vault", b"fees"], &program_id)
+/// );
+/// ```
+pub use solana_sdk_macro::program_address;
 /// Convenience macro to define multiple static public keys.
 pub use solana_sdk_macro::pubkeys;
 #[deprecated(since = "2.2.0", note = "Use `solana-serde` crate instead")]
@@ -155,3 +177,6 @@ pub use solana_time_utils as timing;
 pub use solana_transaction::simple_vote_transaction_checker;
 
 pub extern crate bs58;
+
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
@@ -0,0 +1,301 @@
+//! UniFFI interface for Swift and Kotlin.
+//!
+//! Exposes the subset of the SDK that wallets need to build and sign
+//! transactions: parsing pubkeys, generating keypairs, compiling messages and
+//! signing transactions. Transactions are produced in the exact wire format
+//! of [`VersionedTransaction`], so foreign code never has to encode it
+//! itself.
+//!
+//! The bindings are generated in UniFFI library mode from the library that
+//! links this crate with the `uniffi` feature enabled.
+#![cfg(feature = "uniffi")]
+
+use {
+    solana_keypair::Keypair as SdkKeypair,
+    solana_message::{v0, VersionedMessage},
+    solana_program::{
+        hash::Hash,
+        instruction::{AccountMeta as SdkAccountMeta, Instruction as SdkInstruction},
+    },
+    solana_pubkey::Pubkey as SdkPubkey,
+    solana_signature::Signature,
+    solana_signer::Signer,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{fmt, str::FromStr, sync::Arc},
+};
+
+/// Errors returned to foreign code.
+#[derive(Debug, Clone, PartialEq, Eq, uniffi::Error)]
+#[uniffi(flat_error)]
+pub enum FfiError {
+    InvalidPubkey(String),
+    InvalidKeypair(String),
+    InvalidBlockhash(String),
+    InvalidMessage(String),
+    InvalidTransaction(String),
+    Signer(String),
+}
+
+impl std::error::Error for FfiError {}
+
+impl fmt::Display for FfiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidPubkey(err) => write!(f, "invalid pubkey: {err}"),
+            Self::InvalidKeypair(err) => write!(f, "invalid keypair: {err}"),
+            Self::InvalidBlockhash(err) => write!(f, "invalid blockhash: {err}"),
+            Self::InvalidMessage(err) => write!(f, "invalid message: {err}"),
+            Self::InvalidTransaction(err) => write!(f, "invalid transaction: {err}"),
+            Self::Signer(err) => write!(f, "signer error: {err}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, uniffi::Object)]
+pub struct Pubkey(SdkPubkey);
+
+#[uniffi::export]
+impl Pubkey {
+    /// Parse a base58 encoded pubkey.
+    #[uniffi::constructor]
+    pub fn from_string(s: String) -> Result<Arc<Self>, FfiError> {
+        SdkPubkey::from_str(&s)
+            .map(|pubkey| Arc::new(Self(pubkey)))
+            .map_err(|err| FfiError::InvalidPubkey(err.to_string()))
+    }
+
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, FfiError> {
+        SdkPubkey::try_from(bytes.as_slice())
+            .map(|pubkey| Arc::new(Self(pubkey)))
+            .map_err(|err| FfiError::InvalidPubkey(err.to_string()))
+    }
+
+    pub fn to_base58(&self) -> String {
+        self.0.to_string()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes().to_vec()
+    }
+}
+
+#[derive(Debug, uniffi::Object)]
+pub struct Keypair(SdkKeypair);
+
+#[uniffi::export]
+impl Keypair {
+    /// Generate a new random keypair.
+    #[uniffi::constructor]
+    pub fn generate() -> Arc<Self> {
+        Arc::new(Self(SdkKeypair::new()))
+    }
+
+    /// Recover a keypair from the 64 bytes returned by [`Self::to_bytes`].
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, FfiError> {
+        SdkKeypair::try_from(bytes.as_slice())
+            .map(|keypair| Arc::new(Self(keypair)))
+            .map_err(|err| FfiError::InvalidKeypair(err.to_string()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_bytes().to_vec()
+    }
+
+    pub fn pubkey(&self) -> Arc<Pubkey> {
+        Arc::new(Pubkey(self.0.pubkey()))
+    }
+
+    /// Sign arbitrary bytes, returning the 64 byte signature.
+    pub fn sign_message(&self, message: Vec<u8>) -> Vec<u8> {
+        self.0.sign_message(&message).as_ref().to_vec()
+    }
+}
+
+#[derive(Debug, uniffi::Record)]
+pub struct AccountMeta {
+    pub pubkey: Arc<Pubkey>,
+    pub is_signer: bool,
+    pub is_writable: bool,
+}
+
+#[derive(Debug, uniffi::Record)]
+pub struct Instruction {
+    pub program_id: Arc<Pubkey>,
+    pub accounts: Vec<AccountMeta>,
+    pub data: Vec<u8>,
+}
+
+impl From<Instruction> for SdkInstruction {
+    fn from(instruction: Instruction) -> Self {
+        let accounts = instruction
+            .accounts
+            .into_iter()
+            .map(|meta| SdkAccountMeta {
+                pubkey: meta.pubkey.0,
+                is_signer: meta.is_signer,
+                is_writable: meta.is_writable,
+            })
+            .collect();
+        SdkInstruction {
+            program_id: instruction.program_id.0,
+            accounts,
+            data: instruction.data,
+        }
+    }
+}
+
+/// A versioned transaction, signed or not.
+#[derive(Debug, PartialEq, Eq, uniffi::Object)]
+pub struct Transaction(VersionedTransaction);
+
+#[uniffi::export]
+impl Transaction {
+    /// Compile `instructions` into a v0 message paid by `payer` and return
+    /// an unsigned transaction for it. `recent_blockhash` is base58 encoded.
+    #[uniffi::constructor]
+    pub fn compile(
+        payer: Arc<Pubkey>,
+        instructions: Vec<Instruction>,
+        recent_blockhash: String,
+    ) -> Result<Arc<Self>, FfiError> {
+        let recent_blockhash = Hash::from_str(&recent_blockhash)
+            .map_err(|err| FfiError::InvalidBlockhash(err.to_string()))?;
+        let instructions = instructions
+            .into_iter()
+            .map(SdkInstruction::from)
+            .collect::<Vec<_>>();
+        let message = v0::Message::try_compile(&payer.0, &instructions, &[], recent_blockhash)
+            .map_err(|err| FfiError::InvalidMessage(err.to_string()))?;
+        let signatures =
+            vec![Signature::default(); usize::from(message.header.num_required_signatures)];
+        Ok(Arc::new(Self(VersionedTransaction {
+            signatures,
+            message: VersionedMessage::V0(message),
+        })))
+    }
+
+    /// Decode a transaction from its wire format.
+    #[uniffi::constructor]
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Arc<Self>, FfiError> {
+        let tx: VersionedTransaction = bincode::deserialize(&bytes)
+            .map_err(|err| FfiError::InvalidTransaction(err.to_string()))?;
+        tx.sanitize()
+            .map_err(|err| FfiError::InvalidTransaction(err.to_string()))?;
+        Ok(Arc::new(Self(tx)))
+    }
+
+    /// Return the wire format of the transaction.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(&self.0).unwrap()
+    }
+
+    /// Return the serialized message data to sign.
+    pub fn message_data(&self) -> Vec<u8> {
+        self.0.message.serialize()
+    }
+
+    /// Return the required signers of the transaction, in signature order.
+    pub fn signers(&self) -> Vec<Arc<Pubkey>> {
+        let num_required_signatures = usize::from(self.0.message.header().num_required_signatures);
+        self.0
+            .message
+            .static_account_keys()
+            .iter()
+            .take(num_required_signatures)
+            .map(|pubkey| Arc::new(Pubkey(*pubkey)))
+            .collect()
+    }
+
+    /// Return a copy of the transaction signed by each of `keypairs`, which
+    /// must all be required signers. Other signatures are kept.
+    pub fn sign(&self, keypairs: Vec<Arc<Keypair>>) -> Result<Arc<Self>, FfiError> {
+        let signers = self.signers();
+        let message_data = self.message_data();
+        let mut tx = self.0.clone();
+        tx.signatures.resize(signers.len(), Signature::default());
+        for keypair in keypairs {
+            let pubkey = keypair.0.pubkey();
+            let position = signers
+                .iter()
+                .position(|signer| signer.0 == pubkey)
+                .ok_or_else(|| FfiError::Signer(format!("{pubkey} is not a required signer")))?;
+            tx.signatures[position] = keypair.0.sign_message(&message_data);
+        }
+        Ok(Arc::new(Self(tx)))
+    }
+
+    /// Returns true if every required signer has signed.
+    pub fn is_signed(&self) -> bool {
+        self.0.signatures.len() == self.signers().len()
+            && self
+                .0
+                .signatures
+                .iter()
+                .all(|signature| *signature != Signature::default())
+    }
+
+    /// Verify the signatures of the transaction.
+    pub fn verify(&self) -> Result<(), FfiError> {
+        self.0
+            .verify_and_hash_message()
+            .map(|_| ())
+            .map_err(|err| FfiError::Signer(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_and_sign() {
+        let payer = Keypair::generate();
+        let recipient = Pubkey::from_string(SdkPubkey::new_unique().to_string()).unwrap();
+        let instruction = Instruction {
+            program_id: Arc::new(Pubkey(SdkPubkey::new_unique())),
+            accounts: vec![
+                AccountMeta {
+                    pubkey: payer.pubkey(),
+                    is_signer: true,
+                    is_writable: true,
+                },
+                AccountMeta {
+                    pubkey: recipient,
+                    is_signer: false,
+                    is_writable: true,
+                },
+            ],
+            data: vec![1, 2, 3],
+        };
+
+        let tx = Transaction::compile(
+            payer.pubkey(),
+            vec![instruction],
+            Hash::new_unique().to_string(),
+        )
+        .unwrap();
+        assert!(!tx.is_signed());
+        assert!(matches!(
+            tx.sign(vec![Keypair::generate()]),
+            Err(FfiError::Signer(_))
+        ));
+
+        let tx = tx.sign(vec![payer.clone()]).unwrap();
+        assert!(tx.is_signed());
+        assert_eq!(tx.verify(), Ok(()));
+        assert_eq!(Transaction::from_bytes(tx.to_bytes()).unwrap(), tx);
+
+        let keypair = Keypair::from_bytes(payer.to_bytes()).unwrap();
+        assert_eq!(keypair.pubkey(), payer.pubkey());
+        assert_eq!(
+            Pubkey::from_bytes(payer.pubkey().to_bytes()).unwrap(),
+            payer.pubkey()
+        );
+        assert!(matches!(
+            Pubkey::from_string("not a pubkey".to_string()),
+            Err(FfiError::InvalidPubkey(_))
+        ));
+    }
+}
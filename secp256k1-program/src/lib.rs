@@ -788,6 +788,8 @@
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "bincode")]
 use solana_instruction::Instruction;
+#[cfg(feature = "verify")]
+use solana_precompile_error::PrecompileError;
 use {digest::Digest, solana_signature::error::Error};
 
 pub const SECP256K1_PUBKEY_SIZE: usize = 64;
@@ -899,3 +901,101 @@ pub fn eth_address_from_pubkey(
     assert_eq!(addr.len(), HASHED_PUBKEY_SERIALIZED_SIZE);
     addr
 }
+
+/// Verify the signatures of a secp256k1 instruction with `data`, as the
+/// runtime does before executing the transaction containing it.
+///
+/// `instruction_datas` are the data of every instruction of the transaction,
+/// which the signature offsets refer to.
+#[cfg(feature = "verify")]
+pub fn verify(data: &[u8], instruction_datas: &[&[u8]]) -> Result<(), PrecompileError> {
+    let Some((&count, offsets)) = data.split_first() else {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    };
+    let count = usize::from(count);
+    if count == 0 && !offsets.is_empty() {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+    if offsets.len() < count.saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE) {
+        return Err(PrecompileError::InvalidInstructionDataSize);
+    }
+
+    for offsets in offsets
+        .chunks_exact(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+        .take(count)
+    {
+        let read_u16 = |at: usize| u16::from_le_bytes([offsets[at], offsets[at.saturating_add(1)]]);
+        let offsets = SecpSignatureOffsets {
+            signature_offset: read_u16(0),
+            signature_instruction_index: offsets[2],
+            eth_address_offset: read_u16(3),
+            eth_address_instruction_index: offsets[5],
+            message_data_offset: read_u16(6),
+            message_data_size: read_u16(8),
+            message_instruction_index: offsets[10],
+        };
+
+        let signature_instruction = instruction_datas
+            .get(usize::from(offsets.signature_instruction_index))
+            .ok_or(PrecompileError::InvalidInstructionDataSize)?;
+        let signature_start = usize::from(offsets.signature_offset);
+        let signature_end = signature_start.saturating_add(SIGNATURE_SERIALIZED_SIZE);
+        let (Some(signature), Some(&recovery_id)) = (
+            signature_instruction.get(signature_start..signature_end),
+            signature_instruction.get(signature_end),
+        ) else {
+            return Err(PrecompileError::InvalidSignature);
+        };
+        let signature = libsecp256k1::Signature::parse_standard_slice(signature)
+            .map_err(|_| PrecompileError::InvalidSignature)?;
+        let recovery_id = libsecp256k1::RecoveryId::parse(recovery_id)
+            .map_err(|_| PrecompileError::InvalidRecoveryId)?;
+
+        let eth_address = get_data_slice(
+            instruction_datas,
+            offsets.eth_address_instruction_index,
+            offsets.eth_address_offset,
+            HASHED_PUBKEY_SERIALIZED_SIZE,
+        )?;
+        let message = get_data_slice(
+            instruction_datas,
+            offsets.message_instruction_index,
+            offsets.message_data_offset,
+            usize::from(offsets.message_data_size),
+        )?;
+
+        let message_hash = sha3::Keccak256::digest(message);
+        let pubkey = libsecp256k1::recover(
+            &libsecp256k1::Message::parse(&message_hash.into()),
+            &signature,
+            &recovery_id,
+        )
+        .map_err(|_| PrecompileError::InvalidSignature)?;
+        let pubkey: [u8; SECP256K1_PUBKEY_SIZE] = pubkey.serialize()[1..]
+            .try_into()
+            .expect("uncompressed public keys are 65 bytes");
+        if eth_address != eth_address_from_pubkey(&pubkey).as_slice() {
+            return Err(PrecompileError::InvalidSignature);
+        }
+    }
+    Ok(())
+}
+
+/// Unlike the other precompiles, secp256k1 instructions cannot refer to their
+/// own data with `u8::MAX`, and out of bounds data is reported as an invalid
+/// signature.
+#[cfg(feature = "verify")]
+fn get_data_slice<'a>(
+    instruction_datas: &'a [&[u8]],
+    instruction_index: u8,
+    offset: u16,
+    size: usize,
+) -> Result<&'a [u8], PrecompileError> {
+    let instruction = instruction_datas
+        .get(usize::from(instruction_index))
+        .ok_or(PrecompileError::InvalidDataOffsets)?;
+    let start = usize::from(offset);
+    instruction
+        .get(start..start.saturating_add(size))
+        .ok_or(PrecompileError::InvalidSignature)
+}
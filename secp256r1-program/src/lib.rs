@@ -42,8 +42,16 @@ mod target_arch {
     use {
         crate::Secp256r1SignatureOffsets,
         bytemuck::bytes_of,
-        openssl::{bn::BigNum, ec::EcKey, ecdsa::EcdsaSig, nid::Nid, pkey::PKey, sign::Signer},
+        openssl::{
+            bn::{BigNum, BigNumContext},
+            ec::{EcGroup, EcKey, EcPoint},
+            ecdsa::EcdsaSig,
+            nid::Nid,
+            pkey::PKey,
+            sign::{Signer, Verifier},
+        },
         solana_instruction::Instruction,
+        solana_precompile_error::PrecompileError,
     };
 
     pub const COMPRESSED_PUBKEY_SERIALIZED_SIZE: usize = 33;
@@ -74,6 +82,8 @@ mod target_arch {
     ];
     // Field size in bytes
     pub const FIELD_SIZE: usize = 32;
+    // Maximum number of signatures in a single instruction
+    pub const MAX_SIGNATURES: usize = 8;
 
     pub fn sign_message(
         message: &[u8],
@@ -163,6 +173,113 @@ mod target_arch {
             data: instruction_data,
         }
     }
+
+    /// Verify the signatures of a secp256r1 instruction with `data`, as the
+    /// runtime does before executing the transaction containing it.
+    ///
+    /// `instruction_datas` are the data of every instruction of the
+    /// transaction, which the signature offsets may refer to. Only low-S
+    /// signatures are accepted.
+    pub fn verify(data: &[u8], instruction_datas: &[&[u8]]) -> Result<(), PrecompileError> {
+        if data.len() < SIGNATURE_OFFSETS_START {
+            return Err(PrecompileError::InvalidInstructionDataSize);
+        }
+        let num_signatures = usize::from(data[0]);
+        if num_signatures == 0 || num_signatures > MAX_SIGNATURES {
+            return Err(PrecompileError::InvalidInstructionDataSize);
+        }
+        let expected_data_size = num_signatures
+            .saturating_mul(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+            .saturating_add(SIGNATURE_OFFSETS_START);
+        if data.len() < expected_data_size {
+            return Err(PrecompileError::InvalidInstructionDataSize);
+        }
+
+        let invalid_signature = |_| PrecompileError::InvalidSignature;
+        let one = BigNum::from_u32(1).map_err(invalid_signature)?;
+        let half_order = BigNum::from_slice(&SECP256R1_HALF_ORDER).map_err(invalid_signature)?;
+        let order_minus_one =
+            BigNum::from_slice(&SECP256R1_ORDER_MINUS_ONE).map_err(invalid_signature)?;
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).map_err(invalid_signature)?;
+        let mut context = BigNumContext::new().map_err(invalid_signature)?;
+
+        for offsets in data[SIGNATURE_OFFSETS_START..]
+            .chunks_exact(SIGNATURE_OFFSETS_SERIALIZED_SIZE)
+            .take(num_signatures)
+        {
+            let offsets: Secp256r1SignatureOffsets = bytemuck::pod_read_unaligned(offsets);
+
+            let signature = get_data_slice(
+                data,
+                instruction_datas,
+                offsets.signature_instruction_index,
+                offsets.signature_offset,
+                SIGNATURE_SERIALIZED_SIZE,
+            )?;
+            let pubkey = get_data_slice(
+                data,
+                instruction_datas,
+                offsets.public_key_instruction_index,
+                offsets.public_key_offset,
+                COMPRESSED_PUBKEY_SERIALIZED_SIZE,
+            )?;
+            let message = get_data_slice(
+                data,
+                instruction_datas,
+                offsets.message_instruction_index,
+                offsets.message_data_offset,
+                usize::from(offsets.message_data_size),
+            )?;
+
+            let (r, s) = signature.split_at(FIELD_SIZE);
+            let r = BigNum::from_slice(r).map_err(invalid_signature)?;
+            let s = BigNum::from_slice(s).map_err(invalid_signature)?;
+            if r < one || r > order_minus_one || s < one || s > half_order {
+                return Err(PrecompileError::InvalidSignature);
+            }
+            let signature = EcdsaSig::from_private_components(r, s)
+                .and_then(|signature| signature.to_der())
+                .map_err(invalid_signature)?;
+
+            let invalid_public_key = |_| PrecompileError::InvalidPublicKey;
+            let point =
+                EcPoint::from_bytes(&group, pubkey, &mut context).map_err(invalid_public_key)?;
+            let pubkey = EcKey::from_public_key(&group, &point)
+                .and_then(PKey::from_ec_key)
+                .map_err(invalid_public_key)?;
+
+            let mut verifier = Verifier::new(openssl::hash::MessageDigest::sha256(), &pubkey)
+                .map_err(invalid_signature)?;
+            verifier.update(message).map_err(invalid_signature)?;
+            if !verifier.verify(&signature).map_err(invalid_signature)? {
+                return Err(PrecompileError::InvalidSignature);
+            }
+        }
+        Ok(())
+    }
+
+    /// Slice of `size` bytes at `offset` in the data of the instruction at
+    /// `instruction_index`, where `u16::MAX` refers to the instruction being
+    /// verified.
+    fn get_data_slice<'a>(
+        data: &'a [u8],
+        instruction_datas: &'a [&[u8]],
+        instruction_index: u16,
+        offset: u16,
+        size: usize,
+    ) -> Result<&'a [u8], PrecompileError> {
+        let instruction = if instruction_index == u16::MAX {
+            data
+        } else {
+            instruction_datas
+                .get(usize::from(instruction_index))
+                .ok_or(PrecompileError::InvalidDataOffsets)?
+        };
+        let start = usize::from(offset);
+        instruction
+            .get(start..start.saturating_add(size))
+            .ok_or(PrecompileError::InvalidDataOffsets)
+    }
 }
 
 pub use self::target_arch::*;
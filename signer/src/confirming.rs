@@ -0,0 +1,82 @@
+//! A `Signer` wrapper that asks for confirmation before signing.
+
+use {
+    crate::{Signer, SignerError},
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+};
+
+/// A [`Signer`] that signs with `S` only the messages `confirm` approves.
+///
+/// `confirm` receives the raw message bytes, typically to show them to the
+/// user. `solana_transaction::summary::confirming_signer` builds a
+/// `ConfirmingSigner` whose callback receives a decoded summary of the
+/// message instead; it lives there because this crate cannot decode
+/// messages.
+pub struct ConfirmingSigner<S, F> {
+    signer: S,
+    confirm: F,
+}
+
+impl<S, F> ConfirmingSigner<S, F>
+where
+    S: Signer,
+    F: Fn(&[u8]) -> bool,
+{
+    pub fn new(signer: S, confirm: F) -> Self {
+        Self { signer, confirm }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.signer
+    }
+}
+
+impl<S, F> Signer for ConfirmingSigner<S, F>
+where
+    S: Signer,
+    F: Fn(&[u8]) -> bool,
+{
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        self.signer.try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        if !(self.confirm)(message) {
+            return Err(SignerError::UserCancel("signing rejected".to_string()));
+        }
+        self.signer.try_sign_message(message)
+    }
+
+    fn is_interactive(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::null_signer::RecordingSigner, std::cell::Cell};
+
+    #[test]
+    fn test_confirming_signer() {
+        let pubkey = Pubkey::new_unique();
+        let approve = Cell::new(true);
+        let signer = ConfirmingSigner::new(RecordingSigner::new(&pubkey), |message: &[u8]| {
+            assert_eq!(message, b"message");
+            approve.get()
+        });
+        assert!(signer.is_interactive());
+        assert_eq!(signer.pubkey(), pubkey);
+        assert_eq!(
+            signer.try_sign_message(b"message"),
+            Ok(Signature::default())
+        );
+
+        approve.set(false);
+        assert_eq!(
+            signer.try_sign_message(b"message"),
+            Err(SignerError::UserCancel("signing rejected".to_string()))
+        );
+        assert_eq!(signer.into_inner().messages(), vec![b"message".to_vec()]);
+    }
+}
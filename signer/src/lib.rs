@@ -14,7 +14,13 @@ use {
     },
 };
 
+pub mod confirming;
 pub mod null_signer;
+#[cfg(feature = "signer-pkcs11")]
+pub mod pkcs11;
+pub mod registry;
+#[cfg(feature = "remote-signer")]
+pub mod remote;
 pub mod signers;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -2,6 +2,7 @@ use {
     crate::{Signer, SignerError},
     solana_pubkey::Pubkey,
     solana_signature::Signature,
+    std::sync::Mutex,
 };
 
 /// NullSigner - A `Signer` implementation that always produces `Signature::default()`.
@@ -40,3 +41,75 @@ where
         self.pubkey == other.pubkey()
     }
 }
+
+/// RecordingSigner - A `NullSigner` that keeps every message it is asked to sign.
+/// Used in dry runs and sign-only flows to audit, or export for offline signing,
+/// exactly what would have been signed
+#[derive(Debug, Default)]
+pub struct RecordingSigner {
+    pubkey: Pubkey,
+    messages: Mutex<Vec<Vec<u8>>>,
+}
+
+impl RecordingSigner {
+    pub fn new(pubkey: &Pubkey) -> Self {
+        Self {
+            pubkey: *pubkey,
+            messages: Mutex::default(),
+        }
+    }
+
+    /// The messages signed so far, oldest first
+    pub fn messages(&self) -> Vec<Vec<u8>> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// Removes and returns the messages signed so far, oldest first
+    pub fn take_messages(&self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut *self.messages.lock().unwrap())
+    }
+}
+
+impl Signer for RecordingSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.messages.lock().unwrap().push(message.to_vec());
+        Ok(Signature::default())
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+impl<T> PartialEq<T> for RecordingSigner
+where
+    T: Signer,
+{
+    fn eq(&self, other: &T) -> bool {
+        self.pubkey == other.pubkey()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_signer() {
+        let pubkey = Pubkey::new_unique();
+        let signer = RecordingSigner::new(&pubkey);
+        assert_eq!(signer.pubkey(), pubkey);
+        assert_eq!(signer.sign_message(b"first"), Signature::default());
+        assert_eq!(signer.sign_message(b"second"), Signature::default());
+        assert_eq!(
+            signer.messages(),
+            vec![b"first".to_vec(), b"second".to_vec()]
+        );
+        assert_eq!(signer.take_messages().len(), 2);
+        assert!(signer.messages().is_empty());
+    }
+}
@@ -0,0 +1,233 @@
+//! A `Signer` backed by a key on a PKCS#11 token, such as an HSM.
+//!
+//! The private key never leaves the token: messages are sent to it for
+//! signing with the `CKM_EDDSA` mechanism. The key is found by its label
+//! among the `CKK_EC_EDWARDS` keys of the token, and the pubkey is read from
+//! the `CKA_EC_POINT` attribute of the public key with the same label.
+//! Signatures returned by the token are verified against that pubkey.
+
+use {
+    crate::{Signer, SignerError},
+    cryptoki::{
+        context::{CInitializeArgs, Pkcs11},
+        error::{Error, RvError},
+        mechanism::Mechanism,
+        object::{Attribute, AttributeType, KeyType, ObjectClass, ObjectHandle},
+        session::{Session, UserType},
+        types::AuthPin,
+    },
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{path::Path, sync::Mutex},
+};
+
+/// Maps PKCS#11 errors to the `SignerError`s of other hardware signers.
+fn signer_error(err: Error) -> SignerError {
+    match err {
+        Error::LibraryLoading(err) => SignerError::Connection(err.to_string()),
+        Error::Pkcs11(RvError::TokenNotPresent | RvError::DeviceRemoved) => {
+            SignerError::NoDeviceFound
+        }
+        Error::Pkcs11(RvError::FunctionCanceled) => {
+            SignerError::UserCancel("PKCS#11 operation canceled".to_string())
+        }
+        Error::Pkcs11(RvError::DeviceError | RvError::DeviceMemory) => {
+            SignerError::Connection(err.to_string())
+        }
+        err => SignerError::Protocol(format!("PKCS#11 error: {err}")),
+    }
+}
+
+/// Extracts the 32-byte point of a `CKA_EC_POINT`, which tokens store either
+/// raw or wrapped in a DER octet string.
+fn pubkey_from_ec_point(ec_point: &[u8]) -> Option<Pubkey> {
+    let point = match ec_point {
+        [0x04, 0x20, point @ ..] if point.len() == 32 => point,
+        point => point,
+    };
+    Pubkey::try_from(point).ok()
+}
+
+/// Sign `message` with `sign_on_token` and check that the result is a
+/// signature by `pubkey`, so that a private key not matching the public key
+/// of the same label is caught before the signature ends up in a transaction.
+fn sign_and_check(
+    pubkey: &Pubkey,
+    message: &[u8],
+    sign_on_token: impl FnOnce(&[u8]) -> Result<Vec<u8>, Error>,
+) -> Result<Signature, SignerError> {
+    let signature = sign_on_token(message).map_err(signer_error)?;
+    let signature = Signature::try_from(signature.as_slice())
+        .map_err(|_| SignerError::Protocol("invalid signature length".to_string()))?;
+    if !signature.verify(pubkey.as_ref(), message) {
+        return Err(SignerError::Protocol(format!(
+            "PKCS#11 token returned a signature that does not verify for {pubkey}"
+        )));
+    }
+    Ok(signature)
+}
+
+/// A [`Signer`] delegating ed25519 signing to a PKCS#11 token.
+pub struct Pkcs11Signer {
+    // sessions may not be used by several threads at once
+    session: Mutex<Session>,
+    private_key: ObjectHandle,
+    pubkey: Pubkey,
+}
+
+impl Pkcs11Signer {
+    /// Load the PKCS#11 module at `module_path`, log in to the token labeled
+    /// `token_label` with `pin`, and sign with its ed25519 key labeled
+    /// `key_label`.
+    pub fn new<P: AsRef<Path>>(
+        module_path: P,
+        token_label: &str,
+        pin: &str,
+        key_label: &str,
+    ) -> Result<Self, SignerError> {
+        let pkcs11 = Pkcs11::new(module_path).map_err(signer_error)?;
+        match pkcs11.initialize(CInitializeArgs::OsThreads) {
+            Ok(()) | Err(Error::Pkcs11(RvError::CryptokiAlreadyInitialized)) => {}
+            Err(err) => return Err(signer_error(err)),
+        }
+
+        let mut token_slot = None;
+        for slot in pkcs11.get_slots_with_token().map_err(signer_error)? {
+            let token_info = pkcs11.get_token_info(slot).map_err(signer_error)?;
+            if token_info.label() == token_label {
+                token_slot = Some(slot);
+                break;
+            }
+        }
+        let slot = token_slot.ok_or(SignerError::NoDeviceFound)?;
+
+        let session = pkcs11.open_ro_session(slot).map_err(signer_error)?;
+        session
+            .login(UserType::User, Some(&AuthPin::new(pin.to_string())))
+            .map_err(signer_error)?;
+
+        let find_key = |class| {
+            let template = [
+                Attribute::Class(class),
+                Attribute::KeyType(KeyType::EC_EDWARDS),
+                Attribute::Label(key_label.as_bytes().to_vec()),
+            ];
+            match session.find_objects(&template).map_err(signer_error)?[..] {
+                [key] => Ok(key),
+                [] => Err(SignerError::InvalidInput(format!(
+                    "no ed25519 key labeled {key_label}"
+                ))),
+                _ => Err(SignerError::InvalidInput(format!(
+                    "several ed25519 keys labeled {key_label}"
+                ))),
+            }
+        };
+        let private_key = find_key(ObjectClass::PRIVATE_KEY)?;
+        let public_key = find_key(ObjectClass::PUBLIC_KEY)?;
+
+        let attributes = session
+            .get_attributes(public_key, &[AttributeType::EcPoint])
+            .map_err(signer_error)?;
+        let pubkey = attributes
+            .iter()
+            .find_map(|attribute| match attribute {
+                Attribute::EcPoint(ec_point) => pubkey_from_ec_point(ec_point),
+                _ => None,
+            })
+            .ok_or_else(|| SignerError::Protocol("invalid ed25519 public key".to_string()))?;
+
+        Ok(Self {
+            session: Mutex::new(session),
+            private_key,
+            pubkey,
+        })
+    }
+}
+
+impl Signer for Pkcs11Signer {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        sign_and_check(&self.pubkey, message, |message| {
+            self.session
+                .lock()
+                .unwrap()
+                .sign(&Mechanism::Eddsa, self.private_key, message)
+        })
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pubkey_from_ec_point() {
+        let pubkey = Pubkey::new_unique();
+        assert_eq!(pubkey_from_ec_point(pubkey.as_ref()), Some(pubkey));
+        let der = [&[0x04, 0x20][..], pubkey.as_ref()].concat();
+        assert_eq!(pubkey_from_ec_point(&der), Some(pubkey));
+        assert_eq!(pubkey_from_ec_point(&der[1..]), None);
+
+        // a raw point that happens to start like a DER octet string
+        let mut bytes = pubkey.to_bytes();
+        bytes[..2].copy_from_slice(&[0x04, 0x20]);
+        let pubkey = Pubkey::from(bytes);
+        assert_eq!(pubkey_from_ec_point(pubkey.as_ref()), Some(pubkey));
+    }
+
+    #[test]
+    fn test_sign_and_check() {
+        use ed25519_dalek::Signer as _;
+        let keypair = |seed| {
+            let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+            let public = ed25519_dalek::PublicKey::from(&secret);
+            ed25519_dalek::Keypair { secret, public }
+        };
+        let token_key = keypair(7);
+        let pubkey = Pubkey::from(token_key.public.to_bytes());
+        let message = b"message";
+        let signature = token_key.sign(message).to_bytes();
+        let other_signature = keypair(8).sign(message).to_bytes();
+
+        assert_eq!(
+            sign_and_check(&pubkey, message, |_| Ok(signature.to_vec())),
+            Ok(Signature::from(signature))
+        );
+        // a token whose private key doesn't match the public key
+        assert!(matches!(
+            sign_and_check(&pubkey, message, |_| Ok(other_signature.to_vec())),
+            Err(SignerError::Protocol(_))
+        ));
+        assert_eq!(
+            sign_and_check(&pubkey, message, |_| Ok(vec![0; 32])),
+            Err(SignerError::Protocol(
+                "invalid signature length".to_string()
+            ))
+        );
+        assert_eq!(
+            sign_and_check(&pubkey, message, |_| Err(Error::Pkcs11(
+                RvError::DeviceRemoved
+            ))),
+            Err(SignerError::NoDeviceFound)
+        );
+    }
+
+    #[test]
+    fn test_signer_error() {
+        assert_eq!(
+            signer_error(Error::Pkcs11(RvError::TokenNotPresent)),
+            SignerError::NoDeviceFound
+        );
+        assert!(matches!(
+            signer_error(Error::Pkcs11(RvError::PinIncorrect)),
+            SignerError::Protocol(_)
+        ));
+    }
+}
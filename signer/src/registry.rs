@@ -0,0 +1,143 @@
+//! A collection of signers looked up by pubkey.
+
+use {
+    crate::{Signer, SignerError},
+    solana_pubkey::Pubkey,
+    std::{collections::HashMap, iter::Map, slice},
+};
+
+type Entry = (Pubkey, Box<dyn Signer>);
+
+/// Signers of any kind, such as keypair files, hardware wallets and remote
+/// signers, keyed by their pubkeys.
+///
+/// A registry implements [`Signers`](crate::signers::Signers) over all of its
+/// signers, in the order they were inserted. [`SignerRegistry::signers_for`]
+/// selects the signers of the accounts required to sign a message, in the
+/// order the message lists them.
+#[derive(Default)]
+pub struct SignerRegistry {
+    entries: Vec<Entry>,
+    indexes: HashMap<Pubkey, usize>,
+}
+
+impl SignerRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `signer` under its pubkey, returning the signer it replaces.
+    pub fn insert(
+        &mut self,
+        signer: Box<dyn Signer>,
+    ) -> Result<Option<Box<dyn Signer>>, SignerError> {
+        let pubkey = signer.try_pubkey()?;
+        match self.indexes.get(&pubkey) {
+            Some(index) => Ok(Some(std::mem::replace(&mut self.entries[*index].1, signer))),
+            None => {
+                self.indexes.insert(pubkey, self.entries.len());
+                self.entries.push((pubkey, signer));
+                Ok(None)
+            }
+        }
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&dyn Signer> {
+        self.indexes
+            .get(pubkey)
+            .map(|index| self.entries[*index].1.as_ref())
+    }
+
+    pub fn contains(&self, pubkey: &Pubkey) -> bool {
+        self.indexes.contains_key(pubkey)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The signers of `required_signers`, e.g. the first
+    /// `num_required_signatures` account keys of a message, in that order.
+    ///
+    /// Fails with [`SignerError::NotEnoughSigners`] if any of them is not
+    /// registered.
+    pub fn signers_for(
+        &self,
+        required_signers: &[Pubkey],
+    ) -> Result<Vec<&dyn Signer>, SignerError> {
+        required_signers
+            .iter()
+            .map(|pubkey| self.get(pubkey).ok_or(SignerError::NotEnoughSigners))
+            .collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a SignerRegistry {
+    type Item = &'a (dyn Signer + 'static);
+    type IntoIter = Map<slice::Iter<'a, Entry>, fn(&'a Entry) -> Self::Item>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        let signer: fn(&'a Entry) -> Self::Item = |(_, signer)| signer.as_ref();
+        self.entries.iter().map(signer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, crate::signers::Signers, solana_signature::Signature};
+
+    /// Signs every message with a signature naming its pubkey.
+    struct TestSigner(Pubkey);
+
+    impl Signer for TestSigner {
+        fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+            Ok(self.0)
+        }
+
+        fn try_sign_message(&self, _message: &[u8]) -> Result<Signature, SignerError> {
+            let mut signature = [0u8; 64];
+            signature[..32].copy_from_slice(self.0.as_ref());
+            Ok(Signature::from(signature))
+        }
+
+        fn is_interactive(&self) -> bool {
+            false
+        }
+    }
+
+    #[test]
+    fn test_signer_registry() {
+        let keys: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let mut registry = SignerRegistry::new();
+        for key in &keys {
+            assert!(registry
+                .insert(Box::new(TestSigner(*key)))
+                .unwrap()
+                .is_none());
+        }
+        assert!(registry
+            .insert(Box::new(TestSigner(keys[1])))
+            .unwrap()
+            .is_some());
+        assert_eq!(registry.len(), 3);
+        assert_eq!(registry.pubkeys(), keys);
+
+        let required = [keys[2], keys[0]];
+        let signers = registry.signers_for(&required).unwrap();
+        assert_eq!(signers.pubkeys(), required);
+        let signatures = signers.try_sign_message(b"message").unwrap();
+        assert_eq!(
+            signatures[0],
+            registry.get(&keys[2]).unwrap().sign_message(b"")
+        );
+
+        assert_eq!(
+            registry.signers_for(&[Pubkey::new_unique()]).err(),
+            Some(SignerError::NotEnoughSigners)
+        );
+    }
+}
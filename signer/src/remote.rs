@@ -0,0 +1,358 @@
+//! A `Signer` forwarding signing requests to a remote signing service over
+//! gRPC.
+//!
+//! The protocol is defined in `proto/remote_signer.proto` of this crate, and
+//! the messages in [`proto`] match it. Each request is bounded by
+//! [`RemoteSignerConfig::timeout`], and requests failing with a transient
+//! status are retried up to [`RemoteSignerConfig::max_attempts`] times.
+
+use {
+    crate::{Signer, SignerError},
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{thread, time::Duration},
+    tokio::runtime::Runtime,
+    tonic::{
+        client::Grpc,
+        codec::ProstCodec,
+        codegen::http::uri::PathAndQuery,
+        transport::{Channel, Endpoint},
+        Code, Status,
+    },
+};
+
+/// Messages of the `solana.signer.v1` protocol.
+pub mod proto {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetPubkeyRequest {
+        #[prost(string, tag = "1")]
+        pub key_id: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct GetPubkeyResponse {
+        #[prost(bytes = "vec", tag = "1")]
+        pub pubkey: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SignMessageRequest {
+        #[prost(string, tag = "1")]
+        pub key_id: String,
+        #[prost(bytes = "vec", tag = "2")]
+        pub message: Vec<u8>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct SignMessageResponse {
+        #[prost(bytes = "vec", tag = "1")]
+        pub signature: Vec<u8>,
+    }
+}
+
+const GET_PUBKEY_PATH: &str = "/solana.signer.v1.RemoteSigner/GetPubkey";
+const SIGN_MESSAGE_PATH: &str = "/solana.signer.v1.RemoteSigner/SignMessage";
+
+/// How a [`RemoteSigner`] bounds and retries its requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteSignerConfig {
+    /// Time allowed for each attempt, including connecting.
+    pub timeout: Duration,
+    /// Attempts made before giving up on a transient failure.
+    pub max_attempts: usize,
+    /// Pause between attempts, doubled after each one.
+    pub retry_backoff: Duration,
+}
+
+impl Default for RemoteSignerConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(5),
+            max_attempts: 3,
+            retry_backoff: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Why one attempt at a request failed.
+enum AttemptError {
+    Timeout,
+    Status(Status),
+}
+
+fn is_transient(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::ResourceExhausted | Code::Aborted | Code::DeadlineExceeded
+    )
+}
+
+fn status_error(status: Status) -> SignerError {
+    let message = status.message().to_string();
+    match status.code() {
+        Code::Unavailable => SignerError::Connection(message),
+        Code::DeadlineExceeded => SignerError::Connection(format!("timeout: {message}")),
+        Code::NotFound | Code::InvalidArgument => SignerError::InvalidInput(message),
+        Code::Cancelled => SignerError::UserCancel(message),
+        code => SignerError::Protocol(format!("remote signer returned {code:?}: {message}")),
+    }
+}
+
+/// Makes attempts until one succeeds, one fails permanently, or
+/// `config.max_attempts` were made.
+fn with_retries<T>(
+    config: &RemoteSignerConfig,
+    mut attempt: impl FnMut() -> Result<T, AttemptError>,
+) -> Result<T, SignerError> {
+    let mut backoff = config.retry_backoff;
+    let max_attempts = config.max_attempts.max(1);
+    let mut num_attempts = 0usize;
+    loop {
+        num_attempts = num_attempts.saturating_add(1);
+        let error = match attempt() {
+            Ok(value) => return Ok(value),
+            Err(AttemptError::Status(status)) if !is_transient(&status) => {
+                return Err(status_error(status))
+            }
+            Err(AttemptError::Status(status)) => status_error(status),
+            Err(AttemptError::Timeout) => {
+                SignerError::Connection(format!("no response within {:?}", config.timeout))
+            }
+        };
+        if num_attempts == max_attempts {
+            return Err(if max_attempts == 1 {
+                error
+            } else {
+                SignerError::Connection(format!(
+                    "{num_attempts} attempts failed, last with: {error}"
+                ))
+            });
+        }
+        thread::sleep(backoff);
+        backoff = backoff.saturating_mul(2);
+    }
+}
+
+/// Check that the service returned a signature of `message` by `pubkey`, so
+/// that a misrouted key id or a faulty service is caught before the signature
+/// ends up in a transaction.
+fn check_signature(
+    pubkey: &Pubkey,
+    message: &[u8],
+    signature: &[u8],
+) -> Result<Signature, SignerError> {
+    let signature = Signature::try_from(signature)
+        .map_err(|_| SignerError::Protocol("invalid signature length".to_string()))?;
+    if !signature.verify(pubkey.as_ref(), message) {
+        return Err(SignerError::Protocol(format!(
+            "remote signer returned a signature that does not verify for {pubkey}"
+        )));
+    }
+    Ok(signature)
+}
+
+/// A [`Signer`] whose key is held by a remote signing service.
+///
+/// Requests are made synchronously on an internal runtime, so a
+/// `RemoteSigner` must not be used from within an async task.
+pub struct RemoteSigner {
+    runtime: Runtime,
+    channel: Channel,
+    key_id: String,
+    pubkey: Pubkey,
+    config: RemoteSignerConfig,
+}
+
+impl RemoteSigner {
+    /// Connect to the signing service at `endpoint`, e.g.
+    /// `https://signer.example.com:443`, and fetch the pubkey of `key_id`.
+    pub fn connect(
+        endpoint: &str,
+        key_id: impl Into<String>,
+        config: RemoteSignerConfig,
+    ) -> Result<Self, SignerError> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|err| SignerError::Connection(err.to_string()))?;
+        // the channel spawns its connection task on the runtime
+        let guard = runtime.enter();
+        let channel = Endpoint::from_shared(endpoint.to_string())
+            .map_err(|err| SignerError::InvalidInput(err.to_string()))?
+            .connect_timeout(config.timeout)
+            .timeout(config.timeout)
+            .connect_lazy();
+        drop(guard);
+        let mut signer = Self {
+            runtime,
+            channel,
+            key_id: key_id.into(),
+            pubkey: Pubkey::default(),
+            config,
+        };
+
+        let request = proto::GetPubkeyRequest {
+            key_id: signer.key_id.clone(),
+        };
+        let response: proto::GetPubkeyResponse = signer.call(GET_PUBKEY_PATH, request)?;
+        signer.pubkey = Pubkey::try_from(response.pubkey.as_slice())
+            .map_err(|_| SignerError::Protocol("invalid pubkey length".to_string()))?;
+        Ok(signer)
+    }
+
+    fn call<Req, Resp>(&self, path: &'static str, request: Req) -> Result<Resp, SignerError>
+    where
+        Req: prost::Message + Clone + Send + Sync + 'static,
+        Resp: prost::Message + Default + Send + Sync + 'static,
+    {
+        with_retries(&self.config, || {
+            let mut grpc = Grpc::new(self.channel.clone());
+            let request = tonic::Request::new(request.clone());
+            let response = self.runtime.block_on(async {
+                tokio::time::timeout(self.config.timeout, async {
+                    grpc.ready()
+                        .await
+                        .map_err(|err| Status::unavailable(err.to_string()))?;
+                    grpc.unary(
+                        request,
+                        PathAndQuery::from_static(path),
+                        ProstCodec::default(),
+                    )
+                    .await
+                })
+                .await
+            });
+            match response {
+                Ok(Ok(response)) => Ok(response.into_inner()),
+                Ok(Err(status)) => Err(AttemptError::Status(status)),
+                Err(_elapsed) => Err(AttemptError::Timeout),
+            }
+        })
+    }
+}
+
+impl Signer for RemoteSigner {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        Ok(self.pubkey)
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        let request = proto::SignMessageRequest {
+            key_id: self.key_id.clone(),
+            message: message.to_vec(),
+        };
+        let response: proto::SignMessageResponse = self.call(SIGN_MESSAGE_PATH, request)?;
+        check_signature(&self.pubkey, message, &response.signature)
+    }
+
+    fn is_interactive(&self) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, std::cell::Cell};
+
+    fn config(max_attempts: usize) -> RemoteSignerConfig {
+        RemoteSignerConfig {
+            timeout: Duration::from_millis(10),
+            max_attempts,
+            retry_backoff: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_with_retries() {
+        let attempts = Cell::new(0);
+        let result = with_retries(&config(3), || {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < 3 {
+                Err(AttemptError::Status(Status::unavailable("busy")))
+            } else {
+                Ok(42)
+            }
+        });
+        assert_eq!(result, Ok(42));
+        assert_eq!(attempts.get(), 3);
+
+        attempts.set(0);
+        let result: Result<(), _> = with_retries(&config(3), || {
+            attempts.set(attempts.get() + 1);
+            Err(AttemptError::Timeout)
+        });
+        let Err(SignerError::Connection(message)) = result else {
+            panic!("expected a connection error, got {result:?}");
+        };
+        assert!(message.starts_with("3 attempts failed"), "{message}");
+        assert_eq!(attempts.get(), 3);
+
+        // permanent failures are not retried
+        attempts.set(0);
+        let result: Result<(), _> = with_retries(&config(3), || {
+            attempts.set(attempts.get() + 1);
+            Err(AttemptError::Status(Status::not_found("no such key")))
+        });
+        assert_eq!(
+            result,
+            Err(SignerError::InvalidInput("no such key".to_string()))
+        );
+        assert_eq!(attempts.get(), 1);
+
+        let result: Result<(), _> = with_retries(&config(1), || Err(AttemptError::Timeout));
+        assert_eq!(
+            result,
+            Err(SignerError::Connection(
+                "no response within 10ms".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_status_error() {
+        assert_eq!(
+            status_error(Status::cancelled("rejected by operator")),
+            SignerError::UserCancel("rejected by operator".to_string())
+        );
+        assert!(matches!(
+            status_error(Status::permission_denied("policy")),
+            SignerError::Protocol(_)
+        ));
+    }
+
+    #[test]
+    fn test_check_signature() {
+        use ed25519_dalek::Signer as _;
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = ed25519_dalek::PublicKey::from(&secret);
+        let keypair = ed25519_dalek::Keypair { secret, public };
+        let pubkey = Pubkey::from(public.to_bytes());
+        let message = b"message";
+        let signature = keypair.sign(message).to_bytes();
+
+        assert_eq!(
+            check_signature(&pubkey, message, &signature),
+            Ok(Signature::from(signature))
+        );
+        assert!(matches!(
+            check_signature(&pubkey, b"other message", &signature),
+            Err(SignerError::Protocol(_))
+        ));
+        assert!(matches!(
+            check_signature(&Pubkey::new_unique(), message, &signature),
+            Err(SignerError::Protocol(_))
+        ));
+        assert_eq!(
+            check_signature(&pubkey, message, &signature[..32]),
+            Err(SignerError::Protocol(
+                "invalid signature length".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_connect_unreachable() {
+        let result = RemoteSigner::connect("http://127.0.0.1:1", "key", config(2));
+        assert!(matches!(result, Err(SignerError::Connection(_))));
+    }
+}
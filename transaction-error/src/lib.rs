@@ -4,6 +4,8 @@
 use serde_derive::{Deserialize, Serialize};
 #[cfg(feature = "frozen-abi")]
 use solana_frozen_abi_macro::{AbiEnumVisitor, AbiExample};
+#[cfg(not(target_os = "solana"))]
+use solana_pubkey::Pubkey;
 use {core::fmt, solana_instruction_error::InstructionError, solana_sanitize::SanitizeError};
 
 pub type TransactionResult<T> = Result<T, TransactionError>;
@@ -140,6 +142,14 @@ pub enum TransactionError {
 
     /// Commit cancelled internally.
     CommitCancelled,
+
+    /// Transaction is larger than the maximum size of a packet.
+    SizeLimitExceeded {
+        /// Size of the serialized transaction on the wire, in bytes.
+        size: u64,
+        /// Maximum size of a transaction, `PACKET_DATA_SIZE` bytes.
+        max_size: u64,
+    },
 }
 
 impl std::error::Error for TransactionError {}
@@ -225,6 +235,8 @@ impl fmt::Display for TransactionError {
              => f.write_str("Program cache hit max limit"),
             Self::CommitCancelled
              => f.write_str("CommitCancelled"),
+            Self::SizeLimitExceeded { size, max_size }
+             => write!(f, "Transaction too large: {size} bytes, max {max_size}"),
         }
     }
 }
@@ -265,6 +277,14 @@ pub enum AddressLoaderError {
 
     /// Address lookup contains an invalid index
     InvalidLookupIndex,
+
+    /// Address lookup contains an index past the end of its table
+    LookupIndexOutOfRange {
+        lookup_table_key: Pubkey,
+        index: u8,
+        /// Number of addresses in the table
+        table_len: usize,
+    },
 }
 
 #[cfg(not(target_os = "solana"))]
@@ -286,6 +306,15 @@ impl fmt::Display for AddressLoaderError {
                 f.write_str("Attempted to lookup addresses from an invalid account")
             }
             Self::InvalidLookupIndex => f.write_str("Address lookup contains an invalid index"),
+            Self::LookupIndexOutOfRange {
+                lookup_table_key,
+                index,
+                table_len,
+            } => write!(
+                f,
+                "Address lookup index {index} is out of range for table {lookup_table_key} with \
+                 {table_len} addresses"
+            ),
         }
     }
 }
@@ -299,7 +328,10 @@ impl From<AddressLoaderError> for TransactionError {
             AddressLoaderError::LookupTableAccountNotFound => Self::AddressLookupTableNotFound,
             AddressLoaderError::InvalidAccountOwner => Self::InvalidAddressLookupTableOwner,
             AddressLoaderError::InvalidAccountData => Self::InvalidAddressLookupTableData,
-            AddressLoaderError::InvalidLookupIndex => Self::InvalidAddressLookupTableIndex,
+            AddressLoaderError::InvalidLookupIndex
+            | AddressLoaderError::LookupIndexOutOfRange { .. } => {
+                Self::InvalidAddressLookupTableIndex
+            }
         }
     }
 }
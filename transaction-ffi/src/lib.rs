@@ -0,0 +1,319 @@
+//! C ABI for decoding and verifying transactions.
+//!
+//! Native code links against this library to decode wire transactions,
+//! verify their signatures and read their account keys with the same
+//! implementation as the validator. A C header for these functions is
+//! generated with `cbindgen --config cbindgen.toml`.
+//!
+//! A transaction decoded with [`solana_transaction_deserialize`] is owned by
+//! the caller and must be released with [`solana_transaction_free`]. Every
+//! function taking a transaction pointer returns
+//! [`SolanaResult::NullPointer`], or zero for the counts, if it is null.
+
+use {
+    solana_bincode::limited_deserialize,
+    solana_packet::PACKET_DATA_SIZE,
+    solana_transaction::versioned::VersionedTransaction,
+    std::{ptr, slice},
+};
+
+/// Number of bytes of a pubkey.
+pub const SOLANA_PUBKEY_BYTES: usize = 32;
+/// Number of bytes of a signature.
+pub const SOLANA_SIGNATURE_BYTES: usize = 64;
+
+/// Status returned by the functions of this library.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SolanaResult {
+    Ok = 0,
+    /// A required pointer argument was null.
+    NullPointer = 1,
+    /// The bytes are not a valid, sanitized wire transaction.
+    InvalidTransaction = 2,
+    /// At least one signature does not verify.
+    SignatureFailure = 3,
+    /// The index is past the end of the account keys or signatures.
+    IndexOutOfBounds = 4,
+}
+
+/// A decoded transaction, opaque to C.
+pub struct SolanaTransaction(VersionedTransaction);
+
+/// Decode and sanitize the wire transaction in the `len` bytes at `data`,
+/// which must be at most `PACKET_DATA_SIZE` bytes long and must not be
+/// followed by other bytes. On success, `*out` is set to a transaction that
+/// must be released with [`solana_transaction_free`].
+///
+/// # Safety
+///
+/// If `len` is at most `PACKET_DATA_SIZE`, `data` must be null or point to
+/// `len` readable bytes. Larger lengths are rejected before `data` is read.
+/// `out` must be null or valid for writing a pointer.
+#[no_mangle]
+pub unsafe extern "C" fn solana_transaction_deserialize(
+    data: *const u8,
+    len: usize,
+    out: *mut *mut SolanaTransaction,
+) -> SolanaResult {
+    if data.is_null() || out.is_null() {
+        return SolanaResult::NullPointer;
+    }
+    if len > PACKET_DATA_SIZE {
+        return SolanaResult::InvalidTransaction;
+    }
+    let bytes = slice::from_raw_parts(data, len);
+    let Ok(tx) = limited_deserialize::<VersionedTransaction>(bytes, PACKET_DATA_SIZE as u64) else {
+        return SolanaResult::InvalidTransaction;
+    };
+    // limited_deserialize ignores trailing bytes
+    let is_exact_len = bincode::serialized_size(&tx).is_ok_and(|size| size == len as u64);
+    if !is_exact_len || tx.sanitize().is_err() {
+        return SolanaResult::InvalidTransaction;
+    }
+    *out = Box::into_raw(Box::new(SolanaTransaction(tx)));
+    SolanaResult::Ok
+}
+
+/// Release a transaction returned by [`solana_transaction_deserialize`].
+/// Does nothing if `tx` is null.
+///
+/// # Safety
+///
+/// `tx` must be null or a transaction returned by
+/// [`solana_transaction_deserialize`] that has not been released yet. It must
+/// not be used after this call.
+#[no_mangle]
+pub unsafe extern "C" fn solana_transaction_free(tx: *mut SolanaTransaction) {
+    if !tx.is_null() {
+        drop(Box::from_raw(tx));
+    }
+}
+
+/// Verify every signature of the transaction against its message.
+///
+/// # Safety
+///
+/// `tx` must be null or a transaction returned by
+/// [`solana_transaction_deserialize`] that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn solana_transaction_verify(tx: *const SolanaTransaction) -> SolanaResult {
+    let Some(tx) = tx.as_ref() else {
+        return SolanaResult::NullPointer;
+    };
+    if tx
+        .0
+        .verify_with_results()
+        .into_iter()
+        .all(|verified| verified)
+    {
+        SolanaResult::Ok
+    } else {
+        SolanaResult::SignatureFailure
+    }
+}
+
+/// Return the number of static account keys of the transaction. Addresses
+/// loaded from address lookup tables are not included.
+///
+/// # Safety
+///
+/// `tx` must be null or a transaction returned by
+/// [`solana_transaction_deserialize`] that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn solana_transaction_num_account_keys(
+    tx: *const SolanaTransaction,
+) -> usize {
+    tx.as_ref()
+        .map_or(0, |tx| tx.0.message.static_account_keys().len())
+}
+
+/// Copy the static account key at `index` to the
+/// [`SOLANA_PUBKEY_BYTES`] bytes at `out`.
+///
+/// # Safety
+///
+/// `tx` must be null or a transaction returned by
+/// [`solana_transaction_deserialize`] that has not been released yet.
+/// `out` must be null or valid for writing [`SOLANA_PUBKEY_BYTES`] bytes.
+#[no_mangle]
+pub unsafe extern "C" fn solana_transaction_account_key(
+    tx: *const SolanaTransaction,
+    index: usize,
+    out: *mut u8,
+) -> SolanaResult {
+    let Some(tx) = tx.as_ref() else {
+        return SolanaResult::NullPointer;
+    };
+    let Some(pubkey) = tx.0.message.static_account_keys().get(index) else {
+        return SolanaResult::IndexOutOfBounds;
+    };
+    copy_to(pubkey.as_ref(), out)
+}
+
+/// Return the number of signatures of the transaction, which is the number
+/// of required signers of its message.
+///
+/// # Safety
+///
+/// `tx` must be null or a transaction returned by
+/// [`solana_transaction_deserialize`] that has not been released yet.
+#[no_mangle]
+pub unsafe extern "C" fn solana_transaction_num_signatures(tx: *const SolanaTransaction) -> usize {
+    tx.as_ref().map_or(0, |tx| tx.0.signatures.len())
+}
+
+/// Copy the signature at `index` to the [`SOLANA_SIGNATURE_BYTES`] bytes at
+/// `out`. The signer of the signature at `index` is the account key at the
+/// same index.
+///
+/// # Safety
+///
+/// `tx` must be null or a transaction returned by
+/// [`solana_transaction_deserialize`] that has not been released yet.
+/// `out` must be null or valid for writing [`SOLANA_SIGNATURE_BYTES`] bytes.
+#[no_mangle]
+pub unsafe extern "C" fn solana_transaction_signature(
+    tx: *const SolanaTransaction,
+    index: usize,
+    out: *mut u8,
+) -> SolanaResult {
+    let Some(tx) = tx.as_ref() else {
+        return SolanaResult::NullPointer;
+    };
+    let Some(signature) = tx.0.signatures.get(index) else {
+        return SolanaResult::IndexOutOfBounds;
+    };
+    copy_to(signature.as_ref(), out)
+}
+
+/// # Safety
+///
+/// `out` must be null or valid for writing `bytes.len()` bytes.
+unsafe fn copy_to(bytes: &[u8], out: *mut u8) -> SolanaResult {
+    if out.is_null() {
+        return SolanaResult::NullPointer;
+    }
+    ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len());
+    SolanaResult::Ok
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_hash::Hash, solana_instruction::Instruction, solana_keypair::Keypair,
+        solana_pubkey::Pubkey, solana_signer::Signer,
+        solana_system_interface::instruction as system_instruction,
+        solana_transaction::Transaction,
+    };
+
+    fn deserialize(bytes: &[u8]) -> Result<*mut SolanaTransaction, SolanaResult> {
+        let mut tx = ptr::null_mut();
+        match unsafe { solana_transaction_deserialize(bytes.as_ptr(), bytes.len(), &mut tx) } {
+            SolanaResult::Ok => Ok(tx),
+            err => Err(err),
+        }
+    }
+
+    #[test]
+    fn test_deserialize_and_verify() {
+        let payer = Keypair::new();
+        let to = Pubkey::new_unique();
+        let mut tx = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(&payer.pubkey(), &to, 1)],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        let ffi_tx = deserialize(&bincode::serialize(&tx).unwrap()).unwrap();
+        unsafe {
+            assert_eq!(solana_transaction_verify(ffi_tx), SolanaResult::Ok);
+
+            assert_eq!(solana_transaction_num_account_keys(ffi_tx), 3);
+            let mut pubkey = [0; SOLANA_PUBKEY_BYTES];
+            assert_eq!(
+                solana_transaction_account_key(ffi_tx, 1, pubkey.as_mut_ptr()),
+                SolanaResult::Ok
+            );
+            assert_eq!(Pubkey::from(pubkey), to);
+            assert_eq!(
+                solana_transaction_account_key(ffi_tx, 3, pubkey.as_mut_ptr()),
+                SolanaResult::IndexOutOfBounds
+            );
+
+            assert_eq!(solana_transaction_num_signatures(ffi_tx), 1);
+            let mut signature = [0; SOLANA_SIGNATURE_BYTES];
+            assert_eq!(
+                solana_transaction_signature(ffi_tx, 0, signature.as_mut_ptr()),
+                SolanaResult::Ok
+            );
+            assert_eq!(signature, *tx.signatures[0].as_array());
+            solana_transaction_free(ffi_tx);
+        }
+
+        tx.signatures[0] = payer.sign_message(b"another message");
+        let ffi_tx = deserialize(&bincode::serialize(&tx).unwrap()).unwrap();
+        unsafe {
+            assert_eq!(
+                solana_transaction_verify(ffi_tx),
+                SolanaResult::SignatureFailure
+            );
+            solana_transaction_free(ffi_tx);
+        }
+    }
+
+    #[test]
+    fn test_deserialize_rejects_trailing_and_oversized_data() {
+        let payer = Keypair::new();
+        let transaction = |data_len: usize| {
+            let instruction =
+                Instruction::new_with_bytes(Pubkey::new_unique(), &vec![7; data_len], vec![]);
+            bincode::serialize(&Transaction::new_signed_with_payer(
+                &[instruction],
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::new_unique(),
+            ))
+            .unwrap()
+        };
+
+        let mut bytes = transaction(100);
+        let ffi_tx = deserialize(&bytes).unwrap();
+        unsafe { solana_transaction_free(ffi_tx) };
+        bytes.push(0);
+        assert_eq!(deserialize(&bytes), Err(SolanaResult::InvalidTransaction));
+
+        assert_eq!(
+            deserialize(&transaction(PACKET_DATA_SIZE)),
+            Err(SolanaResult::InvalidTransaction)
+        );
+
+        // oversized lengths are rejected before the data is read
+        let mut tx = ptr::null_mut();
+        assert_eq!(
+            unsafe { solana_transaction_deserialize(bytes.as_ptr(), usize::MAX, &mut tx) },
+            SolanaResult::InvalidTransaction
+        );
+        assert!(tx.is_null());
+    }
+
+    #[test]
+    fn test_invalid_arguments() {
+        assert_eq!(
+            deserialize(&[1, 2, 3]),
+            Err(SolanaResult::InvalidTransaction)
+        );
+        unsafe {
+            assert_eq!(
+                solana_transaction_deserialize(ptr::null(), 0, &mut ptr::null_mut()),
+                SolanaResult::NullPointer
+            );
+            assert_eq!(
+                solana_transaction_verify(ptr::null()),
+                SolanaResult::NullPointer
+            );
+            assert_eq!(solana_transaction_num_account_keys(ptr::null()), 0);
+            solana_transaction_free(ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,279 @@
+//! Heuristics that flag risky transactions before a wallet signs them.
+//!
+//! None of the patterns reported here is invalid on its own, and many
+//! legitimate transactions contain some of them. They are the patterns that
+//! drainers rely on, so a wallet should show them to the user before asking
+//! for a signature. [`analyze`] reports every [`Risk`] it finds in a
+//! transaction, from the point of view of the wallet's signing key.
+#![cfg(feature = "analysis")]
+
+use {
+    crate::{sanitized::SanitizedTransaction, PACKET_DATA_SIZE},
+    solana_bincode::limited_deserialize,
+    solana_pubkey::Pubkey,
+    solana_sdk_ids::{
+        address_lookup_table, compute_budget, ed25519_program, secp256k1_program,
+        secp256r1_program, stake, system_program, vote,
+    },
+    solana_system_interface::instruction::SystemInstruction,
+    std::collections::{BTreeSet, HashSet},
+};
+
+/// Configuration of [`analyze`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnalysisConfig {
+    /// The key the wallet is asked to sign with.
+    pub signer: Pubkey,
+    /// Programs trusted to receive writable signers.
+    pub known_programs: HashSet<Pubkey>,
+    /// Whether the wallet requested a durable nonce transaction.
+    pub expect_durable_nonce: bool,
+}
+
+impl AnalysisConfig {
+    /// Configuration for `signer` that trusts the native programs.
+    pub fn new(signer: Pubkey) -> Self {
+        Self {
+            signer,
+            known_programs: [
+                address_lookup_table::id(),
+                compute_budget::id(),
+                ed25519_program::id(),
+                secp256k1_program::id(),
+                secp256r1_program::id(),
+                stake::id(),
+                system_program::id(),
+                vote::id(),
+            ]
+            .into_iter()
+            .collect(),
+            expect_durable_nonce: false,
+        }
+    }
+}
+
+/// A risky pattern found in a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Risk {
+    /// The transaction fees are paid by another account than the signer, so
+    /// someone else controls whether and when it lands.
+    FeePayerIsNotSigner { fee_payer: Pubkey },
+    /// The signer is writable for another reason than paying fees, so
+    /// instructions may debit it.
+    WritableSigner { account: Pubkey },
+    /// A system instruction assigns an account to a new owner program, which
+    /// then controls its lamports and data.
+    OwnerReassignment {
+        instruction_index: usize,
+        account: Pubkey,
+        new_owner: Pubkey,
+    },
+    /// The transaction advances a durable nonce, so it never expires, and the
+    /// wallet did not ask for one.
+    UnexpectedDurableNonce { nonce_account: Pubkey },
+    /// An unknown program receives a writable signer, which it may drain.
+    WritableSignerForUnknownProgram {
+        instruction_index: usize,
+        program_id: Pubkey,
+        account: Pubkey,
+    },
+}
+
+/// Report the risky patterns found in `tx`.
+pub fn analyze(tx: &SanitizedTransaction, config: &AnalysisConfig) -> Vec<Risk> {
+    let message = tx.message();
+    let mut risks = vec![];
+
+    let fee_payer = message.fee_payer();
+    let is_writable_signer = if fee_payer == &config.signer {
+        // The fee payer is always writable, so only report it if it is also
+        // passed to an instruction, which then receives it as writable.
+        message.resolved_instructions_iter().any(|instruction| {
+            instruction
+                .accounts()
+                .any(|account| account.pubkey == &config.signer)
+        })
+    } else {
+        risks.push(Risk::FeePayerIsNotSigner {
+            fee_payer: *fee_payer,
+        });
+        message
+            .account_keys()
+            .iter()
+            .position(|key| key == &config.signer)
+            .is_some_and(|index| message.is_writable(index))
+    };
+    if is_writable_signer {
+        risks.push(Risk::WritableSigner {
+            account: config.signer,
+        });
+    }
+
+    if !config.expect_durable_nonce {
+        if let Some(nonce_account) = tx.get_durable_nonce() {
+            risks.push(Risk::UnexpectedDurableNonce {
+                nonce_account: *nonce_account,
+            });
+        }
+    }
+
+    for (instruction_index, instruction) in message.resolved_instructions_iter().enumerate() {
+        let program_id = instruction.program_id();
+        if system_program::check_id(program_id) {
+            let new_owner = match limited_deserialize(instruction.data(), PACKET_DATA_SIZE as u64) {
+                Ok(SystemInstruction::Assign { owner }) => Some(owner),
+                Ok(SystemInstruction::AssignWithSeed { owner, .. }) => Some(owner),
+                _ => None,
+            };
+            if let (Some(new_owner), Some(account)) = (new_owner, instruction.accounts().next()) {
+                risks.push(Risk::OwnerReassignment {
+                    instruction_index,
+                    account: *account.pubkey,
+                    new_owner,
+                });
+            }
+        } else if !config.known_programs.contains(program_id) {
+            risks.extend(
+                instruction
+                    .accounts()
+                    .filter(|account| account.is_signer && account.is_writable)
+                    .map(|account| *account.pubkey)
+                    .collect::<BTreeSet<_>>()
+                    .into_iter()
+                    .map(|account| Risk::WritableSignerForUnknownProgram {
+                        instruction_index,
+                        program_id: *program_id,
+                        account,
+                    }),
+            );
+        }
+    }
+
+    risks
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_system_interface::instruction as system_instruction,
+    };
+
+    fn sanitize(instructions: &[Instruction], payer: &Pubkey) -> SanitizedTransaction {
+        SanitizedTransaction::from_transaction_for_tests(Transaction::new_unsigned(
+            solana_message::Message::new_with_blockhash(
+                instructions,
+                Some(payer),
+                &Hash::new_unique(),
+            ),
+        ))
+    }
+
+    #[test]
+    fn test_analyze_transfer() {
+        let signer = Pubkey::new_unique();
+        let tx = sanitize(
+            &[system_instruction::transfer(
+                &signer,
+                &Pubkey::new_unique(),
+                1,
+            )],
+            &signer,
+        );
+        assert_eq!(
+            analyze(&tx, &AnalysisConfig::new(signer)),
+            vec![Risk::WritableSigner { account: signer }]
+        );
+
+        // Paying fees alone does not make the signer a writable signer
+        let tx = sanitize(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[],
+                vec![AccountMeta::new_readonly(Pubkey::new_unique(), false)],
+            )],
+            &signer,
+        );
+        assert_eq!(analyze(&tx, &AnalysisConfig::new(signer)), vec![]);
+    }
+
+    #[test]
+    fn test_analyze_drainer() {
+        let signer = Pubkey::new_unique();
+        let fee_payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let new_owner = Pubkey::new_unique();
+        // Sorted after the signer to check that the reported order is stable
+        let other_signer = Pubkey::new_from_array([0xff; 32]);
+        let instructions = [
+            system_instruction::assign(&signer, &new_owner),
+            Instruction::new_with_bytes(
+                program_id,
+                &[],
+                vec![
+                    AccountMeta::new(signer, true),
+                    AccountMeta::new(other_signer, true),
+                    AccountMeta::new(Pubkey::new_unique(), false),
+                ],
+            ),
+        ];
+        let tx = sanitize(&instructions, &fee_payer);
+
+        let mut config = AnalysisConfig::new(signer);
+        assert_eq!(
+            analyze(&tx, &config),
+            vec![
+                Risk::FeePayerIsNotSigner { fee_payer },
+                Risk::WritableSigner { account: signer },
+                Risk::OwnerReassignment {
+                    instruction_index: 0,
+                    account: signer,
+                    new_owner,
+                },
+                Risk::WritableSignerForUnknownProgram {
+                    instruction_index: 1,
+                    program_id,
+                    account: signer,
+                },
+                Risk::WritableSignerForUnknownProgram {
+                    instruction_index: 1,
+                    program_id,
+                    account: other_signer,
+                },
+            ]
+        );
+
+        config.known_programs.insert(program_id);
+        assert!(!analyze(&tx, &config)
+            .iter()
+            .any(|risk| matches!(risk, Risk::WritableSignerForUnknownProgram { .. })));
+    }
+
+    #[test]
+    fn test_analyze_durable_nonce() {
+        let signer = Pubkey::new_unique();
+        let nonce_account = Pubkey::new_unique();
+        let instructions = [
+            system_instruction::advance_nonce_account(&nonce_account, &signer),
+            system_instruction::transfer(&signer, &Pubkey::new_unique(), 1),
+        ];
+        let tx = sanitize(&instructions, &signer);
+
+        let mut config = AnalysisConfig::new(signer);
+        assert_eq!(
+            analyze(&tx, &config),
+            vec![
+                Risk::WritableSigner { account: signer },
+                Risk::UnexpectedDurableNonce { nonce_account },
+            ]
+        );
+        config.expect_durable_nonce = true;
+        assert_eq!(
+            analyze(&tx, &config),
+            vec![Risk::WritableSigner { account: signer }]
+        );
+    }
+}
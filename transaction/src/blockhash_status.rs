@@ -0,0 +1,184 @@
+//! Expiry of the recent blockhash of a transaction.
+//!
+//! A transaction can only land while its recent blockhash is recent enough.
+//! RPC nodes report, with each blockhash, the last block height at which
+//! transactions using it are still accepted. [`RecentBlockhashes`] keeps those
+//! pairs and reports the [`BlockhashStatus`] of a transaction at a given block
+//! height, so that retry logic can tell whether resending a transaction may
+//! still succeed or whether it must be re-signed with a new blockhash.
+
+use {crate::versioned::VersionedTransaction, solana_hash::Hash, std::collections::HashMap};
+
+/// Whether a transaction using some blockhash can still land.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockhashStatus {
+    /// The blockhash is still valid.
+    Valid {
+        /// Number of blocks that can still be produced before the blockhash
+        /// expires. Skipped slots do not produce blocks, so at least as many
+        /// slots remain.
+        blocks_remaining: u64,
+    },
+    /// The last block height at which the blockhash was valid has passed.
+    Expired,
+    /// The blockhash is not in the queue, so its expiry is not known.
+    Unknown,
+}
+
+impl BlockhashStatus {
+    /// Compute the status of a blockhash that is valid up to
+    /// `last_valid_block_height` inclusive.
+    pub fn new(last_valid_block_height: u64, current_block_height: u64) -> Self {
+        match last_valid_block_height.checked_sub(current_block_height) {
+            Some(blocks_remaining) => Self::Valid { blocks_remaining },
+            None => Self::Expired,
+        }
+    }
+
+    /// Returns true if a transaction with this blockhash may still land.
+    pub fn is_landable(&self) -> bool {
+        matches!(self, Self::Valid { .. })
+    }
+
+    /// Number of blocks remaining before expiry, if the blockhash is valid.
+    pub fn blocks_remaining(&self) -> Option<u64> {
+        match self {
+            Self::Valid { blocks_remaining } => Some(*blocks_remaining),
+            Self::Expired | Self::Unknown => None,
+        }
+    }
+}
+
+/// Recent blockhashes with the last block height at which each is valid.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecentBlockhashes {
+    last_valid_block_heights: HashMap<Hash, u64>,
+}
+
+impl RecentBlockhashes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record that `blockhash` is valid up to `last_valid_block_height`.
+    pub fn insert(&mut self, blockhash: Hash, last_valid_block_height: u64) {
+        self.last_valid_block_heights
+            .insert(blockhash, last_valid_block_height);
+    }
+
+    /// Last block height at which `blockhash` is valid, if it is known.
+    pub fn last_valid_block_height(&self, blockhash: &Hash) -> Option<u64> {
+        self.last_valid_block_heights.get(blockhash).copied()
+    }
+
+    /// Status of `blockhash` at `current_block_height`.
+    pub fn status(&self, blockhash: &Hash, current_block_height: u64) -> BlockhashStatus {
+        self.last_valid_block_height(blockhash).map_or(
+            BlockhashStatus::Unknown,
+            |last_valid_block_height| {
+                BlockhashStatus::new(last_valid_block_height, current_block_height)
+            },
+        )
+    }
+
+    /// Status of the recent blockhash of `tx` at `current_block_height`.
+    ///
+    /// Transactions that advance a durable nonce use the nonce value as their
+    /// blockhash; their status is usually [`BlockhashStatus::Unknown`].
+    pub fn transaction_status(
+        &self,
+        tx: &VersionedTransaction,
+        current_block_height: u64,
+    ) -> BlockhashStatus {
+        self.status(tx.message.recent_blockhash(), current_block_height)
+    }
+
+    /// Forget the blockhashes that expired before `current_block_height`.
+    pub fn purge_expired(&mut self, current_block_height: u64) {
+        self.last_valid_block_heights
+            .retain(|_, last_valid_block_height| *last_valid_block_height >= current_block_height);
+    }
+
+    pub fn len(&self) -> usize {
+        self.last_valid_block_heights.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.last_valid_block_heights.is_empty()
+    }
+}
+
+impl FromIterator<(Hash, u64)> for RecentBlockhashes {
+    fn from_iter<I: IntoIterator<Item = (Hash, u64)>>(iter: I) -> Self {
+        Self {
+            last_valid_block_heights: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl Extend<(Hash, u64)> for RecentBlockhashes {
+    fn extend<I: IntoIterator<Item = (Hash, u64)>>(&mut self, iter: I) {
+        self.last_valid_block_heights.extend(iter);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_message::{Message, VersionedMessage},
+    };
+
+    #[test]
+    fn test_blockhash_status() {
+        assert_eq!(
+            BlockhashStatus::new(100, 90),
+            BlockhashStatus::Valid {
+                blocks_remaining: 10
+            }
+        );
+        assert_eq!(BlockhashStatus::new(100, 100).blocks_remaining(), Some(0));
+        assert!(BlockhashStatus::new(100, 100).is_landable());
+        assert_eq!(BlockhashStatus::new(100, 101), BlockhashStatus::Expired);
+        assert!(!BlockhashStatus::Expired.is_landable());
+        assert!(!BlockhashStatus::Unknown.is_landable());
+        assert_eq!(BlockhashStatus::Unknown.blocks_remaining(), None);
+    }
+
+    #[test]
+    fn test_recent_blockhashes() {
+        let (old, new) = (Hash::new_unique(), Hash::new_unique());
+        let mut blockhashes: RecentBlockhashes = [(old, 150)].into_iter().collect();
+        blockhashes.insert(new, 300);
+
+        let tx = VersionedTransaction {
+            signatures: vec![],
+            message: VersionedMessage::Legacy(Message {
+                recent_blockhash: old,
+                ..Message::default()
+            }),
+        };
+        assert_eq!(
+            blockhashes.transaction_status(&tx, 140),
+            BlockhashStatus::Valid {
+                blocks_remaining: 10
+            }
+        );
+        assert_eq!(
+            blockhashes.transaction_status(&tx, 151),
+            BlockhashStatus::Expired
+        );
+        assert_eq!(
+            blockhashes.status(&Hash::new_unique(), 140),
+            BlockhashStatus::Unknown
+        );
+
+        blockhashes.purge_expired(151);
+        assert_eq!(blockhashes.len(), 1);
+        assert_eq!(
+            blockhashes.transaction_status(&tx, 151),
+            BlockhashStatus::Unknown
+        );
+        assert_eq!(blockhashes.last_valid_block_height(&new), Some(300));
+    }
+}
@@ -0,0 +1,411 @@
+//! Ordered groups of transactions that are executed atomically.
+//!
+//! Block builders accept bundles: a few transactions that must all land, in
+//! order, in the same block. A [`TransactionBundle`] checks the properties a
+//! bundle must have before it is forwarded: that it is not too large, that no
+//! transaction appears twice and that each transaction can take its account
+//! locks. It also exposes the combined account locks of the bundle so that
+//! bundles can be checked against each other for conflicts.
+
+use {
+    crate::sanitized::{SanitizedTransaction, TransactionAccountLocks, MAX_TX_ACCOUNT_LOCKS},
+    solana_signature::Signature,
+    solana_transaction_error::TransactionError,
+    std::{
+        collections::{HashMap, HashSet},
+        fmt,
+    },
+};
+
+/// Maximum number of transactions in a bundle.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+/// Limits that a bundle must respect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BundleLimits {
+    /// Maximum number of transactions in the bundle.
+    pub max_transactions: usize,
+    /// Maximum number of accounts each transaction may lock.
+    pub tx_account_lock_limit: usize,
+    /// Maximum sum of the serialized sizes of the transactions, if any.
+    pub max_serialized_size: Option<usize>,
+    /// Maximum sum of the compute units requested by the transactions, if
+    /// any.
+    pub max_compute_units: Option<u64>,
+}
+
+impl Default for BundleLimits {
+    fn default() -> Self {
+        Self {
+            max_transactions: MAX_BUNDLE_TRANSACTIONS,
+            tx_account_lock_limit: MAX_TX_ACCOUNT_LOCKS,
+            max_serialized_size: None,
+            max_compute_units: None,
+        }
+    }
+}
+
+/// Reasons a bundle is invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    /// The bundle has no transactions.
+    Empty,
+    /// The bundle has more transactions than allowed.
+    TooManyTransactions(usize),
+    /// The transactions at these indexes have the same signature.
+    DuplicateSignature(usize, usize),
+    /// The transaction at this index cannot take its account locks, or
+    /// requests its compute budget with invalid instructions.
+    InvalidTransaction(usize, TransactionError),
+    /// The serialized transactions take more bytes than allowed.
+    TooLarge(usize),
+    /// The transactions request more compute units than allowed.
+    ComputeBudgetExceeded(u64),
+}
+
+impl std::error::Error for BundleError {}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Empty => f.write_str("bundle has no transactions"),
+            Self::TooManyTransactions(count) => {
+                write!(f, "bundle has too many transactions: {count}")
+            }
+            Self::DuplicateSignature(first, second) => {
+                write!(
+                    f,
+                    "transactions {first} and {second} have the same signature"
+                )
+            }
+            Self::InvalidTransaction(index, err) => write!(f, "transaction {index}: {err}"),
+            Self::TooLarge(size) => write!(f, "bundle is too large: {size} bytes"),
+            Self::ComputeBudgetExceeded(compute_units) => {
+                write!(f, "bundle requests too many compute units: {compute_units}")
+            }
+        }
+    }
+}
+
+/// An ordered, non-empty group of transactions that passed bundle-level
+/// validation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionBundle {
+    transactions: Vec<SanitizedTransaction>,
+    serialized_size: usize,
+    compute_units: u64,
+}
+
+impl TransactionBundle {
+    /// Validate `transactions` as a bundle.
+    pub fn try_new(
+        transactions: Vec<SanitizedTransaction>,
+        limits: &BundleLimits,
+    ) -> Result<Self, BundleError> {
+        if transactions.is_empty() {
+            return Err(BundleError::Empty);
+        }
+        if transactions.len() > limits.max_transactions {
+            return Err(BundleError::TooManyTransactions(transactions.len()));
+        }
+
+        let mut signatures = HashMap::<&Signature, usize>::with_capacity(transactions.len());
+        let mut serialized_size = 0usize;
+        let mut compute_units = 0u64;
+        for (index, tx) in transactions.iter().enumerate() {
+            for signature in tx.signatures() {
+                if let Some(first) = signatures.insert(signature, index) {
+                    return Err(BundleError::DuplicateSignature(first, index));
+                }
+            }
+            tx.get_account_locks(limits.tx_account_lock_limit)
+                .map_err(|err| BundleError::InvalidTransaction(index, err))?;
+            serialized_size =
+                serialized_size.saturating_add(tx.as_versioned_transaction_ref().serialized_size());
+            let tx_compute_units = tx
+                .compute_unit_limit()
+                .map_err(|err| BundleError::InvalidTransaction(index, err))?;
+            compute_units = compute_units.saturating_add(u64::from(tx_compute_units));
+        }
+
+        if let Some(max_serialized_size) = limits.max_serialized_size {
+            if serialized_size > max_serialized_size {
+                return Err(BundleError::TooLarge(serialized_size));
+            }
+        }
+        if let Some(max_compute_units) = limits.max_compute_units {
+            if compute_units > max_compute_units {
+                return Err(BundleError::ComputeBudgetExceeded(compute_units));
+            }
+        }
+
+        Ok(Self {
+            transactions,
+            serialized_size,
+            compute_units,
+        })
+    }
+
+    /// The transactions of the bundle, in execution order.
+    pub fn transactions(&self) -> &[SanitizedTransaction] {
+        &self.transactions
+    }
+
+    pub fn into_transactions(self) -> Vec<SanitizedTransaction> {
+        self.transactions
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    /// Always false: a bundle has at least one transaction.
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    /// Sum of the serialized sizes of the transactions.
+    pub fn serialized_size(&self) -> usize {
+        self.serialized_size
+    }
+
+    /// Sum of the compute units requested by the transactions.
+    pub fn compute_units(&self) -> u64 {
+        self.compute_units
+    }
+
+    /// Accounts that must be locked to execute the whole bundle.
+    ///
+    /// An account is locked as writable if any transaction writes it, and as
+    /// readonly if the transactions only read it. Each account appears once.
+    pub fn account_locks(&self) -> TransactionAccountLocks<'_> {
+        let mut writable = HashSet::new();
+        let mut readonly = HashSet::new();
+        for tx in &self.transactions {
+            let locks = tx.get_account_locks_unchecked();
+            writable.extend(locks.writable);
+            readonly.extend(locks.readonly);
+        }
+        let mut account_locks = TransactionAccountLocks {
+            readonly: readonly
+                .into_iter()
+                .filter(|key| !writable.contains(key))
+                .collect(),
+            writable: writable.into_iter().collect(),
+        };
+        account_locks.readonly.sort_unstable();
+        account_locks.writable.sort_unstable();
+        account_locks
+    }
+
+    /// Pairs of transactions in the bundle that lock an account in
+    /// conflicting modes, i.e. both write it or one writes what the other
+    /// reads.
+    ///
+    /// Such transactions are still valid in a bundle, where they run in
+    /// order, but cannot be reordered or executed in parallel.
+    pub fn conflicting_transactions(&self) -> Vec<(usize, usize)> {
+        let locks: Vec<_> = self
+            .transactions
+            .iter()
+            .map(SanitizedTransaction::get_account_locks_unchecked)
+            .collect();
+        let mut conflicts = vec![];
+        for (i, first) in locks.iter().enumerate() {
+            for (j, second) in locks.iter().enumerate().skip(i.saturating_add(1)) {
+                if locks_conflict(first, second) {
+                    conflicts.push((i, j));
+                }
+            }
+        }
+        conflicts
+    }
+
+    /// Returns true if this bundle and `other` lock an account in
+    /// conflicting modes, so that they cannot execute in parallel.
+    pub fn conflicts_with(&self, other: &Self) -> bool {
+        locks_conflict(&self.account_locks(), &other.account_locks())
+    }
+}
+
+fn locks_conflict(first: &TransactionAccountLocks, second: &TransactionAccountLocks) -> bool {
+    first
+        .writable
+        .iter()
+        .any(|key| second.writable.contains(key) || second.readonly.contains(key))
+        || first
+            .readonly
+            .iter()
+            .any(|key| second.writable.contains(key))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        solana_compute_budget_interface::ComputeBudgetInstruction,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+        solana_pubkey::Pubkey,
+        solana_signer::Signer,
+    };
+
+    fn tx(
+        payer: &Keypair,
+        writable: &[Pubkey],
+        readonly: &[Pubkey],
+        compute_unit_limit: Option<u32>,
+    ) -> SanitizedTransaction {
+        let mut instructions: Vec<_> = compute_unit_limit
+            .map(ComputeBudgetInstruction::set_compute_unit_limit)
+            .into_iter()
+            .collect();
+        instructions.push(Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            writable
+                .iter()
+                .map(|key| AccountMeta::new(*key, false))
+                .chain(
+                    readonly
+                        .iter()
+                        .map(|key| AccountMeta::new_readonly(*key, false)),
+                )
+                .collect(),
+        ));
+        SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            Hash::new_unique(),
+        ))
+    }
+
+    #[test]
+    fn test_bundle_accounting() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let first = tx(&Keypair::new(), &[a], &[b], Some(10_000));
+        let second = tx(&Keypair::new(), &[], &[a, b], None);
+        let size = first.as_versioned_transaction_ref().serialized_size()
+            + second.as_versioned_transaction_ref().serialized_size();
+
+        let bundle =
+            TransactionBundle::try_new(vec![first, second], &BundleLimits::default()).unwrap();
+        assert_eq!(bundle.len(), 2);
+        assert_eq!(bundle.serialized_size(), size);
+        assert_eq!(
+            bundle.compute_units(),
+            10_000 + u64::from(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        );
+        assert_eq!(bundle.conflicting_transactions(), vec![(0, 1)]);
+
+        let locks = bundle.account_locks();
+        assert!(locks.writable.contains(&&a));
+        assert!(!locks.readonly.contains(&&a));
+        assert!(locks.readonly.contains(&&b));
+
+        let limits = BundleLimits {
+            max_compute_units: Some(bundle.compute_units() - 1),
+            ..BundleLimits::default()
+        };
+        assert_eq!(
+            TransactionBundle::try_new(bundle.clone().into_transactions(), &limits),
+            Err(BundleError::ComputeBudgetExceeded(bundle.compute_units()))
+        );
+        let limits = BundleLimits {
+            max_serialized_size: Some(size - 1),
+            ..BundleLimits::default()
+        };
+        assert_eq!(
+            TransactionBundle::try_new(bundle.into_transactions(), &limits),
+            Err(BundleError::TooLarge(size))
+        );
+    }
+
+    #[test]
+    fn test_bundle_validation() {
+        let limits = BundleLimits::default();
+        assert_eq!(
+            TransactionBundle::try_new(vec![], &limits),
+            Err(BundleError::Empty)
+        );
+
+        let payer = Keypair::new();
+        let transaction = tx(&payer, &[], &[], None);
+        assert_eq!(
+            TransactionBundle::try_new(vec![transaction.clone(); 2], &limits),
+            Err(BundleError::DuplicateSignature(0, 1))
+        );
+
+        // Signatures other than the first are checked too
+        let cosigner = Keypair::new();
+        let cosigned =
+            SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &[Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[],
+                    vec![AccountMeta::new_readonly(cosigner.pubkey(), true)],
+                )],
+                Some(&payer.pubkey()),
+                &[&payer, &cosigner],
+                Hash::new_unique(),
+            ));
+        let resigned = SanitizedTransaction::try_new_from_fields(
+            cosigned.message().clone(),
+            *cosigned.message_hash(),
+            false,
+            vec![Signature::default(), cosigned.signatures()[1]],
+        )
+        .unwrap();
+        assert_eq!(
+            TransactionBundle::try_new(vec![cosigned, resigned], &limits),
+            Err(BundleError::DuplicateSignature(0, 1))
+        );
+
+        let transactions = (0..=MAX_BUNDLE_TRANSACTIONS)
+            .map(|_| tx(&payer, &[], &[], None))
+            .collect();
+        assert_eq!(
+            TransactionBundle::try_new(transactions, &limits),
+            Err(BundleError::TooManyTransactions(
+                MAX_BUNDLE_TRANSACTIONS + 1
+            ))
+        );
+
+        let locks = tx(
+            &payer,
+            &[Pubkey::new_unique(), Pubkey::new_unique()],
+            &[],
+            None,
+        );
+        let limits = BundleLimits {
+            tx_account_lock_limit: 2,
+            ..BundleLimits::default()
+        };
+        assert_eq!(
+            TransactionBundle::try_new(vec![locks], &limits),
+            Err(BundleError::InvalidTransaction(
+                0,
+                TransactionError::TooManyAccountLocks
+            ))
+        );
+    }
+
+    #[test]
+    fn test_bundle_conflicts() {
+        let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let limits = BundleLimits::default();
+        let bundle = |writable: &[Pubkey], readonly: &[Pubkey]| {
+            TransactionBundle::try_new(vec![tx(&Keypair::new(), writable, readonly, None)], &limits)
+                .unwrap()
+        };
+        let reads_a = bundle(&[], &[a]);
+        assert!(!reads_a.conflicts_with(&bundle(&[b], &[a])));
+        assert!(reads_a.conflicts_with(&bundle(&[a], &[])));
+        assert!(bundle(&[a], &[]).conflicts_with(&reads_a));
+        assert!(bundle(&[a], &[]).conflicts_with(&bundle(&[a], &[])));
+    }
+}
@@ -0,0 +1,549 @@
+//! Compact encoding of sanitized transactions for caches.
+//!
+//! This is not the wire format and is not meant to be sent to other nodes.
+//! [`encode_transactions`] lays out a batch of [`SanitizedTransaction`]s
+//! column by column: every pubkey and blockhash is stored once in an interning
+//! table and referred to by index, signatures and message hashes are arrays of
+//! fixed width, and the accounts resolved during sanitization are kept so that
+//! [`decode_transactions`] needs no address loader. Transactions in a batch
+//! tend to share fee payers, programs and blockhashes, so the encoding is much
+//! smaller than the serde encoding of each transaction.
+//!
+//! The encoding starts with [`COLUMNAR_FORMAT_VERSION`]. Decoding rejects any
+//! other version, so that a cache written by another version of this crate is
+//! rebuilt rather than misread.
+
+use {
+    crate::sanitized::SanitizedTransaction,
+    solana_hash::{Hash, HASH_BYTES},
+    solana_message::{
+        compiled_instruction::CompiledInstruction,
+        legacy,
+        v0::{self, LoadedAddresses, MessageAddressTableLookup},
+        AccountKeyPositions, ComputeBudgetDetailsCache, LegacyMessage, MessageHeader,
+        SanitizedMessage,
+    },
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+    solana_signature::{Signature, SIGNATURE_BYTES},
+    std::{borrow::Cow, collections::HashMap, fmt},
+};
+
+/// Version of the encoding produced by [`encode_transactions`].
+pub const COLUMNAR_FORMAT_VERSION: u8 = 2;
+
+/// Flag set for v0 messages.
+const FLAG_V0: u8 = 1;
+/// Flag set for simple vote transactions.
+const FLAG_SIMPLE_VOTE: u8 = 1 << 1;
+
+/// Errors that may be returned by [`decode_transactions`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColumnarError {
+    /// The encoding has a version other than [`COLUMNAR_FORMAT_VERSION`].
+    UnsupportedVersion(u8),
+    /// The encoding ends before a column or one of its values.
+    Truncated,
+    /// Bytes remain after the last column.
+    TrailingBytes,
+    /// A pubkey or blockhash index is out of bounds of its table.
+    InvalidIndex(u32),
+    /// A decoded transaction is inconsistent.
+    InvalidTransaction(String),
+}
+
+impl std::error::Error for ColumnarError {}
+
+impl fmt::Display for ColumnarError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion(version) => {
+                write!(f, "unsupported columnar format version {version}")
+            }
+            Self::Truncated => f.write_str("columnar encoding is truncated"),
+            Self::TrailingBytes => f.write_str("columnar encoding has trailing bytes"),
+            Self::InvalidIndex(index) => write!(f, "invalid table index {index}"),
+            Self::InvalidTransaction(err) => write!(f, "invalid transaction: {err}"),
+        }
+    }
+}
+
+/// Values stored once and referred to by their index.
+struct Interner<T> {
+    indexes: HashMap<T, u32>,
+    values: Vec<T>,
+}
+
+impl<T: Copy + Eq + std::hash::Hash> Interner<T> {
+    fn new() -> Self {
+        Self {
+            indexes: HashMap::new(),
+            values: Vec::new(),
+        }
+    }
+
+    fn intern(&mut self, value: T) -> u32 {
+        *self.indexes.entry(value).or_insert_with(|| {
+            let index = self.values.len() as u32;
+            self.values.push(value);
+            index
+        })
+    }
+}
+
+/// The columns of an encoded batch, in encoding order.
+#[derive(Default)]
+struct Columns {
+    /// Flags, header and blockhash index of each transaction.
+    meta: Vec<u8>,
+    /// Message hash of each transaction.
+    message_hashes: Vec<u8>,
+    /// Number of signatures then signatures of each transaction. A
+    /// transaction may carry more signatures than its message requires.
+    signatures: Vec<u8>,
+    /// Address table lookups of each v0 transaction.
+    lookups: Vec<u8>,
+    /// Static keys then loaded addresses of each transaction.
+    account_keys: Vec<u8>,
+    /// Bitmap of the writable accounts of each transaction.
+    writable: Vec<u8>,
+    /// Compiled instructions of each transaction.
+    instructions: Vec<u8>,
+}
+
+impl Columns {
+    fn as_array(&self) -> [&[u8]; 7] {
+        [
+            &self.meta,
+            &self.message_hashes,
+            &self.signatures,
+            &self.lookups,
+            &self.account_keys,
+            &self.writable,
+            &self.instructions,
+        ]
+    }
+
+    fn push(
+        &mut self,
+        tx: &SanitizedTransaction,
+        pubkeys: &mut Interner<Pubkey>,
+        blockhashes: &mut Interner<Hash>,
+    ) {
+        let message = tx.message();
+        let mut flags = 0;
+        if matches!(message, SanitizedMessage::V0(_)) {
+            flags |= FLAG_V0;
+        }
+        if tx.is_simple_vote_transaction() {
+            flags |= FLAG_SIMPLE_VOTE;
+        }
+        let header = message.header();
+        self.meta.extend_from_slice(&[
+            flags,
+            header.num_required_signatures,
+            header.num_readonly_signed_accounts,
+            header.num_readonly_unsigned_accounts,
+        ]);
+        put_u32(
+            &mut self.meta,
+            blockhashes.intern(*message.recent_blockhash()),
+        );
+
+        self.message_hashes
+            .extend_from_slice(tx.message_hash().as_ref());
+        put_u32(&mut self.signatures, tx.signatures().len() as u32);
+        for signature in tx.signatures() {
+            self.signatures.extend_from_slice(signature.as_ref());
+        }
+
+        if let SanitizedMessage::V0(message) = message {
+            let lookups = &message.message.address_table_lookups;
+            put_u32(&mut self.lookups, lookups.len() as u32);
+            for lookup in lookups {
+                put_u32(&mut self.lookups, pubkeys.intern(lookup.account_key));
+                put_bytes(&mut self.lookups, &lookup.writable_indexes);
+                put_bytes(&mut self.lookups, &lookup.readonly_indexes);
+            }
+        }
+
+        // The number of loaded addresses follows from the lookups
+        let account_keys = message.account_keys();
+        put_u32(
+            &mut self.account_keys,
+            message.static_account_keys().len() as u32,
+        );
+        for key in account_keys.iter() {
+            put_u32(&mut self.account_keys, pubkeys.intern(*key));
+        }
+
+        let mut bitmap = vec![0u8; account_keys.len().div_ceil(8)];
+        for index in 0..account_keys.len() {
+            if message.is_writable(index) {
+                bitmap[index / 8] |= 1 << (index % 8);
+            }
+        }
+        self.writable.extend_from_slice(&bitmap);
+
+        put_u32(&mut self.instructions, message.instructions().len() as u32);
+        for instruction in message.instructions() {
+            self.instructions.push(instruction.program_id_index);
+            put_bytes(&mut self.instructions, &instruction.accounts);
+            put_bytes(&mut self.instructions, &instruction.data);
+        }
+    }
+}
+
+/// Encode a batch of transactions.
+pub fn encode_transactions(txs: &[SanitizedTransaction]) -> Vec<u8> {
+    let mut pubkeys = Interner::new();
+    let mut blockhashes = Interner::new();
+    let mut columns = Columns::default();
+    for tx in txs {
+        columns.push(tx, &mut pubkeys, &mut blockhashes);
+    }
+
+    let mut bytes = vec![COLUMNAR_FORMAT_VERSION];
+    put_u32(&mut bytes, txs.len() as u32);
+    put_u32(&mut bytes, pubkeys.values.len() as u32);
+    for pubkey in &pubkeys.values {
+        bytes.extend_from_slice(pubkey.as_ref());
+    }
+    put_u32(&mut bytes, blockhashes.values.len() as u32);
+    for blockhash in &blockhashes.values {
+        bytes.extend_from_slice(blockhash.as_ref());
+    }
+    for column in columns.as_array() {
+        put_bytes(&mut bytes, column);
+    }
+    bytes
+}
+
+/// Decode a batch of transactions encoded by [`encode_transactions`].
+pub fn decode_transactions(bytes: &[u8]) -> Result<Vec<SanitizedTransaction>, ColumnarError> {
+    let mut reader = Reader { bytes };
+    let version = reader.read_u8()?;
+    if version != COLUMNAR_FORMAT_VERSION {
+        return Err(ColumnarError::UnsupportedVersion(version));
+    }
+    let num_transactions = reader.read_u32()?;
+    let num_pubkeys = reader.read_u32()?;
+    let pubkeys = read_n(num_pubkeys, || {
+        Ok(Pubkey::from(reader.read_array::<PUBKEY_BYTES>()?))
+    })?;
+    let num_blockhashes = reader.read_u32()?;
+    let blockhashes = read_n(num_blockhashes, || {
+        Ok(Hash::new_from_array(reader.read_array::<HASH_BYTES>()?))
+    })?;
+    let mut meta = reader.read_column()?;
+    let mut message_hashes = reader.read_column()?;
+    let mut signatures = reader.read_column()?;
+    let mut lookups = reader.read_column()?;
+    let mut account_keys = reader.read_column()?;
+    let mut writable = reader.read_column()?;
+    let mut instructions = reader.read_column()?;
+    reader.finish()?;
+    let pubkey = |index: u32| {
+        pubkeys
+            .get(index as usize)
+            .copied()
+            .ok_or(ColumnarError::InvalidIndex(index))
+    };
+
+    let txs = read_n(num_transactions, || {
+        let flags = meta.read_u8()?;
+        let [num_required_signatures, num_readonly_signed_accounts, num_readonly_unsigned_accounts] =
+            meta.read_array::<3>()?;
+        let header = MessageHeader {
+            num_required_signatures,
+            num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts,
+        };
+        let blockhash_index = meta.read_u32()?;
+        let recent_blockhash = *blockhashes
+            .get(blockhash_index as usize)
+            .ok_or(ColumnarError::InvalidIndex(blockhash_index))?;
+        let message_hash = Hash::new_from_array(message_hashes.read_array()?);
+        let num_signatures = signatures.read_u32()?;
+        let tx_signatures = read_n(num_signatures, || {
+            Ok(Signature::from(signatures.read_array::<SIGNATURE_BYTES>()?))
+        })?;
+
+        let address_table_lookups = if flags & FLAG_V0 != 0 {
+            let num_lookups = lookups.read_u32()?;
+            read_n(num_lookups, || {
+                Ok(MessageAddressTableLookup {
+                    account_key: pubkey(lookups.read_u32()?)?,
+                    writable_indexes: lookups.read_bytes()?.to_vec(),
+                    readonly_indexes: lookups.read_bytes()?.to_vec(),
+                })
+            })?
+        } else {
+            vec![]
+        };
+
+        let num_static_keys = account_keys.read_u32()?;
+        let static_keys = read_n(num_static_keys, || pubkey(account_keys.read_u32()?))?;
+        let num_loaded_writable = address_table_lookups
+            .iter()
+            .map(|lookup| lookup.writable_indexes.len() as u32)
+            .sum();
+        let num_loaded_readonly = address_table_lookups
+            .iter()
+            .map(|lookup| lookup.readonly_indexes.len() as u32)
+            .sum();
+        let loaded_addresses = LoadedAddresses {
+            writable: read_n(num_loaded_writable, || pubkey(account_keys.read_u32()?))?,
+            readonly: read_n(num_loaded_readonly, || pubkey(account_keys.read_u32()?))?,
+        };
+
+        let num_keys = static_keys.len().saturating_add(loaded_addresses.len());
+        let bitmap = writable.take(num_keys.div_ceil(8))?;
+        let is_writable_account_cache = (0..num_keys)
+            .map(|index| bitmap[index / 8] & (1 << (index % 8)) != 0)
+            .collect();
+
+        let num_instructions = instructions.read_u32()?;
+        let compiled_instructions = read_n(num_instructions, || {
+            Ok(CompiledInstruction {
+                program_id_index: instructions.read_u8()?,
+                accounts: instructions.read_bytes()?.to_vec(),
+                data: instructions.read_bytes()?.to_vec(),
+            })
+        })?;
+
+        let message = if flags & FLAG_V0 != 0 {
+            SanitizedMessage::V0(v0::LoadedMessage {
+                message: Cow::Owned(v0::Message {
+                    header,
+                    account_keys: static_keys,
+                    recent_blockhash,
+                    instructions: compiled_instructions,
+                    address_table_lookups,
+                }),
+                loaded_addresses: Cow::Owned(loaded_addresses),
+                is_writable_account_cache,
+                compute_budget_details: ComputeBudgetDetailsCache::default(),
+                account_key_positions: AccountKeyPositions::default(),
+            })
+        } else {
+            SanitizedMessage::Legacy(LegacyMessage {
+                message: Cow::Owned(legacy::Message {
+                    header,
+                    account_keys: static_keys,
+                    recent_blockhash,
+                    instructions: compiled_instructions,
+                }),
+                is_writable_account_cache,
+                compute_budget_details: ComputeBudgetDetailsCache::default(),
+                account_key_positions: AccountKeyPositions::default(),
+            })
+        };
+        SanitizedTransaction::try_from_resolved_fields(
+            message,
+            message_hash,
+            flags & FLAG_SIMPLE_VOTE != 0,
+            tx_signatures,
+        )
+        .map_err(ColumnarError::InvalidTransaction)
+    })?;
+
+    for column in [
+        meta,
+        message_hashes,
+        signatures,
+        lookups,
+        account_keys,
+        writable,
+        instructions,
+    ] {
+        column.finish()?;
+    }
+    Ok(txs)
+}
+
+fn put_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn put_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    put_u32(bytes, value.len() as u32);
+    bytes.extend_from_slice(value);
+}
+
+/// Collect `count` values read by `read`, stopping at the first error. The
+/// count is not trusted to preallocate.
+fn read_n<T>(
+    count: u32,
+    mut read: impl FnMut() -> Result<T, ColumnarError>,
+) -> Result<Vec<T>, ColumnarError> {
+    (0..count).map(|_| read()).collect()
+}
+
+/// Cursor over an encoded batch or one of its columns.
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ColumnarError> {
+        if len > self.bytes.len() {
+            return Err(ColumnarError::Truncated);
+        }
+        let (value, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(value)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> Result<[u8; N], ColumnarError> {
+        Ok(self.take(N)?.try_into().expect("slice of length N"))
+    }
+
+    fn read_u8(&mut self) -> Result<u8, ColumnarError> {
+        let [value] = self.read_array::<1>()?;
+        Ok(value)
+    }
+
+    fn read_u32(&mut self) -> Result<u32, ColumnarError> {
+        Ok(u32::from_le_bytes(self.read_array()?))
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], ColumnarError> {
+        let len = self.read_u32()?;
+        self.take(len as usize)
+    }
+
+    fn read_column(&mut self) -> Result<Self, ColumnarError> {
+        Ok(Self {
+            bytes: self.read_bytes()?,
+        })
+    }
+
+    fn finish(&self) -> Result<(), ColumnarError> {
+        if !self.bytes.is_empty() {
+            return Err(ColumnarError::TrailingBytes);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{versioned::VersionedTransaction, Transaction},
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::{SimpleAddressLoader, VersionedMessage},
+        solana_signer::Signer,
+        std::collections::HashSet,
+    };
+
+    fn transactions() -> Vec<SanitizedTransaction> {
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let blockhash = Hash::new_unique();
+        let mut txs = (0..3)
+            .map(|i| {
+                SanitizedTransaction::from_transaction_for_tests(
+                    Transaction::new_signed_with_payer(
+                        &[Instruction::new_with_bytes(
+                            program_id,
+                            &[i],
+                            vec![AccountMeta::new(Pubkey::new_unique(), false)],
+                        )],
+                        Some(&payer.pubkey()),
+                        &[&payer],
+                        blockhash,
+                    ),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![Pubkey::new_unique()],
+            readonly: vec![Pubkey::new_unique(), program_id],
+        };
+        let v0_tx = VersionedTransaction {
+            signatures: vec![Signature::new_unique()],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![payer.pubkey(), Pubkey::new_unique()],
+                recent_blockhash: blockhash,
+                instructions: vec![CompiledInstruction::new_from_raw_parts(
+                    1,
+                    vec![1, 2, 3],
+                    vec![0, 2, 3, 4],
+                )],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1, 2],
+                }],
+            }),
+        };
+        txs.push(
+            SanitizedTransaction::try_create(
+                v0_tx,
+                Hash::new_unique(),
+                Some(false),
+                SimpleAddressLoader::Enabled(loaded_addresses),
+                &HashSet::default(),
+            )
+            .unwrap(),
+        );
+        txs
+    }
+
+    #[test]
+    fn test_columnar_round_trip() {
+        let txs = transactions();
+        let bytes = encode_transactions(&txs);
+        let decoded = decode_transactions(&bytes).unwrap();
+        assert_eq!(decoded, txs);
+        for (decoded, tx) in decoded.iter().zip(&txs) {
+            assert_eq!(decoded.message_hash(), tx.message_hash());
+            assert_eq!(
+                decoded.get_account_locks_unchecked(),
+                tx.get_account_locks_unchecked()
+            );
+        }
+
+        // Shared keys and blockhashes are stored once
+        let serde_len = txs
+            .iter()
+            .map(|tx| bincode::serialize(tx).unwrap().len())
+            .sum::<usize>();
+        assert!(bytes.len() < serde_len);
+
+        assert_eq!(decode_transactions(&encode_transactions(&[])), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_columnar_decode_errors() {
+        let bytes = encode_transactions(&transactions());
+
+        let mut other_version = bytes.clone();
+        other_version[0] = COLUMNAR_FORMAT_VERSION + 1;
+        assert_eq!(
+            decode_transactions(&other_version),
+            Err(ColumnarError::UnsupportedVersion(
+                COLUMNAR_FORMAT_VERSION + 1
+            ))
+        );
+
+        for len in 0..bytes.len() {
+            assert!(decode_transactions(&bytes[..len]).is_err());
+        }
+
+        let mut trailing = bytes;
+        trailing.push(0);
+        assert_eq!(
+            decode_transactions(&trailing),
+            Err(ColumnarError::TrailingBytes)
+        );
+    }
+}
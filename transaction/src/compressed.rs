@@ -0,0 +1,257 @@
+//! Compressed storage of transactions.
+//!
+//! A [`CompressedTransaction`] holds the zstd compression of the wire
+//! encoding of a transaction, and only decompresses it the first time the
+//! transaction is accessed. Transactions are small and share most of their
+//! layout, so they compress far better with a [`TransactionDictionary`]
+//! trained on a sample of similar transactions. A transaction compressed with
+//! a dictionary can only be decompressed with the same dictionary, which
+//! zstd identifies in the compressed frame.
+#![cfg(all(feature = "compression", not(target_arch = "wasm32")))]
+
+use {
+    crate::{versioned::VersionedTransaction, PACKET_DATA_SIZE},
+    std::{
+        fmt,
+        sync::{Arc, OnceLock},
+    },
+};
+
+/// zstd compression level used by [`CompressedTransaction::new`].
+pub const DEFAULT_COMPRESSION_LEVEL: i32 = zstd::DEFAULT_COMPRESSION_LEVEL;
+
+/// Errors that may be returned when compressing or decompressing a
+/// transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CompressedTransactionError {
+    /// The wire encoding of the transaction is larger than a packet.
+    TooLarge(usize),
+    /// zstd failed to compress, decompress or train a dictionary.
+    Zstd(String),
+    /// The decompressed bytes are not a valid wire transaction.
+    InvalidTransaction(String),
+}
+
+impl std::error::Error for CompressedTransactionError {}
+
+impl fmt::Display for CompressedTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TooLarge(size) => write!(
+                f,
+                "transaction too large: {size} bytes, max {PACKET_DATA_SIZE}"
+            ),
+            Self::Zstd(err) => write!(f, "zstd error: {err}"),
+            Self::InvalidTransaction(err) => write!(f, "invalid transaction: {err}"),
+        }
+    }
+}
+
+impl From<std::io::Error> for CompressedTransactionError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Zstd(err.to_string())
+    }
+}
+
+/// A zstd dictionary for compressing transactions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransactionDictionary {
+    bytes: Vec<u8>,
+}
+
+impl TransactionDictionary {
+    /// Train a dictionary of at most `max_size` bytes on the wire encoding of
+    /// `samples`. zstd needs a few hundred samples at least, representative
+    /// of the transactions that will be compressed.
+    pub fn train(
+        samples: &[VersionedTransaction],
+        max_size: usize,
+    ) -> Result<Self, CompressedTransactionError> {
+        let samples = samples
+            .iter()
+            .map(serialize)
+            .collect::<Result<Vec<_>, _>>()?;
+        let bytes = zstd::dict::from_samples(&samples, max_size)?;
+        Ok(Self { bytes })
+    }
+
+    /// Load a dictionary previously saved with [`Self::as_bytes`].
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+/// The zstd compressed wire encoding of a transaction.
+#[derive(Debug, Clone)]
+pub struct CompressedTransaction {
+    compressed: Vec<u8>,
+    dictionary: Option<Arc<TransactionDictionary>>,
+    transaction: OnceLock<VersionedTransaction>,
+}
+
+impl PartialEq for CompressedTransaction {
+    fn eq(&self, other: &Self) -> bool {
+        self.compressed == other.compressed && self.dictionary == other.dictionary
+    }
+}
+
+impl Eq for CompressedTransaction {}
+
+impl CompressedTransaction {
+    /// Compress `tx` without a dictionary.
+    pub fn new(tx: &VersionedTransaction) -> Result<Self, CompressedTransactionError> {
+        let compressed = zstd::bulk::compress(&serialize(tx)?, DEFAULT_COMPRESSION_LEVEL)?;
+        Ok(Self::from_compressed_bytes(compressed, None))
+    }
+
+    /// Compress `tx` with `dictionary` at the given zstd compression `level`.
+    pub fn new_with_dictionary(
+        tx: &VersionedTransaction,
+        dictionary: Arc<TransactionDictionary>,
+        level: i32,
+    ) -> Result<Self, CompressedTransactionError> {
+        let mut compressor = zstd::bulk::Compressor::with_dictionary(level, dictionary.as_bytes())?;
+        let compressed = compressor.compress(&serialize(tx)?)?;
+        Ok(Self::from_compressed_bytes(compressed, Some(dictionary)))
+    }
+
+    /// Wrap bytes previously returned by [`Self::as_compressed_bytes`],
+    /// with the dictionary they were compressed with if any. The bytes are
+    /// only checked when the transaction is first accessed.
+    pub fn from_compressed_bytes(
+        compressed: Vec<u8>,
+        dictionary: Option<Arc<TransactionDictionary>>,
+    ) -> Self {
+        Self {
+            compressed,
+            dictionary,
+            transaction: OnceLock::new(),
+        }
+    }
+
+    pub fn as_compressed_bytes(&self) -> &[u8] {
+        &self.compressed
+    }
+
+    pub fn into_compressed_bytes(self) -> Vec<u8> {
+        self.compressed
+    }
+
+    pub fn dictionary(&self) -> Option<&Arc<TransactionDictionary>> {
+        self.dictionary.as_ref()
+    }
+
+    /// Return the transaction, decompressing it on first access only.
+    pub fn transaction(&self) -> Result<&VersionedTransaction, CompressedTransactionError> {
+        if let Some(tx) = self.transaction.get() {
+            return Ok(tx);
+        }
+        let tx = self.decompress()?;
+        Ok(self.transaction.get_or_init(|| tx))
+    }
+
+    /// Decompress the transaction, reusing it if it was already accessed.
+    pub fn into_transaction(self) -> Result<VersionedTransaction, CompressedTransactionError> {
+        match self.transaction.into_inner() {
+            Some(tx) => Ok(tx),
+            None => Self::from_compressed_bytes(self.compressed, self.dictionary).decompress(),
+        }
+    }
+
+    fn decompress(&self) -> Result<VersionedTransaction, CompressedTransactionError> {
+        let bytes = match &self.dictionary {
+            Some(dictionary) => zstd::bulk::Decompressor::with_dictionary(dictionary.as_bytes())?
+                .decompress(&self.compressed, PACKET_DATA_SIZE)?,
+            None => zstd::bulk::decompress(&self.compressed, PACKET_DATA_SIZE)?,
+        };
+        bincode::deserialize(&bytes)
+            .map_err(|err| CompressedTransactionError::InvalidTransaction(err.to_string()))
+    }
+}
+
+fn serialize(tx: &VersionedTransaction) -> Result<Vec<u8>, CompressedTransactionError> {
+    let bytes = bincode::serialize(tx)
+        .map_err(|err| CompressedTransactionError::InvalidTransaction(err.to_string()))?;
+    if bytes.len() > PACKET_DATA_SIZE {
+        return Err(CompressedTransactionError::TooLarge(bytes.len()));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::Transaction, solana_hash::Hash, solana_keypair::Keypair,
+        solana_pubkey::Pubkey, solana_signer::Signer,
+        solana_system_interface::instruction as system_instruction,
+    };
+
+    fn transfer() -> VersionedTransaction {
+        let payer = Keypair::new();
+        Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_compressed_transaction() {
+        let tx = transfer();
+        let compressed = CompressedTransaction::new(&tx).unwrap();
+        assert_eq!(compressed.transaction(), Ok(&tx));
+        // Cached after the first access
+        assert!(std::ptr::eq(
+            compressed.transaction().unwrap(),
+            compressed.transaction().unwrap()
+        ));
+
+        let restored = CompressedTransaction::from_compressed_bytes(
+            compressed.as_compressed_bytes().to_vec(),
+            None,
+        );
+        assert_eq!(restored, compressed);
+        assert_eq!(restored.into_transaction(), Ok(tx));
+
+        let corrupted = CompressedTransaction::from_compressed_bytes(vec![1, 2, 3], None);
+        assert!(matches!(
+            corrupted.transaction(),
+            Err(CompressedTransactionError::Zstd(_))
+        ));
+    }
+
+    #[test]
+    fn test_compressed_transaction_with_dictionary() {
+        let samples = (0..1_000).map(|_| transfer()).collect::<Vec<_>>();
+        let dictionary = Arc::new(TransactionDictionary::train(&samples, 4 * 1024).unwrap());
+
+        let tx = transfer();
+        let plain = CompressedTransaction::new(&tx).unwrap();
+        let compressed = CompressedTransaction::new_with_dictionary(
+            &tx,
+            dictionary.clone(),
+            DEFAULT_COMPRESSION_LEVEL,
+        )
+        .unwrap();
+        assert!(compressed.as_compressed_bytes().len() <= plain.as_compressed_bytes().len());
+        assert_eq!(compressed.transaction(), Ok(&tx));
+
+        let restored = CompressedTransaction::from_compressed_bytes(
+            compressed.into_compressed_bytes(),
+            Some(Arc::new(TransactionDictionary::from_bytes(
+                dictionary.as_bytes().to_vec(),
+            ))),
+        );
+        assert_eq!(restored.into_transaction(), Ok(tx));
+    }
+}
@@ -0,0 +1,215 @@
+//! Static cost of a transaction in the validator cost model.
+//!
+//! Before executing a transaction, the leader charges it a cost in compute
+//! units against the block and account limits. Most of that cost can be
+//! computed from the transaction alone: its signatures, the accounts it write
+//! locks, the size of its instruction data, and the compute units and account
+//! data it requests. [`TransactionCost`] computes those components with the
+//! same constants as the cost model, so that block packing can be simulated
+//! without the runtime.
+
+use {
+    crate::sanitized::SanitizedTransaction, solana_transaction_error::TransactionResult as Result,
+};
+
+/// Number of compute units that take one microsecond to execute.
+pub const COMPUTE_UNIT_TO_US_RATIO: u64 = 30;
+/// Cost of verifying a transaction signature.
+pub const SIGNATURE_COST: u64 = COMPUTE_UNIT_TO_US_RATIO * 24;
+/// Cost of verifying a secp256k1 precompile signature.
+pub const SECP256K1_VERIFY_COST: u64 = COMPUTE_UNIT_TO_US_RATIO * 223;
+/// Cost of strictly verifying an ed25519 precompile signature.
+pub const ED25519_VERIFY_STRICT_COST: u64 = COMPUTE_UNIT_TO_US_RATIO * 80;
+/// Cost of verifying a secp256r1 precompile signature.
+pub const SECP256R1_VERIFY_COST: u64 = COMPUTE_UNIT_TO_US_RATIO * 160;
+/// Cost of each write lock.
+pub const WRITE_LOCK_UNITS: u64 = COMPUTE_UNIT_TO_US_RATIO * 10;
+/// Number of bytes of instruction data that cost one compute unit.
+pub const INSTRUCTION_DATA_BYTES_COST: u64 = 140 / COMPUTE_UNIT_TO_US_RATIO;
+/// Size of a page of loaded account data.
+pub const ACCOUNT_DATA_COST_PAGE_SIZE: u64 = 32 * 1024;
+/// Cost of each page of loaded account data.
+pub const ACCOUNT_DATA_PAGE_COST: u64 = 8;
+
+/// Compute units charged for executing a simple vote transaction.
+const SIMPLE_VOTE_PROGRAMS_EXECUTION_COST: u64 = 2_100;
+/// Write locks charged for a simple vote transaction: the vote account and
+/// the fee payer.
+const SIMPLE_VOTE_WRITE_LOCKS: u64 = 2;
+
+/// The static components of the cost of a transaction.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TransactionCost {
+    /// Cost of the transaction signatures and of the signatures verified by
+    /// precompile instructions.
+    pub signature_cost: u64,
+    /// Cost of the accounts write locked by the transaction.
+    pub write_lock_cost: u64,
+    /// Cost of the instruction data.
+    pub data_bytes_cost: u64,
+    /// Compute units requested for executing the instructions.
+    pub programs_execution_cost: u64,
+    /// Cost of the account data the transaction may load.
+    pub loaded_accounts_data_size_cost: u64,
+}
+
+impl TransactionCost {
+    /// Compute the static cost of `tx`.
+    ///
+    /// ed25519 precompile signatures are charged as strictly verified. Simple
+    /// vote transactions are charged the fixed cost of the cost model,
+    /// whatever their contents.
+    ///
+    /// Fails if the compute budget instructions of `tx` are invalid.
+    pub fn new(tx: &SanitizedTransaction) -> Result<Self> {
+        if tx.is_simple_vote_transaction() {
+            return Ok(Self {
+                signature_cost: SIGNATURE_COST,
+                write_lock_cost: WRITE_LOCK_UNITS * SIMPLE_VOTE_WRITE_LOCKS,
+                data_bytes_cost: 0,
+                programs_execution_cost: SIMPLE_VOTE_PROGRAMS_EXECUTION_COST,
+                loaded_accounts_data_size_cost: ACCOUNT_DATA_PAGE_COST,
+            });
+        }
+
+        let message = tx.message();
+        let compute_budget_details = tx.get_compute_budget_details()?;
+        let signature_details = message.get_signature_details();
+        let signature_cost = signature_details
+            .num_transaction_signatures()
+            .saturating_mul(SIGNATURE_COST)
+            .saturating_add(
+                signature_details
+                    .num_secp256k1_instruction_signatures()
+                    .saturating_mul(SECP256K1_VERIFY_COST),
+            )
+            .saturating_add(
+                signature_details
+                    .num_ed25519_instruction_signatures()
+                    .saturating_mul(ED25519_VERIFY_STRICT_COST),
+            )
+            .saturating_add(
+                signature_details
+                    .num_secp256r1_instruction_signatures()
+                    .saturating_mul(SECP256R1_VERIFY_COST),
+            );
+
+        let num_write_locks = (0..message.account_keys().len())
+            .filter(|index| message.is_writable(*index))
+            .count() as u64;
+        let data_bytes_len = message
+            .instructions()
+            .iter()
+            .map(|instruction| instruction.data.len() as u64)
+            .sum::<u64>();
+
+        Ok(Self {
+            signature_cost,
+            write_lock_cost: num_write_locks.saturating_mul(WRITE_LOCK_UNITS),
+            data_bytes_cost: data_bytes_len / INSTRUCTION_DATA_BYTES_COST,
+            programs_execution_cost: u64::from(compute_budget_details.compute_unit_limit()),
+            loaded_accounts_data_size_cost: loaded_accounts_data_size_cost(u64::from(
+                compute_budget_details.loaded_accounts_data_size_limit(),
+            )),
+        })
+    }
+
+    /// Total cost of the transaction.
+    pub fn sum(&self) -> u64 {
+        self.signature_cost
+            .saturating_add(self.write_lock_cost)
+            .saturating_add(self.data_bytes_cost)
+            .saturating_add(self.programs_execution_cost)
+            .saturating_add(self.loaded_accounts_data_size_cost)
+    }
+}
+
+/// Cost of loading up to `loaded_accounts_data_size` bytes of account data,
+/// charged per started page.
+fn loaded_accounts_data_size_cost(loaded_accounts_data_size: u64) -> u64 {
+    loaded_accounts_data_size
+        .div_ceil(ACCOUNT_DATA_COST_PAGE_SIZE)
+        .saturating_mul(ACCOUNT_DATA_PAGE_COST)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        solana_compute_budget_interface::ComputeBudgetInstruction,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::SimpleAddressLoader,
+        solana_pubkey::Pubkey,
+        solana_signer::Signer,
+        solana_vote_interface::{instruction as vote_instruction, state::Vote},
+        std::collections::HashSet,
+    };
+
+    #[test]
+    fn test_transaction_cost() {
+        let payer = Keypair::new();
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(10_000),
+            ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(40 * 1024),
+            Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[0; 11],
+                vec![
+                    AccountMeta::new(Pubkey::new_unique(), false),
+                    AccountMeta::new_readonly(Pubkey::new_unique(), false),
+                ],
+            ),
+        ];
+        let tx =
+            SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::new_unique(),
+            ));
+
+        let cost = TransactionCost::new(&tx).unwrap();
+        // One transaction signature, and the payer and one account written.
+        assert_eq!(cost.signature_cost, SIGNATURE_COST);
+        assert_eq!(cost.write_lock_cost, 2 * WRITE_LOCK_UNITS);
+        // Two 5 byte compute budget instructions and 11 bytes of data.
+        assert_eq!(cost.data_bytes_cost, 21 / INSTRUCTION_DATA_BYTES_COST);
+        assert_eq!(cost.programs_execution_cost, 10_000);
+        assert_eq!(
+            cost.loaded_accounts_data_size_cost,
+            2 * ACCOUNT_DATA_PAGE_COST
+        );
+        assert_eq!(
+            cost.sum(),
+            SIGNATURE_COST + 2 * WRITE_LOCK_UNITS + 5 + 10_000 + 16
+        );
+    }
+
+    #[test]
+    fn test_simple_vote_cost() {
+        let payer = Keypair::new();
+        let instruction = vote_instruction::vote(
+            &Pubkey::new_unique(),
+            &payer.pubkey(),
+            Vote::new(vec![1], Hash::default()),
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        let tx = SanitizedTransaction::try_create(
+            tx.into(),
+            Hash::new_unique(),
+            Some(true),
+            SimpleAddressLoader::Disabled,
+            &HashSet::default(),
+        )
+        .unwrap();
+        assert_eq!(TransactionCost::new(&tx).unwrap().sum(), 3_428);
+    }
+}
@@ -0,0 +1,44 @@
+//! Structurally plausible transactions for fuzzing.
+//!
+//! Generated transactions carry a message from the [`Arbitrary`]
+//! implementation of [`VersionedMessage`] and one random signature per
+//! required signer, so that they pass [`VersionedTransaction::sanitize`]. The
+//! signatures do not verify.
+#![cfg(all(feature = "fuzz", not(target_os = "solana")))]
+
+use {
+    crate::versioned::VersionedTransaction,
+    arbitrary::{Arbitrary, Result, Unstructured},
+    proptest::strategy::Strategy,
+    solana_message::{fuzz::arbitrary_strategy, VersionedMessage},
+    solana_signature::Signature,
+};
+
+pub fn versioned_transaction() -> impl Strategy<Value = VersionedTransaction> {
+    arbitrary_strategy()
+}
+
+impl<'a> Arbitrary<'a> for VersionedTransaction {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let message = VersionedMessage::arbitrary(u)?;
+        let signatures = (0..message.header().num_required_signatures)
+            .map(|_| Ok(Signature::from(u.arbitrary::<[u8; 64]>()?)))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            signatures,
+            message,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, proptest::prelude::*};
+
+    proptest! {
+        #[test]
+        fn test_arbitrary_transactions_sanitize(tx in versioned_transaction()) {
+            prop_assert_eq!(tx.sanitize(), Ok(()));
+        }
+    }
+}
@@ -123,7 +123,7 @@ use {
     solana_hash::Hash,
     solana_message::compiled_instruction::CompiledInstruction,
     solana_sdk_ids::system_program,
-    solana_signer::{signers::Signers, SignerError},
+    solana_signer::{signers::Signers, Signer, SignerError},
     solana_system_interface::instruction::SystemInstruction,
 };
 use {
@@ -136,9 +136,31 @@ use {
     std::result,
 };
 
+pub mod analysis;
+pub mod blockhash_status;
+pub mod bundle;
+pub mod columnar;
+pub mod compressed;
+pub mod cost;
+pub mod fuzz;
+pub mod observer;
+pub mod packer;
+pub mod policy;
+pub mod pool;
+pub mod precompiles;
+pub mod replica;
+pub mod rpc_json;
 pub mod sanitized;
+pub mod sig_verifier;
+pub mod sign_only;
+pub mod signing_session;
 pub mod simple_vote_transaction_checker;
+pub mod status_meta;
+pub mod summary;
+pub mod svm;
+pub mod unsigned;
 pub mod versioned;
+pub mod view;
 mod wasm;
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -159,7 +181,6 @@ const NONCED_TX_MARKER_IX_INDEX: u8 = 0;
 // inlined to avoid solana-packet dep
 #[cfg(test)]
 static_assertions::const_assert_eq!(PACKET_DATA_SIZE, solana_packet::PACKET_DATA_SIZE);
-#[cfg(feature = "bincode")]
 const PACKET_DATA_SIZE: usize = 1280 - 40 - 8;
 
 /// An atomically-committed sequence of instructions.
@@ -586,6 +607,58 @@ impl Transaction {
         Transaction::new(from_keypairs, message, recent_blockhash)
     }
 
+    /// Create a fully-signed transaction that uses a durable transaction nonce
+    /// instead of a recent blockhash.
+    ///
+    /// An [`AdvanceNonceAccount`] instruction is inserted as the first
+    /// instruction, as required by the runtime, and the durable nonce stored
+    /// in `nonce_state` is used as the transaction's blockhash.
+    /// `nonce_authority` must be the authority recorded in `nonce_state`. It
+    /// may also appear in `signing_keypairs`, in which case the transaction
+    /// still carries a single signature for it.
+    ///
+    /// [`AdvanceNonceAccount`]: https://docs.rs/solana-system-interface/latest/solana_system_interface/instruction/enum.SystemInstruction.html#variant.AdvanceNonceAccount
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SignerError::InvalidInput`] if the nonce account is not
+    /// initialized, [`SignerError::KeypairPubkeyMismatch`] if `nonce_authority`
+    /// is not the authority of the nonce account, and any error returned by
+    /// [`Transaction::try_sign`].
+    #[cfg(feature = "bincode")]
+    pub fn try_new_signed_with_nonce<T: Signers + ?Sized>(
+        instructions: &[Instruction],
+        payer: Option<&Pubkey>,
+        nonce_account_pubkey: &Pubkey,
+        nonce_authority: &dyn Signer,
+        nonce_state: &solana_nonce::state::State,
+        signing_keypairs: &T,
+    ) -> result::Result<Self, SignerError> {
+        let solana_nonce::state::State::Initialized(nonce_data) = nonce_state else {
+            return Err(SignerError::InvalidInput(
+                "nonce account is not initialized".to_string(),
+            ));
+        };
+        if nonce_authority.try_pubkey()? != nonce_data.authority {
+            return Err(SignerError::KeypairPubkeyMismatch);
+        }
+
+        let message = Message::new_with_nonce(
+            instructions.to_vec(),
+            payer,
+            nonce_account_pubkey,
+            &nonce_data.authority,
+        );
+        let blockhash = nonce_data.blockhash();
+        let mut tx = Self::new_unsigned(message);
+        tx.try_partial_sign(signing_keypairs, blockhash)?;
+        tx.try_partial_sign(&[nonce_authority], blockhash)?;
+        if !tx.is_signed() {
+            return Err(SignerError::NotEnoughSigners);
+        }
+        Ok(tx)
+    }
+
     /// Get the data for an instruction at the given index.
     ///
     /// The `instruction_index` corresponds to the [`instructions`] vector of
@@ -996,6 +1069,30 @@ impl Transaction {
         Ok(())
     }
 
+    /// Replace the recent blockhash and re-sign the transaction with all
+    /// required keys.
+    ///
+    /// All existing signatures are discarded, so every required signer must
+    /// be provided. On error the transaction is left unchanged. This is
+    /// intended for retry loops that resubmit a transaction after its
+    /// blockhash has expired.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Transaction::try_sign`].
+    #[cfg(feature = "bincode")]
+    pub fn resign_with_blockhash<T: Signers + ?Sized>(
+        &mut self,
+        keypairs: &T,
+        recent_blockhash: Hash,
+    ) -> result::Result<(), SignerError> {
+        let mut tx = Self::new_unsigned(self.message.clone());
+        tx.message.recent_blockhash = recent_blockhash;
+        tx.try_sign(keypairs, recent_blockhash)?;
+        *self = tx;
+        Ok(())
+    }
+
     /// Returns a signature that is not valid for signing this transaction.
     pub fn get_invalid_signature() -> Signature {
         Signature::default()
@@ -1628,6 +1725,109 @@ mod tests {
         assert!(uses_durable_nonce(&tx).is_none());
     }
 
+    #[test]
+    fn test_try_new_signed_with_nonce() {
+        let payer = Keypair::new();
+        let nonce_authority = Keypair::new();
+        let nonce_pubkey = Pubkey::new_unique();
+        let durable_nonce = solana_nonce::state::DurableNonce::from_blockhash(&Hash::new_unique());
+        let nonce_state = solana_nonce::state::State::new_initialized(
+            &nonce_authority.pubkey(),
+            durable_nonce,
+            5000,
+        );
+        let instructions = [system_instruction::transfer(
+            &payer.pubkey(),
+            &Pubkey::new_unique(),
+            42,
+        )];
+
+        let tx = Transaction::try_new_signed_with_nonce(
+            &instructions,
+            Some(&payer.pubkey()),
+            &nonce_pubkey,
+            &nonce_authority,
+            &nonce_state,
+            &[&payer],
+        )
+        .unwrap();
+        assert_eq!(tx.message.recent_blockhash, *durable_nonce.as_hash());
+        assert!(uses_durable_nonce(&tx).is_some());
+        assert_eq!(tx.key(0, 0), Some(&nonce_pubkey));
+        assert!(tx.verify().is_ok());
+
+        // The authority may also be passed among the signing keypairs
+        let tx = Transaction::try_new_signed_with_nonce(
+            &instructions,
+            Some(&payer.pubkey()),
+            &nonce_pubkey,
+            &nonce_authority,
+            &nonce_state,
+            &[&payer, &nonce_authority],
+        )
+        .unwrap();
+        assert_eq!(tx.signatures.len(), 2);
+        assert!(tx.verify().is_ok());
+
+        let wrong_authority = Keypair::new();
+        assert_eq!(
+            Transaction::try_new_signed_with_nonce(
+                &instructions,
+                Some(&payer.pubkey()),
+                &nonce_pubkey,
+                &wrong_authority,
+                &nonce_state,
+                &[&payer],
+            ),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+
+        assert!(matches!(
+            Transaction::try_new_signed_with_nonce(
+                &instructions,
+                Some(&payer.pubkey()),
+                &nonce_pubkey,
+                &nonce_authority,
+                &solana_nonce::state::State::Uninitialized,
+                &[&payer],
+            ),
+            Err(SignerError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn test_resign_with_blockhash() {
+        let payer = Keypair::new();
+        let other = Keypair::new();
+        let ix = Instruction::new_with_bincode(
+            Pubkey::new_unique(),
+            &0,
+            vec![AccountMeta::new(other.pubkey(), true)],
+        );
+        let blockhash = Hash::new_unique();
+        let mut tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[&payer, &other],
+            blockhash,
+        );
+        let original = tx.clone();
+
+        // Missing signer leaves the transaction untouched
+        let new_blockhash = Hash::new_unique();
+        assert_eq!(
+            tx.resign_with_blockhash(&[&payer], new_blockhash),
+            Err(SignerError::NotEnoughSigners)
+        );
+        assert_eq!(tx, original);
+
+        tx.resign_with_blockhash(&[&other, &payer], new_blockhash)
+            .unwrap();
+        assert_eq!(tx.message.recent_blockhash, new_blockhash);
+        assert_ne!(tx.signatures, original.signatures);
+        assert!(tx.verify().is_ok());
+    }
+
     #[test]
     fn tx_keypair_pubkey_mismatch() {
         let from_keypair = Keypair::new();
@@ -0,0 +1,243 @@
+//! Hooks for observing transaction sanitization and verification.
+//!
+//! A service that wants metrics on these hot paths installs a
+//! [`TransactionObserver`] once at startup with
+//! [`set_transaction_observer`]. [`SanitizedTransaction::try_create`],
+//! [`SanitizedTransaction::verify`], [`SanitizedTransaction::verify_with`] and
+//! [`SanitizedTransaction::validate_account_locks`] then report to it. Until
+//! an observer is installed, nothing is reported and the only cost is checking
+//! that none is.
+//!
+//! Durations are always zero on wasm32, which has no monotonic clock.
+//!
+//! [`SanitizedTransaction::try_create`]: crate::sanitized::SanitizedTransaction::try_create
+//! [`SanitizedTransaction::verify`]: crate::sanitized::SanitizedTransaction::verify
+//! [`SanitizedTransaction::verify_with`]: crate::sanitized::SanitizedTransaction::verify_with
+//! [`SanitizedTransaction::validate_account_locks`]: crate::sanitized::SanitizedTransaction::validate_account_locks
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+use {
+    solana_transaction_error::{TransactionError, TransactionResult as Result},
+    std::{sync::OnceLock, time::Duration},
+};
+
+static OBSERVER: OnceLock<Box<dyn TransactionObserver>> = OnceLock::new();
+
+/// Callbacks invoked on sanitization and verification of transactions.
+///
+/// Callbacks run inline on the thread processing the transaction, so they
+/// should be cheap, e.g. incrementing counters. Every callback does nothing
+/// by default.
+pub trait TransactionObserver: Send + Sync {
+    /// Called when the sanitization of a transaction starts.
+    fn sanitize_started(&self) {}
+
+    /// Called when the sanitization of a transaction finishes, with its
+    /// outcome and how long it took.
+    fn sanitize_finished(
+        &self,
+        _result: std::result::Result<(), &TransactionError>,
+        _elapsed: Duration,
+    ) {
+    }
+
+    /// Called when the signatures of a transaction have been verified, with
+    /// the outcome and how long verification took.
+    fn verify_finished(
+        &self,
+        _result: std::result::Result<(), &TransactionError>,
+        _elapsed: Duration,
+    ) {
+    }
+
+    /// Called when a transaction fails account lock validation.
+    fn account_locks_rejected(&self, _err: &TransactionError) {}
+}
+
+/// Install the process-wide transaction observer.
+///
+/// The observer can only be installed once. Later calls leave the installed
+/// observer in place and return the one they were given.
+pub fn set_transaction_observer(
+    observer: Box<dyn TransactionObserver>,
+) -> std::result::Result<(), Box<dyn TransactionObserver>> {
+    OBSERVER.set(observer)
+}
+
+/// Return the installed transaction observer, if any.
+pub fn transaction_observer() -> Option<&'static dyn TransactionObserver> {
+    OBSERVER.get().map(Box::as_ref)
+}
+
+#[cfg(feature = "bincode")]
+pub(crate) fn observe_sanitize<T>(sanitize: impl FnOnce() -> Result<T>) -> Result<T> {
+    let Some(observer) = transaction_observer() else {
+        return sanitize();
+    };
+    observer.sanitize_started();
+    let (result, elapsed) = timed(sanitize);
+    observer.sanitize_finished(result.as_ref().map(|_| ()), elapsed);
+    result
+}
+
+#[cfg(feature = "verify")]
+pub(crate) fn observe_verify(verify: impl FnOnce() -> Result<()>) -> Result<()> {
+    let Some(observer) = transaction_observer() else {
+        return verify();
+    };
+    let (result, elapsed) = timed(verify);
+    observer.verify_finished(result.as_ref().copied(), elapsed);
+    result
+}
+
+pub(crate) fn observe_account_locks(result: Result<()>) -> Result<()> {
+    if let (Err(err), Some(observer)) = (&result, transaction_observer()) {
+        observer.account_locks_rejected(err);
+    }
+    result
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[cfg_attr(not(any(feature = "bincode", feature = "verify")), allow(dead_code))]
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    let start = Instant::now();
+    let result = f();
+    (result, start.elapsed())
+}
+
+#[cfg(target_arch = "wasm32")]
+#[cfg_attr(not(any(feature = "bincode", feature = "verify")), allow(dead_code))]
+fn timed<T>(f: impl FnOnce() -> T) -> (T, Duration) {
+    (f(), Duration::ZERO)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{sanitized::SanitizedTransaction, sig_verifier::CpuSigVerifier, Transaction},
+        solana_hash::Hash,
+        solana_keypair::Keypair,
+        solana_message::SimpleAddressLoader,
+        solana_pubkey::Pubkey,
+        solana_signer::Signer,
+        solana_system_interface::instruction as system_instruction,
+        std::{
+            collections::HashSet,
+            sync::{Arc, Mutex},
+            thread::{self, ThreadId},
+        },
+    };
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Event {
+        SanitizeStarted,
+        SanitizeFinished(Result<()>),
+        VerifyFinished(Result<()>),
+        AccountLocksRejected(TransactionError),
+    }
+
+    /// Records the events of a single thread, as other tests of this crate
+    /// run concurrently and report to the same observer.
+    struct RecordingObserver {
+        thread: ThreadId,
+        events: Arc<Mutex<Vec<Event>>>,
+    }
+
+    impl RecordingObserver {
+        fn record(&self, event: Event) {
+            if thread::current().id() == self.thread {
+                self.events.lock().unwrap().push(event);
+            }
+        }
+    }
+
+    impl TransactionObserver for RecordingObserver {
+        fn sanitize_started(&self) {
+            self.record(Event::SanitizeStarted);
+        }
+
+        fn sanitize_finished(
+            &self,
+            result: std::result::Result<(), &TransactionError>,
+            _elapsed: Duration,
+        ) {
+            self.record(Event::SanitizeFinished(result.map_err(Clone::clone)));
+        }
+
+        fn verify_finished(
+            &self,
+            result: std::result::Result<(), &TransactionError>,
+            _elapsed: Duration,
+        ) {
+            self.record(Event::VerifyFinished(result.map_err(Clone::clone)));
+        }
+
+        fn account_locks_rejected(&self, err: &TransactionError) {
+            self.record(Event::AccountLocksRejected(err.clone()));
+        }
+    }
+
+    #[test]
+    fn test_transaction_observer() {
+        let events = Arc::<Mutex<Vec<Event>>>::default();
+        let observer = || {
+            Box::new(RecordingObserver {
+                thread: thread::current().id(),
+                events: events.clone(),
+            })
+        };
+        assert!(set_transaction_observer(observer()).is_ok());
+        assert!(set_transaction_observer(observer()).is_err());
+
+        let payer = Keypair::new();
+        let mut tx = Transaction::new_signed_with_payer(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        let sanitized = SanitizedTransaction::try_create(
+            tx.clone().into(),
+            Hash::new_unique(),
+            None,
+            SimpleAddressLoader::Disabled,
+            &HashSet::new(),
+        )
+        .unwrap();
+        assert_eq!(sanitized.verify(), Ok(()));
+        assert_eq!(sanitized.verify_with(&CpuSigVerifier), Ok(()));
+        assert_eq!(
+            SanitizedTransaction::validate_account_locks(sanitized.message(), 1),
+            Err(TransactionError::TooManyAccountLocks)
+        );
+
+        tx.signatures.clear();
+        assert!(SanitizedTransaction::try_create(
+            tx.into(),
+            Hash::new_unique(),
+            None,
+            SimpleAddressLoader::Disabled,
+            &HashSet::new(),
+        )
+        .is_err());
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                Event::SanitizeStarted,
+                Event::SanitizeFinished(Ok(())),
+                Event::VerifyFinished(Ok(())),
+                Event::VerifyFinished(Ok(())),
+                Event::AccountLocksRejected(TransactionError::TooManyAccountLocks),
+                Event::SanitizeStarted,
+                Event::SanitizeFinished(Err(TransactionError::SanitizeFailure)),
+            ]
+        );
+    }
+}
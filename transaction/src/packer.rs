@@ -0,0 +1,443 @@
+//! Greedy packing of instructions into as few transactions as possible.
+//!
+//! Tools that submit many independent instructions, such as airdrops or batch
+//! settlements, need to split them across several transactions. Each
+//! transaction must fit in a single packet, must not lock more accounts than
+//! the runtime allows and, optionally, must stay within a compute budget.
+//! [`pack_instructions`] fills each transaction in order until adding the
+//! next instruction would break one of those limits.
+#![cfg(feature = "bincode")]
+
+use {
+    crate::{sanitized::MAX_TX_ACCOUNT_LOCKS, PACKET_DATA_SIZE},
+    solana_hash::HASH_BYTES,
+    solana_instruction::Instruction,
+    solana_message::{Message, MESSAGE_HEADER_LENGTH},
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+    solana_signature::SIGNATURE_BYTES,
+    std::{collections::HashSet, fmt},
+};
+
+/// Limits that every packed transaction must respect.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PackingLimits {
+    /// Maximum size in bytes of a signed, serialized transaction.
+    pub max_transaction_size: usize,
+    /// Maximum number of accounts a transaction may reference.
+    pub max_account_locks: usize,
+    /// Maximum sum of the compute unit estimates of the instructions in a
+    /// transaction, if any.
+    pub max_compute_units: Option<u64>,
+}
+
+impl Default for PackingLimits {
+    fn default() -> Self {
+        Self {
+            max_transaction_size: PACKET_DATA_SIZE,
+            max_account_locks: MAX_TX_ACCOUNT_LOCKS,
+            max_compute_units: None,
+        }
+    }
+}
+
+/// Reasons instructions could not be packed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PackError {
+    /// The instruction at this index does not fit within the size limit, even
+    /// in a transaction of its own.
+    TransactionTooLarge(usize),
+    /// The instruction at this index references more accounts than the
+    /// account lock limit allows, even in a transaction of its own.
+    TooManyAccountLocks(usize),
+    /// The compute unit estimate of the instruction at this index exceeds the
+    /// compute unit limit.
+    ComputeBudgetExceeded(usize),
+}
+
+impl std::error::Error for PackError {}
+
+impl fmt::Display for PackError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::TransactionTooLarge(index) => {
+                write!(f, "instruction {index} does not fit in a transaction")
+            }
+            Self::TooManyAccountLocks(index) => {
+                write!(f, "instruction {index} locks too many accounts")
+            }
+            Self::ComputeBudgetExceeded(index) => {
+                write!(f, "instruction {index} exceeds the compute unit limit")
+            }
+        }
+    }
+}
+
+/// Pack `instructions` into the minimum number of messages that greedy
+/// in-order packing allows, each paid for by `payer`.
+///
+/// The relative order of instructions is preserved. The returned messages
+/// have a default blockhash and still need to be signed.
+pub fn pack_instructions(
+    payer: &Pubkey,
+    instructions: &[Instruction],
+    limits: &PackingLimits,
+) -> Result<Vec<Message>, PackError> {
+    pack(
+        payer,
+        instructions.iter().map(|instruction| (instruction, 0)),
+        limits,
+    )
+}
+
+/// Like [`pack_instructions`] but with a compute unit estimate for each
+/// instruction, which is checked against [`PackingLimits::max_compute_units`].
+pub fn pack_instructions_with_compute_units(
+    payer: &Pubkey,
+    instructions: &[(Instruction, u64)],
+    limits: &PackingLimits,
+) -> Result<Vec<Message>, PackError> {
+    pack(
+        payer,
+        instructions
+            .iter()
+            .map(|(instruction, compute_units)| (instruction, *compute_units)),
+        limits,
+    )
+}
+
+fn pack<'a>(
+    payer: &Pubkey,
+    instructions: impl Iterator<Item = (&'a Instruction, u64)>,
+    limits: &PackingLimits,
+) -> Result<Vec<Message>, PackError> {
+    let mut messages = vec![];
+    let mut batch = Batch::new(payer);
+
+    for (index, (instruction, compute_units)) in instructions.enumerate() {
+        if limits
+            .max_compute_units
+            .is_some_and(|max_compute_units| compute_units > max_compute_units)
+        {
+            return Err(PackError::ComputeBudgetExceeded(index));
+        }
+
+        let Err(err) = batch.try_push(instruction, compute_units, limits) else {
+            continue;
+        };
+        if batch.instructions.is_empty() {
+            // The instruction doesn't fit in a transaction of its own
+            return Err(err.with_index(index));
+        }
+
+        // The instruction doesn't fit, so close the current batch and start
+        // a new one with just this instruction.
+        messages.push(batch.to_message(payer));
+        batch = Batch::new(payer);
+        batch
+            .try_push(instruction, compute_units, limits)
+            .map_err(|err| err.with_index(index))?;
+    }
+
+    if !batch.instructions.is_empty() {
+        messages.push(batch.to_message(payer));
+    }
+    Ok(messages)
+}
+
+/// The instructions of the transaction being filled, along with what is
+/// needed to compute the size of its message without compiling it.
+struct Batch {
+    instructions: Vec<Instruction>,
+    account_keys: HashSet<Pubkey>,
+    signers: HashSet<Pubkey>,
+    instructions_size: usize,
+    compute_units: u64,
+}
+
+impl Batch {
+    fn new(payer: &Pubkey) -> Self {
+        Self {
+            instructions: vec![],
+            account_keys: HashSet::from([*payer]),
+            signers: HashSet::from([*payer]),
+            instructions_size: 0,
+            compute_units: 0,
+        }
+    }
+
+    /// Add `instruction` to the batch if the resulting transaction stays
+    /// within `limits`, leaving the batch unchanged otherwise.
+    fn try_push(
+        &mut self,
+        instruction: &Instruction,
+        compute_units: u64,
+        limits: &PackingLimits,
+    ) -> Result<(), LimitError> {
+        let mut new_account_keys = HashSet::new();
+        let mut new_signers = HashSet::new();
+        if !self.account_keys.contains(&instruction.program_id) {
+            new_account_keys.insert(instruction.program_id);
+        }
+        for account in &instruction.accounts {
+            if !self.account_keys.contains(&account.pubkey) {
+                new_account_keys.insert(account.pubkey);
+            }
+            if account.is_signer && !self.signers.contains(&account.pubkey) {
+                new_signers.insert(account.pubkey);
+            }
+        }
+
+        let num_account_keys = self
+            .account_keys
+            .len()
+            .saturating_add(new_account_keys.len());
+        if num_account_keys > limits.max_account_locks {
+            return Err(LimitError::TooManyAccountLocks);
+        }
+        let instructions_size = self
+            .instructions_size
+            .saturating_add(compiled_instruction_size(instruction));
+        let size = transaction_size(
+            self.signers.len().saturating_add(new_signers.len()),
+            num_account_keys,
+            self.instructions.len().saturating_add(1),
+            instructions_size,
+        );
+        if size > limits.max_transaction_size {
+            return Err(LimitError::TransactionTooLarge);
+        }
+        let compute_units = self.compute_units.saturating_add(compute_units);
+        if limits
+            .max_compute_units
+            .is_some_and(|max_compute_units| compute_units > max_compute_units)
+        {
+            return Err(LimitError::ComputeBudgetExceeded);
+        }
+
+        self.instructions.push(instruction.clone());
+        self.account_keys.extend(new_account_keys);
+        self.signers.extend(new_signers);
+        self.instructions_size = instructions_size;
+        self.compute_units = compute_units;
+        Ok(())
+    }
+
+    fn to_message(&self, payer: &Pubkey) -> Message {
+        Message::new(&self.instructions, Some(payer))
+    }
+}
+
+/// Number of bytes used by the compact-u16 encoding of `len`.
+fn short_vec_encoded_len(len: usize) -> usize {
+    match len {
+        0..=0x7f => 1,
+        0x80..=0x3fff => 2,
+        _ => 3,
+    }
+}
+
+/// Number of bytes `instruction` occupies once compiled into a message.
+fn compiled_instruction_size(instruction: &Instruction) -> usize {
+    1usize // program_id_index
+        .saturating_add(short_vec_encoded_len(instruction.accounts.len()))
+        .saturating_add(instruction.accounts.len())
+        .saturating_add(short_vec_encoded_len(instruction.data.len()))
+        .saturating_add(instruction.data.len())
+}
+
+/// Number of bytes a signed legacy transaction occupies on the wire.
+fn transaction_size(
+    num_signers: usize,
+    num_account_keys: usize,
+    num_instructions: usize,
+    instructions_size: usize,
+) -> usize {
+    short_vec_encoded_len(num_signers)
+        .saturating_add(num_signers.saturating_mul(SIGNATURE_BYTES))
+        .saturating_add(MESSAGE_HEADER_LENGTH)
+        .saturating_add(short_vec_encoded_len(num_account_keys))
+        .saturating_add(num_account_keys.saturating_mul(PUBKEY_BYTES))
+        .saturating_add(HASH_BYTES)
+        .saturating_add(short_vec_encoded_len(num_instructions))
+        .saturating_add(instructions_size)
+}
+
+/// The limit a candidate transaction violates, without an instruction index.
+#[derive(Debug)]
+enum LimitError {
+    TransactionTooLarge,
+    TooManyAccountLocks,
+    ComputeBudgetExceeded,
+}
+
+impl LimitError {
+    fn with_index(self, index: usize) -> PackError {
+        match self {
+            Self::TransactionTooLarge => PackError::TransactionTooLarge(index),
+            Self::TooManyAccountLocks => PackError::TooManyAccountLocks(index),
+            Self::ComputeBudgetExceeded => PackError::ComputeBudgetExceeded(index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::Transaction, solana_instruction::AccountMeta,
+        solana_system_interface::instruction as system_instruction,
+    };
+
+    fn transfers(payer: &Pubkey, count: usize) -> Vec<Instruction> {
+        (0..count)
+            .map(|_| system_instruction::transfer(payer, &Pubkey::new_unique(), 1))
+            .collect()
+    }
+
+    fn transaction_size_of(message: &Message) -> usize {
+        let tx = Transaction::new_unsigned(message.clone());
+        bincode::serialized_size(&tx).unwrap() as usize
+    }
+
+    fn assert_within_limits(messages: &[Message], limits: &PackingLimits) {
+        for message in messages {
+            assert!(transaction_size_of(message) <= limits.max_transaction_size);
+            assert!(message.account_keys.len() <= limits.max_account_locks);
+        }
+    }
+
+    #[test]
+    fn test_batch_size() {
+        let payer = Pubkey::new_unique();
+        let signer = Pubkey::new_unique();
+        let mut instructions = transfers(&payer, 3);
+        instructions.push(Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1; 200],
+            vec![
+                AccountMeta::new(signer, true),
+                AccountMeta::new_readonly(payer, false),
+                AccountMeta::new(signer, false),
+            ],
+        ));
+        instructions.push(system_instruction::transfer(&signer, &payer, 1));
+
+        let mut batch = Batch::new(&payer);
+        for instruction in &instructions {
+            batch
+                .try_push(instruction, 0, &PackingLimits::default())
+                .unwrap();
+            let message = batch.to_message(&payer);
+            assert_eq!(batch.account_keys.len(), message.account_keys.len());
+            assert_eq!(
+                transaction_size(
+                    batch.signers.len(),
+                    batch.account_keys.len(),
+                    batch.instructions.len(),
+                    batch.instructions_size,
+                ),
+                transaction_size_of(&message)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pack_instructions_by_size() {
+        let payer = Pubkey::new_unique();
+        let instructions = transfers(&payer, 50);
+        let limits = PackingLimits::default();
+        let messages = pack_instructions(&payer, &instructions, &limits).unwrap();
+        assert!(messages.len() > 1);
+        assert_within_limits(&messages, &limits);
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| message.instructions.len())
+                .sum::<usize>(),
+            instructions.len()
+        );
+
+        // Each batch is full: the first instruction of the next batch would
+        // not have fit.
+        let mut start = 0;
+        for pair in messages.windows(2) {
+            let len = pair[0].instructions.len();
+            let overfull = Message::new(&instructions[start..=start + len], Some(&payer));
+            assert!(transaction_size_of(&overfull) > limits.max_transaction_size);
+            start += len;
+        }
+    }
+
+    #[test]
+    fn test_pack_instructions_by_account_locks() {
+        let payer = Pubkey::new_unique();
+        let instructions = transfers(&payer, 10);
+        let limits = PackingLimits {
+            max_account_locks: 5,
+            ..PackingLimits::default()
+        };
+        // payer + system program + 3 recipients
+        let messages = pack_instructions(&payer, &instructions, &limits).unwrap();
+        assert_eq!(
+            messages
+                .iter()
+                .map(|message| message.instructions.len())
+                .collect::<Vec<_>>(),
+            vec![3, 3, 3, 1]
+        );
+        assert_within_limits(&messages, &limits);
+    }
+
+    #[test]
+    fn test_pack_instructions_by_compute_units() {
+        let payer = Pubkey::new_unique();
+        let instructions: Vec<_> = transfers(&payer, 5)
+            .into_iter()
+            .map(|instruction| (instruction, 150))
+            .collect();
+        let limits = PackingLimits {
+            max_compute_units: Some(300),
+            ..PackingLimits::default()
+        };
+        let messages =
+            pack_instructions_with_compute_units(&payer, &instructions, &limits).unwrap();
+        assert_eq!(messages.len(), 3);
+
+        let limits = PackingLimits {
+            max_compute_units: Some(100),
+            ..PackingLimits::default()
+        };
+        assert_eq!(
+            pack_instructions_with_compute_units(&payer, &instructions, &limits),
+            Err(PackError::ComputeBudgetExceeded(0))
+        );
+    }
+
+    #[test]
+    fn test_pack_instructions_oversized() {
+        let payer = Pubkey::new_unique();
+        let mut instructions = transfers(&payer, 2);
+        instructions.push(Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[0; PACKET_DATA_SIZE],
+            vec![AccountMeta::new(payer, true)],
+        ));
+        assert_eq!(
+            pack_instructions(&payer, &instructions, &PackingLimits::default()),
+            Err(PackError::TransactionTooLarge(2))
+        );
+
+        let limits = PackingLimits {
+            max_account_locks: 2,
+            ..PackingLimits::default()
+        };
+        assert_eq!(
+            pack_instructions(&payer, &instructions, &limits),
+            Err(PackError::TooManyAccountLocks(0))
+        );
+
+        assert_eq!(
+            pack_instructions(&payer, &[], &PackingLimits::default()),
+            Ok(vec![])
+        );
+    }
+}
@@ -0,0 +1,442 @@
+//! A `Signer` wrapper that refuses to sign messages breaking a policy.
+//!
+//! [`PolicySigner`] decodes every message before passing it to the signer it
+//! wraps, and checks it against a [`SigningPolicy`]: which programs it may
+//! invoke, how many lamports its system instructions may move, whether bytes
+//! that are not a message of a known version may be signed at all, and how
+//! often signatures may be produced.
+//!
+//! Rate limits are not available on wasm32, which has no monotonic clock.
+#![cfg(feature = "bincode")]
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+use {
+    crate::PACKET_DATA_SIZE,
+    solana_bincode::limited_deserialize,
+    solana_message::VersionedMessage,
+    solana_pubkey::Pubkey,
+    solana_sdk_ids::system_program,
+    solana_signature::Signature,
+    solana_signer::{Signer, SignerError},
+    solana_system_interface::instruction::SystemInstruction,
+    std::{collections::HashSet, fmt},
+};
+
+/// At most `max_signatures` signatures in any `period`.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    pub max_signatures: usize,
+    pub period: Duration,
+}
+
+/// The rules a [`PolicySigner`] enforces. The default policy allows
+/// everything except blind signing.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SigningPolicy {
+    /// Programs messages may invoke, or `None` to allow any program.
+    pub allowed_programs: Option<HashSet<Pubkey>>,
+    /// Most lamports the system instructions of a message may transfer or
+    /// fund new accounts with, in total.
+    pub max_lamports: Option<u64>,
+    /// Whether bytes that are not a message of a known version may be signed.
+    pub allow_blind_signing: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    pub rate_limit: Option<RateLimit>,
+}
+
+/// Why a [`PolicySigner`] refused to sign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PolicyViolation {
+    /// The bytes are not a message of a known version.
+    BlindSigning,
+    ProgramNotAllowed(Pubkey),
+    LamportLimitExceeded {
+        lamports: u64,
+        limit: u64,
+    },
+    RateLimited,
+}
+
+impl std::error::Error for PolicyViolation {}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::BlindSigning => f.write_str("refusing to sign an undecodable message"),
+            Self::ProgramNotAllowed(program_id) => {
+                write!(f, "program {program_id} is not allowed")
+            }
+            Self::LamportLimitExceeded { lamports, limit } => {
+                write!(
+                    f,
+                    "message moves {lamports} lamports, over the limit of {limit}"
+                )
+            }
+            Self::RateLimited => f.write_str("signing rate limit reached"),
+        }
+    }
+}
+
+impl From<PolicyViolation> for SignerError {
+    fn from(violation: PolicyViolation) -> Self {
+        SignerError::Custom(format!("policy violation: {violation}"))
+    }
+}
+
+/// Decode `bytes` as a message, only if they are exactly one valid message of
+/// a known version.
+pub(crate) fn decode_message(bytes: &[u8]) -> Option<VersionedMessage> {
+    let message: VersionedMessage = bincode::deserialize(bytes).ok()?;
+    // Rejects messages of unknown versions too
+    message.sanitize().ok()?;
+    (bincode::serialized_size(&message).ok()? == bytes.len() as u64).then_some(message)
+}
+
+/// Lamports moved by a system instruction, including withdrawals from nonce
+/// accounts.
+fn system_instruction_lamports(data: &[u8]) -> u64 {
+    match limited_deserialize(data, PACKET_DATA_SIZE as u64) {
+        Ok(SystemInstruction::Transfer { lamports })
+        | Ok(SystemInstruction::TransferWithSeed { lamports, .. })
+        | Ok(SystemInstruction::CreateAccount { lamports, .. })
+        | Ok(SystemInstruction::CreateAccountWithSeed { lamports, .. })
+        | Ok(SystemInstruction::WithdrawNonceAccount(lamports)) => lamports,
+        _ => 0,
+    }
+}
+
+impl SigningPolicy {
+    /// Check the message `bytes` against every rule but the rate limit.
+    pub fn check_message(&self, bytes: &[u8]) -> Result<(), PolicyViolation> {
+        let Some(message) = decode_message(bytes) else {
+            return if self.allow_blind_signing {
+                Ok(())
+            } else {
+                Err(PolicyViolation::BlindSigning)
+            };
+        };
+
+        let account_keys = message.static_account_keys();
+        let mut lamports = 0u64;
+        for instruction in message.instructions() {
+            let program_id = instruction.program_id(account_keys);
+            if let Some(allowed_programs) = &self.allowed_programs {
+                if !allowed_programs.contains(program_id) {
+                    return Err(PolicyViolation::ProgramNotAllowed(*program_id));
+                }
+            }
+            if system_program::check_id(program_id) {
+                lamports = lamports.saturating_add(system_instruction_lamports(&instruction.data));
+            }
+        }
+        if let Some(limit) = self.max_lamports {
+            if lamports > limit {
+                return Err(PolicyViolation::LamportLimitExceeded { lamports, limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A [`Signer`] that signs with `S` only messages allowed by a
+/// [`SigningPolicy`].
+pub struct PolicySigner<S> {
+    signer: S,
+    policy: SigningPolicy,
+    #[cfg(not(target_arch = "wasm32"))]
+    recent_signatures: Mutex<VecDeque<Instant>>,
+}
+
+impl<S: Signer> PolicySigner<S> {
+    pub fn new(signer: S, policy: SigningPolicy) -> Self {
+        Self {
+            signer,
+            policy,
+            #[cfg(not(target_arch = "wasm32"))]
+            recent_signatures: Mutex::default(),
+        }
+    }
+
+    pub fn policy(&self) -> &SigningPolicy {
+        &self.policy
+    }
+
+    pub fn into_inner(self) -> S {
+        self.signer
+    }
+
+    /// Reserve a signature under the rate limit, if it allows one more.
+    /// Returns the time it was reserved at, or `None` without a rate limit.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn reserve_signature(&self) -> Result<Option<Instant>, PolicyViolation> {
+        let Some(RateLimit {
+            max_signatures,
+            period,
+        }) = self.policy.rate_limit
+        else {
+            return Ok(None);
+        };
+        let now = Instant::now();
+        let mut recent_signatures = self.recent_signatures.lock().unwrap();
+        while recent_signatures
+            .front()
+            .is_some_and(|signed_at| now.duration_since(*signed_at) >= period)
+        {
+            recent_signatures.pop_front();
+        }
+        if recent_signatures.len() >= max_signatures {
+            return Err(PolicyViolation::RateLimited);
+        }
+        recent_signatures.push_back(now);
+        Ok(Some(now))
+    }
+
+    /// Give back a signature reserved by [`Self::reserve_signature`] that
+    /// wasn't produced.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn release_signature(&self, reserved_at: Instant) {
+        let mut recent_signatures = self.recent_signatures.lock().unwrap();
+        if let Some(index) = recent_signatures
+            .iter()
+            .rposition(|signed_at| *signed_at == reserved_at)
+        {
+            recent_signatures.remove(index);
+        }
+    }
+}
+
+impl<S: Signer> Signer for PolicySigner<S> {
+    fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+        self.signer.try_pubkey()
+    }
+
+    fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+        self.policy.check_message(message)?;
+        #[cfg(not(target_arch = "wasm32"))]
+        let reserved_at = self.reserve_signature()?;
+        let result = self.signer.try_sign_message(message);
+        // Only signatures actually produced count against the rate limit
+        #[cfg(not(target_arch = "wasm32"))]
+        if let (Err(_), Some(reserved_at)) = (&result, reserved_at) {
+            self.release_signature(reserved_at);
+        }
+        result
+    }
+
+    fn is_interactive(&self) -> bool {
+        self.signer.is_interactive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, solana_hash::Hash, solana_instruction::Instruction, solana_keypair::Keypair,
+        solana_message::Message, solana_system_interface::instruction as system_instruction,
+    };
+
+    fn message_bytes(instructions: &[Instruction], payer: &Pubkey) -> Vec<u8> {
+        Message::new_with_blockhash(instructions, Some(payer), &Hash::new_unique()).serialize()
+    }
+
+    #[test]
+    fn test_check_message() {
+        let payer = Pubkey::new_unique();
+        let transfer = message_bytes(
+            &[system_instruction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                100,
+            )],
+            &payer,
+        );
+        let program_id = Pubkey::new_unique();
+        let other_program = message_bytes(
+            &[Instruction::new_with_bytes(program_id, &[], vec![])],
+            &payer,
+        );
+
+        let policy = SigningPolicy {
+            allowed_programs: Some([system_program::id()].into_iter().collect()),
+            max_lamports: Some(100),
+            ..SigningPolicy::default()
+        };
+        assert_eq!(policy.check_message(&transfer), Ok(()));
+        assert_eq!(
+            policy.check_message(&other_program),
+            Err(PolicyViolation::ProgramNotAllowed(program_id))
+        );
+        assert_eq!(
+            policy.check_message(b"off-chain message"),
+            Err(PolicyViolation::BlindSigning)
+        );
+        // trailing bytes are not a message either
+        let mut padded = transfer.clone();
+        padded.push(0);
+        assert_eq!(
+            policy.check_message(&padded),
+            Err(PolicyViolation::BlindSigning)
+        );
+
+        let policy = SigningPolicy {
+            max_lamports: Some(99),
+            allow_blind_signing: true,
+            ..SigningPolicy::default()
+        };
+        assert_eq!(
+            policy.check_message(&transfer),
+            Err(PolicyViolation::LamportLimitExceeded {
+                lamports: 100,
+                limit: 99
+            })
+        );
+        assert_eq!(policy.check_message(&other_program), Ok(()));
+        assert_eq!(policy.check_message(b"off-chain message"), Ok(()));
+
+        let withdraw = message_bytes(
+            &[system_instruction::withdraw_nonce_account(
+                &Pubkey::new_unique(),
+                &payer,
+                &payer,
+                100,
+            )],
+            &payer,
+        );
+        assert_eq!(
+            policy.check_message(&withdraw),
+            Err(PolicyViolation::LamportLimitExceeded {
+                lamports: 100,
+                limit: 99
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_unsanitized_message() {
+        let payer = Pubkey::new_unique();
+        let mut message = Message::new_with_blockhash(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[],
+                vec![],
+            )],
+            Some(&payer),
+            &Hash::new_unique(),
+        );
+        message.instructions[0].program_id_index = 9;
+        assert_eq!(
+            SigningPolicy::default().check_message(&message.serialize()),
+            Err(PolicyViolation::BlindSigning)
+        );
+    }
+
+    #[test]
+    fn test_policy_signer() {
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let message = message_bytes(
+            &[system_instruction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                1,
+            )],
+            &payer,
+        );
+        let signer = PolicySigner::new(
+            keypair,
+            SigningPolicy {
+                rate_limit: Some(RateLimit {
+                    max_signatures: 2,
+                    period: Duration::from_secs(3600),
+                }),
+                ..SigningPolicy::default()
+            },
+        );
+        assert_eq!(signer.pubkey(), payer);
+
+        let signature = signer.try_sign_message(&message).unwrap();
+        assert!(signature.verify(payer.as_ref(), &message));
+        assert!(matches!(
+            signer.try_sign_message(b"blind"),
+            Err(SignerError::Custom(_))
+        ));
+        // rejected messages do not count against the limit
+        assert!(signer.try_sign_message(&message).is_ok());
+        assert_eq!(
+            signer.try_sign_message(&message),
+            Err(PolicyViolation::RateLimited.into())
+        );
+    }
+
+    #[test]
+    fn test_policy_signer_failed_signatures_not_counted() {
+        /// Fails to sign until `fail` is cleared.
+        struct FlakySigner {
+            keypair: Keypair,
+            fail: std::sync::atomic::AtomicBool,
+        }
+
+        impl Signer for FlakySigner {
+            fn try_pubkey(&self) -> Result<Pubkey, SignerError> {
+                self.keypair.try_pubkey()
+            }
+
+            fn try_sign_message(&self, message: &[u8]) -> Result<Signature, SignerError> {
+                if self.fail.load(std::sync::atomic::Ordering::Relaxed) {
+                    Err(SignerError::Connection("unplugged".to_string()))
+                } else {
+                    self.keypair.try_sign_message(message)
+                }
+            }
+
+            fn is_interactive(&self) -> bool {
+                false
+            }
+        }
+
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let message = message_bytes(
+            &[system_instruction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                1,
+            )],
+            &payer,
+        );
+        let signer = PolicySigner::new(
+            FlakySigner {
+                keypair,
+                fail: true.into(),
+            },
+            SigningPolicy {
+                rate_limit: Some(RateLimit {
+                    max_signatures: 1,
+                    period: Duration::from_secs(3600),
+                }),
+                ..SigningPolicy::default()
+            },
+        );
+        for _ in 0..2 {
+            assert!(matches!(
+                signer.try_sign_message(&message),
+                Err(SignerError::Connection(_))
+            ));
+        }
+        signer
+            .signer
+            .fail
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        assert!(signer.try_sign_message(&message).is_ok());
+        assert_eq!(
+            signer.try_sign_message(&message),
+            Err(PolicyViolation::RateLimited.into())
+        );
+    }
+}
@@ -0,0 +1,257 @@
+//! Allocation reuse for sanitizing large batches of transactions.
+//!
+//! Services that sanitize every packet they receive spend much of that time
+//! in the allocator: each transaction gets fresh vectors for its signatures,
+//! account keys and instructions, and computing the message hash serializes
+//! the message into yet another buffer. A [`SanitizedTransactionPool`] keeps
+//! those buffers around. Transactions parsed with
+//! [`SanitizedTransactionPool::try_create_from_bytes`] are built from recycled
+//! vectors and hash the message bytes in place, and transactions handed back
+//! with [`SanitizedTransactionPool::recycle`] return their vectors to the pool.
+//!
+//! Account keys are not interned. A message stores each [`Pubkey`] by value
+//! in its account key vector, so a key costs no allocation of its own and
+//! reusing the vectors already removes all allocations for keys. Interning
+//! would require messages to hold shared handles to keys instead, which
+//! changes the public message types, and looking a key up in an intern table
+//! costs more than copying its 32 bytes.
+#![cfg(feature = "blake3")]
+
+use {
+    crate::{
+        sanitized::{MessageHash, SanitizedTransaction},
+        versioned::VersionedTransaction,
+        view::TransactionView,
+    },
+    solana_message::{
+        compiled_instruction::CompiledInstruction,
+        legacy,
+        v0::{self, MessageAddressTableLookup},
+        AddressLoader, VersionedMessage,
+    },
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_transaction_error::TransactionResult as Result,
+    std::collections::HashSet,
+};
+
+/// Maximum number of recycled vectors of each kind kept by a pool.
+const MAX_POOLED_BUFFERS: usize = 1024;
+
+/// A pool of buffers reused across sanitized transaction construction.
+///
+/// A pool is meant to be owned by a single sanitizing thread.
+#[derive(Debug, Default)]
+pub struct SanitizedTransactionPool {
+    message_buffer: Vec<u8>,
+    signatures: Vec<Vec<Signature>>,
+    account_keys: Vec<Vec<Pubkey>>,
+    instructions: Vec<Vec<CompiledInstruction>>,
+}
+
+impl SanitizedTransactionPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Like [`SanitizedTransaction::try_create`], but serializes the message
+    /// into a reused buffer when its hash must be computed.
+    pub fn try_create(
+        &mut self,
+        tx: VersionedTransaction,
+        message_hash: impl Into<MessageHash>,
+        is_simple_vote_tx: Option<bool>,
+        address_loader: impl AddressLoader,
+        reserved_account_keys: &HashSet<Pubkey>,
+    ) -> Result<SanitizedTransaction> {
+        let message_hash = match message_hash.into() {
+            MessageHash::Precomputed(hash) => hash,
+            MessageHash::Compute => {
+                self.message_buffer.clear();
+                bincode::serialize_into(&mut self.message_buffer, &tx.message)
+                    .expect("serializing into a vector cannot fail");
+                VersionedMessage::hash_raw_message(&self.message_buffer)
+            }
+        };
+        SanitizedTransaction::try_create(
+            tx,
+            message_hash,
+            is_simple_vote_tx,
+            address_loader,
+            reserved_account_keys,
+        )
+    }
+
+    /// Parse and sanitize a serialized transaction, building it from recycled
+    /// vectors and hashing its message without reserializing it.
+    pub fn try_create_from_bytes(
+        &mut self,
+        bytes: &[u8],
+        is_simple_vote_tx: Option<bool>,
+        address_loader: impl AddressLoader,
+        reserved_account_keys: &HashSet<Pubkey>,
+    ) -> Result<SanitizedTransaction> {
+        let view = TransactionView::try_new(bytes)?;
+        let message_hash = VersionedMessage::hash_raw_message(view.message_data());
+
+        let mut signatures = self.signatures.pop().unwrap_or_default();
+        signatures.extend_from_slice(view.signatures());
+        let mut account_keys = self.account_keys.pop().unwrap_or_default();
+        account_keys.extend_from_slice(view.static_account_keys());
+        let mut instructions = self.instructions.pop().unwrap_or_default();
+        instructions.extend(view.instructions().map(|instruction| {
+            CompiledInstruction::new_from_raw_parts(
+                instruction.program_id_index,
+                instruction.data.to_vec(),
+                instruction.accounts.to_vec(),
+            )
+        }));
+
+        let header = *view.header();
+        let recent_blockhash = *view.recent_blockhash();
+        let message = match view.version() {
+            None => VersionedMessage::Legacy(legacy::Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+            }),
+            Some(_) => VersionedMessage::V0(v0::Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+                address_table_lookups: view
+                    .address_table_lookups()
+                    .map(|lookup| MessageAddressTableLookup {
+                        account_key: *lookup.account_key,
+                        writable_indexes: lookup.writable_indexes.to_vec(),
+                        readonly_indexes: lookup.readonly_indexes.to_vec(),
+                    })
+                    .collect(),
+            }),
+        };
+
+        SanitizedTransaction::try_create(
+            VersionedTransaction {
+                signatures,
+                message,
+            },
+            message_hash,
+            is_simple_vote_tx,
+            address_loader,
+            reserved_account_keys,
+        )
+    }
+
+    /// Return the vectors of a transaction that is no longer needed to the
+    /// pool.
+    pub fn recycle(&mut self, tx: SanitizedTransaction) {
+        let VersionedTransaction {
+            signatures,
+            message,
+        } = tx.into_versioned_transaction();
+        let (account_keys, instructions) = match message {
+            VersionedMessage::Legacy(message) => (message.account_keys, message.instructions),
+            VersionedMessage::V0(message) => (message.account_keys, message.instructions),
+        };
+        push_cleared(&mut self.signatures, signatures);
+        push_cleared(&mut self.account_keys, account_keys);
+        push_cleared(&mut self.instructions, instructions);
+    }
+}
+
+fn push_cleared<T>(pool: &mut Vec<Vec<T>>, mut buffer: Vec<T>) {
+    if pool.len() < MAX_POOLED_BUFFERS && buffer.capacity() > 0 {
+        buffer.clear();
+        pool.push(buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::SimpleAddressLoader,
+        solana_signer::Signer,
+    };
+
+    fn transfer_tx(payer: &Keypair) -> Transaction {
+        Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1, 2, 3],
+                vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            )],
+            Some(&payer.pubkey()),
+            &[payer],
+            Hash::new_unique(),
+        )
+    }
+
+    #[test]
+    fn test_pool_matches_try_create() {
+        let payer = Keypair::new();
+        let reserved_account_keys = HashSet::default();
+        let mut pool = SanitizedTransactionPool::new();
+
+        for _ in 0..3 {
+            let tx = VersionedTransaction::from(transfer_tx(&payer));
+            let bytes = bincode::serialize(&tx).unwrap();
+            let expected = SanitizedTransaction::try_create(
+                tx.clone(),
+                MessageHash::Compute,
+                None,
+                SimpleAddressLoader::Disabled,
+                &reserved_account_keys,
+            )
+            .unwrap();
+
+            let from_tx = pool
+                .try_create(
+                    tx,
+                    MessageHash::Compute,
+                    None,
+                    SimpleAddressLoader::Disabled,
+                    &reserved_account_keys,
+                )
+                .unwrap();
+            assert_eq!(from_tx, expected);
+
+            let from_bytes = pool
+                .try_create_from_bytes(
+                    &bytes,
+                    None,
+                    SimpleAddressLoader::Disabled,
+                    &reserved_account_keys,
+                )
+                .unwrap();
+            assert_eq!(from_bytes, expected);
+
+            pool.recycle(from_tx);
+            pool.recycle(from_bytes);
+        }
+        assert!(!pool.signatures.is_empty());
+        assert!(pool.signatures.iter().all(Vec::is_empty));
+    }
+
+    #[test]
+    fn test_pool_rejects_invalid_bytes() {
+        let payer = Keypair::new();
+        let mut bytes = bincode::serialize(&transfer_tx(&payer)).unwrap();
+        bytes.pop();
+        let mut pool = SanitizedTransactionPool::new();
+        assert!(pool
+            .try_create_from_bytes(
+                &bytes,
+                None,
+                SimpleAddressLoader::Disabled,
+                &HashSet::default(),
+            )
+            .is_err());
+    }
+}
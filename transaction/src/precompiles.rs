@@ -0,0 +1,247 @@
+//! Verification of the signatures checked by precompiled programs.
+//!
+//! The ed25519, secp256k1 and secp256r1 programs do not run as regular
+//! programs: the runtime verifies the signatures described by their
+//! instruction data before executing the transaction, and rejects it if any of
+//! them is invalid. [`SanitizedTransaction::verify_precompiles`] performs the
+//! same checks, so that a transaction can be rejected before it is forwarded.
+//!
+//! Signatures are verified by the `verify` functions of the precompile program
+//! crates. The secp256r1 program crate relies on openssl, which is not
+//! available on wasm, so there secp256r1 instructions are left for the runtime
+//! to verify.
+#![cfg(feature = "precompiles")]
+
+use {
+    crate::sanitized::SanitizedTransaction,
+    solana_instruction::error::InstructionError,
+    solana_precompile_error::PrecompileError,
+    solana_sdk_ids::{ed25519_program, secp256k1_program, secp256r1_program},
+    solana_transaction_error::{TransactionError, TransactionResult as Result},
+};
+
+/// Feature gates that change how precompile instructions are verified.
+///
+/// The default matches a cluster where none of these features are active.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrecompileFeatures {
+    /// Verify ed25519 signatures with `verify_strict`, which rejects small
+    /// order public keys and non-canonical signatures.
+    pub ed25519_verify_strict: bool,
+    /// Treat the secp256r1 program as a precompile. When disabled, its
+    /// instructions are not verified.
+    pub secp256r1_enabled: bool,
+}
+
+impl PrecompileFeatures {
+    /// Features of a cluster where every precompile feature is active.
+    pub fn all_enabled() -> Self {
+        Self {
+            ed25519_verify_strict: true,
+            secp256r1_enabled: true,
+        }
+    }
+}
+
+impl SanitizedTransaction {
+    /// Verify the signatures of every precompile instruction, as the runtime
+    /// does before executing the transaction.
+    ///
+    /// A failing instruction is reported as
+    /// [`TransactionError::InstructionError`] holding the
+    /// [`PrecompileError`] as a custom error code.
+    pub fn verify_precompiles(&self, features: &PrecompileFeatures) -> Result<()> {
+        let message = self.message();
+        let instruction_datas: Vec<_> = message
+            .instructions()
+            .iter()
+            .map(|instruction| instruction.data.as_slice())
+            .collect();
+        for (index, (program_id, instruction)) in message.program_instructions_iter().enumerate() {
+            let result = if ed25519_program::check_id(program_id) {
+                solana_ed25519_program::verify(
+                    &instruction.data,
+                    &instruction_datas,
+                    features.ed25519_verify_strict,
+                )
+            } else if secp256k1_program::check_id(program_id) {
+                solana_secp256k1_program::verify(&instruction.data, &instruction_datas)
+            } else if features.secp256r1_enabled && secp256r1_program::check_id(program_id) {
+                verify_secp256r1(&instruction.data, &instruction_datas)
+            } else {
+                continue;
+            };
+            result.map_err(|err| {
+                // Sanitized messages have at most 64 instructions
+                TransactionError::InstructionError(
+                    u8::try_from(index).unwrap_or(u8::MAX),
+                    InstructionError::Custom(err as u32),
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn verify_secp256r1(
+    data: &[u8],
+    instruction_datas: &[&[u8]],
+) -> std::result::Result<(), PrecompileError> {
+    solana_secp256r1_program::verify(data, instruction_datas)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn verify_secp256r1(
+    _data: &[u8],
+    _instruction_datas: &[&[u8]],
+) -> std::result::Result<(), PrecompileError> {
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::Transaction, solana_hash::Hash, solana_instruction::Instruction,
+        solana_keypair::Keypair, solana_pubkey::Pubkey, solana_signer::Signer,
+    };
+
+    fn sanitized_tx(instructions: &[Instruction]) -> SanitizedTransaction {
+        let payer = Keypair::new();
+        SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+            instructions,
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        ))
+    }
+
+    fn ed25519_instruction(keypair: &Keypair, message: &[u8]) -> Instruction {
+        let signature = keypair.sign_message(message);
+        solana_ed25519_program::new_ed25519_instruction_with_signature(
+            message,
+            signature.as_array(),
+            &keypair.pubkey().to_bytes(),
+        )
+    }
+
+    fn custom_error(index: u8, err: PrecompileError) -> TransactionError {
+        TransactionError::InstructionError(index, InstructionError::Custom(err as u32))
+    }
+
+    #[test]
+    fn test_verify_ed25519() {
+        let keypair = Keypair::new();
+        let features = PrecompileFeatures::all_enabled();
+        let tx = sanitized_tx(&[ed25519_instruction(&keypair, b"hello")]);
+        assert_eq!(tx.verify_precompiles(&features), Ok(()));
+
+        let mut instruction = ed25519_instruction(&keypair, b"hello");
+        *instruction.data.last_mut().unwrap() ^= 1;
+        let noop = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let tx = sanitized_tx(&[noop, instruction]);
+        assert_eq!(
+            tx.verify_precompiles(&features),
+            Err(custom_error(1, PrecompileError::InvalidSignature))
+        );
+    }
+
+    #[test]
+    fn test_verify_ed25519_invalid_offsets() {
+        let keypair = Keypair::new();
+        let mut instruction = ed25519_instruction(&keypair, b"hello");
+        // Point the signature at an instruction that does not exist.
+        instruction.data[4..6].copy_from_slice(&3u16.to_le_bytes());
+        let tx = sanitized_tx(&[instruction]);
+        assert_eq!(
+            tx.verify_precompiles(&PrecompileFeatures::default()),
+            Err(custom_error(0, PrecompileError::InvalidDataOffsets))
+        );
+
+        let instruction =
+            Instruction::new_with_bytes(solana_sdk_ids::ed25519_program::id(), &[1], vec![]);
+        assert_eq!(
+            sanitized_tx(&[instruction]).verify_precompiles(&PrecompileFeatures::default()),
+            Err(custom_error(0, PrecompileError::InvalidInstructionDataSize))
+        );
+    }
+
+    #[test]
+    fn test_verify_secp256k1() {
+        let secret_key = libsecp256k1::SecretKey::parse(&[7; 32]).unwrap();
+        let pubkey = libsecp256k1::PublicKey::from_secret_key(&secret_key);
+        let eth_address = solana_secp256k1_program::eth_address_from_pubkey(
+            &pubkey.serialize()[1..].try_into().unwrap(),
+        );
+        let message = b"hello";
+        let (signature, recovery_id) =
+            solana_secp256k1_program::sign_message(&secret_key.serialize(), message).unwrap();
+        let instruction = solana_secp256k1_program::new_secp256k1_instruction_with_signature(
+            message,
+            &signature,
+            recovery_id,
+            &eth_address,
+        );
+        let features = PrecompileFeatures::default();
+        assert_eq!(
+            sanitized_tx(std::slice::from_ref(&instruction)).verify_precompiles(&features),
+            Ok(())
+        );
+
+        let mut instruction = instruction;
+        *instruction.data.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            sanitized_tx(&[instruction]).verify_precompiles(&features),
+            Err(custom_error(0, PrecompileError::InvalidSignature))
+        );
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    #[test]
+    fn test_verify_secp256r1() {
+        use openssl::{
+            bn::BigNumContext,
+            ec::{EcGroup, EcKey, PointConversionForm},
+            nid::Nid,
+        };
+
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let secret_key = EcKey::generate(&group).unwrap();
+        let mut context = BigNumContext::new().unwrap();
+        let pubkey: [u8; 33] = secret_key
+            .public_key()
+            .to_bytes(&group, PointConversionForm::COMPRESSED, &mut context)
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let message = b"hello";
+        let signature = solana_secp256r1_program::sign_message(
+            message,
+            &secret_key.private_key_to_der().unwrap(),
+        )
+        .unwrap();
+        let mut instruction = solana_secp256r1_program::new_secp256r1_instruction_with_signature(
+            message, &signature, &pubkey,
+        );
+        *instruction.data.last_mut().unwrap() ^= 1;
+        let tx = sanitized_tx(&[instruction]);
+
+        // Without the feature, secp256r1 instructions are not verified.
+        assert_eq!(
+            tx.verify_precompiles(&PrecompileFeatures::default()),
+            Ok(())
+        );
+        assert_eq!(
+            tx.verify_precompiles(&PrecompileFeatures::all_enabled()),
+            Err(custom_error(0, PrecompileError::InvalidSignature))
+        );
+
+        let instruction = solana_secp256r1_program::new_secp256r1_instruction_with_signature(
+            message, &signature, &pubkey,
+        );
+        assert_eq!(
+            sanitized_tx(&[instruction]).verify_precompiles(&PrecompileFeatures::all_enabled()),
+            Ok(())
+        );
+    }
+}
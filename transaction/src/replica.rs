@@ -0,0 +1,198 @@
+//! Transactions in the form notified to geyser plugins.
+//!
+//! [`ReplicaTransactionInfo`] carries what a plugin needs to index a
+//! transaction without access to the bank: its first signature, whether it
+//! is a simple vote, its message and signatures, and the addresses that were
+//! loaded from lookup tables to execute it. It converts from a
+//! [`SanitizedTransaction`] on the validator side and back into one on the
+//! plugin side.
+
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+#[cfg(feature = "blake3")]
+use {
+    crate::sanitized::MessageHash, solana_message::SimpleAddressLoader, solana_pubkey::Pubkey,
+    std::collections::HashSet,
+};
+use {
+    crate::{
+        sanitized::SanitizedTransaction,
+        simple_vote_transaction_checker::is_simple_vote_transaction,
+        versioned::{sanitized::SanitizedVersionedTransaction, VersionedTransaction},
+    },
+    solana_message::{v0::LoadedAddresses, VersionedMessage},
+    solana_signature::Signature,
+    solana_transaction_error::TransactionResult as Result,
+};
+
+/// A transaction as notified to geyser plugins.
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplicaTransactionInfo {
+    /// The first signature of the transaction, which identifies it.
+    pub signature: Signature,
+    /// Whether the transaction is a simple vote transaction.
+    pub is_vote: bool,
+    pub signatures: Vec<Signature>,
+    pub message: VersionedMessage,
+    /// Addresses loaded from the lookup tables of a v0 message. Empty for
+    /// legacy messages.
+    pub loaded_addresses: LoadedAddresses,
+}
+
+impl ReplicaTransactionInfo {
+    /// Sanitize `tx` and pair it with the addresses loaded for its lookup
+    /// tables. A transaction without signatures fails sanitization.
+    pub fn try_from_versioned_transaction(
+        tx: VersionedTransaction,
+        loaded_addresses: LoadedAddresses,
+    ) -> Result<Self> {
+        let tx = SanitizedVersionedTransaction::try_from(tx)?;
+        let is_vote = is_simple_vote_transaction(&tx);
+        let signatures = tx.signatures;
+        Ok(Self {
+            signature: signatures[0],
+            is_vote,
+            signatures,
+            message: tx.message.message,
+            loaded_addresses,
+        })
+    }
+
+    /// Return the transaction without the loaded addresses.
+    pub fn to_versioned_transaction(&self) -> VersionedTransaction {
+        VersionedTransaction {
+            signatures: self.signatures.clone(),
+            message: self.message.clone(),
+        }
+    }
+
+    /// Sanitize the transaction again, resolving its lookup tables to the
+    /// recorded loaded addresses.
+    #[cfg(feature = "blake3")]
+    pub fn try_into_sanitized_transaction(
+        self,
+        reserved_account_keys: &HashSet<Pubkey>,
+    ) -> Result<SanitizedTransaction> {
+        SanitizedTransaction::try_create(
+            VersionedTransaction {
+                signatures: self.signatures,
+                message: self.message,
+            },
+            MessageHash::Compute,
+            Some(self.is_vote),
+            SimpleAddressLoader::Enabled(self.loaded_addresses),
+            reserved_account_keys,
+        )
+    }
+}
+
+impl From<&SanitizedTransaction> for ReplicaTransactionInfo {
+    fn from(tx: &SanitizedTransaction) -> Self {
+        let VersionedTransaction {
+            signatures,
+            message,
+        } = tx.to_versioned_transaction();
+        Self {
+            signature: *tx.signature(),
+            is_vote: tx.is_simple_vote_transaction(),
+            signatures,
+            message,
+            loaded_addresses: tx.get_loaded_addresses(),
+        }
+    }
+}
+
+impl From<ReplicaTransactionInfo> for VersionedTransaction {
+    fn from(info: ReplicaTransactionInfo) -> Self {
+        Self {
+            signatures: info.signatures,
+            message: info.message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_message::{
+            compiled_instruction::CompiledInstruction,
+            v0::{self, MessageAddressTableLookup},
+            MessageHeader,
+        },
+    };
+
+    fn v0_transaction() -> VersionedTransaction {
+        VersionedTransaction {
+            signatures: vec![Signature::from([7; 64])],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![CompiledInstruction::new_from_raw_parts(
+                    1,
+                    vec![],
+                    vec![0, 2, 3],
+                )],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1],
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_replica_transaction_info_round_trip() {
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![Pubkey::new_unique()],
+            readonly: vec![Pubkey::new_unique()],
+        };
+        let sanitized = SanitizedTransaction::try_create(
+            v0_transaction(),
+            MessageHash::Compute,
+            None,
+            SimpleAddressLoader::Enabled(loaded_addresses.clone()),
+            &HashSet::new(),
+        )
+        .unwrap();
+
+        let info = ReplicaTransactionInfo::from(&sanitized);
+        assert_eq!(info.signature, Signature::from([7; 64]));
+        assert!(!info.is_vote);
+        assert_eq!(info.loaded_addresses, loaded_addresses);
+        assert_eq!(
+            ReplicaTransactionInfo::try_from_versioned_transaction(
+                info.to_versioned_transaction(),
+                loaded_addresses
+            ),
+            Ok(info.clone())
+        );
+        assert_eq!(
+            info.try_into_sanitized_transaction(&HashSet::new()),
+            Ok(sanitized)
+        );
+    }
+
+    #[test]
+    fn test_replica_transaction_info_requires_signature() {
+        let mut tx = v0_transaction();
+        tx.signatures.clear();
+        assert!(ReplicaTransactionInfo::try_from_versioned_transaction(
+            tx,
+            LoadedAddresses::default()
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,418 @@
+//! JSON representation of transactions as returned by the Solana RPC.
+//!
+//! [`UiTransaction`] mirrors the `json` transaction encoding of RPC methods
+//! such as `getTransaction`: signatures, blockhash and instruction data are
+//! base58 strings, and each account key is annotated with whether it signs,
+//! whether it is writable and whether it was loaded from an address lookup
+//! table. Instructions keep their compiled form and refer to accounts by
+//! index.
+#![cfg(feature = "rpc-json")]
+
+use {
+    crate::{sanitized::SanitizedTransaction, versioned::VersionedTransaction},
+    serde_derive::{Deserialize, Serialize},
+    solana_hash::Hash,
+    solana_message::{
+        compiled_instruction::CompiledInstruction,
+        legacy,
+        v0::{self, LoadedAddresses, MessageAddressTableLookup},
+        MessageHeader, SanitizedMessage, VersionedMessage,
+    },
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    std::{fmt, str::FromStr},
+};
+
+/// A transaction in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransaction {
+    /// Base58 encoded signatures
+    pub signatures: Vec<String>,
+    pub message: UiMessage,
+}
+
+/// A message in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiMessage {
+    pub header: MessageHeader,
+    pub account_keys: Vec<UiAccountKey>,
+    /// Base58 encoded blockhash
+    pub recent_blockhash: String,
+    pub instructions: Vec<UiCompiledInstruction>,
+    /// Address table lookups, present for v0 messages only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_table_lookups: Option<Vec<UiAddressTableLookup>>,
+}
+
+/// Where an account key of a message comes from.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UiAccountKeySource {
+    /// Stored in the message itself
+    Transaction,
+    /// Loaded from an address lookup table
+    LookupTable,
+}
+
+/// An account key annotated with its role in the message.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiAccountKey {
+    /// Base58 encoded address
+    pub pubkey: String,
+    pub writable: bool,
+    pub signer: bool,
+    pub source: UiAccountKeySource,
+}
+
+/// A compiled instruction in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiCompiledInstruction {
+    pub program_id_index: u8,
+    pub accounts: Vec<u8>,
+    /// Base58 encoded instruction data
+    pub data: String,
+}
+
+/// An address table lookup in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiAddressTableLookup {
+    /// Base58 encoded address of the lookup table
+    pub account_key: String,
+    pub writable_indexes: Vec<u8>,
+    pub readonly_indexes: Vec<u8>,
+}
+
+/// Errors that may be returned when converting a [`UiTransaction`] back to a
+/// transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseUiTransactionError {
+    InvalidSignature(String),
+    InvalidPubkey(String),
+    InvalidBlockhash(String),
+    InvalidInstructionData(usize),
+    InvalidReturnData(String),
+    MissingStatusMeta,
+}
+
+impl std::error::Error for ParseUiTransactionError {}
+
+impl fmt::Display for ParseUiTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidSignature(s) => write!(f, "invalid signature: {s}"),
+            Self::InvalidPubkey(s) => write!(f, "invalid pubkey: {s}"),
+            Self::InvalidBlockhash(s) => write!(f, "invalid blockhash: {s}"),
+            Self::InvalidInstructionData(index) => {
+                write!(f, "invalid data for instruction {index}")
+            }
+            Self::InvalidReturnData(s) => write!(f, "invalid return data: {s}"),
+            Self::MissingStatusMeta => f.write_str("missing transaction status meta"),
+        }
+    }
+}
+
+impl From<&CompiledInstruction> for UiCompiledInstruction {
+    fn from(instruction: &CompiledInstruction) -> Self {
+        Self {
+            program_id_index: instruction.program_id_index,
+            accounts: instruction.accounts.clone(),
+            data: bs58::encode(&instruction.data).into_string(),
+        }
+    }
+}
+
+impl From<&MessageAddressTableLookup> for UiAddressTableLookup {
+    fn from(lookup: &MessageAddressTableLookup) -> Self {
+        Self {
+            account_key: lookup.account_key.to_string(),
+            writable_indexes: lookup.writable_indexes.clone(),
+            readonly_indexes: lookup.readonly_indexes.clone(),
+        }
+    }
+}
+
+impl From<&VersionedTransaction> for UiTransaction {
+    /// Convert without loaded addresses. Writability of static keys is
+    /// reported as requested by the message, before any demotion.
+    fn from(tx: &VersionedTransaction) -> Self {
+        let message = &tx.message;
+        let account_keys = message
+            .static_account_keys()
+            .iter()
+            .enumerate()
+            .map(|(index, pubkey)| UiAccountKey {
+                pubkey: pubkey.to_string(),
+                writable: message.is_maybe_writable(index, None),
+                signer: message.is_signer(index),
+                source: UiAccountKeySource::Transaction,
+            })
+            .collect();
+        Self {
+            signatures: tx.signatures.iter().map(ToString::to_string).collect(),
+            message: UiMessage {
+                header: *message.header(),
+                account_keys,
+                recent_blockhash: message.recent_blockhash().to_string(),
+                instructions: message.instructions().iter().map(Into::into).collect(),
+                address_table_lookups: message
+                    .address_table_lookups()
+                    .map(|lookups| lookups.iter().map(Into::into).collect()),
+            },
+        }
+    }
+}
+
+impl From<&SanitizedTransaction> for UiTransaction {
+    /// Convert including loaded addresses, with writability as resolved by
+    /// the sanitized message.
+    fn from(tx: &SanitizedTransaction) -> Self {
+        let message = tx.message();
+        let num_static_keys = message.static_account_keys().len();
+        let account_keys = message
+            .account_keys()
+            .iter()
+            .enumerate()
+            .map(|(index, pubkey)| UiAccountKey {
+                pubkey: pubkey.to_string(),
+                writable: message.is_writable(index),
+                signer: message.is_signer(index),
+                source: if index < num_static_keys {
+                    UiAccountKeySource::Transaction
+                } else {
+                    UiAccountKeySource::LookupTable
+                },
+            })
+            .collect();
+        let address_table_lookups = match message {
+            SanitizedMessage::Legacy(_) => None,
+            SanitizedMessage::V0(_) => Some(
+                message
+                    .message_address_table_lookups()
+                    .iter()
+                    .map(Into::into)
+                    .collect(),
+            ),
+        };
+        Self {
+            signatures: tx.signatures().iter().map(ToString::to_string).collect(),
+            message: UiMessage {
+                header: *message.header(),
+                account_keys,
+                recent_blockhash: message.recent_blockhash().to_string(),
+                instructions: message.instructions().iter().map(Into::into).collect(),
+                address_table_lookups,
+            },
+        }
+    }
+}
+
+impl UiTransaction {
+    /// Return the addresses that were loaded from lookup tables, as recorded
+    /// in the account keys of the message.
+    pub fn loaded_addresses(&self) -> Result<LoadedAddresses, ParseUiTransactionError> {
+        let mut loaded_addresses = LoadedAddresses::default();
+        for account_key in &self.message.account_keys {
+            if account_key.source == UiAccountKeySource::LookupTable {
+                let pubkey = parse_pubkey(&account_key.pubkey)?;
+                if account_key.writable {
+                    loaded_addresses.writable.push(pubkey);
+                } else {
+                    loaded_addresses.readonly.push(pubkey);
+                }
+            }
+        }
+        Ok(loaded_addresses)
+    }
+}
+
+impl TryFrom<&UiTransaction> for VersionedTransaction {
+    type Error = ParseUiTransactionError;
+
+    fn try_from(ui_tx: &UiTransaction) -> Result<Self, Self::Error> {
+        let signatures = ui_tx
+            .signatures
+            .iter()
+            .map(|s| {
+                Signature::from_str(s)
+                    .map_err(|_| ParseUiTransactionError::InvalidSignature(s.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let ui_message = &ui_tx.message;
+        let account_keys = ui_message
+            .account_keys
+            .iter()
+            .filter(|account_key| account_key.source == UiAccountKeySource::Transaction)
+            .map(|account_key| parse_pubkey(&account_key.pubkey))
+            .collect::<Result<_, _>>()?;
+        let recent_blockhash = Hash::from_str(&ui_message.recent_blockhash).map_err(|_| {
+            ParseUiTransactionError::InvalidBlockhash(ui_message.recent_blockhash.clone())
+        })?;
+        let instructions = ui_message
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(index, instruction)| {
+                let data = bs58::decode(&instruction.data)
+                    .into_vec()
+                    .map_err(|_| ParseUiTransactionError::InvalidInstructionData(index))?;
+                Ok(CompiledInstruction::new_from_raw_parts(
+                    instruction.program_id_index,
+                    data,
+                    instruction.accounts.clone(),
+                ))
+            })
+            .collect::<Result<_, _>>()?;
+
+        let message = match &ui_message.address_table_lookups {
+            None => VersionedMessage::Legacy(legacy::Message {
+                header: ui_message.header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+            }),
+            Some(lookups) => VersionedMessage::V0(v0::Message {
+                header: ui_message.header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+                address_table_lookups: lookups
+                    .iter()
+                    .map(|lookup| {
+                        Ok(MessageAddressTableLookup {
+                            account_key: parse_pubkey(&lookup.account_key)?,
+                            writable_indexes: lookup.writable_indexes.clone(),
+                            readonly_indexes: lookup.readonly_indexes.clone(),
+                        })
+                    })
+                    .collect::<Result<_, _>>()?,
+            }),
+        };
+
+        Ok(Self {
+            signatures,
+            message,
+        })
+    }
+}
+
+fn parse_pubkey(s: &str) -> Result<Pubkey, ParseUiTransactionError> {
+    Pubkey::from_str(s).map_err(|_| ParseUiTransactionError::InvalidPubkey(s.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_message::SimpleAddressLoader, std::collections::HashSet};
+
+    fn v0_transaction() -> VersionedTransaction {
+        VersionedTransaction {
+            signatures: vec![Signature::from([7; 64])],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![CompiledInstruction::new_from_raw_parts(
+                    1,
+                    vec![1, 2, 3],
+                    vec![0, 2, 3],
+                )],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1],
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_versioned_transaction_round_trip() {
+        let tx = v0_transaction();
+        let ui_tx = UiTransaction::from(&tx);
+        assert_eq!(ui_tx.message.account_keys.len(), 2);
+        assert!(ui_tx.message.account_keys[0].signer);
+        assert!(ui_tx.message.account_keys[0].writable);
+        assert!(!ui_tx.message.account_keys[1].writable);
+        assert_eq!(ui_tx.message.instructions[0].data, "Ldp");
+
+        let json = serde_json::to_string(&ui_tx).unwrap();
+        let decoded: UiTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, ui_tx);
+        assert_eq!(VersionedTransaction::try_from(&decoded).unwrap(), tx);
+
+        let legacy_tx = VersionedTransaction::from(crate::Transaction::new_with_payer(
+            &[],
+            Some(&Pubkey::new_unique()),
+        ));
+        let ui_tx = UiTransaction::from(&legacy_tx);
+        assert!(!serde_json::to_string(&ui_tx)
+            .unwrap()
+            .contains("addressTableLookups"));
+        assert_eq!(VersionedTransaction::try_from(&ui_tx).unwrap(), legacy_tx);
+    }
+
+    #[test]
+    fn test_sanitized_transaction_includes_loaded_addresses() {
+        let tx = v0_transaction();
+        let loaded_addresses = LoadedAddresses {
+            writable: vec![Pubkey::new_unique()],
+            readonly: vec![Pubkey::new_unique()],
+        };
+        let sanitized_tx = SanitizedTransaction::try_create(
+            tx.clone(),
+            Hash::new_unique(),
+            Some(false),
+            SimpleAddressLoader::Enabled(loaded_addresses.clone()),
+            &HashSet::default(),
+        )
+        .unwrap();
+
+        let ui_tx = UiTransaction::from(&sanitized_tx);
+        let sources: Vec<_> = ui_tx
+            .message
+            .account_keys
+            .iter()
+            .map(|account_key| (account_key.source, account_key.writable))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                (UiAccountKeySource::Transaction, true),
+                (UiAccountKeySource::Transaction, false),
+                (UiAccountKeySource::LookupTable, true),
+                (UiAccountKeySource::LookupTable, false),
+            ]
+        );
+        assert_eq!(ui_tx.loaded_addresses().unwrap(), loaded_addresses);
+        assert_eq!(VersionedTransaction::try_from(&ui_tx).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_invalid_ui_transaction() {
+        let mut ui_tx = UiTransaction::from(&v0_transaction());
+        ui_tx.message.instructions[0].data = "0OIl".to_string();
+        assert_eq!(
+            VersionedTransaction::try_from(&ui_tx),
+            Err(ParseUiTransactionError::InvalidInstructionData(0))
+        );
+        ui_tx.message.recent_blockhash = "bad".to_string();
+        assert_eq!(
+            VersionedTransaction::try_from(&ui_tx),
+            Err(ParseUiTransactionError::InvalidBlockhash("bad".to_string()))
+        );
+        ui_tx.signatures[0] = "bad".to_string();
+        assert_eq!(
+            VersionedTransaction::try_from(&ui_tx),
+            Err(ParseUiTransactionError::InvalidSignature("bad".to_string()))
+        );
+    }
+}
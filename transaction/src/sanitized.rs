@@ -1,29 +1,70 @@
+#[cfg(feature = "bincode")]
+use crate::{observer::observe_sanitize, unsigned::UnsignedTransaction};
+#[cfg(feature = "verify")]
+use crate::{
+    observer::observe_verify,
+    sig_verifier::{SigVerifier, SigVerifyItem},
+};
+#[cfg(feature = "bincode")]
+use solana_message::MessageHasher;
+#[cfg(feature = "blake3")]
+use {crate::Transaction, solana_message::Blake3MessageHasher, solana_sanitize::Sanitize};
 use {
-    crate::versioned::{sanitized::SanitizedVersionedTransaction, VersionedTransaction},
+    crate::{
+        observer::observe_account_locks,
+        versioned::{
+            sanitized::SanitizedVersionedTransaction, VersionedTransaction, VersionedTransactionRef,
+        },
+    },
     solana_hash::Hash,
     solana_message::{
-        legacy,
         v0::{self, LoadedAddresses},
-        AddressLoader, LegacyMessage, SanitizedMessage, SanitizedVersionedMessage,
-        VersionedMessage,
+        AddressLoader, ComputeBudgetDetails, LegacyMessage, SanitizedMessage,
+        SanitizedVersionedMessage, VersionedMessage,
     },
     solana_pubkey::Pubkey,
     solana_signature::Signature,
     solana_transaction_error::{TransactionError, TransactionResult as Result},
-    std::collections::HashSet,
+    std::{
+        collections::{HashMap, HashSet},
+        fmt,
+        ops::{BitAnd, BitOr},
+        sync::Arc,
+    },
+};
+#[cfg(feature = "serde")]
+use {
+    serde::{de, Deserializer, Serializer},
+    serde_derive::{Deserialize, Serialize},
+    solana_message::{legacy, AccountKeyPositions, ComputeBudgetDetailsCache},
+    std::borrow::Cow,
 };
-#[cfg(feature = "blake3")]
-use {crate::Transaction, solana_sanitize::Sanitize};
 
 /// Maximum number of accounts that a transaction may lock.
 /// 128 was chosen because it is the minimum number of accounts
 /// needed for the Neon EVM implementation.
 pub const MAX_TX_ACCOUNT_LOCKS: usize = 128;
 
+/// Number of transactions verified together in a single ed25519 batch by
+/// [`SanitizedTransaction::verify_batch`].
+#[cfg(feature = "verify-batch")]
+const VERIFY_BATCH_SHARD_SIZE: usize = 64;
+
 /// Sanitized transaction and the hash of its message
+///
+/// The message is shared between clones, so cloning a sanitized transaction
+/// does not copy its message.
+///
+/// # Serialization
+///
+/// The serde encoding keeps everything resolved during sanitization: the
+/// message hash, whether the transaction is a simple vote, the addresses
+/// loaded from lookup tables and which accounts are writable. Restoring a
+/// transaction therefore does not need an address loader or the reserved
+/// account keys that were used to create it.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct SanitizedTransaction {
-    message: SanitizedMessage,
+    message: Arc<SanitizedMessage>,
     message_hash: Hash,
     is_simple_vote_tx: bool,
     signatures: Vec<Signature>,
@@ -38,6 +79,81 @@ pub struct TransactionAccountLocks<'a> {
     pub writable: Vec<&'a Pubkey>,
 }
 
+/// Set of accounts that must be locked for safe transaction processing, as
+/// bitsets of indexes into the transaction's account keys
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct TransactionAccountLocksBitset {
+    /// Indexes of readonly account key locks
+    pub readonly: AccountIndexBitset,
+    /// Indexes of writable account key locks
+    pub writable: AccountIndexBitset,
+}
+
+/// Fixed-size set of account indexes, large enough for every index a
+/// sanitized message can have.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub struct AccountIndexBitset([u64; 4]);
+
+impl AccountIndexBitset {
+    /// Number of indexes the set can hold.
+    pub const CAPACITY: usize = 256;
+
+    /// Add `index` to the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index` is not less than [`Self::CAPACITY`].
+    pub fn insert(&mut self, index: usize) {
+        assert!(index < Self::CAPACITY, "account index out of range");
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    /// Returns true if `index` is in the set.
+    pub fn contains(&self, index: usize) -> bool {
+        index < Self::CAPACITY && self.0[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    /// Number of indexes in the set.
+    pub fn len(&self) -> usize {
+        self.0.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|word| *word == 0)
+    }
+
+    /// Returns true if both sets have an index in common.
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.0.iter().zip(other.0).any(|(a, b)| a & b != 0)
+    }
+
+    /// Iterate over the indexes in the set in increasing order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..Self::CAPACITY).filter(|index| self.contains(*index))
+    }
+
+    /// Return the underlying words, least significant index first.
+    pub fn as_words(&self) -> &[u64; 4] {
+        &self.0
+    }
+}
+
+impl BitAnd for AccountIndexBitset {
+    type Output = Self;
+
+    fn bitand(self, other: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] & other.0[i]))
+    }
+}
+
+impl BitOr for AccountIndexBitset {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        Self(std::array::from_fn(|i| self.0[i] | other.0[i]))
+    }
+}
+
 /// Type that represents whether the transaction message has been precomputed or
 /// not.
 pub enum MessageHash {
@@ -80,7 +196,7 @@ impl SanitizedTransaction {
         };
 
         Ok(Self {
-            message,
+            message: Arc::new(message),
             message_hash,
             is_simple_vote_tx,
             signatures,
@@ -98,25 +214,51 @@ impl SanitizedTransaction {
         address_loader: impl AddressLoader,
         reserved_account_keys: &HashSet<Pubkey>,
     ) -> Result<Self> {
-        let sanitized_versioned_tx = SanitizedVersionedTransaction::try_from(tx)?;
-        let is_simple_vote_tx = is_simple_vote_tx.unwrap_or_else(|| {
-            crate::simple_vote_transaction_checker::is_simple_vote_transaction(
-                &sanitized_versioned_tx,
-            )
-        });
-        let message_hash = match message_hash.into() {
-            MessageHash::Compute => sanitized_versioned_tx.message.message.hash(),
-            MessageHash::Precomputed(hash) => hash,
-        };
-        Self::try_new(
-            sanitized_versioned_tx,
+        Self::try_create_with_hasher(
+            tx,
             message_hash,
             is_simple_vote_tx,
             address_loader,
             reserved_account_keys,
+            &Blake3MessageHasher,
         )
     }
 
+    #[cfg(feature = "bincode")]
+    /// Like [`Self::try_create`], but computes the message hash, if it is not
+    /// precomputed, with `message_hasher`.
+    pub fn try_create_with_hasher(
+        tx: VersionedTransaction,
+        message_hash: impl Into<MessageHash>,
+        is_simple_vote_tx: Option<bool>,
+        address_loader: impl AddressLoader,
+        reserved_account_keys: &HashSet<Pubkey>,
+        message_hasher: &impl MessageHasher,
+    ) -> Result<Self> {
+        observe_sanitize(|| {
+            let sanitized_versioned_tx = SanitizedVersionedTransaction::try_from(tx)?;
+            let is_simple_vote_tx = is_simple_vote_tx.unwrap_or_else(|| {
+                crate::simple_vote_transaction_checker::is_simple_vote_transaction(
+                    &sanitized_versioned_tx,
+                )
+            });
+            let message_hash = match message_hash.into() {
+                MessageHash::Compute => sanitized_versioned_tx
+                    .message
+                    .message
+                    .hash_with(message_hasher),
+                MessageHash::Precomputed(hash) => hash,
+            };
+            Self::try_new(
+                sanitized_versioned_tx,
+                message_hash,
+                is_simple_vote_tx,
+                address_loader,
+                reserved_account_keys,
+            )
+        })
+    }
+
     /// Create a sanitized transaction from a legacy transaction
     #[cfg(feature = "blake3")]
     pub fn try_from_legacy_transaction(
@@ -127,10 +269,10 @@ impl SanitizedTransaction {
 
         Ok(Self {
             message_hash: tx.message.hash(),
-            message: SanitizedMessage::Legacy(LegacyMessage::new(
+            message: Arc::new(SanitizedMessage::Legacy(LegacyMessage::new(
                 tx.message,
                 reserved_account_keys,
-            )),
+            ))),
             is_simple_vote_tx: false,
             signatures: tx.signatures,
         })
@@ -158,13 +300,57 @@ impl SanitizedTransaction {
         )?;
 
         Ok(Self {
-            message,
+            message: Arc::new(message),
             message_hash,
             signatures,
             is_simple_vote_tx,
         })
     }
 
+    /// Create a sanitized transaction from a message resolved by an earlier
+    /// sanitization, such as one restored from a cache. Checks that the
+    /// message sanitizes, that its loaded addresses match its address table
+    /// lookups and that every account has a writable flag.
+    pub(crate) fn try_from_resolved_fields(
+        message: SanitizedMessage,
+        message_hash: Hash,
+        is_simple_vote_tx: bool,
+        signatures: Vec<Signature>,
+    ) -> std::result::Result<Self, String> {
+        let is_writable_account_cache = match &message {
+            SanitizedMessage::Legacy(message) => {
+                solana_sanitize::Sanitize::sanitize(message.message.as_ref())
+                    .map_err(|err| err.to_string())?;
+                &message.is_writable_account_cache
+            }
+            SanitizedMessage::V0(message) => {
+                message.message.sanitize().map_err(|err| err.to_string())?;
+                let (num_writable, num_readonly) = message
+                    .message
+                    .address_table_lookups
+                    .iter()
+                    .fold((0usize, 0usize), |(writable, readonly), lookup| {
+                        (
+                            writable.saturating_add(lookup.writable_indexes.len()),
+                            readonly.saturating_add(lookup.readonly_indexes.len()),
+                        )
+                    });
+                if message.loaded_addresses.writable.len() != num_writable
+                    || message.loaded_addresses.readonly.len() != num_readonly
+                {
+                    return Err("loaded addresses do not match address table lookups".to_string());
+                }
+                &message.is_writable_account_cache
+            }
+        };
+        if is_writable_account_cache.len() != message.account_keys().len() {
+            return Err("writable account cache does not match account keys".to_string());
+        }
+
+        Self::try_new_from_fields(message, message_hash, is_simple_vote_tx, signatures)
+            .map_err(|err| err.to_string())
+    }
+
     /// Return the first signature for this transaction.
     ///
     /// Notes:
@@ -181,6 +367,35 @@ impl SanitizedTransaction {
         &self.signatures
     }
 
+    /// Return the signers of this transaction mapped to the index of their
+    /// signature. If a signer key appears more than once, its first position
+    /// is used.
+    pub fn signer_positions(&self) -> HashMap<Pubkey, usize> {
+        let mut positions = HashMap::with_capacity(self.signatures.len());
+        for (index, pubkey) in self.signer_keys().iter().enumerate() {
+            positions.entry(*pubkey).or_insert(index);
+        }
+        positions
+    }
+
+    /// Return the signature of `pubkey`, if it is a signer of this
+    /// transaction.
+    pub fn signature_for(&self, pubkey: &Pubkey) -> Option<&Signature> {
+        self.signer_keys()
+            .iter()
+            .position(|signer| signer == pubkey)
+            .and_then(|index| self.signatures.get(index))
+    }
+
+    /// The static account keys which must sign this transaction, in
+    /// signature order.
+    fn signer_keys(&self) -> &[Pubkey] {
+        let static_account_keys = self.message.static_account_keys();
+        let num_signers = usize::from(self.message.header().num_required_signatures)
+            .min(static_account_keys.len());
+        &static_account_keys[..num_signers]
+    }
+
     /// Return the signed message
     pub fn message(&self) -> &SanitizedMessage {
         &self.message
@@ -198,20 +413,82 @@ impl SanitizedTransaction {
 
     /// Convert this sanitized transaction into a versioned transaction for
     /// recording in the ledger.
+    ///
+    /// This clones the signatures and message. Use
+    /// [`Self::as_versioned_transaction_ref`] to serialize the transaction
+    /// without copying it.
     pub fn to_versioned_transaction(&self) -> VersionedTransaction {
-        let signatures = self.signatures.clone();
-        match &self.message {
-            SanitizedMessage::V0(sanitized_msg) => VersionedTransaction {
-                signatures,
-                message: VersionedMessage::V0(v0::Message::clone(&sanitized_msg.message)),
-            },
-            SanitizedMessage::Legacy(legacy_message) => VersionedTransaction {
-                signatures,
-                message: VersionedMessage::Legacy(legacy::Message::clone(&legacy_message.message)),
-            },
+        self.as_versioned_transaction_ref().into()
+    }
+
+    /// Borrow this sanitized transaction as a versioned transaction for
+    /// recording in the ledger.
+    ///
+    /// The returned view shares the signatures and message of this
+    /// transaction and serializes to the same bytes as the owned
+    /// [`VersionedTransaction`], so nothing is copied.
+    pub fn as_versioned_transaction_ref(&self) -> VersionedTransactionRef<'_> {
+        VersionedTransactionRef {
+            signatures: &self.signatures,
+            message: self.message.as_versioned_message(),
         }
     }
 
+    /// Convert this sanitized transaction into a versioned transaction for
+    /// recording in the ledger.
+    ///
+    /// The message is moved rather than copied unless it is still shared with
+    /// a clone of this transaction.
+    pub fn into_versioned_transaction(self) -> VersionedTransaction {
+        let message = match Arc::try_unwrap(self.message) {
+            Ok(message) => message,
+            Err(message) => SanitizedMessage::clone(&message),
+        };
+        let message = match message {
+            SanitizedMessage::V0(sanitized_msg) => {
+                VersionedMessage::V0(sanitized_msg.message.into_owned())
+            }
+            SanitizedMessage::Legacy(legacy_message) => {
+                VersionedMessage::Legacy(legacy_message.message.into_owned())
+            }
+        };
+        VersionedTransaction {
+            signatures: self.signatures,
+            message,
+        }
+    }
+
+    /// Drop the signatures of this transaction, keeping its message and the
+    /// keys required to sign it so that it can be signed again.
+    #[cfg(feature = "bincode")]
+    pub fn without_signatures(&self) -> UnsignedTransaction {
+        UnsignedTransaction::new(self.message.as_versioned_message().into())
+    }
+
+    /// Return the compute budget details of this transaction, cached by its
+    /// message. See [`SanitizedMessage::get_compute_budget_details`].
+    pub fn get_compute_budget_details(&self) -> Result<ComputeBudgetDetails> {
+        self.message.get_compute_budget_details()
+    }
+
+    /// Price of a compute unit in micro-lamports, 0 if the transaction does
+    /// not set one.
+    pub fn compute_unit_price(&self) -> Result<u64> {
+        Ok(self.get_compute_budget_details()?.compute_unit_price())
+    }
+
+    /// Maximum number of compute units the transaction may consume.
+    pub fn compute_unit_limit(&self) -> Result<u32> {
+        Ok(self.get_compute_budget_details()?.compute_unit_limit())
+    }
+
+    /// Maximum number of bytes of account data the transaction may load.
+    pub fn loaded_accounts_data_size_limit(&self) -> Result<u32> {
+        Ok(self
+            .get_compute_budget_details()?
+            .loaded_accounts_data_size_limit())
+    }
+
     /// Validate and return the account keys locked by this transaction
     pub fn get_account_locks(
         &self,
@@ -224,36 +501,66 @@ impl SanitizedTransaction {
     /// Return the list of accounts that must be locked during processing this transaction.
     pub fn get_account_locks_unchecked(&self) -> TransactionAccountLocks<'_> {
         let message = &self.message;
-        let account_keys = message.account_keys();
         let num_readonly_accounts = message.num_readonly_accounts();
-        let num_writable_accounts = account_keys.len().saturating_sub(num_readonly_accounts);
+        let num_writable_accounts = message
+            .account_keys()
+            .len()
+            .saturating_sub(num_readonly_accounts);
 
         let mut account_locks = TransactionAccountLocks {
             writable: Vec::with_capacity(num_writable_accounts),
             readonly: Vec::with_capacity(num_readonly_accounts),
         };
+        self.get_account_locks_into(&mut account_locks);
+        account_locks
+    }
 
-        for (i, key) in account_keys.iter().enumerate() {
+    /// Write the accounts that must be locked during processing this
+    /// transaction into `account_locks`, replacing its previous contents.
+    ///
+    /// Reusing the same `account_locks` across transactions avoids allocating
+    /// new lists for each of them once their capacity is large enough.
+    pub fn get_account_locks_into<'a>(&'a self, account_locks: &mut TransactionAccountLocks<'a>) {
+        let message = &self.message;
+        account_locks.writable.clear();
+        account_locks.readonly.clear();
+        for (i, key) in message.account_keys().iter().enumerate() {
             if message.is_writable(i) {
                 account_locks.writable.push(key);
             } else {
                 account_locks.readonly.push(key);
             }
         }
+    }
 
-        account_locks
+    /// Validate and return the indexes of the account keys locked by this
+    /// transaction
+    pub fn get_account_locks_bitset(
+        &self,
+        tx_account_lock_limit: usize,
+    ) -> Result<TransactionAccountLocksBitset> {
+        Self::validate_account_locks(self.message(), tx_account_lock_limit)?;
+        let message = &self.message;
+        let mut account_locks = TransactionAccountLocksBitset::default();
+        for index in 0..message.account_keys().len() {
+            if message.is_writable(index) {
+                account_locks.writable.insert(index);
+            } else {
+                account_locks.readonly.insert(index);
+            }
+        }
+        Ok(account_locks)
     }
 
     /// Return the list of addresses loaded from on-chain address lookup tables
     pub fn get_loaded_addresses(&self) -> LoadedAddresses {
-        match &self.message {
+        match self.message.as_ref() {
             SanitizedMessage::Legacy(_) => LoadedAddresses::default(),
             SanitizedMessage::V0(message) => LoadedAddresses::clone(&message.loaded_addresses),
         }
     }
 
     /// If the transaction uses a durable nonce, return the pubkey of the nonce account
-    #[cfg(feature = "bincode")]
     pub fn get_durable_nonce(&self) -> Option<&Pubkey> {
         self.message.get_durable_nonce()
     }
@@ -261,7 +568,7 @@ impl SanitizedTransaction {
     #[cfg(feature = "verify")]
     /// Return the serialized message data to sign.
     fn message_data(&self) -> Vec<u8> {
-        match &self.message {
+        match self.message.as_ref() {
             SanitizedMessage::Legacy(legacy_message) => legacy_message.message.serialize(),
             SanitizedMessage::V0(loaded_msg) => loaded_msg.message.serialize(),
         }
@@ -270,18 +577,146 @@ impl SanitizedTransaction {
     #[cfg(feature = "verify")]
     /// Verify the transaction signatures
     pub fn verify(&self) -> Result<()> {
-        let message_bytes = self.message_data();
-        if self
-            .signatures
+        observe_verify(|| {
+            let message_bytes = self.message_data();
+            if self
+                .signatures
+                .iter()
+                .zip(self.message.account_keys().iter())
+                .map(|(signature, pubkey)| signature.verify(pubkey.as_ref(), &message_bytes))
+                .any(|verified| !verified)
+            {
+                Err(TransactionError::SignatureFailure)
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    #[cfg(feature = "verify")]
+    /// Verify the transaction signatures with the given backend
+    pub fn verify_with(&self, verifier: &impl SigVerifier) -> Result<()> {
+        observe_verify(|| {
+            let message_bytes = self.message_data();
+            let items: Vec<_> = self.sig_verify_items(&message_bytes).collect();
+            let results = verifier.verify_batch(&items);
+            if results.len() != items.len() || results.contains(&false) {
+                Err(TransactionError::SignatureFailure)
+            } else {
+                Ok(())
+            }
+        })
+    }
+
+    #[cfg(feature = "verify")]
+    /// Verify the signatures of many transactions with a single call to
+    /// [`SigVerifier::verify_batch`], returning one result per transaction
+    /// in input order.
+    ///
+    /// If the backend doesn't return exactly one result per signature, the
+    /// results can't be attributed and every transaction fails.
+    pub fn verify_batch_with(txs: &[Self], verifier: &impl SigVerifier) -> Vec<Result<()>> {
+        let message_datas: Vec<Vec<u8>> = txs.iter().map(Self::message_data).collect();
+        let items: Vec<_> = txs
+            .iter()
+            .zip(&message_datas)
+            .flat_map(|(tx, message_data)| tx.sig_verify_items(message_data))
+            .collect();
+        let results = verifier.verify_batch(&items);
+        if results.len() != items.len() {
+            return vec![Err(TransactionError::SignatureFailure); txs.len()];
+        }
+        let mut results = results.into_iter();
+        txs.iter()
+            .map(|tx| {
+                let num_items = tx.signatures.len().min(tx.message.account_keys().len());
+                // Consume every result of this transaction, even after a failure.
+                let num_failed = results
+                    .by_ref()
+                    .take(num_items)
+                    .filter(|verified| !verified)
+                    .count();
+                if num_failed == 0 {
+                    Ok(())
+                } else {
+                    Err(TransactionError::SignatureFailure)
+                }
+            })
+            .collect()
+    }
+
+    #[cfg(feature = "verify")]
+    fn sig_verify_items<'a>(
+        &'a self,
+        message_data: &'a [u8],
+    ) -> impl Iterator<Item = SigVerifyItem<'a>> + 'a {
+        self.signatures
             .iter()
             .zip(self.message.account_keys().iter())
-            .map(|(signature, pubkey)| signature.verify(pubkey.as_ref(), &message_bytes))
-            .any(|verified| !verified)
-        {
-            Err(TransactionError::SignatureFailure)
-        } else {
-            Ok(())
+            .map(move |(signature, pubkey)| SigVerifyItem {
+                signature,
+                pubkey,
+                message: message_data,
+            })
+    }
+
+    #[cfg(feature = "verify-batch")]
+    /// Verify the signatures of many transactions at once.
+    ///
+    /// Transactions are split into shards which are verified in parallel, and
+    /// the signatures within each shard are checked with a single ed25519
+    /// batch verification. If a shard fails batch verification, or contains a
+    /// signature that batch verification could accept although the strict
+    /// checks of [`SanitizedTransaction::verify`] reject it, each of its
+    /// transactions is verified individually. The returned results therefore
+    /// match those of [`SanitizedTransaction::verify`], in input order.
+    pub fn verify_batch(txs: &[Self]) -> Vec<Result<()>> {
+        use rayon::prelude::*;
+
+        txs.par_chunks(VERIFY_BATCH_SHARD_SIZE)
+            .map(|shard| {
+                if Self::batch_verify_shard(shard) {
+                    vec![Ok(()); shard.len()]
+                } else {
+                    shard.iter().map(Self::verify).collect()
+                }
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    #[cfg(feature = "verify-batch")]
+    /// Batch verify all signatures in the shard, returning false if any
+    /// signature or public key is invalid.
+    fn batch_verify_shard(shard: &[Self]) -> bool {
+        let message_datas: Vec<Vec<u8>> = shard.iter().map(Self::message_data).collect();
+        let num_signatures = shard.iter().map(|tx| tx.signatures.len()).sum();
+
+        let mut messages = Vec::with_capacity(num_signatures);
+        let mut signatures = Vec::with_capacity(num_signatures);
+        let mut public_keys = Vec::with_capacity(num_signatures);
+        for (tx, message_data) in shard.iter().zip(&message_datas) {
+            let account_keys = tx.message.account_keys();
+            for (signature, pubkey) in tx.signatures.iter().zip(account_keys.iter()) {
+                if !is_strictly_batch_verifiable(signature, pubkey) {
+                    return false;
+                }
+                let Ok(signature) = ed25519_dalek::Signature::try_from(signature.as_ref()) else {
+                    return false;
+                };
+                let Ok(public_key) = ed25519_dalek::PublicKey::from_bytes(pubkey.as_ref()) else {
+                    return false;
+                };
+                messages.push(message_data.as_slice());
+                signatures.push(signature);
+                public_keys.push(public_key);
+            }
         }
+
+        messages.is_empty()
+            || ed25519_dalek::verify_batch(&messages, &signatures, &public_keys).is_ok()
     }
 
     /// Validate a transaction message against locked accounts
@@ -289,13 +724,13 @@ impl SanitizedTransaction {
         message: &SanitizedMessage,
         tx_account_lock_limit: usize,
     ) -> Result<()> {
-        if message.has_duplicates() {
+        observe_account_locks(if message.has_duplicates() {
             Err(TransactionError::AccountLoadedTwice)
         } else if message.account_keys().len() > tx_account_lock_limit {
             Err(TransactionError::TooManyAccountLocks)
         } else {
             Ok(())
-        }
+        })
     }
 
     #[cfg(feature = "dev-context-only-utils")]
@@ -305,7 +740,7 @@ impl SanitizedTransaction {
         is_simple_vote_tx: bool,
     ) -> SanitizedTransaction {
         SanitizedTransaction {
-            message,
+            message: Arc::new(message),
             message_hash: Hash::new_unique(),
             signatures,
             is_simple_vote_tx,
@@ -313,17 +748,260 @@ impl SanitizedTransaction {
     }
 }
 
+/// Serialized form of a [`SanitizedTransaction`], borrowing its fields.
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(rename = "SanitizedTransaction", rename_all = "camelCase")]
+struct SanitizedTransactionRef<'a> {
+    signatures: &'a [Signature],
+    message: SanitizedMessageRef<'a>,
+    message_hash: &'a Hash,
+    is_simple_vote_tx: bool,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Serialize)]
+#[serde(rename = "SanitizedMessage", rename_all = "camelCase")]
+enum SanitizedMessageRef<'a> {
+    #[serde(rename_all = "camelCase")]
+    Legacy {
+        message: &'a legacy::Message,
+        is_writable_account_cache: &'a [bool],
+    },
+    #[serde(rename_all = "camelCase")]
+    V0 {
+        message: &'a v0::Message,
+        loaded_addresses: &'a LoadedAddresses,
+        is_writable_account_cache: &'a [bool],
+    },
+}
+
+/// Deserialized form of a [`SanitizedTransaction`], matching
+/// [`SanitizedTransactionRef`].
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "SanitizedTransaction", rename_all = "camelCase")]
+struct SanitizedTransactionFields {
+    signatures: Vec<Signature>,
+    message: SanitizedMessageFields,
+    message_hash: Hash,
+    is_simple_vote_tx: bool,
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename = "SanitizedMessage", rename_all = "camelCase")]
+enum SanitizedMessageFields {
+    #[serde(rename_all = "camelCase")]
+    Legacy {
+        message: legacy::Message,
+        is_writable_account_cache: Vec<bool>,
+    },
+    #[serde(rename_all = "camelCase")]
+    V0 {
+        message: v0::Message,
+        loaded_addresses: LoadedAddresses,
+        is_writable_account_cache: Vec<bool>,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for SanitizedTransaction {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let message = match self.message.as_ref() {
+            SanitizedMessage::Legacy(message) => SanitizedMessageRef::Legacy {
+                message: &message.message,
+                is_writable_account_cache: &message.is_writable_account_cache,
+            },
+            SanitizedMessage::V0(message) => SanitizedMessageRef::V0 {
+                message: &message.message,
+                loaded_addresses: &message.loaded_addresses,
+                is_writable_account_cache: &message.is_writable_account_cache,
+            },
+        };
+        let fields = SanitizedTransactionRef {
+            signatures: &self.signatures,
+            message,
+            message_hash: &self.message_hash,
+            is_simple_vote_tx: self.is_simple_vote_tx,
+        };
+        serde::Serialize::serialize(&fields, serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for SanitizedTransaction {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let SanitizedTransactionFields {
+            signatures,
+            message,
+            message_hash,
+            is_simple_vote_tx,
+        } = <SanitizedTransactionFields as serde::Deserialize>::deserialize(deserializer)?;
+
+        let message = match message {
+            SanitizedMessageFields::Legacy {
+                message,
+                is_writable_account_cache,
+            } => SanitizedMessage::Legacy(LegacyMessage {
+                message: Cow::Owned(message),
+                is_writable_account_cache,
+                compute_budget_details: ComputeBudgetDetailsCache::default(),
+                account_key_positions: AccountKeyPositions::default(),
+            }),
+            SanitizedMessageFields::V0 {
+                message,
+                loaded_addresses,
+                is_writable_account_cache,
+            } => SanitizedMessage::V0(v0::LoadedMessage {
+                message: Cow::Owned(message),
+                loaded_addresses: Cow::Owned(loaded_addresses),
+                is_writable_account_cache,
+                compute_budget_details: ComputeBudgetDetailsCache::default(),
+                account_key_positions: AccountKeyPositions::default(),
+            }),
+        };
+        Self::try_from_resolved_fields(message, message_hash, is_simple_vote_tx, signatures)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Renders the transaction over multiple lines: its signatures, fee payer,
+/// each account with its resolved role and each instruction with its program
+/// id. Intended for logs and debugging, not for parsing.
+impl fmt::Display for SanitizedTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = self.message.as_ref();
+        let account_keys = message.account_keys();
+        let num_static_keys = message.static_account_keys().len();
+
+        writeln!(f, "Transaction {}", self.signature())?;
+        let version = match message {
+            SanitizedMessage::Legacy(_) => "legacy",
+            SanitizedMessage::V0(_) => "0",
+        };
+        writeln!(f, "  Version: {version}")?;
+        writeln!(f, "  Message hash: {}", self.message_hash)?;
+        writeln!(f, "  Recent blockhash: {}", message.recent_blockhash())?;
+        writeln!(f, "  Fee payer: {}", message.fee_payer())?;
+        if let Some(nonce_account) = message.get_durable_nonce() {
+            writeln!(f, "  Durable nonce account: {nonce_account}")?;
+        }
+        if self.is_simple_vote_tx {
+            writeln!(f, "  Simple vote: true")?;
+        }
+
+        writeln!(f, "  Signatures:")?;
+        for (index, signature) in self.signatures.iter().enumerate() {
+            writeln!(f, "    {index}: {signature}")?;
+        }
+
+        writeln!(f, "  Accounts:")?;
+        for (index, pubkey) in account_keys.iter().enumerate() {
+            write!(f, "    {index}: {pubkey}")?;
+            let roles = [
+                (message.is_signer(index), "signer"),
+                (message.is_writable(index), "writable"),
+                (index >= num_static_keys, "lookup table"),
+            ];
+            let roles: Vec<_> = roles
+                .into_iter()
+                .filter_map(|(has_role, role)| has_role.then_some(role))
+                .collect();
+            if !roles.is_empty() {
+                write!(f, " ({})", roles.join(", "))?;
+            }
+            writeln!(f)?;
+        }
+
+        writeln!(f, "  Instructions:")?;
+        for (index, (program_id, instruction)) in message.program_instructions_iter().enumerate() {
+            writeln!(f, "    {index}: program {program_id}")?;
+            writeln!(f, "       accounts: {:?}", instruction.accounts)?;
+            write!(f, "       data: ")?;
+            for byte in &instruction.data {
+                write!(f, "{byte:02x}")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether batch verification of `signature` agrees with strict verification.
+///
+/// Strict verification rejects a signature whose R point or public key has a
+/// small order, while batch verification skips that check and can accept a
+/// signature that only holds up to a small order component. Neither happens
+/// when both points are in the prime order subgroup and not the identity.
+#[cfg(feature = "verify-batch")]
+fn is_strictly_batch_verifiable(signature: &Signature, pubkey: &Pubkey) -> bool {
+    use curve25519_dalek::edwards::CompressedEdwardsY;
+
+    let is_prime_order = |bytes: [u8; 32]| {
+        CompressedEdwardsY(bytes)
+            .decompress()
+            .is_some_and(|point| !point.is_small_order() && point.is_torsion_free())
+    };
+    signature
+        .as_ref()
+        .first_chunk::<32>()
+        .is_some_and(|r| is_prime_order(*r))
+        && is_prime_order(pubkey.to_bytes())
+}
+
 #[cfg(test)]
 #[allow(clippy::arithmetic_side_effects)]
 mod tests {
     use {
         super::*,
+        crate::sig_verifier::CpuSigVerifier,
+        solana_instruction::{AccountMeta, Instruction},
         solana_keypair::Keypair,
         solana_message::{MessageHeader, SimpleAddressLoader},
         solana_signer::Signer,
         solana_vote_interface::{instruction, state::Vote},
     };
 
+    #[test]
+    fn test_try_create_with_hasher() {
+        let keypair = Keypair::new();
+        let tx = VersionedTransaction::from(Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            )],
+            Some(&keypair.pubkey()),
+            &[&keypair],
+            Hash::new_unique(),
+        ));
+        let create = |message_hasher: &dyn Fn(&[u8]) -> Hash| {
+            SanitizedTransaction::try_create_with_hasher(
+                tx.clone(),
+                MessageHash::Compute,
+                None,
+                SimpleAddressLoader::Disabled,
+                &HashSet::default(),
+                &message_hasher,
+            )
+            .unwrap()
+        };
+
+        let fixed_hash = Hash::new_unique();
+        assert_eq!(*create(&|_| fixed_hash).message_hash(), fixed_hash);
+        assert_eq!(
+            *create(&VersionedMessage::hash_raw_message).message_hash(),
+            tx.message.hash()
+        );
+    }
+
     #[test]
     fn test_try_create_simple_vote_tx() {
         let bank_hash = Hash::default();
@@ -444,4 +1122,531 @@ mod tests {
             .is_ok());
         }
     }
+
+    #[test]
+    fn test_account_index_bitset() {
+        let mut a = AccountIndexBitset::default();
+        assert!(a.is_empty());
+        a.insert(0);
+        a.insert(64);
+        a.insert(255);
+        assert_eq!(a.len(), 3);
+        assert!(a.contains(64));
+        assert!(!a.contains(63));
+        assert!(!a.contains(256));
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![0, 64, 255]);
+
+        let mut b = AccountIndexBitset::default();
+        b.insert(1);
+        assert!(!a.intersects(&b));
+        b.insert(255);
+        assert!(a.intersects(&b));
+        assert_eq!((a & b).iter().collect::<Vec<_>>(), vec![255]);
+        assert_eq!((a | b).len(), 4);
+    }
+
+    #[test]
+    fn test_get_account_locks_bitset() {
+        let payer = Keypair::new();
+        let writable = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+        let tx = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[],
+                vec![
+                    AccountMeta::new(writable, false),
+                    AccountMeta::new_readonly(readonly, false),
+                ],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::default(),
+        );
+        let tx = SanitizedTransaction::from_transaction_for_tests(tx);
+
+        let locks = tx.get_account_locks_bitset(MAX_TX_ACCOUNT_LOCKS).unwrap();
+        let account_keys = tx.message().account_keys();
+        let keys = |bitset: AccountIndexBitset| {
+            bitset
+                .iter()
+                .map(|index| account_keys[index])
+                .collect::<Vec<_>>()
+        };
+        let expected = tx.get_account_locks_unchecked();
+        assert_eq!(
+            keys(locks.writable),
+            expected.writable.into_iter().copied().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            keys(locks.readonly),
+            expected.readonly.into_iter().copied().collect::<Vec<_>>()
+        );
+        assert!(!locks.writable.intersects(&locks.readonly));
+
+        assert_eq!(
+            tx.get_account_locks_bitset(2),
+            Err(TransactionError::TooManyAccountLocks)
+        );
+    }
+
+    #[test]
+    fn test_get_account_locks_into() {
+        let payer = Keypair::new();
+        let txs: Vec<_> = (0..3)
+            .map(|num_accounts| {
+                let accounts = (0..num_accounts)
+                    .map(|_| AccountMeta::new(Pubkey::new_unique(), false))
+                    .collect();
+                SanitizedTransaction::from_transaction_for_tests(
+                    Transaction::new_signed_with_payer(
+                        &[Instruction::new_with_bytes(
+                            Pubkey::new_unique(),
+                            &[],
+                            accounts,
+                        )],
+                        Some(&payer.pubkey()),
+                        &[&payer],
+                        Hash::default(),
+                    ),
+                )
+            })
+            .collect();
+
+        let mut account_locks = TransactionAccountLocks {
+            writable: Vec::with_capacity(MAX_TX_ACCOUNT_LOCKS),
+            readonly: Vec::with_capacity(MAX_TX_ACCOUNT_LOCKS),
+        };
+        let capacity = account_locks.writable.capacity();
+        for tx in txs.iter().rev() {
+            tx.get_account_locks_into(&mut account_locks);
+            assert_eq!(account_locks, tx.get_account_locks_unchecked());
+            assert_eq!(account_locks.writable.capacity(), capacity);
+        }
+    }
+
+    #[test]
+    fn test_into_versioned_transaction() {
+        let payer = Keypair::new();
+        let tx = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        let sanitized_tx = SanitizedTransaction::from_transaction_for_tests(tx.clone());
+        let expected = VersionedTransaction::from(tx);
+
+        // Clones share the message
+        let clone = sanitized_tx.clone();
+        assert!(Arc::ptr_eq(&clone.message, &sanitized_tx.message));
+
+        // The borrowed view matches the owned transaction on the wire
+        let versioned_ref = sanitized_tx.as_versioned_transaction_ref();
+        assert_eq!(
+            versioned_ref.signatures.as_ptr(),
+            sanitized_tx.signatures.as_ptr()
+        );
+        assert_eq!(
+            bincode::serialize(&versioned_ref).unwrap(),
+            bincode::serialize(&expected).unwrap()
+        );
+        assert_eq!(versioned_ref.serialized_size(), expected.serialized_size());
+        assert_eq!(VersionedTransaction::from(versioned_ref), expected);
+        assert_eq!(sanitized_tx.to_versioned_transaction(), expected);
+        assert_eq!(clone.into_versioned_transaction(), expected);
+        assert_eq!(sanitized_tx.into_versioned_transaction(), expected);
+    }
+
+    #[test]
+    fn test_without_signatures() {
+        let payer = Keypair::new();
+        let tx = Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        );
+        let sanitized_tx = SanitizedTransaction::from_transaction_for_tests(tx.clone());
+        let tx = VersionedTransaction::from(tx);
+
+        let unsigned = sanitized_tx.without_signatures();
+        assert_eq!(unsigned, tx.clone().into_unsigned());
+        assert_eq!(unsigned.signers(), &[payer.pubkey()]);
+        assert_eq!(unsigned.message(), &tx.message);
+        assert_eq!(unsigned.into_signed(&[&payer]).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_signer_positions() {
+        let payer = Keypair::new();
+        let readonly_signer = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![AccountMeta::new_readonly(readonly_signer.pubkey(), true)],
+        );
+        let tx = Transaction::new_signed_with_payer(
+            &[instruction],
+            Some(&payer.pubkey()),
+            &[&payer, &readonly_signer],
+            Hash::default(),
+        );
+        let tx = SanitizedTransaction::from_transaction_for_tests(tx);
+
+        let positions = tx.signer_positions();
+        assert_eq!(positions.len(), 2);
+        assert_eq!(positions[&payer.pubkey()], 0);
+        assert_eq!(positions[&readonly_signer.pubkey()], 1);
+
+        assert_eq!(tx.signature_for(&payer.pubkey()), Some(&tx.signatures()[0]));
+        assert_eq!(
+            tx.signature_for(&readonly_signer.pubkey()),
+            Some(&tx.signatures()[1])
+        );
+        assert!(tx.signatures()[1].verify(readonly_signer.pubkey().as_ref(), &tx.message_data()));
+        let program_id = tx.message().instructions()[0].program_id_index;
+        let program_id = tx.message().account_keys()[usize::from(program_id)];
+        assert_eq!(tx.signature_for(&program_id), None);
+    }
+
+    #[test]
+    fn test_serde() {
+        let payer = Keypair::new();
+        let reserved = Pubkey::new_unique();
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer.pubkey(), reserved, Pubkey::new_unique()],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![solana_message::compiled_instruction::CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1, 3, 4],
+                data: vec![1],
+            }],
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![1],
+            }],
+        };
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+        let tx = SanitizedTransaction::try_create(
+            tx,
+            MessageHash::Compute,
+            Some(true),
+            SimpleAddressLoader::Enabled(LoadedAddresses {
+                writable: vec![Pubkey::new_unique()],
+                readonly: vec![Pubkey::new_unique()],
+            }),
+            &HashSet::from([reserved]),
+        )
+        .unwrap();
+        // The reserved key would be writable if the cache were recomputed
+        assert!(!tx.message().is_writable(1));
+
+        let bytes = bincode::serialize(&tx).unwrap();
+        let restored: SanitizedTransaction = bincode::deserialize(&bytes).unwrap();
+        assert_eq!(restored, tx);
+
+        let json = serde_json::to_string(&tx).unwrap();
+        let restored: SanitizedTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, tx);
+
+        // Loaded addresses must match the lookups
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["message"]["V0"]["loadedAddresses"]["readonly"] = serde_json::json!([]);
+        assert!(serde_json::from_value::<SanitizedTransaction>(value).is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        let payer = Keypair::new();
+        let readonly = Pubkey::new_unique();
+        let loaded = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let message = v0::Message {
+            header: MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 2,
+            },
+            account_keys: vec![payer.pubkey(), readonly, program_id],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![solana_message::compiled_instruction::CompiledInstruction {
+                program_id_index: 2,
+                accounts: vec![0, 1, 3],
+                data: vec![0xde, 0xad],
+            }],
+            address_table_lookups: vec![v0::MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![0],
+                readonly_indexes: vec![],
+            }],
+        };
+        let tx = VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap();
+        let tx = SanitizedTransaction::try_create(
+            tx,
+            MessageHash::Compute,
+            Some(false),
+            SimpleAddressLoader::Enabled(LoadedAddresses {
+                writable: vec![loaded],
+                readonly: vec![],
+            }),
+            &HashSet::default(),
+        )
+        .unwrap();
+
+        let output = tx.to_string();
+        assert!(output.starts_with(&format!("Transaction {}\n", tx.signature())));
+        assert!(output.contains("  Version: 0\n"));
+        assert!(output.contains(&format!("  Fee payer: {}\n", payer.pubkey())));
+        assert!(output.contains(&format!("    0: {} (signer, writable)\n", payer.pubkey())));
+        assert!(output.contains(&format!("    1: {readonly}\n")));
+        assert!(output.contains(&format!("    3: {loaded} (writable, lookup table)\n")));
+        assert!(output.contains(&format!("    0: program {program_id}\n")));
+        assert!(output.contains("       accounts: [0, 1, 3]\n"));
+        assert!(output.contains("       data: dead\n"));
+        assert!(!output.contains("Durable nonce"));
+    }
+
+    #[test]
+    fn test_compute_budget_accessors() {
+        use solana_compute_budget_interface::ComputeBudgetInstruction;
+
+        let payer = Keypair::new();
+        let instructions = [
+            ComputeBudgetInstruction::set_compute_unit_limit(50_000),
+            ComputeBudgetInstruction::set_compute_unit_price(7),
+            Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]),
+        ];
+        let tx =
+            SanitizedTransaction::from_transaction_for_tests(Transaction::new_signed_with_payer(
+                &instructions,
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::new_unique(),
+            ));
+        let details = tx.message().get_compute_budget_details();
+        assert_eq!(tx.get_compute_budget_details(), details);
+        assert_eq!(tx.compute_unit_price(), Ok(7));
+        assert_eq!(tx.compute_unit_limit(), Ok(50_000));
+        assert_eq!(
+            tx.loaded_accounts_data_size_limit(),
+            Ok(solana_message::MAX_LOADED_ACCOUNTS_DATA_SIZE_BYTES)
+        );
+        // The cached details do not affect equality.
+        assert_eq!(tx, SanitizedTransaction::clone(&tx));
+        let uncached = SanitizedTransaction::try_new_from_fields(
+            tx.message().clone(),
+            *tx.message_hash(),
+            tx.is_simple_vote_transaction(),
+            tx.signatures().to_vec(),
+        )
+        .unwrap();
+        assert_eq!(tx, uncached);
+    }
+
+    #[test]
+    fn test_verify_batch() {
+        let txs: Vec<_> = (0..150)
+            .map(|_| {
+                let payer = Keypair::new();
+                let ix = instruction::vote(
+                    &Pubkey::new_unique(),
+                    &payer.pubkey(),
+                    Vote::new(vec![1], Hash::default()),
+                );
+                let tx = Transaction::new_signed_with_payer(
+                    &[ix],
+                    Some(&payer.pubkey()),
+                    &[&payer],
+                    Hash::new_unique(),
+                );
+                SanitizedTransaction::from_transaction_for_tests(tx)
+            })
+            .collect();
+        assert_eq!(
+            SanitizedTransaction::verify_batch(&txs),
+            vec![Ok(()); txs.len()]
+        );
+
+        let mut invalid_txs = txs;
+        let invalid_index = 100;
+        invalid_txs[invalid_index].signatures[0] = Signature::default();
+        let results = SanitizedTransaction::verify_batch(&invalid_txs);
+        assert_eq!(results.len(), invalid_txs.len());
+        for (i, result) in results.into_iter().enumerate() {
+            if i == invalid_index {
+                assert_eq!(result, Err(TransactionError::SignatureFailure));
+            } else {
+                assert_eq!(result, Ok(()));
+            }
+        }
+
+        assert!(SanitizedTransaction::verify_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_is_strictly_batch_verifiable() {
+        use curve25519_dalek::{constants::EIGHT_TORSION, edwards::CompressedEdwardsY};
+
+        let keypair = Keypair::new();
+        let signature = keypair.sign_message(b"message");
+        assert!(is_strictly_batch_verifiable(&signature, &keypair.pubkey()));
+
+        // Small order points
+        for torsion_point in EIGHT_TORSION {
+            let small_order = torsion_point.compress().to_bytes();
+            assert!(!is_strictly_batch_verifiable(
+                &signature,
+                &Pubkey::from(small_order)
+            ));
+            let mut small_order_r = <[u8; 64]>::from(signature);
+            small_order_r[..32].copy_from_slice(&small_order);
+            assert!(!is_strictly_batch_verifiable(
+                &Signature::from(small_order_r),
+                &keypair.pubkey()
+            ));
+        }
+
+        // A point with a small order component
+        let mixed_order_key = CompressedEdwardsY(keypair.pubkey().to_bytes())
+            .decompress()
+            .unwrap()
+            + EIGHT_TORSION[1];
+        assert!(!is_strictly_batch_verifiable(
+            &signature,
+            &Pubkey::from(mixed_order_key.compress().to_bytes())
+        ));
+    }
+
+    #[test]
+    fn test_verify_batch_with() {
+        use {
+            crate::sig_verifier::{SigVerifier, SigVerifyItem},
+            std::cell::Cell,
+        };
+
+        /// Rejects the signatures of one key and counts batch calls.
+        struct RejectKey {
+            rejected: Pubkey,
+            batches: Cell<usize>,
+        }
+
+        impl SigVerifier for RejectKey {
+            fn verify(&self, item: &SigVerifyItem<'_>) -> bool {
+                item.pubkey != &self.rejected
+            }
+
+            fn verify_batch(&self, items: &[SigVerifyItem<'_>]) -> Vec<bool> {
+                self.batches.set(self.batches.get() + 1);
+                items.iter().map(|item| self.verify(item)).collect()
+            }
+        }
+
+        let signer = Keypair::new();
+        let txs: Vec<_> = (0..3)
+            .map(|i| {
+                let payer = Keypair::new();
+                let mut signers = vec![&payer];
+                if i == 1 {
+                    signers.insert(0, &signer);
+                }
+                let instruction = Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &[],
+                    signers
+                        .iter()
+                        .map(|keypair| AccountMeta::new(keypair.pubkey(), true))
+                        .collect(),
+                );
+                SanitizedTransaction::from_transaction_for_tests(
+                    Transaction::new_signed_with_payer(
+                        &[instruction],
+                        Some(&signers[0].pubkey()),
+                        &signers,
+                        Hash::new_unique(),
+                    ),
+                )
+            })
+            .collect();
+
+        let verifier = RejectKey {
+            rejected: signer.pubkey(),
+            batches: Cell::new(0),
+        };
+        assert_eq!(
+            SanitizedTransaction::verify_batch_with(&txs, &verifier),
+            vec![Ok(()), Err(TransactionError::SignatureFailure), Ok(())]
+        );
+        assert_eq!(verifier.batches.get(), 1);
+        assert_eq!(
+            txs[1].verify_with(&verifier),
+            Err(TransactionError::SignatureFailure)
+        );
+        assert_eq!(txs[0].verify_with(&verifier), Ok(()));
+        assert_eq!(
+            SanitizedTransaction::verify_batch_with(&txs, &CpuSigVerifier),
+            vec![Ok(()); 3]
+        );
+    }
+
+    #[test]
+    fn test_verify_with_missing_results() {
+        use crate::sig_verifier::{SigVerifier, SigVerifyItem};
+
+        /// Accepts every signature but drops the last `missing` results.
+        struct ShortBatch {
+            missing: usize,
+        }
+
+        impl SigVerifier for ShortBatch {
+            fn verify(&self, _item: &SigVerifyItem<'_>) -> bool {
+                true
+            }
+
+            fn verify_batch(&self, items: &[SigVerifyItem<'_>]) -> Vec<bool> {
+                vec![true; items.len().saturating_sub(self.missing)]
+            }
+        }
+
+        let keypair = Keypair::new();
+        let txs: Vec<_> = (0..2)
+            .map(|_| {
+                SanitizedTransaction::from_transaction_for_tests(
+                    Transaction::new_signed_with_payer(
+                        &[],
+                        Some(&keypair.pubkey()),
+                        &[&keypair],
+                        Hash::new_unique(),
+                    ),
+                )
+            })
+            .collect();
+
+        for missing in [1, usize::MAX] {
+            let verifier = ShortBatch { missing };
+            assert_eq!(
+                txs[0].verify_with(&verifier),
+                Err(TransactionError::SignatureFailure)
+            );
+            assert_eq!(
+                SanitizedTransaction::verify_batch_with(&txs, &verifier),
+                vec![Err(TransactionError::SignatureFailure); 2]
+            );
+        }
+        assert_eq!(txs[0].verify_with(&ShortBatch { missing: 0 }), Ok(()));
+    }
 }
@@ -0,0 +1,60 @@
+//! Pluggable backends for transaction signature verification.
+//!
+//! [`SanitizedTransaction::verify`] checks signatures on the CPU, one at a
+//! time. Validators that offload verification to a GPU or an FPGA implement
+//! [`SigVerifier`] instead and pass it to
+//! [`SanitizedTransaction::verify_with`] or
+//! [`SanitizedTransaction::verify_batch_with`].
+//!
+//! [`SanitizedTransaction::verify`]: crate::sanitized::SanitizedTransaction::verify
+//! [`SanitizedTransaction::verify_with`]: crate::sanitized::SanitizedTransaction::verify_with
+//! [`SanitizedTransaction::verify_batch_with`]: crate::sanitized::SanitizedTransaction::verify_batch_with
+#![cfg(feature = "verify")]
+
+use {solana_pubkey::Pubkey, solana_signature::Signature};
+
+/// A signature to verify, with the key that must have produced it and the
+/// signed message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SigVerifyItem<'a> {
+    pub signature: &'a Signature,
+    pub pubkey: &'a Pubkey,
+    pub message: &'a [u8],
+}
+
+/// A signature verification backend.
+pub trait SigVerifier {
+    /// Returns true if `item.signature` is a valid signature of
+    /// `item.message` by `item.pubkey`.
+    fn verify(&self, item: &SigVerifyItem<'_>) -> bool;
+
+    /// Verify many signatures at once, returning one result per item in
+    /// input order. Callers reject every item if the number of results
+    /// differs from the number of items.
+    ///
+    /// Backends that amortize work across signatures should override this;
+    /// the default verifies each item in turn.
+    fn verify_batch(&self, items: &[SigVerifyItem<'_>]) -> Vec<bool> {
+        items.iter().map(|item| self.verify(item)).collect()
+    }
+}
+
+impl<T: SigVerifier + ?Sized> SigVerifier for &T {
+    fn verify(&self, item: &SigVerifyItem<'_>) -> bool {
+        (**self).verify(item)
+    }
+
+    fn verify_batch(&self, items: &[SigVerifyItem<'_>]) -> Vec<bool> {
+        (**self).verify_batch(items)
+    }
+}
+
+/// Verifies signatures on the CPU with [`Signature::verify`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CpuSigVerifier;
+
+impl SigVerifier for CpuSigVerifier {
+    fn verify(&self, item: &SigVerifyItem<'_>) -> bool {
+        item.signature.verify(item.pubkey.as_ref(), item.message)
+    }
+}
@@ -0,0 +1,343 @@
+//! Portable partially-signed transactions for offline signing workflows.
+//!
+//! A [`SignOnlyTransaction`] carries a message together with the signatures
+//! that have been collected for it so far. Each signer, which may live on a
+//! separate air-gapped machine, adds its signature and exports the result;
+//! the artifacts are then merged and turned back into a
+//! [`VersionedTransaction`] once every required signature is present.
+#![cfg(feature = "verify")]
+
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+use {
+    crate::versioned::VersionedTransaction,
+    solana_message::VersionedMessage,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_signer::{signers::Signers, PresignerError, SignerError},
+};
+
+/// A message and the signatures collected for it so far.
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "camelCase")
+)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SignOnlyTransaction {
+    /// The message to sign.
+    pub message: VersionedMessage,
+    /// Signatures collected so far, paired with the key of their signer.
+    pub signatures: Vec<(Pubkey, Signature)>,
+}
+
+impl SignOnlyTransaction {
+    /// Create a sign-only transaction with no signatures.
+    pub fn new(message: VersionedMessage) -> Self {
+        Self {
+            message,
+            signatures: vec![],
+        }
+    }
+
+    /// Create a sign-only transaction from a possibly partially signed
+    /// transaction, keeping every signature that is not the default one.
+    pub fn from_transaction(tx: &VersionedTransaction) -> Self {
+        let signatures = tx
+            .signatures
+            .iter()
+            .zip(tx.message.static_account_keys())
+            .filter(|(signature, _)| **signature != Signature::default())
+            .map(|(signature, pubkey)| (*pubkey, *signature))
+            .collect();
+        Self {
+            message: tx.message.clone(),
+            signatures,
+        }
+    }
+
+    /// Return the serialized message data to sign.
+    pub fn message_data(&self) -> Vec<u8> {
+        self.message.serialize()
+    }
+
+    /// Return the keys that must sign the message, in signature order.
+    pub fn required_signers(&self) -> &[Pubkey] {
+        let static_account_keys = self.message.static_account_keys();
+        let num_required_signatures = usize::from(self.message.header().num_required_signatures)
+            .min(static_account_keys.len());
+        &static_account_keys[..num_required_signatures]
+    }
+
+    /// Return the signature collected for `pubkey`, if any.
+    pub fn signature_for(&self, pubkey: &Pubkey) -> Option<&Signature> {
+        self.signatures
+            .iter()
+            .find(|(signer, _)| signer == pubkey)
+            .map(|(_, signature)| signature)
+    }
+
+    /// Return the required signers that have not signed yet.
+    pub fn absent_signers(&self) -> Vec<Pubkey> {
+        self.required_signers()
+            .iter()
+            .filter(|pubkey| self.signature_for(pubkey).is_none())
+            .copied()
+            .collect()
+    }
+
+    /// Returns true if every required signer has signed.
+    pub fn is_complete(&self) -> bool {
+        self.required_signers()
+            .iter()
+            .all(|pubkey| self.signature_for(pubkey).is_some())
+    }
+
+    /// Sign the message with each of the provided signers, all of which must
+    /// be required signers of the message. Each signature is verified like in
+    /// [`SignOnlyTransaction::add_signature`], and none are added if any
+    /// fails.
+    pub fn try_sign<T: Signers + ?Sized>(&mut self, keypairs: &T) -> Result<(), SignerError> {
+        let message_data = self.message_data();
+        self.try_sign_message_data(keypairs, &message_data)
+    }
+
+    /// Add a signature produced elsewhere, replacing any existing signature
+    /// from the same signer. The signature must verify against the message.
+    pub fn add_signature(
+        &mut self,
+        pubkey: Pubkey,
+        signature: Signature,
+    ) -> Result<(), SignerError> {
+        let message_data = self.message_data();
+        self.add_signature_for_message_data(pubkey, signature, &message_data)
+    }
+
+    /// [`Self::try_sign`] with the already serialized message.
+    pub(crate) fn try_sign_message_data<T: Signers + ?Sized>(
+        &mut self,
+        keypairs: &T,
+        message_data: &[u8],
+    ) -> Result<(), SignerError> {
+        let pubkeys = keypairs.try_pubkeys()?;
+        if pubkeys
+            .iter()
+            .any(|pubkey| !self.required_signers().contains(pubkey))
+        {
+            return Err(SignerError::KeypairPubkeyMismatch);
+        }
+
+        let signatures = keypairs.try_sign_message(message_data)?;
+        for (pubkey, signature) in pubkeys.iter().zip(&signatures) {
+            self.check_signature(pubkey, signature, message_data)?;
+        }
+        for (pubkey, signature) in pubkeys.into_iter().zip(signatures) {
+            self.insert_signature(pubkey, signature);
+        }
+        Ok(())
+    }
+
+    /// [`Self::add_signature`] with the already serialized message.
+    pub(crate) fn add_signature_for_message_data(
+        &mut self,
+        pubkey: Pubkey,
+        signature: Signature,
+        message_data: &[u8],
+    ) -> Result<(), SignerError> {
+        self.check_signature(&pubkey, &signature, message_data)?;
+        self.insert_signature(pubkey, signature);
+        Ok(())
+    }
+
+    /// Merge the signatures collected in `other` into this transaction. Both
+    /// must be for the same message.
+    pub fn merge(&mut self, other: &Self) -> Result<(), SignerError> {
+        if self.message != other.message {
+            return Err(SignerError::InvalidInput("message mismatch".to_string()));
+        }
+        let message_data = self.message_data();
+        for (pubkey, signature) in &other.signatures {
+            self.check_signature(pubkey, signature, &message_data)?;
+        }
+        for (pubkey, signature) in &other.signatures {
+            self.insert_signature(*pubkey, *signature);
+        }
+        Ok(())
+    }
+
+    /// Copy the collected signatures into `tx`, which must have the same
+    /// message. Signatures already present in `tx` for other signers are kept.
+    pub fn merge_into(&self, tx: &mut VersionedTransaction) -> Result<(), SignerError> {
+        if self.message != tx.message {
+            return Err(SignerError::InvalidInput("message mismatch".to_string()));
+        }
+        let message_data = self.message_data();
+        for (pubkey, signature) in &self.signatures {
+            self.check_signature(pubkey, signature, &message_data)?;
+        }
+
+        let required_signers = self.required_signers();
+        tx.signatures
+            .resize(required_signers.len(), Signature::default());
+        for (tx_signature, pubkey) in tx.signatures.iter_mut().zip(required_signers) {
+            if let Some(signature) = self.signature_for(pubkey) {
+                *tx_signature = *signature;
+            }
+        }
+        Ok(())
+    }
+
+    /// Convert into a transaction, leaving default signatures in place of any
+    /// absent signers.
+    pub fn into_transaction(self) -> VersionedTransaction {
+        let signatures = self
+            .required_signers()
+            .iter()
+            .map(|pubkey| self.signature_for(pubkey).copied().unwrap_or_default())
+            .collect();
+        VersionedTransaction {
+            signatures,
+            message: self.message,
+        }
+    }
+
+    /// Convert into a transaction, failing if any required signer has not
+    /// signed yet.
+    pub fn try_into_transaction(self) -> Result<VersionedTransaction, SignerError> {
+        if !self.is_complete() {
+            return Err(SignerError::NotEnoughSigners);
+        }
+        Ok(self.into_transaction())
+    }
+
+    fn check_signature(
+        &self,
+        pubkey: &Pubkey,
+        signature: &Signature,
+        message_data: &[u8],
+    ) -> Result<(), SignerError> {
+        if !self.required_signers().contains(pubkey) {
+            return Err(SignerError::KeypairPubkeyMismatch);
+        }
+        if !signature.verify(pubkey.as_ref(), message_data) {
+            return Err(PresignerError::VerificationFailure.into());
+        }
+        Ok(())
+    }
+
+    fn insert_signature(&mut self, pubkey: Pubkey, signature: Signature) {
+        match self
+            .signatures
+            .iter_mut()
+            .find(|(signer, _)| *signer == pubkey)
+        {
+            Some((_, existing)) => *existing = signature,
+            None => self.signatures.push((pubkey, signature)),
+        }
+    }
+}
+
+impl From<SignOnlyTransaction> for VersionedTransaction {
+    fn from(sign_only: SignOnlyTransaction) -> Self {
+        sign_only.into_transaction()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::Message,
+        solana_signer::Signer,
+    };
+
+    fn two_signer_message(payer: &Keypair, other: &Keypair) -> VersionedMessage {
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[0],
+            vec![AccountMeta::new_readonly(other.pubkey(), true)],
+        );
+        let mut message = Message::new(&[instruction], Some(&payer.pubkey()));
+        message.recent_blockhash = Hash::new_unique();
+        VersionedMessage::Legacy(message)
+    }
+
+    #[test]
+    fn test_offline_sign_and_merge() {
+        let payer = Keypair::new();
+        let other = Keypair::new();
+        let message = two_signer_message(&payer, &other);
+
+        // Each signer works on its own copy, which is exported and imported
+        let mut payer_artifact = SignOnlyTransaction::new(message.clone());
+        payer_artifact.try_sign(&[&payer]).unwrap();
+        assert!(!payer_artifact.is_complete());
+        assert_eq!(payer_artifact.absent_signers(), vec![other.pubkey()]);
+        let payer_artifact: SignOnlyTransaction =
+            bincode::deserialize(&bincode::serialize(&payer_artifact).unwrap()).unwrap();
+
+        let mut other_artifact = SignOnlyTransaction::new(message);
+        other_artifact.try_sign(&[&other]).unwrap();
+
+        let mut merged = payer_artifact.clone();
+        merged.merge(&other_artifact).unwrap();
+        assert!(merged.is_complete());
+        assert!(merged.absent_signers().is_empty());
+
+        let tx = merged.try_into_transaction().unwrap();
+        assert_eq!(tx.verify_with_results(), vec![true, true]);
+
+        // Merging into an existing partially signed transaction
+        let mut tx = payer_artifact.into_transaction();
+        assert_eq!(tx.signatures[1], Signature::default());
+        other_artifact.merge_into(&mut tx).unwrap();
+        assert_eq!(tx.verify_with_results(), vec![true, true]);
+        assert_eq!(
+            SignOnlyTransaction::from_transaction(&tx).signatures.len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_sign_only_rejects_invalid_signatures() {
+        let payer = Keypair::new();
+        let other = Keypair::new();
+        let message = two_signer_message(&payer, &other);
+        let mut sign_only = SignOnlyTransaction::new(message.clone());
+
+        let stranger = Keypair::new();
+        assert_eq!(
+            sign_only.try_sign(&[&stranger]),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+        assert_eq!(
+            sign_only.try_sign(&[
+                &payer as &dyn Signer,
+                &solana_signer::null_signer::NullSigner::new(&other.pubkey()),
+            ]),
+            Err(SignerError::PresignerError(
+                PresignerError::VerificationFailure
+            ))
+        );
+        assert!(sign_only.signatures.is_empty());
+        assert_eq!(
+            sign_only.add_signature(other.pubkey(), other.sign_message(b"wrong message")),
+            Err(SignerError::PresignerError(
+                PresignerError::VerificationFailure
+            ))
+        );
+        assert_eq!(
+            sign_only.clone().try_into_transaction(),
+            Err(SignerError::NotEnoughSigners)
+        );
+
+        let other_message = two_signer_message(&payer, &other);
+        assert!(sign_only
+            .merge(&SignOnlyTransaction::new(other_message))
+            .is_err());
+        assert!(sign_only.signatures.is_empty());
+    }
+}
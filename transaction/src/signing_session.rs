@@ -0,0 +1,204 @@
+//! Incremental collection of the signatures of a multisig transaction.
+//!
+//! A [`SigningSession`] is created for a message and accepts the signatures
+//! of its required signers one at a time, in any order, as they come in from
+//! the parties involved. Each signature is verified against the message when
+//! it is added, so that a bad signature is reported by the party that
+//! submitted it rather than when the finished transaction is rejected.
+#![cfg(feature = "verify")]
+
+use {
+    crate::{sign_only::SignOnlyTransaction, versioned::VersionedTransaction},
+    solana_message::VersionedMessage,
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+    solana_signer::{signers::Signers, SignerError},
+};
+
+/// A message and the signatures of its required signers collected so far.
+///
+/// The signatures are kept in a [`SignOnlyTransaction`], which follows the
+/// same rules, and the serialized message is cached for verifying them.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SigningSession {
+    sign_only: SignOnlyTransaction,
+    message_data: Vec<u8>,
+}
+
+impl SigningSession {
+    /// Start a session with no signatures for `message`.
+    pub fn new(message: VersionedMessage) -> Self {
+        let sign_only = SignOnlyTransaction::new(message);
+        let message_data = sign_only.message_data();
+        Self {
+            sign_only,
+            message_data,
+        }
+    }
+
+    pub fn message(&self) -> &VersionedMessage {
+        &self.sign_only.message
+    }
+
+    /// Return the serialized message data to sign.
+    pub fn message_data(&self) -> &[u8] {
+        &self.message_data
+    }
+
+    /// Return the signatures collected so far, to export them to other
+    /// parties.
+    pub fn sign_only(&self) -> &SignOnlyTransaction {
+        &self.sign_only
+    }
+
+    /// Return the keys that must sign the message, in signature order.
+    pub fn required_signers(&self) -> &[Pubkey] {
+        self.sign_only.required_signers()
+    }
+
+    /// Return the signature collected for `pubkey`, if any.
+    pub fn signature_for(&self, pubkey: &Pubkey) -> Option<&Signature> {
+        self.sign_only.signature_for(pubkey)
+    }
+
+    /// Return the required signers that have not signed yet, in signature
+    /// order.
+    pub fn remaining_signers(&self) -> Vec<Pubkey> {
+        self.sign_only.absent_signers()
+    }
+
+    /// Returns true if every required signer has signed.
+    pub fn is_complete(&self) -> bool {
+        self.sign_only.is_complete()
+    }
+
+    /// Add the signature of `pubkey`, which must be a required signer, after
+    /// verifying it against the message. A signature already collected for
+    /// the same signer is replaced.
+    pub fn add_signature(
+        &mut self,
+        pubkey: &Pubkey,
+        signature: Signature,
+    ) -> Result<(), SignerError> {
+        self.sign_only
+            .add_signature_for_message_data(*pubkey, signature, &self.message_data)
+    }
+
+    /// Sign the message with each of the provided signers, all of which must
+    /// be required signers of the message. Each signature is verified like in
+    /// [`SigningSession::add_signature`], and none are added if any fails.
+    pub fn sign<T: Signers + ?Sized>(&mut self, keypairs: &T) -> Result<(), SignerError> {
+        self.sign_only
+            .try_sign_message_data(keypairs, &self.message_data)
+    }
+
+    /// Finish the session, failing if any required signer has not signed yet.
+    pub fn finalize(self) -> Result<VersionedTransaction, SignerError> {
+        self.sign_only.try_into_transaction()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::Message,
+        solana_signer::{null_signer::NullSigner, PresignerError, Signer},
+    };
+
+    fn multisig_message(payer: &Keypair, approvers: &[&Keypair]) -> VersionedMessage {
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[0],
+            approvers
+                .iter()
+                .map(|approver| AccountMeta::new_readonly(approver.pubkey(), true))
+                .collect(),
+        );
+        let mut message = Message::new(&[instruction], Some(&payer.pubkey()));
+        message.recent_blockhash = Hash::new_unique();
+        VersionedMessage::Legacy(message)
+    }
+
+    #[test]
+    fn test_signing_session() {
+        let payer = Keypair::new();
+        let approver0 = Keypair::new();
+        let approver1 = Keypair::new();
+        let message = multisig_message(&payer, &[&approver0, &approver1]);
+        let mut session = SigningSession::new(message.clone());
+        // Signers other than the payer are ordered by pubkey
+        let signers = message.static_account_keys()
+            [..usize::from(message.header().num_required_signatures)]
+            .to_vec();
+        assert_eq!(signers.len(), 3);
+        assert_eq!(session.remaining_signers(), signers);
+
+        // Signatures arrive out of order
+        let signature = approver1.sign_message(session.message_data());
+        session
+            .add_signature(&approver1.pubkey(), signature)
+            .unwrap();
+        assert_eq!(session.signature_for(&approver1.pubkey()), Some(&signature));
+        assert_eq!(
+            session.remaining_signers(),
+            signers
+                .iter()
+                .copied()
+                .filter(|signer| *signer != approver1.pubkey())
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            session.clone().finalize(),
+            Err(SignerError::NotEnoughSigners)
+        );
+
+        session.sign(&[&payer, &approver0]).unwrap();
+        assert!(session.is_complete());
+        let tx = session.finalize().unwrap();
+        assert_eq!(tx.message, message);
+        assert_eq!(tx.verify_with_results(), vec![true; 3]);
+    }
+
+    #[test]
+    fn test_signing_session_rejects_invalid_signatures() {
+        let payer = Keypair::new();
+        let approver = Keypair::new();
+        let mut session = SigningSession::new(multisig_message(&payer, &[&approver]));
+
+        let stranger = Keypair::new();
+        let signature = stranger.sign_message(session.message_data());
+        assert_eq!(
+            session.add_signature(&stranger.pubkey(), signature),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+        assert_eq!(
+            session.sign(&[&payer, &stranger]),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+        assert!(session.signature_for(&payer.pubkey()).is_none());
+
+        let signature = approver.sign_message(b"another message");
+        assert_eq!(
+            session.add_signature(&approver.pubkey(), signature),
+            Err(PresignerError::VerificationFailure.into())
+        );
+        assert_eq!(
+            session.remaining_signers(),
+            vec![payer.pubkey(), approver.pubkey()]
+        );
+
+        let null_signer = NullSigner::new(&approver.pubkey());
+        assert_eq!(
+            session.sign(&[&payer as &dyn Signer, &null_signer]),
+            Err(PresignerError::VerificationFailure.into())
+        );
+        assert_eq!(
+            session.remaining_signers(),
+            vec![payer.pubkey(), approver.pubkey()]
+        );
+    }
+}
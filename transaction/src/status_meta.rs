@@ -0,0 +1,434 @@
+//! Transactions paired with the metadata of their execution.
+//!
+//! [`TransactionStatusMeta`] records the outcome of an executed transaction
+//! as indexers store it: its status, fee, balances, logs, inner
+//! instructions, loaded addresses and return data.
+//! [`UiTransactionWithStatusMeta`] and [`UiTransactionStatusMeta`] mirror the
+//! `json` encoding returned by RPC methods such as `getTransaction`, and
+//! convert to and from the native types.
+//!
+//! Token balances and rewards depend on types defined outside of the SDK and
+//! are not included.
+#![cfg(feature = "rpc-json")]
+
+use {
+    crate::{
+        rpc_json::{ParseUiTransactionError, UiCompiledInstruction, UiTransaction},
+        versioned::{TransactionVersion, VersionedTransaction},
+    },
+    base64::{prelude::BASE64_STANDARD, Engine},
+    serde_derive::{Deserialize, Serialize},
+    solana_message::{
+        compiled_instruction::CompiledInstruction,
+        inner_instruction::{InnerInstruction, InnerInstructionsList},
+        v0::LoadedAddresses,
+    },
+    solana_pubkey::Pubkey,
+    solana_transaction_error::{TransactionError, TransactionResult as Result},
+    std::str::FromStr,
+};
+
+/// Data set by a program with `set_return_data`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransactionReturnData {
+    pub program_id: Pubkey,
+    pub data: Vec<u8>,
+}
+
+/// Metadata of an executed transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TransactionStatusMeta {
+    pub status: Result<()>,
+    pub fee: u64,
+    /// Lamport balances of the account keys before execution, in account key
+    /// order including loaded addresses.
+    pub pre_balances: Vec<u64>,
+    /// Lamport balances of the account keys after execution.
+    pub post_balances: Vec<u64>,
+    /// Instructions invoked by each instruction of the transaction, if
+    /// recorded.
+    pub inner_instructions: Option<InnerInstructionsList>,
+    pub log_messages: Option<Vec<String>>,
+    pub loaded_addresses: LoadedAddresses,
+    pub return_data: Option<TransactionReturnData>,
+    pub compute_units_consumed: Option<u64>,
+}
+
+/// A transaction and the metadata of its execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VersionedTransactionWithStatusMeta {
+    pub transaction: VersionedTransaction,
+    pub meta: TransactionStatusMeta,
+}
+
+/// A transaction and the metadata of its execution in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransactionWithStatusMeta {
+    pub transaction: UiTransaction,
+    pub meta: Option<UiTransactionStatusMeta>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<TransactionVersion>,
+}
+
+/// The metadata of an executed transaction in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransactionStatusMeta {
+    pub err: Option<TransactionError>,
+    /// Kept for compatibility, equivalent to `err`
+    pub status: Result<()>,
+    pub fee: u64,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub inner_instructions: Option<Vec<UiInnerInstructions>>,
+    pub log_messages: Option<Vec<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub loaded_addresses: Option<UiLoadedAddresses>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub return_data: Option<UiTransactionReturnData>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub compute_units_consumed: Option<u64>,
+}
+
+/// The instructions invoked by the transaction instruction at `index`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiInnerInstructions {
+    pub index: u8,
+    pub instructions: Vec<UiInnerInstruction>,
+}
+
+/// An inner instruction in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiInnerInstruction {
+    #[serde(flatten)]
+    pub instruction: UiCompiledInstruction,
+    pub stack_height: Option<u32>,
+}
+
+/// Addresses loaded from lookup tables in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiLoadedAddresses {
+    /// Base58 encoded writable addresses
+    pub writable: Vec<String>,
+    /// Base58 encoded readonly addresses
+    pub readonly: Vec<String>,
+}
+
+/// Return data in RPC JSON form.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct UiTransactionReturnData {
+    /// Base58 encoded program id
+    pub program_id: String,
+    pub data: (String, UiReturnDataEncoding),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UiReturnDataEncoding {
+    Base64,
+}
+
+impl From<&LoadedAddresses> for UiLoadedAddresses {
+    fn from(loaded_addresses: &LoadedAddresses) -> Self {
+        Self {
+            writable: loaded_addresses
+                .writable
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+            readonly: loaded_addresses
+                .readonly
+                .iter()
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+}
+
+impl From<&TransactionReturnData> for UiTransactionReturnData {
+    fn from(return_data: &TransactionReturnData) -> Self {
+        Self {
+            program_id: return_data.program_id.to_string(),
+            data: (
+                BASE64_STANDARD.encode(&return_data.data),
+                UiReturnDataEncoding::Base64,
+            ),
+        }
+    }
+}
+
+impl From<&TransactionStatusMeta> for UiTransactionStatusMeta {
+    /// Transaction instructions that did not invoke any instruction are
+    /// omitted from the inner instructions.
+    fn from(meta: &TransactionStatusMeta) -> Self {
+        let inner_instructions = meta.inner_instructions.as_ref().map(|list| {
+            list.iter()
+                .enumerate()
+                .filter(|(_, instructions)| !instructions.is_empty())
+                .map(|(index, instructions)| UiInnerInstructions {
+                    index: index as u8,
+                    instructions: instructions
+                        .iter()
+                        .map(|inner| UiInnerInstruction {
+                            instruction: (&inner.instruction).into(),
+                            stack_height: Some(u32::from(inner.stack_height)),
+                        })
+                        .collect(),
+                })
+                .collect()
+        });
+        Self {
+            err: meta.status.clone().err(),
+            status: meta.status.clone(),
+            fee: meta.fee,
+            pre_balances: meta.pre_balances.clone(),
+            post_balances: meta.post_balances.clone(),
+            inner_instructions,
+            log_messages: meta.log_messages.clone(),
+            loaded_addresses: Some((&meta.loaded_addresses).into()),
+            return_data: meta.return_data.as_ref().map(Into::into),
+            compute_units_consumed: meta.compute_units_consumed,
+        }
+    }
+}
+
+impl TryFrom<&UiTransactionStatusMeta> for TransactionStatusMeta {
+    type Error = ParseUiTransactionError;
+
+    /// Inner instructions without a stack height, recorded before stack
+    /// heights were, are given a stack height of 2.
+    fn try_from(ui_meta: &UiTransactionStatusMeta) -> std::result::Result<Self, Self::Error> {
+        let inner_instructions = ui_meta
+            .inner_instructions
+            .as_ref()
+            .map(|ui_list| {
+                let mut list = InnerInstructionsList::new();
+                for ui_inner_instructions in ui_list {
+                    let index = usize::from(ui_inner_instructions.index);
+                    if list.len() <= index {
+                        list.resize(index.saturating_add(1), vec![]);
+                    }
+                    list[index] = ui_inner_instructions
+                        .instructions
+                        .iter()
+                        .map(|ui_inner| {
+                            let data = bs58::decode(&ui_inner.instruction.data)
+                                .into_vec()
+                                .map_err(|_| {
+                                    ParseUiTransactionError::InvalidInstructionData(index)
+                                })?;
+                            Ok(InnerInstruction {
+                                instruction: CompiledInstruction::new_from_raw_parts(
+                                    ui_inner.instruction.program_id_index,
+                                    data,
+                                    ui_inner.instruction.accounts.clone(),
+                                ),
+                                stack_height: ui_inner
+                                    .stack_height
+                                    .map_or(2, |stack_height| stack_height as u8),
+                            })
+                        })
+                        .collect::<std::result::Result<_, _>>()?;
+                }
+                Ok(list)
+            })
+            .transpose()?;
+        let loaded_addresses = match &ui_meta.loaded_addresses {
+            Some(ui_loaded_addresses) => LoadedAddresses {
+                writable: parse_pubkeys(&ui_loaded_addresses.writable)?,
+                readonly: parse_pubkeys(&ui_loaded_addresses.readonly)?,
+            },
+            None => LoadedAddresses::default(),
+        };
+        let return_data = ui_meta
+            .return_data
+            .as_ref()
+            .map(|ui_return_data| {
+                let (data, UiReturnDataEncoding::Base64) = &ui_return_data.data;
+                Ok(TransactionReturnData {
+                    program_id: parse_pubkey(&ui_return_data.program_id)?,
+                    data: BASE64_STANDARD
+                        .decode(data)
+                        .map_err(|_| ParseUiTransactionError::InvalidReturnData(data.clone()))?,
+                })
+            })
+            .transpose()?;
+        Ok(Self {
+            status: ui_meta.status.clone(),
+            fee: ui_meta.fee,
+            pre_balances: ui_meta.pre_balances.clone(),
+            post_balances: ui_meta.post_balances.clone(),
+            inner_instructions,
+            log_messages: ui_meta.log_messages.clone(),
+            loaded_addresses,
+            return_data,
+            compute_units_consumed: ui_meta.compute_units_consumed,
+        })
+    }
+}
+
+impl From<&VersionedTransactionWithStatusMeta> for UiTransactionWithStatusMeta {
+    fn from(tx_with_meta: &VersionedTransactionWithStatusMeta) -> Self {
+        Self {
+            transaction: (&tx_with_meta.transaction).into(),
+            meta: Some((&tx_with_meta.meta).into()),
+            version: Some(tx_with_meta.transaction.version()),
+        }
+    }
+}
+
+impl TryFrom<&UiTransactionWithStatusMeta> for VersionedTransactionWithStatusMeta {
+    type Error = ParseUiTransactionError;
+
+    fn try_from(
+        ui_tx_with_meta: &UiTransactionWithStatusMeta,
+    ) -> std::result::Result<Self, Self::Error> {
+        let meta = ui_tx_with_meta
+            .meta
+            .as_ref()
+            .ok_or(ParseUiTransactionError::MissingStatusMeta)?;
+        Ok(Self {
+            transaction: (&ui_tx_with_meta.transaction).try_into()?,
+            meta: meta.try_into()?,
+        })
+    }
+}
+
+fn parse_pubkey(s: &str) -> std::result::Result<Pubkey, ParseUiTransactionError> {
+    Pubkey::from_str(s).map_err(|_| ParseUiTransactionError::InvalidPubkey(s.to_string()))
+}
+
+fn parse_pubkeys(pubkeys: &[String]) -> std::result::Result<Vec<Pubkey>, ParseUiTransactionError> {
+    pubkeys.iter().map(|s| parse_pubkey(s)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_instruction_error::InstructionError,
+        solana_message::{v0, MessageHeader, VersionedMessage},
+        solana_signature::Signature,
+    };
+
+    fn transaction_with_meta() -> VersionedTransactionWithStatusMeta {
+        let transaction = VersionedTransaction {
+            signatures: vec![Signature::from([7; 64])],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![CompiledInstruction::new_from_raw_parts(1, vec![1], vec![0])],
+                address_table_lookups: vec![],
+            }),
+        };
+        let program_id = transaction.message.static_account_keys()[1];
+        let meta = TransactionStatusMeta {
+            status: Err(TransactionError::InstructionError(
+                0,
+                InstructionError::Custom(1),
+            )),
+            fee: 5000,
+            pre_balances: vec![10_000, 1],
+            post_balances: vec![5_000, 1],
+            inner_instructions: Some(vec![vec![InnerInstruction {
+                instruction: CompiledInstruction::new_from_raw_parts(1, vec![2, 3], vec![0]),
+                stack_height: 2,
+            }]]),
+            log_messages: Some(vec![format!("Program {program_id} invoke [1]")]),
+            loaded_addresses: LoadedAddresses::default(),
+            return_data: Some(TransactionReturnData {
+                program_id,
+                data: vec![1, 2, 3],
+            }),
+            compute_units_consumed: Some(1_234),
+        };
+        VersionedTransactionWithStatusMeta { transaction, meta }
+    }
+
+    #[test]
+    fn test_transaction_with_status_meta_round_trip() {
+        let tx_with_meta = transaction_with_meta();
+        let ui_tx_with_meta = UiTransactionWithStatusMeta::from(&tx_with_meta);
+        let json = serde_json::to_value(&ui_tx_with_meta).unwrap();
+        assert_eq!(json["version"], 0);
+        let meta = &json["meta"];
+        assert_eq!(
+            meta["err"],
+            serde_json::json!({"InstructionError": [0, {"Custom": 1}]})
+        );
+        assert_eq!(meta["status"]["Err"], meta["err"]);
+        assert_eq!(meta["preBalances"], serde_json::json!([10_000, 1]));
+        assert_eq!(
+            meta["innerInstructions"],
+            serde_json::json!([{
+                "index": 0,
+                "instructions": [{
+                    "programIdIndex": 1,
+                    "accounts": [0],
+                    "data": "9t",
+                    "stackHeight": 2,
+                }],
+            }])
+        );
+        assert_eq!(
+            meta["loadedAddresses"],
+            serde_json::json!({"writable": [], "readonly": []})
+        );
+        assert_eq!(
+            meta["returnData"]["data"],
+            serde_json::json!(["AQID", "base64"])
+        );
+        assert_eq!(meta["computeUnitsConsumed"], 1_234);
+
+        let decoded: UiTransactionWithStatusMeta = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded, ui_tx_with_meta);
+        assert_eq!(
+            VersionedTransactionWithStatusMeta::try_from(&decoded).unwrap(),
+            tx_with_meta
+        );
+    }
+
+    #[test]
+    fn test_parse_ui_transaction_status_meta() {
+        // Metadata recorded before loaded addresses, return data and stack
+        // heights were
+        let json = serde_json::json!({
+            "err": null,
+            "status": {"Ok": null},
+            "fee": 5000,
+            "preBalances": [1],
+            "postBalances": [1],
+            "innerInstructions": [{
+                "index": 1,
+                "instructions": [{"programIdIndex": 1, "accounts": [], "data": ""}],
+            }],
+            "logMessages": null,
+        });
+        let ui_meta: UiTransactionStatusMeta = serde_json::from_value(json).unwrap();
+        let meta = TransactionStatusMeta::try_from(&ui_meta).unwrap();
+        assert_eq!(meta.status, Ok(()));
+        assert_eq!(meta.loaded_addresses, LoadedAddresses::default());
+        let inner_instructions = meta.inner_instructions.unwrap();
+        assert_eq!(inner_instructions.len(), 2);
+        assert!(inner_instructions[0].is_empty());
+        assert_eq!(inner_instructions[1][0].stack_height, 2);
+
+        let mut ui_tx_with_meta = UiTransactionWithStatusMeta::from(&transaction_with_meta());
+        ui_tx_with_meta.meta = None;
+        assert_eq!(
+            VersionedTransactionWithStatusMeta::try_from(&ui_tx_with_meta),
+            Err(ParseUiTransactionError::MissingStatusMeta)
+        );
+    }
+}
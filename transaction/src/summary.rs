@@ -0,0 +1,161 @@
+//! Summaries of messages for users to review before signing.
+//!
+//! [`confirming_signer`] wraps a signer so that it asks for confirmation of
+//! the [`MessageSummary`] of each message before signing it.
+#![cfg(feature = "bincode")]
+
+use {
+    crate::policy::decode_message,
+    solana_message::VersionedMessage,
+    solana_pubkey::Pubkey,
+    solana_signer::{confirming::ConfirmingSigner, Signer},
+};
+
+/// What a message does, in terms a user can review.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageSummary {
+    pub fee_payer: Pubkey,
+    /// Accounts that must sign, starting with the fee payer.
+    pub signers: Vec<Pubkey>,
+    /// Invoked programs, in order of first invocation.
+    pub programs: Vec<Pubkey>,
+    /// Statically listed accounts the message may write to.
+    pub writable_accounts: Vec<Pubkey>,
+    /// Number of accounts loaded as writable from address lookup tables,
+    /// which cannot be named without fetching the tables.
+    pub num_writable_lookup_accounts: usize,
+}
+
+impl MessageSummary {
+    pub fn new(message: &VersionedMessage) -> Self {
+        let account_keys = message.static_account_keys();
+        let mut programs = vec![];
+        for instruction in message.instructions() {
+            let program_id = instruction.program_id(account_keys);
+            if !programs.contains(program_id) {
+                programs.push(*program_id);
+            }
+        }
+        Self {
+            fee_payer: account_keys.first().copied().unwrap_or_default(),
+            signers: account_keys
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| message.is_signer(*index))
+                .map(|(_, key)| *key)
+                .collect(),
+            programs,
+            writable_accounts: account_keys
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| message.is_maybe_writable(*index, None))
+                .map(|(_, key)| *key)
+                .collect(),
+            num_writable_lookup_accounts: message
+                .address_table_lookups()
+                .unwrap_or_default()
+                .iter()
+                .map(|lookup| lookup.writable_indexes.len())
+                .sum(),
+        }
+    }
+
+    /// Summarize the message `bytes`, or return `None` if they are not
+    /// exactly one valid message of a known version.
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        decode_message(bytes).map(|message| Self::new(&message))
+    }
+}
+
+/// Wrap `signer` in a [`ConfirmingSigner`] that signs only the messages whose
+/// summary `confirm` approves.
+///
+/// Messages that are not exactly one valid message of a known version cannot
+/// be summarized, and are rejected without calling `confirm`.
+pub fn confirming_signer<S, F>(signer: S, confirm: F) -> ConfirmingSigner<S, impl Fn(&[u8]) -> bool>
+where
+    S: Signer,
+    F: Fn(&MessageSummary) -> bool,
+{
+    ConfirmingSigner::new(signer, move |message: &[u8]| {
+        MessageSummary::decode(message).is_some_and(|summary| confirm(&summary))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::Message,
+        solana_sdk_ids::system_program,
+        solana_signer::SignerError,
+        solana_system_interface::instruction as system_instruction,
+        std::cell::Cell,
+    };
+
+    #[test]
+    fn test_message_summary() {
+        let payer = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let readonly = Pubkey::new_unique();
+        let instructions = [
+            system_instruction::transfer(&payer, &recipient, 1),
+            Instruction::new_with_bytes(
+                program_id,
+                &[],
+                vec![AccountMeta::new_readonly(readonly, false)],
+            ),
+            system_instruction::transfer(&payer, &recipient, 1),
+        ];
+        let message = Message::new_with_blockhash(&instructions, Some(&payer), &Hash::new_unique());
+        let summary = MessageSummary::decode(&message.serialize()).unwrap();
+        assert_eq!(summary.fee_payer, payer);
+        assert_eq!(summary.signers, vec![payer]);
+        assert_eq!(summary.programs, vec![system_program::id(), program_id]);
+        assert_eq!(summary.writable_accounts, vec![payer, recipient]);
+        assert_eq!(summary.num_writable_lookup_accounts, 0);
+
+        assert_eq!(MessageSummary::decode(b"not a message"), None);
+    }
+
+    #[test]
+    fn test_confirming_signer_with_summary() {
+        let keypair = Keypair::new();
+        let payer = keypair.pubkey();
+        let message = Message::new_with_blockhash(
+            &[system_instruction::transfer(
+                &payer,
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer),
+            &Hash::new_unique(),
+        )
+        .serialize();
+
+        let approve = Cell::new(true);
+        let signer = confirming_signer(keypair, |summary| {
+            assert_eq!(summary.fee_payer, payer);
+            approve.get()
+        });
+        assert!(signer.is_interactive());
+        let signature = signer.try_sign_message(&message).unwrap();
+        assert!(signature.verify(payer.as_ref(), &message));
+
+        approve.set(false);
+        assert_eq!(
+            signer.try_sign_message(&message),
+            Err(SignerError::UserCancel("signing rejected".to_string()))
+        );
+
+        approve.set(true);
+        assert_eq!(
+            signer.try_sign_message(b"not a message"),
+            Err(SignerError::UserCancel("signing rejected".to_string()))
+        );
+    }
+}
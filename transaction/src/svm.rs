@@ -0,0 +1,230 @@
+//! Traits abstracting over sanitized transactions.
+//!
+//! The SVM only needs a handful of properties of a transaction to load and
+//! execute it: its account keys and their roles, its instructions, its
+//! signatures and whether it uses a durable nonce. [`SVMMessage`] and
+//! [`SVMTransaction`] describe that surface so that an SVM implementation can
+//! accept a [`SanitizedTransaction`] as well as a transaction type that reads
+//! these properties from a view of the serialized transaction.
+#![cfg(feature = "bincode")]
+
+use {
+    crate::sanitized::SanitizedTransaction,
+    solana_hash::Hash,
+    solana_message::{compiled_instruction::CompiledInstruction, AccountKeys},
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
+};
+
+/// A compiled instruction borrowed from a message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SVMInstruction<'a> {
+    /// Index into the account keys of the program to invoke.
+    pub program_id_index: u8,
+    /// Indexes into the account keys of the instruction accounts.
+    pub accounts: &'a [u8],
+    pub data: &'a [u8],
+}
+
+impl<'a> From<&'a CompiledInstruction> for SVMInstruction<'a> {
+    fn from(instruction: &'a CompiledInstruction) -> Self {
+        Self {
+            program_id_index: instruction.program_id_index,
+            accounts: &instruction.accounts,
+            data: &instruction.data,
+        }
+    }
+}
+
+/// A sanitized message whose addresses have been loaded.
+///
+/// Account indexes cover the static account keys followed by the loaded
+/// addresses, as in [`SVMMessage::account_keys`].
+pub trait SVMMessage {
+    /// Return the number of signatures required by the message.
+    fn num_transaction_signatures(&self) -> u64;
+
+    /// Return the number of write locks requested by the message, before
+    /// any demotion.
+    fn num_write_locks(&self) -> u64;
+
+    fn recent_blockhash(&self) -> &Hash;
+
+    fn num_instructions(&self) -> usize;
+
+    fn instructions_iter(&self) -> impl Iterator<Item = SVMInstruction<'_>>;
+
+    /// Iterate over the instructions with the id of the program they invoke.
+    fn program_instructions_iter(
+        &self,
+    ) -> impl Iterator<Item = (&Pubkey, SVMInstruction<'_>)> + Clone;
+
+    fn static_account_keys(&self) -> &[Pubkey];
+
+    /// Return the static account keys followed by the loaded addresses.
+    fn account_keys(&self) -> AccountKeys<'_>;
+
+    fn fee_payer(&self) -> &Pubkey;
+
+    /// Returns true if the account at `index` is writable once demotion is
+    /// applied.
+    fn is_writable(&self, index: usize) -> bool;
+
+    fn is_signer(&self, index: usize) -> bool;
+
+    /// Returns true if the account at `index` is invoked as a program.
+    fn is_invoked(&self, index: usize) -> bool;
+
+    /// Returns true if the account at `index` is passed to an instruction.
+    fn is_instruction_account(&self, index: usize) -> bool;
+
+    /// If the message uses a durable nonce, return the nonce account.
+    fn get_durable_nonce(&self) -> Option<&Pubkey>;
+
+    /// Return the signers among the accounts of the instruction at `index`.
+    fn get_ix_signers(&self, index: usize) -> impl Iterator<Item = &Pubkey>;
+}
+
+/// A sanitized transaction: an [`SVMMessage`] and its signatures.
+pub trait SVMTransaction: SVMMessage {
+    /// Return the first signature, which identifies the transaction.
+    fn signature(&self) -> &Signature;
+
+    fn signatures(&self) -> &[Signature];
+}
+
+impl SVMMessage for SanitizedTransaction {
+    fn num_transaction_signatures(&self) -> u64 {
+        u64::from(self.message().header().num_required_signatures)
+    }
+
+    fn num_write_locks(&self) -> u64 {
+        self.message().num_write_locks()
+    }
+
+    fn recent_blockhash(&self) -> &Hash {
+        self.message().recent_blockhash()
+    }
+
+    fn num_instructions(&self) -> usize {
+        self.message().instructions().len()
+    }
+
+    fn instructions_iter(&self) -> impl Iterator<Item = SVMInstruction<'_>> {
+        self.message().instructions().iter().map(Into::into)
+    }
+
+    fn program_instructions_iter(
+        &self,
+    ) -> impl Iterator<Item = (&Pubkey, SVMInstruction<'_>)> + Clone {
+        self.message()
+            .program_instructions_iter()
+            .map(|(program_id, instruction)| (program_id, instruction.into()))
+    }
+
+    fn static_account_keys(&self) -> &[Pubkey] {
+        self.message().static_account_keys()
+    }
+
+    fn account_keys(&self) -> AccountKeys<'_> {
+        self.message().account_keys()
+    }
+
+    fn fee_payer(&self) -> &Pubkey {
+        self.message().fee_payer()
+    }
+
+    fn is_writable(&self, index: usize) -> bool {
+        self.message().is_writable(index)
+    }
+
+    fn is_signer(&self, index: usize) -> bool {
+        self.message().is_signer(index)
+    }
+
+    fn is_invoked(&self, index: usize) -> bool {
+        self.message().is_invoked(index)
+    }
+
+    fn is_instruction_account(&self, index: usize) -> bool {
+        self.message().is_instruction_account(index)
+    }
+
+    fn get_durable_nonce(&self) -> Option<&Pubkey> {
+        self.message().get_durable_nonce()
+    }
+
+    fn get_ix_signers(&self, index: usize) -> impl Iterator<Item = &Pubkey> {
+        self.message().get_ix_signers(index)
+    }
+}
+
+impl SVMTransaction for SanitizedTransaction {
+    fn signature(&self) -> &Signature {
+        SanitizedTransaction::signature(self)
+    }
+
+    fn signatures(&self) -> &[Signature] {
+        SanitizedTransaction::signatures(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::Transaction, solana_keypair::Keypair, solana_message::Message,
+        solana_signer::Signer, solana_system_interface::instruction as system_instruction,
+    };
+
+    /// Returns the writable accounts of `tx`, as an SVM implementation
+    /// generic over transactions would.
+    fn writable_accounts(tx: &impl SVMTransaction) -> Vec<Pubkey> {
+        tx.account_keys()
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| tx.is_writable(*index))
+            .map(|(_, pubkey)| *pubkey)
+            .collect()
+    }
+
+    #[test]
+    fn test_sanitized_transaction_svm_transaction() {
+        let payer = Keypair::new();
+        let nonce = Pubkey::new_unique();
+        let to = Pubkey::new_unique();
+        let message = Message::new(
+            &[
+                system_instruction::advance_nonce_account(&nonce, &payer.pubkey()),
+                system_instruction::transfer(&payer.pubkey(), &to, 1),
+            ],
+            Some(&payer.pubkey()),
+        );
+        let tx = SanitizedTransaction::from_transaction_for_tests(Transaction::new(
+            &[&payer],
+            message,
+            Hash::new_unique(),
+        ));
+
+        assert_eq!(SVMTransaction::signature(&tx), &tx.signatures()[0]);
+        assert_eq!(tx.num_transaction_signatures(), 1);
+        assert_eq!(SVMMessage::fee_payer(&tx), &payer.pubkey());
+        assert_eq!(SVMMessage::get_durable_nonce(&tx), Some(&nonce));
+        assert_eq!(tx.num_instructions(), 2);
+        assert_eq!(tx.num_write_locks(), 3);
+
+        let mut writable = writable_accounts(&tx);
+        writable.sort();
+        let mut expected = vec![payer.pubkey(), nonce, to];
+        expected.sort();
+        assert_eq!(writable, expected);
+
+        let (program_id, instruction) = tx.program_instructions_iter().nth(1).unwrap();
+        assert_eq!(program_id, &solana_sdk_ids::system_program::id());
+        assert_eq!(instruction.data, &tx.message().instructions()[1].data[..]);
+        assert!(tx.is_invoked(usize::from(instruction.program_id_index)));
+        assert_eq!(
+            SVMMessage::get_ix_signers(&tx, 1).collect::<Vec<_>>(),
+            vec![&payer.pubkey()]
+        );
+    }
+}
@@ -0,0 +1,177 @@
+//! Transactions prepared ahead of signing.
+//!
+//! Services that build transactions for a user to sign later do not know the
+//! blockhash the transaction will be signed with, and do not hold the signing
+//! keys. An [`UnsignedTransaction`] is the compiled message together with the
+//! keys that must sign it, without placeholder signatures; the blockhash is
+//! filled in with [`UnsignedTransaction::fill_blockhash`] right before it is
+//! signed with [`UnsignedTransaction::into_signed`].
+#![cfg(feature = "bincode")]
+
+#[cfg(feature = "serde")]
+use serde_derive::{Deserialize, Serialize};
+use {
+    crate::versioned::VersionedTransaction,
+    solana_hash::Hash,
+    solana_message::VersionedMessage,
+    solana_pubkey::Pubkey,
+    solana_signer::{signers::Signers, SignerError},
+};
+
+/// A compiled message and the keys that must sign it.
+#[cfg_attr(
+    feature = "serde",
+    derive(Deserialize, Serialize),
+    serde(rename_all = "camelCase", try_from = "UnsignedTransactionFields")
+)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnsignedTransaction {
+    message: VersionedMessage,
+    signers: Vec<Pubkey>,
+}
+
+/// The fields of a deserialized [`UnsignedTransaction`], whose signers must
+/// be the ones required by its message.
+#[cfg(feature = "serde")]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UnsignedTransactionFields {
+    message: VersionedMessage,
+    signers: Vec<Pubkey>,
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<UnsignedTransactionFields> for UnsignedTransaction {
+    type Error = &'static str;
+
+    fn try_from(fields: UnsignedTransactionFields) -> Result<Self, Self::Error> {
+        let unsigned = Self::new(fields.message);
+        if unsigned.signers != fields.signers {
+            return Err("signers do not match the message");
+        }
+        Ok(unsigned)
+    }
+}
+
+impl UnsignedTransaction {
+    /// Create an unsigned transaction for `message`, which may still have the
+    /// default blockhash.
+    pub fn new(message: VersionedMessage) -> Self {
+        let static_account_keys = message.static_account_keys();
+        let num_required_signatures =
+            usize::from(message.header().num_required_signatures).min(static_account_keys.len());
+        let signers = static_account_keys[..num_required_signatures].to_vec();
+        Self { message, signers }
+    }
+
+    pub fn message(&self) -> &VersionedMessage {
+        &self.message
+    }
+
+    pub fn into_message(self) -> VersionedMessage {
+        self.message
+    }
+
+    /// Return the keys that must sign the message, in signature order.
+    pub fn signers(&self) -> &[Pubkey] {
+        &self.signers
+    }
+
+    /// Returns true if a blockhash other than the default one has been set.
+    pub fn has_blockhash(&self) -> bool {
+        self.message.recent_blockhash() != &Hash::default()
+    }
+
+    /// Set the recent blockhash of the message.
+    pub fn fill_blockhash(&mut self, recent_blockhash: Hash) {
+        self.message.set_recent_blockhash(recent_blockhash);
+    }
+
+    /// Sign the message with `keypairs`, which must be exactly the required
+    /// signers in any order.
+    ///
+    /// Fails if the blockhash has not been filled in.
+    pub fn into_signed<T: Signers + ?Sized>(
+        self,
+        keypairs: &T,
+    ) -> Result<VersionedTransaction, SignerError> {
+        if !self.has_blockhash() {
+            return Err(SignerError::InvalidInput(
+                "missing recent blockhash".to_string(),
+            ));
+        }
+        VersionedTransaction::try_new(self.message, keypairs)
+    }
+}
+
+impl From<VersionedMessage> for UnsignedTransaction {
+    fn from(message: VersionedMessage) -> Self {
+        Self::new(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::Message,
+        solana_signer::Signer,
+    };
+
+    #[test]
+    fn test_unsigned_transaction() {
+        let payer = Keypair::new();
+        let other = Keypair::new();
+        let instruction = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[0],
+            vec![AccountMeta::new_readonly(other.pubkey(), true)],
+        );
+        let message = Message::new(&[instruction], Some(&payer.pubkey()));
+        let mut unsigned = UnsignedTransaction::new(VersionedMessage::Legacy(message));
+        assert_eq!(unsigned.signers(), &[payer.pubkey(), other.pubkey()]);
+        assert!(!unsigned.has_blockhash());
+        assert_eq!(
+            unsigned.clone().into_signed(&[&payer, &other]),
+            Err(SignerError::InvalidInput(
+                "missing recent blockhash".to_string()
+            ))
+        );
+
+        let blockhash = Hash::new_unique();
+        unsigned.fill_blockhash(blockhash);
+        assert_eq!(
+            unsigned.clone().into_signed(&[&payer]),
+            Err(SignerError::NotEnoughSigners)
+        );
+        let tx = unsigned.into_signed(&[&other, &payer]).unwrap();
+        assert_eq!(tx.message.recent_blockhash(), &blockhash);
+        assert_eq!(tx.verify_with_results(), vec![true, true]);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_unsigned_transaction_serde() {
+        let payer = Pubkey::new_unique();
+        let message = Message::new(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[0],
+                vec![AccountMeta::new(payer, true)],
+            )],
+            Some(&payer),
+        );
+        let unsigned = UnsignedTransaction::new(VersionedMessage::Legacy(message));
+        let bytes = bincode::serialize(&unsigned).unwrap();
+        assert_eq!(
+            bincode::deserialize::<UnsignedTransaction>(&bytes).unwrap(),
+            unsigned
+        );
+
+        let mismatched =
+            bincode::serialize(&(unsigned.message(), vec![Pubkey::new_unique()])).unwrap();
+        assert!(bincode::deserialize::<UnsignedTransaction>(&mismatched).is_err());
+    }
+}
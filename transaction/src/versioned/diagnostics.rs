@@ -0,0 +1,155 @@
+//! Wire decoding that reports where a malformed transaction goes wrong.
+//!
+//! Deserializing a transaction with bincode only tells that decoding failed.
+//! [`VersionedTransaction::decode_with_diagnostics`] parses the same wire
+//! encoding with [`TransactionView`] and, on failure, reports the field being
+//! decoded and the byte offset at which it starts, such as "instruction 3
+//! accounts shortvec overflows buffer at offset 812".
+#![cfg(feature = "diagnostics")]
+
+pub use crate::view::{WireError, WireErrorKind, WireField};
+use crate::{versioned::VersionedTransaction, view::TransactionView};
+
+impl VersionedTransaction {
+    /// Decode the wire encoding of a transaction, reporting the field and
+    /// offset at which decoding fails.
+    ///
+    /// Unlike bincode deserialization, trailing bytes are rejected, as they
+    /// are for transactions received in packets.
+    pub fn decode_with_diagnostics(bytes: &[u8]) -> Result<Self, WireError> {
+        TransactionView::parse(bytes).map(|view| Self::from_view(&view))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        solana_hash::Hash,
+        solana_instruction::Instruction,
+        solana_keypair::Keypair,
+        solana_message::{
+            compiled_instruction::CompiledInstruction,
+            v0::{self, MessageAddressTableLookup},
+            MessageHeader, VersionedMessage, MESSAGE_VERSION_PREFIX,
+        },
+        solana_pubkey::Pubkey,
+        solana_signature::Signature,
+        solana_signer::Signer,
+    };
+
+    fn v0_transaction() -> VersionedTransaction {
+        VersionedTransaction {
+            signatures: vec![Signature::new_unique()],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 1,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![Pubkey::new_unique(), Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![
+                    CompiledInstruction::new_from_raw_parts(1, vec![7; 3], vec![0, 2]),
+                    CompiledInstruction::new_from_raw_parts(1, vec![], vec![0, 2, 3]),
+                ],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1],
+                }],
+            }),
+        }
+    }
+
+    #[test]
+    fn test_decode_with_diagnostics() {
+        let payer = Keypair::new();
+        let legacy_tx = VersionedTransaction::from(Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1, 2, 3],
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        ));
+        for tx in [legacy_tx, v0_transaction()] {
+            let bytes = bincode::serialize(&tx).unwrap();
+            assert_eq!(
+                VersionedTransaction::decode_with_diagnostics(&bytes),
+                Ok(tx)
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_with_diagnostics_errors() {
+        let bytes = bincode::serialize(&v0_transaction()).unwrap();
+        // signatures, version prefix, header, account keys and blockhash
+        let instructions_offset = 1 + 64 + 1 + 3 + 1 + 2 * 32 + 32;
+        // instructions shortvec, first instruction, second program id index
+        let accounts_offset = instructions_offset + 1 + 1 + 3 + 4 + 1;
+
+        // every truncation must fail
+        for len in 0..bytes.len() {
+            assert!(VersionedTransaction::decode_with_diagnostics(&bytes[..len]).is_err());
+        }
+
+        let err = VersionedTransaction::decode_with_diagnostics(&bytes[..accounts_offset + 2])
+            .unwrap_err();
+        assert_eq!(
+            err,
+            WireError {
+                offset: accounts_offset,
+                field: WireField::InstructionAccounts(1),
+                kind: WireErrorKind::OverflowsBuffer,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            format!("instruction 1 accounts shortvec overflows buffer at offset {accounts_offset}")
+        );
+
+        let mut invalid_len = bytes.clone();
+        invalid_len[instructions_offset] = 0x80;
+        invalid_len[instructions_offset + 1] = 0x00;
+        assert_eq!(
+            VersionedTransaction::decode_with_diagnostics(&invalid_len),
+            Err(WireError {
+                offset: instructions_offset,
+                field: WireField::Instructions,
+                kind: WireErrorKind::InvalidLength,
+            })
+        );
+
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(
+            VersionedTransaction::decode_with_diagnostics(&trailing),
+            Err(WireError {
+                offset: bytes.len(),
+                field: WireField::Transaction,
+                kind: WireErrorKind::TrailingBytes,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_with_diagnostics_unsupported_version() {
+        let tx = v0_transaction();
+        let mut bytes = bincode::serialize(&tx).unwrap();
+        let version_offset = 1 + 64;
+        bytes[version_offset] = MESSAGE_VERSION_PREFIX | 1;
+        assert_eq!(
+            VersionedTransaction::decode_with_diagnostics(&bytes),
+            Err(WireError {
+                offset: version_offset,
+                field: WireField::MessageVersion,
+                kind: WireErrorKind::UnsupportedVersion(1),
+            })
+        );
+    }
+}
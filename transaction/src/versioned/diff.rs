@@ -0,0 +1,295 @@
+//! Structured comparison of two versioned transactions.
+//!
+//! [`VersionedTransaction::diff`] reports what changed between two messages
+//! in terms a user can review, such as a wallet showing how a re-quoted
+//! transaction differs from the one that was first proposed. Instructions are
+//! compared by position, and their accounts are compared by address rather than
+//! by index, so that reordering the account keys alone does not show up as an
+//! instruction change.
+
+use {
+    crate::versioned::{TransactionVersion, VersionedTransaction},
+    solana_hash::Hash,
+    solana_message::{
+        compiled_instruction::CompiledInstruction, v0::MessageAddressTableLookup, MessageHeader,
+        VersionedMessage,
+    },
+    solana_pubkey::Pubkey,
+};
+
+/// A value that differs between the old and the new transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+impl<T: PartialEq> Change<T> {
+    fn compare(old: T, new: T) -> Option<Self> {
+        (old != new).then_some(Self { old, new })
+    }
+}
+
+/// An account referenced by a compiled instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountRef {
+    /// An account key stored in the message.
+    Static(Pubkey),
+    /// An account loaded from an address lookup table, identified by its
+    /// position among the loaded addresses.
+    Loaded(usize),
+}
+
+/// An instruction with its account indexes resolved against the message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedInstruction {
+    pub program_id: AccountRef,
+    pub accounts: Vec<AccountRef>,
+    pub data: Vec<u8>,
+}
+
+/// A difference between the instructions at the same position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstructionDiff {
+    /// The new transaction has an instruction at this index and the old one
+    /// does not.
+    Added {
+        index: usize,
+        instruction: ResolvedInstruction,
+    },
+    /// The old transaction has an instruction at this index and the new one
+    /// does not.
+    Removed {
+        index: usize,
+        instruction: ResolvedInstruction,
+    },
+    /// The instruction at this index differs in program, accounts or data.
+    Changed {
+        index: usize,
+        change: Change<ResolvedInstruction>,
+    },
+}
+
+/// Differences between two transactions, from the old to the new one.
+///
+/// Signatures are not compared: they change whenever the message does.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TransactionDiff {
+    pub version: Option<Change<TransactionVersion>>,
+    pub header: Option<Change<MessageHeader>>,
+    /// Static account keys of the new message that the old one lacks, in
+    /// message order.
+    pub added_account_keys: Vec<Pubkey>,
+    /// Static account keys of the old message that the new one lacks, in
+    /// message order.
+    pub removed_account_keys: Vec<Pubkey>,
+    pub recent_blockhash: Option<Change<Hash>>,
+    pub instructions: Vec<InstructionDiff>,
+    pub address_table_lookups: Option<Change<Vec<MessageAddressTableLookup>>>,
+}
+
+impl TransactionDiff {
+    /// Returns true if the messages of both transactions are equivalent.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
+impl VersionedTransaction {
+    /// Compare this transaction against `other`, treating `self` as the old
+    /// transaction and `other` as the new one.
+    pub fn diff(&self, other: &Self) -> TransactionDiff {
+        let (old, new) = (&self.message, &other.message);
+        let old_keys = old.static_account_keys();
+        let new_keys = new.static_account_keys();
+
+        let num_instructions = old.instructions().len().max(new.instructions().len());
+        let instructions = (0..num_instructions)
+            .filter_map(|index| {
+                let old_instruction = old
+                    .instructions()
+                    .get(index)
+                    .map(|instruction| resolve(old_keys, instruction));
+                let new_instruction = new
+                    .instructions()
+                    .get(index)
+                    .map(|instruction| resolve(new_keys, instruction));
+                match (old_instruction, new_instruction) {
+                    (Some(old), Some(new)) => Change::compare(old, new)
+                        .map(|change| InstructionDiff::Changed { index, change }),
+                    (None, Some(instruction)) => {
+                        Some(InstructionDiff::Added { index, instruction })
+                    }
+                    (Some(instruction), None) => {
+                        Some(InstructionDiff::Removed { index, instruction })
+                    }
+                    (None, None) => None,
+                }
+            })
+            .collect();
+
+        TransactionDiff {
+            version: Change::compare(self.version(), other.version()),
+            header: Change::compare(*old.header(), *new.header()),
+            added_account_keys: missing_keys(new_keys, old_keys),
+            removed_account_keys: missing_keys(old_keys, new_keys),
+            recent_blockhash: Change::compare(*old.recent_blockhash(), *new.recent_blockhash()),
+            instructions,
+            address_table_lookups: Change::compare(
+                address_table_lookups(old),
+                address_table_lookups(new),
+            ),
+        }
+    }
+}
+
+fn resolve(
+    static_account_keys: &[Pubkey],
+    instruction: &CompiledInstruction,
+) -> ResolvedInstruction {
+    let resolve_index = |index: u8| {
+        let index = usize::from(index);
+        match static_account_keys.get(index) {
+            Some(pubkey) => AccountRef::Static(*pubkey),
+            None => AccountRef::Loaded(index.saturating_sub(static_account_keys.len())),
+        }
+    };
+    ResolvedInstruction {
+        program_id: resolve_index(instruction.program_id_index),
+        accounts: instruction
+            .accounts
+            .iter()
+            .map(|index| resolve_index(*index))
+            .collect(),
+        data: instruction.data.clone(),
+    }
+}
+
+/// Keys of `keys` that are not in `other`.
+fn missing_keys(keys: &[Pubkey], other: &[Pubkey]) -> Vec<Pubkey> {
+    keys.iter()
+        .filter(|key| !other.contains(key))
+        .copied()
+        .collect()
+}
+
+fn address_table_lookups(message: &VersionedMessage) -> Vec<MessageAddressTableLookup> {
+    message
+        .address_table_lookups()
+        .map(<[_]>::to_vec)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_message::Message as LegacyMessage,
+        solana_signature::Signature,
+    };
+
+    fn transfer_message(payer: &Pubkey, recipient: &Pubkey, program_id: &Pubkey) -> LegacyMessage {
+        LegacyMessage::new(
+            &[Instruction::new_with_bytes(
+                *program_id,
+                &[1, 2, 3],
+                vec![
+                    AccountMeta::new(*payer, true),
+                    AccountMeta::new(*recipient, false),
+                ],
+            )],
+            Some(payer),
+        )
+    }
+
+    fn tx(message: LegacyMessage) -> VersionedTransaction {
+        VersionedTransaction {
+            signatures: vec![Signature::default()],
+            message: VersionedMessage::Legacy(message),
+        }
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let payer = Pubkey::new_unique();
+        let tx = tx(transfer_message(
+            &payer,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+        ));
+        assert!(tx.diff(&tx).is_empty());
+    }
+
+    #[test]
+    fn test_diff_changed_recipient_and_blockhash() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let old_recipient = Pubkey::new_unique();
+        let new_recipient = Pubkey::new_unique();
+        let old = tx(transfer_message(&payer, &old_recipient, &program_id));
+        let mut new_message = transfer_message(&payer, &new_recipient, &program_id);
+        new_message.recent_blockhash = Hash::new_unique();
+        let new = tx(new_message);
+
+        let diff = old.diff(&new);
+        assert_eq!(diff.version, None);
+        assert_eq!(diff.header, None);
+        assert_eq!(diff.added_account_keys, vec![new_recipient]);
+        assert_eq!(diff.removed_account_keys, vec![old_recipient]);
+        assert_eq!(
+            diff.recent_blockhash,
+            Some(Change {
+                old: Hash::default(),
+                new: *new.message.recent_blockhash(),
+            })
+        );
+        let InstructionDiff::Changed { index, change } = &diff.instructions[0] else {
+            panic!("expected a changed instruction");
+        };
+        assert_eq!(*index, 0);
+        assert_eq!(change.old.accounts[1], AccountRef::Static(old_recipient));
+        assert_eq!(change.new.accounts[1], AccountRef::Static(new_recipient));
+        assert_eq!(change.old.data, change.new.data);
+        assert_eq!(diff.address_table_lookups, None);
+    }
+
+    #[test]
+    fn test_diff_added_instruction() {
+        let payer = Pubkey::new_unique();
+        let program_id = Pubkey::new_unique();
+        let recipient = Pubkey::new_unique();
+        let old = tx(transfer_message(&payer, &recipient, &program_id));
+        let instructions = [
+            Instruction::new_with_bytes(
+                program_id,
+                &[1, 2, 3],
+                vec![
+                    AccountMeta::new(payer, true),
+                    AccountMeta::new(recipient, false),
+                ],
+            ),
+            Instruction::new_with_bytes(program_id, &[4], vec![]),
+        ];
+        let new = tx(LegacyMessage::new(&instructions, Some(&payer)));
+
+        let diff = old.diff(&new);
+        assert_eq!(
+            diff.instructions,
+            vec![InstructionDiff::Added {
+                index: 1,
+                instruction: ResolvedInstruction {
+                    program_id: AccountRef::Static(program_id),
+                    accounts: vec![],
+                    data: vec![4],
+                },
+            }]
+        );
+        assert!(diff.added_account_keys.is_empty());
+        assert!(new
+            .diff(&old)
+            .instructions
+            .iter()
+            .all(|diff| matches!(diff, InstructionDiff::Removed { index: 1, .. })));
+    }
+}
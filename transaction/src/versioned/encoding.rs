@@ -0,0 +1,188 @@
+//! Decoding of encoded wire transactions with a size limit.
+//!
+//! RPC methods such as `sendTransaction` accept a transaction as the base64 or
+//! base58 encoding of its wire format. [`VersionedTransaction::from_base64_str`]
+//! and [`VersionedTransaction::from_base58_str`] decode such strings the way
+//! the RPC does, rejecting oversized input before decoding it, and report why
+//! decoding failed.
+#![cfg(feature = "encoding")]
+
+use {
+    crate::versioned::VersionedTransaction,
+    base64::{prelude::BASE64_STANDARD, Engine},
+    bincode::Options,
+    std::fmt,
+};
+
+/// Errors that may be returned when decoding an encoded transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeTransactionError {
+    /// The encoded string is longer than the encoding of any transaction of
+    /// the maximum size.
+    EncodedTooLarge { len: usize, max_len: usize },
+    /// The string is not valid base64 or base58.
+    InvalidEncoding(String),
+    /// The decoded transaction is larger than the maximum size.
+    TooLarge { size: usize, max_size: usize },
+    /// The decoded bytes are not a valid wire transaction.
+    InvalidTransaction(String),
+}
+
+impl std::error::Error for DecodeTransactionError {}
+
+impl fmt::Display for DecodeTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::EncodedTooLarge { len, max_len } => {
+                write!(
+                    f,
+                    "encoded transaction too large: {len} bytes, max {max_len}"
+                )
+            }
+            Self::InvalidEncoding(err) => write!(f, "invalid transaction encoding: {err}"),
+            Self::TooLarge { size, max_size } => {
+                write!(f, "transaction too large: {size} bytes, max {max_size}")
+            }
+            Self::InvalidTransaction(err) => write!(f, "invalid transaction: {err}"),
+        }
+    }
+}
+
+impl VersionedTransaction {
+    /// Decode a base64 encoded wire transaction of at most `max_size` bytes.
+    ///
+    /// As with the RPC, bytes following the transaction are ignored. The
+    /// transaction is not sanitized.
+    pub fn from_base64_str(encoded: &str, max_size: usize) -> Result<Self, DecodeTransactionError> {
+        check_encoded_len(encoded, max_size.div_ceil(3).saturating_mul(4))?;
+        let bytes = BASE64_STANDARD
+            .decode(encoded)
+            .map_err(|err| DecodeTransactionError::InvalidEncoding(err.to_string()))?;
+        deserialize(&bytes, max_size)
+    }
+
+    /// Decode a base58 encoded wire transaction of at most `max_size` bytes.
+    ///
+    /// As with the RPC, bytes following the transaction are ignored. The
+    /// transaction is not sanitized.
+    pub fn from_base58_str(encoded: &str, max_size: usize) -> Result<Self, DecodeTransactionError> {
+        // Each byte takes at most log(256) / log(58) < 1.38 base58 digits.
+        check_encoded_len(
+            encoded,
+            max_size
+                .saturating_mul(138)
+                .saturating_div(100)
+                .saturating_add(1),
+        )?;
+        let bytes = bs58::decode(encoded)
+            .into_vec()
+            .map_err(|err| DecodeTransactionError::InvalidEncoding(err.to_string()))?;
+        deserialize(&bytes, max_size)
+    }
+}
+
+fn check_encoded_len(encoded: &str, max_len: usize) -> Result<(), DecodeTransactionError> {
+    if encoded.len() > max_len {
+        return Err(DecodeTransactionError::EncodedTooLarge {
+            len: encoded.len(),
+            max_len,
+        });
+    }
+    Ok(())
+}
+
+fn deserialize(
+    bytes: &[u8],
+    max_size: usize,
+) -> Result<VersionedTransaction, DecodeTransactionError> {
+    if bytes.len() > max_size {
+        return Err(DecodeTransactionError::TooLarge {
+            size: bytes.len(),
+            max_size,
+        });
+    }
+    bincode::options()
+        .with_limit(max_size as u64)
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .deserialize(bytes)
+        .map_err(|err| DecodeTransactionError::InvalidTransaction(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*, crate::Transaction, solana_hash::Hash, solana_instruction::Instruction,
+        solana_keypair::Keypair, solana_pubkey::Pubkey, solana_signer::Signer,
+    };
+
+    const MAX_SIZE: usize = 1232;
+
+    fn transaction(data_len: usize) -> VersionedTransaction {
+        let payer = Keypair::new();
+        Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &vec![7; data_len],
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_from_encoded_str() {
+        let tx = transaction(100);
+        let bytes = bincode::serialize(&tx).unwrap();
+        assert_eq!(
+            VersionedTransaction::from_base64_str(&BASE64_STANDARD.encode(&bytes), MAX_SIZE),
+            Ok(tx.clone())
+        );
+        assert_eq!(
+            VersionedTransaction::from_base58_str(&bs58::encode(&bytes).into_string(), MAX_SIZE),
+            Ok(tx)
+        );
+    }
+
+    #[test]
+    fn test_from_encoded_str_errors() {
+        assert!(matches!(
+            VersionedTransaction::from_base64_str("not base64!", MAX_SIZE),
+            Err(DecodeTransactionError::InvalidEncoding(_))
+        ));
+        assert!(matches!(
+            VersionedTransaction::from_base58_str("0OIl", MAX_SIZE),
+            Err(DecodeTransactionError::InvalidEncoding(_))
+        ));
+        assert!(matches!(
+            VersionedTransaction::from_base64_str(&BASE64_STANDARD.encode([1, 2, 3]), MAX_SIZE),
+            Err(DecodeTransactionError::InvalidTransaction(_))
+        ));
+
+        let bytes = bincode::serialize(&transaction(MAX_SIZE)).unwrap();
+        assert_eq!(
+            VersionedTransaction::from_base64_str(&BASE64_STANDARD.encode(&bytes), MAX_SIZE),
+            Err(DecodeTransactionError::EncodedTooLarge {
+                len: bytes.len().div_ceil(3) * 4,
+                max_len: 1644,
+            })
+        );
+        assert_eq!(
+            VersionedTransaction::from_base64_str(
+                &BASE64_STANDARD.encode(&bytes[..1233]),
+                MAX_SIZE
+            ),
+            Err(DecodeTransactionError::TooLarge {
+                size: 1233,
+                max_size: MAX_SIZE,
+            })
+        );
+        assert!(matches!(
+            VersionedTransaction::from_base58_str(&bs58::encode(&bytes).into_string(), MAX_SIZE),
+            Err(DecodeTransactionError::EncodedTooLarge { .. })
+        ));
+    }
+}
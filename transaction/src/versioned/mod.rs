@@ -1,23 +1,38 @@
 //! Defines a transaction which supports multiple versions of messages.
 
-use {
-    crate::Transaction, solana_message::VersionedMessage, solana_sanitize::SanitizeError,
-    solana_signature::Signature, std::cmp::Ordering,
-};
-#[cfg(feature = "serde")]
-use {
-    serde_derive::{Deserialize, Serialize},
-    solana_short_vec as short_vec,
-};
+#[cfg(feature = "borsh")]
+use borsh::{BorshDeserialize, BorshSerialize};
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::wasm_bindgen;
 #[cfg(feature = "bincode")]
 use {
+    crate::unsigned::UnsignedTransaction,
     solana_bincode::limited_deserialize,
     solana_sdk_ids::system_program,
     solana_signer::{signers::Signers, SignerError},
     solana_system_interface::instruction::SystemInstruction,
 };
+use {
+    crate::{Transaction, PACKET_DATA_SIZE},
+    solana_message::{VersionedMessage, VersionedMessageRef},
+    solana_sanitize::SanitizeError,
+    solana_signature::Signature,
+    solana_transaction_error::{TransactionError, TransactionResult},
+    std::cmp::Ordering,
+};
+#[cfg(feature = "serde")]
+use {
+    serde_derive::{Deserialize, Serialize},
+    solana_short_vec as short_vec,
+};
 
+pub mod diagnostics;
+pub mod diff;
+pub mod encoding;
+pub mod packet;
+pub mod relay;
 pub mod sanitized;
+pub mod serde_base64;
 
 /// Type that serializes to the string "legacy"
 #[cfg_attr(
@@ -47,7 +62,21 @@ impl TransactionVersion {
 
 // NOTE: Serialization-related changes must be paired with the direct read at sigverify.
 /// An atomic transaction
+///
+/// # Serialization
+///
+/// Serde formats use the wire encoding. With the `serde-base64` feature,
+/// [`serde_base64`] serializes a transaction as a base64 string of it in
+/// human-readable formats instead.
+/// The borsh encoding is the standard derived encoding of the signatures and
+/// message.
+#[cfg(not(target_arch = "wasm32"))]
 #[cfg_attr(feature = "frozen-abi", derive(solana_frozen_abi_macro::AbiExample))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(BorshSerialize, BorshDeserialize),
+    borsh(crate = "borsh")
+)]
 #[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
 #[derive(Debug, PartialEq, Default, Eq, Clone)]
 pub struct VersionedTransaction {
@@ -58,6 +87,60 @@ pub struct VersionedTransaction {
     pub message: VersionedMessage,
 }
 
+/// wasm-bindgen version of the VersionedTransaction struct.
+/// This duplication is required until https://github.com/rustwasm/wasm-bindgen/issues/3671
+/// is fixed. This must not diverge from the regular non-wasm VersionedTransaction struct.
+#[cfg(target_arch = "wasm32")]
+#[wasm_bindgen]
+#[cfg_attr(feature = "frozen-abi", derive(solana_frozen_abi_macro::AbiExample))]
+#[cfg_attr(
+    feature = "borsh",
+    derive(BorshSerialize, BorshDeserialize),
+    borsh(crate = "borsh")
+)]
+#[cfg_attr(feature = "serde", derive(Deserialize, Serialize))]
+#[derive(Debug, PartialEq, Default, Eq, Clone)]
+pub struct VersionedTransaction {
+    /// List of signatures
+    #[wasm_bindgen(skip)]
+    #[cfg_attr(feature = "serde", serde(with = "short_vec"))]
+    pub signatures: Vec<Signature>,
+    /// Message to sign.
+    #[wasm_bindgen(skip)]
+    pub message: VersionedMessage,
+}
+
+/// A borrowed view of a versioned transaction that serializes exactly like
+/// the equivalent [`VersionedTransaction`], without cloning its signatures or
+/// message.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct VersionedTransactionRef<'a> {
+    /// List of signatures
+    #[cfg_attr(feature = "serde", serde(with = "short_vec"))]
+    pub signatures: &'a [Signature],
+    /// Message to sign.
+    pub message: VersionedMessageRef<'a>,
+}
+
+impl VersionedTransactionRef<'_> {
+    /// Returns the exact number of bytes this transaction occupies on the
+    /// wire, computed without serializing it.
+    pub fn serialized_size(&self) -> usize {
+        self.message
+            .serialized_size_with_signatures(self.signatures.len())
+    }
+}
+
+impl From<VersionedTransactionRef<'_>> for VersionedTransaction {
+    fn from(transaction: VersionedTransactionRef<'_>) -> Self {
+        Self {
+            signatures: transaction.signatures.to_vec(),
+            message: transaction.message.into(),
+        }
+    }
+}
+
 impl From<Transaction> for VersionedTransaction {
     fn from(transaction: Transaction) -> Self {
         Self {
@@ -118,6 +201,39 @@ impl VersionedTransaction {
         })
     }
 
+    /// Returns a copy of this transaction with its compute unit price set to
+    /// `micro_lamports`, re-signed by `keypairs`.
+    ///
+    /// The message is changed as described in
+    /// [`VersionedMessage::set_compute_unit_price`].
+    #[cfg(feature = "bincode")]
+    pub fn with_compute_unit_price<T: Signers + ?Sized>(
+        &self,
+        micro_lamports: u64,
+        keypairs: &T,
+    ) -> std::result::Result<Self, SignerError> {
+        let mut message = self.message.clone();
+        message
+            .set_compute_unit_price(micro_lamports)
+            .map_err(|err| SignerError::InvalidInput(err.to_string()))?;
+        Self::try_new(message, keypairs)
+    }
+
+    /// Replace the recent blockhash and re-sign the transaction with all
+    /// required keys, discarding existing signatures. On error the
+    /// transaction is left unchanged.
+    #[cfg(feature = "bincode")]
+    pub fn resign_with_blockhash<T: Signers + ?Sized>(
+        &mut self,
+        keypairs: &T,
+        recent_blockhash: solana_hash::Hash,
+    ) -> std::result::Result<(), SignerError> {
+        let mut message = self.message.clone();
+        message.set_recent_blockhash(recent_blockhash);
+        *self = Self::try_new(message, keypairs)?;
+        Ok(())
+    }
+
     pub fn sanitize(&self) -> std::result::Result<(), SanitizeError> {
         self.message.sanitize()?;
         self.sanitize_signatures()?;
@@ -154,12 +270,32 @@ impl VersionedTransaction {
 
     /// Returns the version of the transaction
     pub fn version(&self) -> TransactionVersion {
-        match self.message {
-            VersionedMessage::Legacy(_) => TransactionVersion::LEGACY,
-            VersionedMessage::V0(_) => TransactionVersion::Number(0),
+        match self.message.version() {
+            None => TransactionVersion::LEGACY,
+            Some(version) => TransactionVersion::Number(version),
         }
     }
 
+    /// Returns the exact number of bytes this transaction occupies on the
+    /// wire, computed without serializing it.
+    pub fn serialized_size(&self) -> usize {
+        self.message
+            .serialized_size_with_signatures(self.signatures.len())
+    }
+
+    /// Verify that the transaction fits in a packet, returning
+    /// [`TransactionError::SizeLimitExceeded`] with its actual size if not.
+    pub fn validate_size(&self) -> TransactionResult<()> {
+        let size = self.serialized_size();
+        if size > PACKET_DATA_SIZE {
+            return Err(TransactionError::SizeLimitExceeded {
+                size: size as u64,
+                max_size: PACKET_DATA_SIZE as u64,
+            });
+        }
+        Ok(())
+    }
+
     /// Returns a legacy transaction if the transaction message is legacy.
     pub fn into_legacy_transaction(self) -> Option<Transaction> {
         match self.message {
@@ -171,6 +307,13 @@ impl VersionedTransaction {
         }
     }
 
+    /// Drop the signatures, keeping the message and the keys required to
+    /// sign it so that it can be signed again.
+    #[cfg(feature = "bincode")]
+    pub fn into_unsigned(self) -> UnsignedTransaction {
+        UnsignedTransaction::new(self.message)
+    }
+
     #[cfg(feature = "verify")]
     /// Verify the transaction and hash its message
     pub fn verify_and_hash_message(
@@ -182,7 +325,7 @@ impl VersionedTransaction {
             .iter()
             .all(|verify_result| *verify_result)
         {
-            Err(solana_transaction_error::TransactionError::SignatureFailure)
+            Err(TransactionError::SignatureFailure)
         } else {
             Ok(VersionedMessage::hash_raw_message(&message_bytes))
         }
@@ -231,11 +374,13 @@ impl VersionedTransaction {
 mod tests {
     use {
         super::*,
+        solana_compute_budget_interface::ComputeBudgetInstruction,
         solana_hash::Hash,
         solana_instruction::{AccountMeta, Instruction},
         solana_keypair::Keypair,
         solana_message::Message as LegacyMessage,
         solana_pubkey::Pubkey,
+        solana_sdk_ids::compute_budget,
         solana_signer::Signer,
         solana_system_interface::instruction as system_instruction,
     };
@@ -312,12 +457,10 @@ mod tests {
     #[test]
     fn tx_uses_nonce_bad_prog_id_idx_fail() {
         let (_, _, mut tx) = nonced_transfer_tx();
-        match &mut tx.message {
-            VersionedMessage::Legacy(message) => {
-                message.instructions.get_mut(0).unwrap().program_id_index = 255u8;
-            }
-            VersionedMessage::V0(_) => unreachable!(),
+        let VersionedMessage::Legacy(message) = &mut tx.message else {
+            unreachable!()
         };
+        message.instructions.get_mut(0).unwrap().program_id_index = 255u8;
         assert!(!tx.uses_durable_nonce());
     }
 
@@ -358,6 +501,150 @@ mod tests {
         assert!(!tx.uses_durable_nonce());
     }
 
+    #[test]
+    fn test_serialized_size() {
+        let (_, _, tx) = nonced_transfer_tx();
+        assert_eq!(
+            tx.serialized_size(),
+            bincode::serialized_size(&tx).unwrap() as usize
+        );
+
+        let tx = VersionedTransaction::default();
+        assert_eq!(
+            tx.serialized_size(),
+            bincode::serialized_size(&tx).unwrap() as usize
+        );
+    }
+
+    #[test]
+    fn test_validate_size() {
+        let (_, _, tx) = nonced_transfer_tx();
+        assert_eq!(tx.validate_size(), Ok(()));
+
+        let payer = Keypair::new();
+        let tx = VersionedTransaction::from(Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[0; PACKET_DATA_SIZE],
+                vec![],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        ));
+        assert_eq!(
+            tx.validate_size(),
+            Err(TransactionError::SizeLimitExceeded {
+                size: tx.serialized_size() as u64,
+                max_size: PACKET_DATA_SIZE as u64,
+            })
+        );
+    }
+
+    #[test]
+    fn test_with_compute_unit_price() {
+        let payer = Keypair::new();
+        let recipient = Pubkey::new_unique();
+        let message = LegacyMessage::new(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &recipient,
+                42,
+            )],
+            Some(&payer.pubkey()),
+        );
+        let tx =
+            VersionedTransaction::try_new(VersionedMessage::Legacy(message), &[&payer]).unwrap();
+
+        // Inserted when missing
+        let bumped = tx.with_compute_unit_price(100, &[&payer]).unwrap();
+        assert_eq!(bumped.verify_with_results(), vec![true]);
+        assert!(bumped.sanitize().is_ok());
+        let instructions = bumped.message.instructions();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(
+            instructions[0].data,
+            ComputeBudgetInstruction::set_compute_unit_price(100).data
+        );
+        assert_eq!(instructions[1], tx.message.instructions()[0]);
+        assert_eq!(
+            bumped.message.static_account_keys().last(),
+            Some(&compute_budget::id())
+        );
+
+        // Replaced when present
+        let rebumped = bumped.with_compute_unit_price(200, &[&payer]).unwrap();
+        assert_eq!(rebumped.verify_with_results(), vec![true]);
+        assert_eq!(
+            rebumped.message.static_account_keys(),
+            bumped.message.static_account_keys()
+        );
+        assert_eq!(
+            rebumped.message.instructions()[0].data,
+            ComputeBudgetInstruction::set_compute_unit_price(200).data
+        );
+        assert_eq!(rebumped.message.instructions().len(), 2);
+
+        assert_eq!(
+            tx.with_compute_unit_price(100, &[&Keypair::new()]),
+            Err(SignerError::KeypairPubkeyMismatch)
+        );
+    }
+
+    #[test]
+    fn test_resign_with_blockhash() {
+        let (_, _, mut tx) = nonced_transfer_tx();
+        let original = tx.clone();
+        let new_blockhash = Hash::new_unique();
+        assert!(tx
+            .resign_with_blockhash(&[&Keypair::new()], new_blockhash)
+            .is_err());
+        assert_eq!(tx, original);
+
+        let payer = Keypair::new();
+        let message = VersionedMessage::Legacy(LegacyMessage::new(
+            &[system_instruction::transfer(
+                &payer.pubkey(),
+                &Pubkey::new_unique(),
+                1,
+            )],
+            Some(&payer.pubkey()),
+        ));
+        let mut tx = VersionedTransaction::try_new(message, &[&payer]).unwrap();
+        tx.resign_with_blockhash(&[&payer], new_blockhash).unwrap();
+        assert_eq!(tx.message.recent_blockhash(), &new_blockhash);
+        assert_eq!(tx.verify_with_results(), vec![true]);
+    }
+
+    #[test]
+    fn test_borsh() {
+        let (_, _, tx) = nonced_transfer_tx();
+        let bytes = borsh::to_vec(&tx).unwrap();
+        assert_eq!(
+            borsh::from_slice::<VersionedTransaction>(&bytes).unwrap(),
+            tx
+        );
+
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::from([1; 64])],
+            message: VersionedMessage::V0(solana_message::v0::Message {
+                account_keys: vec![Pubkey::new_unique()],
+                recent_blockhash: Hash::new_unique(),
+                address_table_lookups: vec![solana_message::v0::MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0, 1],
+                    readonly_indexes: vec![2],
+                }],
+                ..solana_message::v0::Message::default()
+            }),
+        };
+        let bytes = borsh::to_vec(&tx).unwrap();
+        assert_eq!(
+            borsh::from_slice::<VersionedTransaction>(&bytes).unwrap(),
+            tx
+        );
+    }
+
     #[test]
     fn test_sanitize_signatures_inner() {
         assert_eq!(
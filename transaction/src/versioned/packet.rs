@@ -0,0 +1,196 @@
+//! Decoding of transactions received in packets.
+//!
+//! Ingest stages decode every packet they receive, so the generic bincode
+//! path, which grows each vector as it reads it, is a measurable cost.
+//! [`VersionedTransaction::try_from_packet_data`] instead validates the layout
+//! of the whole buffer with a [`TransactionView`] and then allocates every
+//! vector of the transaction once, at its final length.
+
+#[cfg(feature = "packet")]
+use solana_packet::Packet;
+use {
+    crate::{versioned::VersionedTransaction, view::TransactionView, PACKET_DATA_SIZE},
+    solana_message::{
+        compiled_instruction::CompiledInstruction,
+        legacy,
+        v0::{self, MessageAddressTableLookup},
+        VersionedMessage,
+    },
+    solana_transaction_error::{TransactionError, TransactionResult},
+};
+
+impl VersionedTransaction {
+    /// Decode the wire transaction in `data`, which must be at most
+    /// [`PACKET_DATA_SIZE`] bytes long.
+    ///
+    /// Oversized data is rejected with [`TransactionError::SizeLimitExceeded`]
+    /// before any of it is read. Data that is not exactly one legacy or v0
+    /// transaction, including data with trailing bytes, is rejected with
+    /// [`TransactionError::SanitizeFailure`]. The transaction is not
+    /// sanitized.
+    pub fn try_from_packet_data(data: &[u8]) -> TransactionResult<Self> {
+        if data.len() > PACKET_DATA_SIZE {
+            return Err(TransactionError::SizeLimitExceeded {
+                size: data.len() as u64,
+                max_size: PACKET_DATA_SIZE as u64,
+            });
+        }
+        let view = TransactionView::try_new(data)?;
+        Ok(Self::from_view(&view))
+    }
+
+    /// Copy the transaction of `view`, allocating every vector once, at its
+    /// final length.
+    pub(crate) fn from_view(view: &TransactionView) -> Self {
+        let header = *view.header();
+        let account_keys = view.static_account_keys().to_vec();
+        let recent_blockhash = *view.recent_blockhash();
+        let instructions = view
+            .instructions()
+            .map(|instruction| CompiledInstruction {
+                program_id_index: instruction.program_id_index,
+                accounts: instruction.accounts.to_vec(),
+                data: instruction.data.to_vec(),
+            })
+            .collect();
+        let message = match view.version() {
+            None => VersionedMessage::Legacy(legacy::Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+            }),
+            Some(_) => VersionedMessage::V0(v0::Message {
+                header,
+                account_keys,
+                recent_blockhash,
+                instructions,
+                address_table_lookups: view
+                    .address_table_lookups()
+                    .map(|lookup| MessageAddressTableLookup {
+                        account_key: *lookup.account_key,
+                        writable_indexes: lookup.writable_indexes.to_vec(),
+                        readonly_indexes: lookup.readonly_indexes.to_vec(),
+                    })
+                    .collect(),
+            }),
+        };
+
+        Self {
+            signatures: view.signatures().to_vec(),
+            message,
+        }
+    }
+}
+
+/// Decode the transaction in the valid bytes of a packet with
+/// [`VersionedTransaction::try_from_packet_data`]. A packet marked as discard
+/// is rejected with [`TransactionError::SanitizeFailure`].
+#[cfg(feature = "packet")]
+impl TryFrom<&Packet> for VersionedTransaction {
+    type Error = TransactionError;
+
+    fn try_from(packet: &Packet) -> TransactionResult<Self> {
+        let data = packet.data(..).ok_or(TransactionError::SanitizeFailure)?;
+        Self::try_from_packet_data(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        solana_hash::Hash,
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_pubkey::Pubkey,
+        solana_signer::Signer,
+    };
+
+    fn legacy_transaction(data_len: usize) -> VersionedTransaction {
+        let payer = Keypair::new();
+        Transaction::new_signed_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &vec![7; data_len],
+                vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            )],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        )
+        .into()
+    }
+
+    fn v0_transaction() -> VersionedTransaction {
+        let payer = Keypair::new();
+        let message = v0::Message {
+            header: solana_message::MessageHeader {
+                num_required_signatures: 1,
+                num_readonly_signed_accounts: 0,
+                num_readonly_unsigned_accounts: 1,
+            },
+            account_keys: vec![payer.pubkey(), Pubkey::new_unique()],
+            recent_blockhash: Hash::new_unique(),
+            instructions: vec![CompiledInstruction::new_from_raw_parts(
+                1,
+                vec![1, 2, 3],
+                vec![0, 2, 3],
+            )],
+            address_table_lookups: vec![MessageAddressTableLookup {
+                account_key: Pubkey::new_unique(),
+                writable_indexes: vec![4],
+                readonly_indexes: vec![5, 6],
+            }],
+        };
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[&payer]).unwrap()
+    }
+
+    #[test]
+    fn test_try_from_packet_data() {
+        for tx in [legacy_transaction(10), v0_transaction()] {
+            let data = bincode::serialize(&tx).unwrap();
+            assert_eq!(VersionedTransaction::try_from_packet_data(&data), Ok(tx));
+        }
+    }
+
+    #[test]
+    fn test_try_from_packet_data_rejects_invalid_data() {
+        let tx = legacy_transaction(2000);
+        let data = bincode::serialize(&tx).unwrap();
+        assert_eq!(
+            VersionedTransaction::try_from_packet_data(&data),
+            Err(TransactionError::SizeLimitExceeded {
+                size: data.len() as u64,
+                max_size: PACKET_DATA_SIZE as u64,
+            })
+        );
+
+        let mut data = bincode::serialize(&v0_transaction()).unwrap();
+        data.push(0);
+        assert_eq!(
+            VersionedTransaction::try_from_packet_data(&data),
+            Err(TransactionError::SanitizeFailure)
+        );
+        data.truncate(data.len() - 2);
+        assert_eq!(
+            VersionedTransaction::try_from_packet_data(&data),
+            Err(TransactionError::SanitizeFailure)
+        );
+    }
+
+    #[cfg(feature = "packet")]
+    #[test]
+    fn test_try_from_packet() {
+        let tx = v0_transaction();
+        let mut packet = Packet::from_data(None, &tx).unwrap();
+        assert_eq!(VersionedTransaction::try_from(&packet), Ok(tx));
+
+        packet.meta_mut().set_discard(true);
+        assert_eq!(
+            VersionedTransaction::try_from(&packet),
+            Err(TransactionError::SanitizeFailure)
+        );
+    }
+}
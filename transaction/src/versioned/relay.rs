@@ -0,0 +1,114 @@
+//! Transactions kept for relaying, including ones whose message is of a
+//! version this crate can't parse.
+#![cfg(feature = "unknown-versions")]
+
+use {
+    crate::versioned::VersionedTransaction,
+    solana_message::{RawVersionedMessage, RelayedMessage},
+    solana_signature::Signature,
+};
+
+/// A transaction deserialized by a forwarder.
+///
+/// Unlike [`VersionedTransaction`], the message may be a
+/// [`RawVersionedMessage`] of a version introduced after this crate was
+/// built, so that such transactions can be passed on unchanged instead of
+/// being dropped.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RelayedTransaction {
+    pub signatures: Vec<Signature>,
+    pub message: RelayedMessage,
+}
+
+impl RelayedTransaction {
+    /// Deserialize a transaction that spans all of `bytes`, as one received
+    /// in a packet does. See [`RelayedMessage::deserialize`] for how the
+    /// message is decoded.
+    pub fn deserialize(bytes: &[u8]) -> bincode::Result<Self> {
+        let mut message_bytes = bytes;
+        let solana_short_vec::ShortVec(signatures) = bincode::deserialize_from(&mut message_bytes)?;
+        let message = RelayedMessage::deserialize(message_bytes)?;
+        Ok(Self {
+            signatures,
+            message,
+        })
+    }
+
+    /// Serialize the transaction as it was received.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut bytes =
+            bincode::serialize(&solana_short_vec::ShortVec(self.signatures.clone())).unwrap();
+        bytes.extend(self.message.serialize());
+        bytes
+    }
+
+    /// Convert into a [`VersionedTransaction`], or return `None` if the
+    /// message is of an unknown version.
+    pub fn into_versioned_transaction(self) -> Option<VersionedTransaction> {
+        match self.message {
+            RelayedMessage::Known(message) => Some(VersionedTransaction {
+                signatures: self.signatures,
+                message,
+            }),
+            RelayedMessage::Unknown(_) => None,
+        }
+    }
+
+    /// Returns the raw message if it is of an unknown version.
+    pub fn unknown_message(&self) -> Option<&RawVersionedMessage> {
+        match &self.message {
+            RelayedMessage::Known(_) => None,
+            RelayedMessage::Unknown(message) => Some(message),
+        }
+    }
+}
+
+impl From<VersionedTransaction> for RelayedTransaction {
+    fn from(tx: VersionedTransaction) -> Self {
+        Self {
+            signatures: tx.signatures,
+            message: RelayedMessage::Known(tx.message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, solana_message::MESSAGE_VERSION_PREFIX};
+
+    #[test]
+    fn test_relay_unknown_version() {
+        let tx = RelayedTransaction {
+            signatures: vec![Signature::new_unique(), Signature::new_unique()],
+            message: RelayedMessage::Unknown(RawVersionedMessage {
+                version: 1,
+                bytes: vec![4, 5, 6],
+            }),
+        };
+        let bytes = tx.serialize();
+        assert_eq!(bytes[1 + 2 * 64], MESSAGE_VERSION_PREFIX | 1);
+        assert_eq!(RelayedTransaction::deserialize(&bytes).unwrap(), tx);
+        assert!(bincode::deserialize::<VersionedTransaction>(&bytes).is_err());
+        assert_eq!(tx.into_versioned_transaction(), None);
+    }
+
+    #[test]
+    fn test_relay_known_version() {
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::new_unique()],
+            message: solana_message::VersionedMessage::V0(Default::default()),
+        };
+        let bytes = bincode::serialize(&tx).unwrap();
+        let relayed = RelayedTransaction::deserialize(&bytes).unwrap();
+        assert_eq!(relayed, RelayedTransaction::from(tx.clone()));
+        assert_eq!(relayed.unknown_message(), None);
+        assert_eq!(relayed.serialize(), bytes);
+        assert_eq!(relayed.into_versioned_transaction(), Some(tx));
+
+        assert!(RelayedTransaction::deserialize(&[2, 0]).is_err());
+
+        let mut padded = bytes;
+        padded.push(0);
+        assert!(RelayedTransaction::deserialize(&padded).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Serde helpers for transactions encoded as base64 strings.
+//!
+//! A field annotated with
+//! `#[serde(with = "solana_transaction::versioned::serde_base64")]` is
+//! serialized as a base64 string of its wire encoding in human-readable
+//! formats such as JSON, the encoding accepted by the `sendTransaction` RPC
+//! method, so config files and tooling can round-trip transactions exactly.
+//! Binary formats use the wire encoding, like `VersionedTransaction` does.
+//!
+//! Decoded transactions must be at most [`PACKET_DATA_SIZE`] bytes long and
+//! must not be followed by other bytes.
+#![cfg(feature = "serde-base64")]
+
+use {
+    crate::{versioned::VersionedTransaction, PACKET_DATA_SIZE},
+    base64::{prelude::BASE64_STANDARD, Engine},
+    bincode::Options,
+    serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer},
+};
+
+const MAX_BASE64_LEN: usize = PACKET_DATA_SIZE.div_ceil(3) * 4;
+
+pub fn serialize<S>(transaction: &VersionedTransaction, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    if !serializer.is_human_readable() {
+        return transaction.serialize(serializer);
+    }
+    let bytes = bincode::serialize(transaction).map_err(ser::Error::custom)?;
+    serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+}
+
+pub fn deserialize<'de, D>(deserializer: D) -> Result<VersionedTransaction, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    if !deserializer.is_human_readable() {
+        return VersionedTransaction::deserialize(deserializer);
+    }
+    let encoded = String::deserialize(deserializer)?;
+    if encoded.len() > MAX_BASE64_LEN {
+        return Err(de::Error::invalid_length(
+            encoded.len(),
+            &"a base64 encoded transaction of at most PACKET_DATA_SIZE bytes",
+        ));
+    }
+    let bytes = BASE64_STANDARD.decode(encoded).map_err(de::Error::custom)?;
+    bincode::options()
+        .with_limit(PACKET_DATA_SIZE as u64)
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(&bytes)
+        .map_err(de::Error::custom)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::Transaction,
+        serde_derive::{Deserialize, Serialize},
+        solana_hash::Hash,
+        solana_instruction::Instruction,
+        solana_keypair::Keypair,
+        solana_pubkey::Pubkey,
+        solana_signer::Signer,
+    };
+
+    #[derive(Debug, PartialEq, Deserialize, Serialize)]
+    struct Config {
+        #[serde(with = "super")]
+        transaction: VersionedTransaction,
+    }
+
+    fn config(data_len: usize) -> Config {
+        let payer = Keypair::new();
+        Config {
+            transaction: Transaction::new_signed_with_payer(
+                &[Instruction::new_with_bytes(
+                    Pubkey::new_unique(),
+                    &vec![7; data_len],
+                    vec![],
+                )],
+                Some(&payer.pubkey()),
+                &[&payer],
+                Hash::new_unique(),
+            )
+            .into(),
+        }
+    }
+
+    #[test]
+    fn test_serde_base64() {
+        let config = config(100);
+        let bytes = bincode::serialize(&config.transaction).unwrap();
+
+        // Human-readable formats use base64 of the wire encoding
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            json,
+            format!(r#"{{"transaction":"{}"}}"#, BASE64_STANDARD.encode(&bytes))
+        );
+        assert_eq!(serde_json::from_str::<Config>(&json).unwrap(), config);
+
+        // Binary formats use the wire encoding
+        assert_eq!(bincode::serialize(&config).unwrap(), bytes);
+        assert_eq!(bincode::deserialize::<Config>(&bytes).unwrap(), config);
+    }
+
+    #[test]
+    fn test_serde_base64_errors() {
+        let json =
+            |bytes: &[u8]| format!(r#"{{"transaction":"{}"}}"#, BASE64_STANDARD.encode(bytes));
+        assert!(serde_json::from_str::<Config>(r#"{"transaction":"not base64"}"#).is_err());
+        assert!(serde_json::from_str::<Config>(&json(&[0, 0, 0])).is_err());
+
+        let mut bytes = bincode::serialize(&config(100).transaction).unwrap();
+        bytes.push(0);
+        assert!(serde_json::from_str::<Config>(&json(&bytes)).is_err());
+
+        let bytes = bincode::serialize(&config(PACKET_DATA_SIZE).transaction).unwrap();
+        assert!(serde_json::from_str::<Config>(&json(&bytes)).is_err());
+    }
+}
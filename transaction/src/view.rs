@@ -0,0 +1,690 @@
+//! Zero-copy view of a serialized transaction.
+//!
+//! [`TransactionView`] parses the wire encoding of a [`VersionedTransaction`]
+//! directly from a byte buffer, validating every length prefix and offset up
+//! front, and then hands out borrowed slices of that buffer. No allocation or
+//! copying is performed, which makes it suitable for packet-processing stages
+//! that only need to inspect a few fields of each transaction.
+//!
+//! [`VersionedTransaction`]: crate::versioned::VersionedTransaction
+
+use {
+    solana_hash::{Hash, HASH_BYTES},
+    solana_message::{MessageHeader, MESSAGE_HEADER_LENGTH, MESSAGE_VERSION_PREFIX},
+    solana_pubkey::{Pubkey, PUBKEY_BYTES},
+    solana_sanitize::SanitizeError,
+    solana_signature::{Signature, SIGNATURE_BYTES},
+    std::fmt,
+};
+
+// inlined to avoid a non-optional solana-short-vec dep
+const MAX_SHORT_U16_ENCODING_LENGTH: usize = 3;
+
+/// Decode a compact-u16 length prefix, returning the value and the number of
+/// bytes it occupied.
+pub(crate) fn decode_short_u16(bytes: &[u8]) -> Option<(usize, usize)> {
+    let mut value: usize = 0;
+    for (nth_byte, byte) in bytes.iter().take(MAX_SHORT_U16_ENCODING_LENGTH).enumerate() {
+        let elem = usize::from(*byte & 0x7f);
+        let elem_done = *byte & 0x80 == 0;
+        // Aliased (non-canonical) encodings are rejected
+        if *byte == 0 && nth_byte != 0 {
+            return None;
+        }
+        // The final byte may only carry the two remaining bits of a u16
+        if nth_byte == MAX_SHORT_U16_ENCODING_LENGTH - 1 && (!elem_done || elem > 0x3) {
+            return None;
+        }
+        value |= elem << nth_byte.saturating_mul(7);
+        if elem_done {
+            return Some((value, nth_byte.saturating_add(1)));
+        }
+    }
+    None
+}
+
+/// A field of the wire encoding of a transaction. Instruction and lookup
+/// fields carry the index of their instruction or lookup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireField {
+    Transaction,
+    Signatures,
+    MessageVersion,
+    MessageHeader,
+    AccountKeys,
+    RecentBlockhash,
+    Instructions,
+    InstructionProgramIdIndex(usize),
+    InstructionAccounts(usize),
+    InstructionData(usize),
+    AddressTableLookups,
+    AddressTableLookupAccountKey(usize),
+    AddressTableLookupWritableIndexes(usize),
+    AddressTableLookupReadonlyIndexes(usize),
+}
+
+impl fmt::Display for WireField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Transaction => f.write_str("transaction"),
+            Self::Signatures => f.write_str("signatures shortvec"),
+            Self::MessageVersion => f.write_str("message version"),
+            Self::MessageHeader => f.write_str("message header"),
+            Self::AccountKeys => f.write_str("account keys shortvec"),
+            Self::RecentBlockhash => f.write_str("recent blockhash"),
+            Self::Instructions => f.write_str("instructions shortvec"),
+            Self::InstructionProgramIdIndex(index) => {
+                write!(f, "instruction {index} program id index")
+            }
+            Self::InstructionAccounts(index) => write!(f, "instruction {index} accounts shortvec"),
+            Self::InstructionData(index) => write!(f, "instruction {index} data shortvec"),
+            Self::AddressTableLookups => f.write_str("address table lookups shortvec"),
+            Self::AddressTableLookupAccountKey(index) => {
+                write!(f, "address table lookup {index} account key")
+            }
+            Self::AddressTableLookupWritableIndexes(index) => {
+                write!(f, "address table lookup {index} writable indexes shortvec")
+            }
+            Self::AddressTableLookupReadonlyIndexes(index) => {
+                write!(f, "address table lookup {index} readonly indexes shortvec")
+            }
+        }
+    }
+}
+
+/// Why a field could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireErrorKind {
+    /// The compact-u16 length prefix is malformed or not canonical.
+    InvalidLength,
+    /// The field extends past the end of the buffer.
+    OverflowsBuffer,
+    /// The message version is not supported.
+    UnsupportedVersion(u8),
+    /// Bytes remain after the end of the transaction.
+    TrailingBytes,
+    /// The buffer is longer than `u16::MAX` bytes.
+    TooLarge,
+}
+
+impl fmt::Display for WireErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidLength => f.write_str("has an invalid length"),
+            Self::OverflowsBuffer => f.write_str("overflows buffer"),
+            Self::UnsupportedVersion(version) => write!(f, "{version} is not supported"),
+            Self::TrailingBytes => f.write_str("has trailing bytes"),
+            Self::TooLarge => f.write_str("is too large"),
+        }
+    }
+}
+
+/// Where and why the wire encoding of a transaction could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WireError {
+    /// Offset of the start of the field in the buffer.
+    pub offset: usize,
+    pub field: WireField,
+    pub kind: WireErrorKind,
+}
+
+impl WireError {
+    fn new(offset: usize, field: WireField, kind: WireErrorKind) -> Self {
+        Self {
+            offset,
+            field,
+            kind,
+        }
+    }
+}
+
+impl std::error::Error for WireError {}
+
+impl fmt::Display for WireError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {} at offset {}", self.field, self.kind, self.offset)
+    }
+}
+
+impl From<WireError> for SanitizeError {
+    fn from(err: WireError) -> Self {
+        match err.kind {
+            WireErrorKind::OverflowsBuffer => Self::IndexOutOfBounds,
+            WireErrorKind::TooLarge => Self::ValueOutOfBounds,
+            WireErrorKind::InvalidLength
+            | WireErrorKind::UnsupportedVersion(_)
+            | WireErrorKind::TrailingBytes => Self::InvalidValue,
+        }
+    }
+}
+
+/// Bounds-checked cursor over the transaction buffer.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Read the compact-u16 length prefix of `field`.
+    fn read_short_u16(&mut self, field: WireField) -> Result<usize, WireError> {
+        let remaining = self.bytes.get(self.offset..).unwrap_or_default();
+        let (value, len) = decode_short_u16(remaining).ok_or_else(|| {
+            // A prefix cut short by the end of the buffer has only
+            // continuation bytes.
+            let kind = if remaining.len() < MAX_SHORT_U16_ENCODING_LENGTH
+                && remaining.iter().all(|byte| byte & 0x80 != 0)
+            {
+                WireErrorKind::OverflowsBuffer
+            } else {
+                WireErrorKind::InvalidLength
+            };
+            WireError::new(self.offset, field, kind)
+        })?;
+        self.offset = self.offset.saturating_add(len);
+        Ok(value)
+    }
+
+    fn read_u8(&mut self, field: WireField) -> Result<u8, WireError> {
+        let offset = self.skip(self.offset, 1, field)?;
+        Ok(self.bytes[offset])
+    }
+
+    /// Advance past `len` bytes of `field`, which starts at `field_offset`,
+    /// returning the offset of the first byte.
+    fn skip(
+        &mut self,
+        field_offset: usize,
+        len: usize,
+        field: WireField,
+    ) -> Result<usize, WireError> {
+        let start = self.offset;
+        let end = start
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or_else(|| WireError::new(field_offset, field, WireErrorKind::OverflowsBuffer))?;
+        self.offset = end;
+        Ok(start)
+    }
+
+    /// Advance past a shortvec of items of `item_len` bytes each, returning
+    /// the number of items and the offset of the first one.
+    fn read_vec(&mut self, item_len: usize, field: WireField) -> Result<(usize, usize), WireError> {
+        let field_offset = self.offset;
+        let count = self.read_short_u16(field)?;
+        let start = self.skip(field_offset, count.saturating_mul(item_len), field)?;
+        Ok((count, start))
+    }
+
+    /// Read a shortvec of bytes.
+    fn read_slice(&mut self, field: WireField) -> Result<&'a [u8], WireError> {
+        let (len, start) = self.read_vec(1, field)?;
+        let bytes = self.bytes;
+        Ok(&bytes[start..start.saturating_add(len)])
+    }
+}
+
+/// A borrowed, zero-copy view of a serialized transaction.
+///
+/// Construction with [`TransactionView::try_new`] checks that every section
+/// of the wire encoding is in bounds and that the buffer contains no trailing
+/// bytes. It does not perform [`Sanitize`] checks on the message contents such
+/// as account index validity; callers that need those guarantees should
+/// deserialize and sanitize the transaction as usual.
+///
+/// [`Sanitize`]: solana_sanitize::Sanitize
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransactionView<'a> {
+    bytes: &'a [u8],
+    num_signatures: u16,
+    signatures_offset: u16,
+    message_offset: u16,
+    version: Option<u8>,
+    header: MessageHeader,
+    num_account_keys: u16,
+    account_keys_offset: u16,
+    recent_blockhash_offset: u16,
+    num_instructions: u16,
+    instructions_offset: u16,
+    num_address_table_lookups: u16,
+    address_table_lookups_offset: u16,
+}
+
+impl<'a> TransactionView<'a> {
+    /// Parse and validate the layout of a serialized transaction.
+    pub fn try_new(bytes: &'a [u8]) -> Result<Self, SanitizeError> {
+        Ok(Self::parse(bytes)?)
+    }
+
+    /// Parse and validate the layout of a serialized transaction, reporting
+    /// the field and offset at which parsing fails.
+    pub(crate) fn parse(bytes: &'a [u8]) -> Result<Self, WireError> {
+        // Offsets are stored as u16 which is more than enough for any packet
+        let max_len = usize::from(u16::MAX);
+        if bytes.len() > max_len {
+            return Err(WireError::new(
+                max_len,
+                WireField::Transaction,
+                WireErrorKind::TooLarge,
+            ));
+        }
+
+        let mut reader = Reader { bytes, offset: 0 };
+        let (num_signatures, signatures_offset) =
+            reader.read_vec(SIGNATURE_BYTES, WireField::Signatures)?;
+
+        let message_offset = reader.offset;
+        let version = match bytes.get(message_offset) {
+            Some(byte) if byte & MESSAGE_VERSION_PREFIX != 0 => {
+                reader.offset = reader.offset.saturating_add(1);
+                match byte & !MESSAGE_VERSION_PREFIX {
+                    0 => Some(0),
+                    version => {
+                        return Err(WireError::new(
+                            message_offset,
+                            WireField::MessageVersion,
+                            WireErrorKind::UnsupportedVersion(version),
+                        ))
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let header_offset = reader.skip(
+            reader.offset,
+            MESSAGE_HEADER_LENGTH,
+            WireField::MessageHeader,
+        )?;
+        let header_bytes = &bytes[header_offset..reader.offset];
+        let header = MessageHeader {
+            num_required_signatures: header_bytes[0],
+            num_readonly_signed_accounts: header_bytes[1],
+            num_readonly_unsigned_accounts: header_bytes[2],
+        };
+
+        let (num_account_keys, account_keys_offset) =
+            reader.read_vec(PUBKEY_BYTES, WireField::AccountKeys)?;
+        let recent_blockhash_offset =
+            reader.skip(reader.offset, HASH_BYTES, WireField::RecentBlockhash)?;
+
+        let num_instructions = reader.read_short_u16(WireField::Instructions)?;
+        let instructions_offset = reader.offset;
+        for index in 0..num_instructions {
+            reader.read_u8(WireField::InstructionProgramIdIndex(index))?;
+            reader.read_slice(WireField::InstructionAccounts(index))?;
+            reader.read_slice(WireField::InstructionData(index))?;
+        }
+
+        let (num_address_table_lookups, address_table_lookups_offset) = if version.is_some() {
+            let num_lookups = reader.read_short_u16(WireField::AddressTableLookups)?;
+            let lookups_offset = reader.offset;
+            for index in 0..num_lookups {
+                reader.skip(
+                    reader.offset,
+                    PUBKEY_BYTES,
+                    WireField::AddressTableLookupAccountKey(index),
+                )?;
+                reader.read_slice(WireField::AddressTableLookupWritableIndexes(index))?;
+                reader.read_slice(WireField::AddressTableLookupReadonlyIndexes(index))?;
+            }
+            (num_lookups, lookups_offset)
+        } else {
+            (0, reader.offset)
+        };
+
+        if reader.offset != bytes.len() {
+            return Err(WireError::new(
+                reader.offset,
+                WireField::Transaction,
+                WireErrorKind::TrailingBytes,
+            ));
+        }
+
+        // All values are bounded by `bytes.len()` which was checked above
+        Ok(Self {
+            bytes,
+            num_signatures: num_signatures as u16,
+            signatures_offset: signatures_offset as u16,
+            message_offset: message_offset as u16,
+            version,
+            header,
+            num_account_keys: num_account_keys as u16,
+            account_keys_offset: account_keys_offset as u16,
+            recent_blockhash_offset: recent_blockhash_offset as u16,
+            num_instructions: num_instructions as u16,
+            instructions_offset: instructions_offset as u16,
+            num_address_table_lookups: num_address_table_lookups as u16,
+            address_table_lookups_offset: address_table_lookups_offset as u16,
+        })
+    }
+
+    /// Return the full serialized transaction.
+    pub fn data(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    /// Return the transaction signatures.
+    pub fn signatures(&self) -> &'a [Signature] {
+        let offset = usize::from(self.signatures_offset);
+        let len = usize::from(self.num_signatures);
+        // SAFETY: `Signature` is `repr(transparent)` over `[u8; SIGNATURE_BYTES]`
+        // so it has alignment 1, and the range was bounds-checked in `try_new`.
+        unsafe {
+            core::slice::from_raw_parts(self.bytes[offset..].as_ptr() as *const Signature, len)
+        }
+    }
+
+    /// Return the serialized message, i.e. the bytes covered by the
+    /// signatures.
+    pub fn message_data(&self) -> &'a [u8] {
+        &self.bytes[usize::from(self.message_offset)..]
+    }
+
+    /// Return the message version, or `None` for legacy messages.
+    pub fn version(&self) -> Option<u8> {
+        self.version
+    }
+
+    /// Return the message header.
+    pub fn header(&self) -> &MessageHeader {
+        &self.header
+    }
+
+    /// Return the account keys stored in the message itself.
+    pub fn static_account_keys(&self) -> &'a [Pubkey] {
+        let offset = usize::from(self.account_keys_offset);
+        let len = usize::from(self.num_account_keys);
+        // SAFETY: `Pubkey` is `repr(transparent)` over `[u8; PUBKEY_BYTES]`
+        // so it has alignment 1, and the range was bounds-checked in `try_new`.
+        unsafe { core::slice::from_raw_parts(self.bytes[offset..].as_ptr() as *const Pubkey, len) }
+    }
+
+    /// Return the recent blockhash of the message.
+    pub fn recent_blockhash(&self) -> &'a Hash {
+        let offset = usize::from(self.recent_blockhash_offset);
+        // SAFETY: `Hash` is `repr(transparent)` over `[u8; HASH_BYTES]` so it
+        // has alignment 1, and the range was bounds-checked in `try_new`.
+        unsafe { &*(self.bytes[offset..].as_ptr() as *const Hash) }
+    }
+
+    /// Return the number of instructions in the message.
+    pub fn num_instructions(&self) -> usize {
+        usize::from(self.num_instructions)
+    }
+
+    /// Iterate over the instructions in the message.
+    pub fn instructions(&self) -> InstructionsIter<'a> {
+        InstructionsIter {
+            reader: Reader {
+                bytes: self.bytes,
+                offset: usize::from(self.instructions_offset),
+            },
+            remaining: self.num_instructions,
+        }
+    }
+
+    /// Return the number of address table lookups in the message.
+    pub fn num_address_table_lookups(&self) -> usize {
+        usize::from(self.num_address_table_lookups)
+    }
+
+    /// Iterate over the address table lookups in the message. Legacy messages
+    /// yield no lookups.
+    pub fn address_table_lookups(&self) -> AddressTableLookupsIter<'a> {
+        AddressTableLookupsIter {
+            reader: Reader {
+                bytes: self.bytes,
+                offset: usize::from(self.address_table_lookups_offset),
+            },
+            remaining: self.num_address_table_lookups,
+        }
+    }
+}
+
+/// A borrowed compiled instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct InstructionView<'a> {
+    /// Index into the transaction keys array indicating the program account
+    /// that executes this instruction.
+    pub program_id_index: u8,
+    /// Ordered indices into the transaction keys array indicating which
+    /// accounts to pass to the program.
+    pub accounts: &'a [u8],
+    /// The program input data.
+    pub data: &'a [u8],
+}
+
+/// A borrowed address table lookup.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AddressTableLookupView<'a> {
+    /// Address lookup table account key
+    pub account_key: &'a Pubkey,
+    /// List of indexes used to load writable account addresses
+    pub writable_indexes: &'a [u8],
+    /// List of indexes used to load readonly account addresses
+    pub readonly_indexes: &'a [u8],
+}
+
+/// Iterator over the instructions of a [`TransactionView`].
+pub struct InstructionsIter<'a> {
+    reader: Reader<'a>,
+    remaining: u16,
+}
+
+impl<'a> Iterator for InstructionsIter<'a> {
+    type Item = InstructionView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining = self.remaining.saturating_sub(1);
+        // Layout was validated in `TransactionView::try_new`, so the fields
+        // passed to the reader are never reported
+        let program_id_index = self.reader.read_u8(WireField::Instructions).ok()?;
+        let accounts = self.reader.read_slice(WireField::Instructions).ok()?;
+        let data = self.reader.read_slice(WireField::Instructions).ok()?;
+        Some(InstructionView {
+            program_id_index,
+            accounts,
+            data,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::from(self.remaining);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for InstructionsIter<'_> {}
+
+/// Iterator over the address table lookups of a [`TransactionView`].
+pub struct AddressTableLookupsIter<'a> {
+    reader: Reader<'a>,
+    remaining: u16,
+}
+
+impl<'a> Iterator for AddressTableLookupsIter<'a> {
+    type Item = AddressTableLookupView<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining = self.remaining.saturating_sub(1);
+        // Layout was validated in `TransactionView::try_new`, so the fields
+        // passed to the reader are never reported
+        let field = WireField::AddressTableLookups;
+        let key_offset = self
+            .reader
+            .skip(self.reader.offset, PUBKEY_BYTES, field)
+            .ok()?;
+        let bytes = self.reader.bytes;
+        // SAFETY: `Pubkey` is `repr(transparent)` over `[u8; PUBKEY_BYTES]`
+        // so it has alignment 1, and the range was bounds-checked above.
+        let account_key = unsafe { &*(bytes[key_offset..].as_ptr() as *const Pubkey) };
+        let writable_indexes = self.reader.read_slice(field).ok()?;
+        let readonly_indexes = self.reader.read_slice(field).ok()?;
+        Some(AddressTableLookupView {
+            account_key,
+            writable_indexes,
+            readonly_indexes,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::from(self.remaining);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for AddressTableLookupsIter<'_> {}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        crate::{versioned::VersionedTransaction, Transaction},
+        solana_instruction::{AccountMeta, Instruction},
+        solana_keypair::Keypair,
+        solana_message::{
+            v0::{self, MessageAddressTableLookup},
+            Message, VersionedMessage,
+        },
+        solana_signer::Signer,
+    };
+
+    fn assert_view_matches(tx: &VersionedTransaction) {
+        let bytes = bincode::serialize(tx).unwrap();
+        let view = TransactionView::try_new(&bytes).unwrap();
+        assert_eq!(view.signatures(), tx.signatures.as_slice());
+        assert_eq!(view.message_data(), tx.message.serialize().as_slice());
+        assert_eq!(view.header(), tx.message.header());
+        assert_eq!(view.static_account_keys(), tx.message.static_account_keys());
+        assert_eq!(view.recent_blockhash(), tx.message.recent_blockhash());
+        assert_eq!(view.num_instructions(), tx.message.instructions().len());
+        for (view_ix, ix) in view.instructions().zip(tx.message.instructions()) {
+            assert_eq!(view_ix.program_id_index, ix.program_id_index);
+            assert_eq!(view_ix.accounts, ix.accounts.as_slice());
+            assert_eq!(view_ix.data, ix.data.as_slice());
+        }
+        let lookups = tx.message.address_table_lookups().unwrap_or_default();
+        assert_eq!(view.num_address_table_lookups(), lookups.len());
+        for (view_lookup, lookup) in view.address_table_lookups().zip(lookups) {
+            assert_eq!(view_lookup.account_key, &lookup.account_key);
+            assert_eq!(
+                view_lookup.writable_indexes,
+                lookup.writable_indexes.as_slice()
+            );
+            assert_eq!(
+                view_lookup.readonly_indexes,
+                lookup.readonly_indexes.as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_short_u16() {
+        for len in [0u16, 0x7f, 0x80, 0xff, 0x100, 0x3fff, 0x4000, 0xffff] {
+            let bytes = bincode::serialize(&solana_short_vec::ShortU16(len)).unwrap();
+            assert_eq!(
+                decode_short_u16(&bytes),
+                Some((usize::from(len), bytes.len()))
+            );
+        }
+        assert_eq!(decode_short_u16(&[]), None);
+        assert_eq!(decode_short_u16(&[0x80]), None);
+        assert_eq!(decode_short_u16(&[0x80, 0x00]), None);
+        assert_eq!(decode_short_u16(&[0x80, 0x80, 0x04]), None);
+    }
+
+    #[test]
+    fn test_legacy_transaction_view() {
+        let payer = Keypair::new();
+        let program_id = Pubkey::new_unique();
+        let instructions = [
+            Instruction::new_with_bytes(
+                program_id,
+                &[1, 2, 3],
+                vec![AccountMeta::new(Pubkey::new_unique(), false)],
+            ),
+            Instruction::new_with_bytes(program_id, &[], vec![]),
+        ];
+        let message = Message::new(&instructions, Some(&payer.pubkey()));
+        let tx = Transaction::new(&[&payer], message, Hash::new_unique());
+        let tx = VersionedTransaction::from(tx);
+        assert_view_matches(&tx);
+
+        let bytes = bincode::serialize(&tx).unwrap();
+        assert_eq!(TransactionView::try_new(&bytes).unwrap().version(), None);
+    }
+
+    #[test]
+    fn test_v0_transaction_view() {
+        let tx = VersionedTransaction {
+            signatures: vec![Signature::new_unique(), Signature::new_unique()],
+            message: VersionedMessage::V0(v0::Message {
+                header: MessageHeader {
+                    num_required_signatures: 2,
+                    num_readonly_signed_accounts: 0,
+                    num_readonly_unsigned_accounts: 1,
+                },
+                account_keys: vec![
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                    Pubkey::new_unique(),
+                ],
+                recent_blockhash: Hash::new_unique(),
+                instructions: vec![solana_message::compiled_instruction::CompiledInstruction {
+                    program_id_index: 2,
+                    accounts: vec![0, 1, 3, 4],
+                    data: vec![9; 200],
+                }],
+                address_table_lookups: vec![MessageAddressTableLookup {
+                    account_key: Pubkey::new_unique(),
+                    writable_indexes: vec![0],
+                    readonly_indexes: vec![1, 2],
+                }],
+            }),
+        };
+        assert_view_matches(&tx);
+
+        let bytes = bincode::serialize(&tx).unwrap();
+        assert_eq!(TransactionView::try_new(&bytes).unwrap().version(), Some(0));
+    }
+
+    #[test]
+    fn test_transaction_view_rejects_malformed() {
+        let tx = VersionedTransaction::from(Transaction::new_with_payer(
+            &[Instruction::new_with_bytes(
+                Pubkey::new_unique(),
+                &[1],
+                vec![],
+            )],
+            Some(&Pubkey::new_unique()),
+        ));
+        let bytes = bincode::serialize(&tx).unwrap();
+
+        // every truncation must fail
+        for len in 0..bytes.len() {
+            assert!(TransactionView::try_new(&bytes[..len]).is_err());
+        }
+
+        // trailing bytes
+        let mut extended = bytes.clone();
+        extended.push(0);
+        assert_eq!(
+            TransactionView::try_new(&extended),
+            Err(SanitizeError::InvalidValue)
+        );
+
+        // unsupported message version
+        let mut versioned = bytes;
+        versioned[1 + SIGNATURE_BYTES] = MESSAGE_VERSION_PREFIX | 1;
+        assert_eq!(
+            TransactionView::try_new(&versioned),
+            Err(SanitizeError::InvalidValue)
+        );
+    }
+}
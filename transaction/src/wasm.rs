@@ -1,9 +1,16 @@
-//! `Transaction` Javascript interface
+//! `Transaction` and `VersionedTransaction` Javascript interface
 #![cfg(target_arch = "wasm32")]
 #![allow(non_snake_case)]
+#[cfg(feature = "bincode")]
+use solana_signer::Signer;
 use {
-    crate::Transaction, solana_hash::Hash, solana_instruction::wasm::Instructions,
-    solana_keypair::Keypair, solana_message::Message, solana_pubkey::Pubkey,
+    crate::{versioned::VersionedTransaction, Transaction},
+    solana_hash::Hash,
+    solana_instruction::wasm::Instructions,
+    solana_keypair::Keypair,
+    solana_message::{v0, Message, VersionedMessage},
+    solana_pubkey::Pubkey,
+    solana_signature::Signature,
     wasm_bindgen::prelude::*,
 };
 
@@ -53,3 +60,90 @@ impl Transaction {
         bincode::deserialize(bytes).map_err(|x| std::string::ToString::to_string(&x).into())
     }
 }
+
+#[wasm_bindgen]
+impl VersionedTransaction {
+    /// Create a new unsigned `VersionedTransaction` with a v0 message compiled
+    /// from `instructions`
+    #[wasm_bindgen(constructor)]
+    pub fn constructor(
+        instructions: Instructions,
+        payer: &Pubkey,
+        recent_blockhash: &Hash,
+    ) -> Result<VersionedTransaction, JsValue> {
+        let instructions: Vec<_> = instructions.into();
+        let message = v0::Message::try_compile(payer, &instructions, &[], *recent_blockhash)
+            .map_err(|x| std::string::ToString::to_string(&x))?;
+        let signatures =
+            vec![Signature::default(); usize::from(message.header.num_required_signatures)];
+        Ok(VersionedTransaction {
+            signatures,
+            message: VersionedMessage::V0(message),
+        })
+    }
+
+    /// Create a `VersionedTransaction` from a legacy `Transaction`
+    pub fn fromLegacy(transaction: Transaction) -> VersionedTransaction {
+        transaction.into()
+    }
+
+    /// Return the message if it is a legacy message
+    pub fn legacyMessage(&self) -> Option<Message> {
+        match &self.message {
+            VersionedMessage::Legacy(message) => Some(message.clone()),
+            _ => None,
+        }
+    }
+
+    /// Return the serialized message data to sign.
+    #[cfg(feature = "bincode")]
+    pub fn messageData(&self) -> Box<[u8]> {
+        self.message.serialize().into()
+    }
+
+    #[cfg(feature = "verify")]
+    /// Verify the transaction
+    #[wasm_bindgen(js_name = verify)]
+    pub fn js_verify(&self) -> Result<(), JsValue> {
+        self.verify_and_hash_message()
+            .map(|_| ())
+            .map_err(|x| std::string::ToString::to_string(&x).into())
+    }
+
+    /// Sign the message with `keypair`, which must be one of its required
+    /// signers
+    #[cfg(feature = "bincode")]
+    pub fn partialSign(&mut self, keypair: &Keypair) -> Result<(), JsValue> {
+        let num_required_signatures = usize::from(self.message.header().num_required_signatures);
+        let pubkey = keypair.pubkey();
+        let position = self
+            .message
+            .static_account_keys()
+            .iter()
+            .take(num_required_signatures)
+            .position(|key| *key == pubkey)
+            .ok_or_else(|| JsValue::from_str("keypair is not a required signer"))?;
+        self.signatures
+            .resize(num_required_signatures, Signature::default());
+        self.signatures[position] = keypair.sign_message(&self.message.serialize());
+        Ok(())
+    }
+
+    pub fn isSigned(&self) -> bool {
+        self.signatures.len() == usize::from(self.message.header().num_required_signatures)
+            && self
+                .signatures
+                .iter()
+                .all(|signature| *signature != Signature::default())
+    }
+
+    #[cfg(feature = "bincode")]
+    pub fn toBytes(&self) -> Box<[u8]> {
+        bincode::serialize(self).unwrap().into()
+    }
+
+    #[cfg(feature = "bincode")]
+    pub fn fromBytes(bytes: &[u8]) -> Result<VersionedTransaction, JsValue> {
+        bincode::deserialize(bytes).map_err(|x| std::string::ToString::to_string(&x).into())
+    }
+}